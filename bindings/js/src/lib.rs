@@ -1,8 +1,177 @@
+use firelocal_core::error::FireLocalError;
 use firelocal_core::FireLocal as CoreFireLocal;
-use napi::{Error, Result, Status};
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Error, Result, Status, Task};
 use napi_derive::napi;
 use std::sync::{Arc, Mutex};
 
+/// Convert a core `FireLocalError` into a `napi::Error` whose `.code`
+/// property (via `Status::Custom`) is `FireLocalError::code()` -- e.g.
+/// `"FIRELOCAL_PERMISSION_DENIED"` -- rather than the single
+/// `GenericFailure` every binding method used to return, so JS callers can
+/// branch on the error instead of parsing its message. Can't be a `From`
+/// impl (orphan rule: neither type is local to this crate).
+fn napi_error(err: FireLocalError) -> Error {
+    Error::new(Status::Custom(err.code().to_string()), err.to_string())
+}
+
+/// Like `napi_error`, but for the `anyhow::Error` that `compact`/
+/// `commit_batch` return. Downcasts back to the `FireLocalError` or
+/// `io::Error` that's almost always underneath so the same structured code
+/// reaches JS; anything else (a genuinely unexpected source) falls back to
+/// `GenericFailure` rather than guessing a code for it.
+fn napi_error_anyhow(err: anyhow::Error) -> Error {
+    let err = match err.downcast::<FireLocalError>() {
+        Ok(e) => return napi_error(e),
+        Err(err) => err,
+    };
+    match err.downcast::<std::io::Error>() {
+        Ok(e) => napi_error(FireLocalError::from(e)),
+        Err(e) => Error::new(Status::GenericFailure, e.to_string()),
+    }
+}
+
+/// The poisoned-lock case every binding method hits the same way: another
+/// thread panicked while holding the `Mutex`. Surfaced as
+/// `FireLocalError::LockPoisoned` (via `napi_error`) instead of a bare
+/// `GenericFailure` string, so it's at least distinguishable from other
+/// failures even though the lock itself can't be recovered.
+fn lock_error<T>(_: std::sync::PoisonError<T>) -> Error {
+    napi_error(FireLocalError::LockPoisoned(
+        "a database operation panicked while holding the lock".to_string(),
+    ))
+}
+
+/// Convert a core `BatchCommitResult` into its NAPI shape -- a flat `Vec`
+/// rather than a map, since `napi`'s `#[napi(object)]` derive doesn't cover
+/// `HashMap` (same reasoning as `Vec<RepairReport>` for `repair`'s output).
+fn batch_commit_result_to_napi(
+    result: firelocal_core::transaction::BatchCommitResult,
+) -> Result<BatchCommitResult> {
+    let reads = result
+        .reads
+        .into_iter()
+        .map(|(path, value)| {
+            let value = value
+                .map(String::from_utf8)
+                .transpose()
+                .map_err(|e| napi_error(FireLocalError::Corruption(e.to_string())))?;
+            Ok(BatchReadResult { path, value })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(BatchCommitResult { reads })
+}
+
+/// Runs `CoreFireLocal::put` on libuv's threadpool instead of the JS
+/// thread, so a large write (or one contending with a concurrent
+/// `compact()`) doesn't stall the event loop. Clones the `Arc` rather than
+/// borrowing `&FireLocal`, since `compute` runs on a different thread than
+/// the one that constructed the task.
+struct PutTask {
+    db: Arc<Mutex<CoreFireLocal>>,
+    key: String,
+    value: Vec<u8>,
+}
+
+impl Task for PutTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.db
+            .lock()
+            .map_err(lock_error)?
+            .put(self.key.clone(), self.value.clone())
+            .map_err(napi_error)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Async counterpart of `FireLocal::get` -- see `PutTask`.
+struct GetTask {
+    db: Arc<Mutex<CoreFireLocal>>,
+    key: String,
+}
+
+impl Task for GetTask {
+    type Output = Option<String>;
+    type JsValue = Option<String>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let db = self.db.lock().map_err(lock_error)?;
+        match db.get_checked(&self.key).map_err(napi_error)? {
+            Some(bytes) => {
+                let s = String::from_utf8(bytes)
+                    .map_err(|e| napi_error(FireLocalError::Corruption(e.to_string())))?;
+                Ok(Some(s))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Async counterpart of `FireLocal::compact` -- see `PutTask`. The bulk of
+/// a compaction's cost (rewriting SST segments) runs off-thread; only the
+/// `CompactionStats` conversion happens back on the JS thread in `resolve`.
+struct CompactTask {
+    db: Arc<Mutex<CoreFireLocal>>,
+}
+
+impl Task for CompactTask {
+    type Output = firelocal_core::store::compaction::CompactionStats;
+    type JsValue = CompactionStats;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        self.db
+            .lock()
+            .map_err(lock_error)?
+            .compact()
+            .map_err(napi_error_anyhow)
+    }
+
+    fn resolve(&mut self, _env: Env, stats: Self::Output) -> Result<Self::JsValue> {
+        Ok(CompactionStats {
+            files_before: stats.files_before as u32,
+            files_after: stats.files_after as u32,
+            entries_before: stats.entries_before as u32,
+            entries_after: stats.entries_after as u32,
+            tombstones_removed: stats.tombstones_removed as u32,
+            size_before: stats.size_before as i64,
+            size_after: stats.size_after as i64,
+        })
+    }
+}
+
+/// Async counterpart of `FireLocal::commit_batch` -- see `PutTask`. Takes
+/// the batch's own `Arc` rather than a `&WriteBatch` so the task owns
+/// everything it needs to run on another thread.
+struct CommitBatchTask {
+    db: Arc<Mutex<CoreFireLocal>>,
+    batch: Arc<Mutex<firelocal_core::transaction::WriteBatch>>,
+}
+
+impl Task for CommitBatchTask {
+    type Output = firelocal_core::transaction::BatchCommitResult;
+    type JsValue = BatchCommitResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let mut db = self.db.lock().map_err(lock_error)?;
+        let batch = self.batch.lock().map_err(lock_error)?;
+        db.commit_batch(&batch).map_err(napi_error_anyhow)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        batch_commit_result_to_napi(output)
+    }
+}
+
 #[napi]
 pub struct FireLocal {
     inner: Arc<Mutex<CoreFireLocal>>,
@@ -13,7 +182,7 @@ impl FireLocal {
     #[napi(constructor)]
     pub fn new(path: String) -> Result<Self> {
         let db = CoreFireLocal::new(path)
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            .map_err(|e| napi_error(FireLocalError::from(e)))?;
         Ok(FireLocal {
             inner: Arc::new(Mutex::new(db)),
         })
@@ -23,9 +192,9 @@ impl FireLocal {
     pub fn load_rules(&self, rules: String) -> Result<()> {
         self.inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?
+            .map_err(lock_error)?
             .load_rules(&rules)
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+            .map_err(|e| napi_error(FireLocalError::from(e)))
     }
 
     #[napi]
@@ -33,11 +202,11 @@ impl FireLocal {
         let mut db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         let bytes = value_json.into_bytes();
         db.put(key, bytes)
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+            .map_err(napi_error)
     }
 
     #[napi]
@@ -45,14 +214,16 @@ impl FireLocal {
         let db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
-
-        if let Some(bytes) = db.get(&key) {
-            let s = String::from_utf8(bytes)
-                .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
-            Ok(Some(s))
-        } else {
-            Ok(None)
+            .map_err(lock_error)?;
+
+        match db.get_checked(&key).map_err(napi_error)? {
+            Some(bytes) => {
+                let s = String::from_utf8(bytes).map_err(|e| {
+                    napi_error(FireLocalError::Corruption(e.to_string()))
+                })?;
+                Ok(Some(s))
+            }
+            None => Ok(None),
         }
     }
 
@@ -61,10 +232,10 @@ impl FireLocal {
         let mut db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         db.delete(key)
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+            .map_err(napi_error)
     }
 
     #[napi]
@@ -72,10 +243,10 @@ impl FireLocal {
         let mut db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         db.flush()
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+            .map_err(|e| napi_error(FireLocalError::from(e)))
     }
 
     /// Create a new write batch
@@ -84,7 +255,7 @@ impl FireLocal {
         let db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         let core_batch = db.batch();
         Ok(WriteBatch {
@@ -92,21 +263,24 @@ impl FireLocal {
         })
     }
 
-    /// Commit a write batch atomically
+    /// Commit a write batch atomically, returning the values its staged
+    /// `get` reads held at the commit snapshot alongside the commit outcome.
     #[napi]
-    pub fn commit_batch(&self, batch: &WriteBatch) -> Result<()> {
+    pub fn commit_batch(&self, batch: &WriteBatch) -> Result<BatchCommitResult> {
         let mut db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         let batch_inner = batch
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
-        db.commit_batch(&batch_inner)
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+        let result = db
+            .commit_batch(&batch_inner)
+            .map_err(napi_error_anyhow)?;
+        batch_commit_result_to_napi(result)
     }
 
     /// Run compaction
@@ -115,11 +289,11 @@ impl FireLocal {
         let db = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         let stats = db
             .compact()
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            .map_err(napi_error_anyhow)?;
 
         Ok(CompactionStats {
             files_before: stats.files_before as u32,
@@ -131,6 +305,80 @@ impl FireLocal {
             size_after: stats.size_after as i64,
         })
     }
+
+    /// Scan the WAL and every SST segment for a torn write and truncate
+    /// each back to its last fully-valid record, recovering a database
+    /// after a crash instead of losing the whole segment. The WAL's report
+    /// comes first, followed by one per SST segment found.
+    #[napi]
+    pub fn repair(&self) -> Result<Vec<RepairReport>> {
+        let mut db = self
+            .inner
+            .lock()
+            .map_err(lock_error)?;
+
+        let reports = db
+            .repair()
+            .map_err(|e| napi_error(FireLocalError::from(e)))?;
+
+        Ok(reports
+            .into_iter()
+            .map(|r| RepairReport {
+                records_recovered: r.records_recovered as u32,
+                bytes_truncated: r.bytes_truncated as i64,
+                first_bad_offset: r.first_bad_offset.map(|o| o as i64),
+            })
+            .collect())
+    }
+
+    /// Per-operation (`put`/`get`/`delete`/`query`/`compact`) call counts,
+    /// error counts, and cumulative latency, as a JSON string. For a
+    /// Prometheus scrape endpoint instead, render `CoreFireLocal::metrics_snapshot()`
+    /// on the Rust side.
+    #[napi]
+    pub fn metrics(&self) -> Result<String> {
+        let db = self.inner.lock().map_err(lock_error)?;
+        serde_json::to_string(&db.metrics_registry_snapshot())
+            .map_err(|e| napi_error(FireLocalError::Serialization(e.to_string())))
+    }
+
+    /// Async `put`: runs on libuv's threadpool instead of blocking the
+    /// event loop, so a big write doesn't stall other requests.
+    #[napi]
+    pub fn put_async(&self, key: String, value_json: String) -> AsyncTask<PutTask> {
+        AsyncTask::new(PutTask {
+            db: self.inner.clone(),
+            key,
+            value: value_json.into_bytes(),
+        })
+    }
+
+    /// Async `get` -- see `put_async`.
+    #[napi]
+    pub fn get_async(&self, key: String) -> AsyncTask<GetTask> {
+        AsyncTask::new(GetTask {
+            db: self.inner.clone(),
+            key,
+        })
+    }
+
+    /// Async `compact` -- keeps the server responsive while a large
+    /// database's SST segments are rewritten.
+    #[napi]
+    pub fn compact_async(&self) -> AsyncTask<CompactTask> {
+        AsyncTask::new(CompactTask {
+            db: self.inner.clone(),
+        })
+    }
+
+    /// Async `commit_batch` -- see `put_async`.
+    #[napi]
+    pub fn commit_batch_async(&self, batch: &WriteBatch) -> AsyncTask<CommitBatchTask> {
+        AsyncTask::new(CommitBatchTask {
+            db: self.inner.clone(),
+            batch: batch.inner.clone(),
+        })
+    }
 }
 
 #[napi]
@@ -145,7 +393,7 @@ impl WriteBatch {
         let mut batch = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         batch.set(path, data.into_bytes());
         Ok(())
@@ -156,7 +404,7 @@ impl WriteBatch {
         let mut batch = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         batch.update(path, data.into_bytes());
         Ok(())
@@ -167,11 +415,61 @@ impl WriteBatch {
         let mut batch = self
             .inner
             .lock()
-            .map_err(|_| Error::new(Status::GenericFailure, "Lock error".to_string()))?;
+            .map_err(lock_error)?;
 
         batch.delete(path);
         Ok(())
     }
+
+    /// Stage a read of `path`, resolved at the batch's commit snapshot and
+    /// returned via `commitBatch`'s `BatchCommitResult.reads`.
+    #[napi]
+    pub fn get(&self, path: String) -> Result<()> {
+        let mut batch = self
+            .inner
+            .lock()
+            .map_err(lock_error)?;
+
+        batch.get(path);
+        Ok(())
+    }
+
+    /// Stage a precondition: `commitBatch` fails atomically (no batch writes
+    /// applied) unless `path`'s document is currently at `expected_version`.
+    #[napi]
+    pub fn check_version(&self, path: String, expected_version: i64) -> Result<()> {
+        let mut batch = self
+            .inner
+            .lock()
+            .map_err(lock_error)?;
+
+        batch.check_version(path, expected_version.max(0) as u64);
+        Ok(())
+    }
+
+    /// Stage a set that only takes effect if `path` has no document yet:
+    /// `commitBatch` fails atomically if one already exists.
+    #[napi]
+    pub fn set_if_absent(&self, path: String, data: String) -> Result<()> {
+        let mut batch = self
+            .inner
+            .lock()
+            .map_err(lock_error)?;
+
+        batch.set_if_absent(path, data.into_bytes());
+        Ok(())
+    }
+}
+
+#[napi(object)]
+pub struct BatchReadResult {
+    pub path: String,
+    pub value: Option<String>,
+}
+
+#[napi(object)]
+pub struct BatchCommitResult {
+    pub reads: Vec<BatchReadResult>,
 }
 
 #[napi(object)]
@@ -185,6 +483,13 @@ pub struct CompactionStats {
     pub size_after: i64,
 }
 
+#[napi(object)]
+pub struct RepairReport {
+    pub records_recovered: u32,
+    pub bytes_truncated: i64,
+    pub first_bad_offset: Option<i64>,
+}
+
 /// FieldValue helpers
 #[napi]
 pub fn server_timestamp() -> String {
@@ -1,10 +1,28 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use firelocal_core::dump::Codec;
+use firelocal_core::store::io::{MemoryStorage, Storage};
 use firelocal_core::FireLocal;
 use owo_colors::OwoColorize;
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 
+/// Storage backend a command opens its database against. `File` (the
+/// default) is the on-disk `StdStorage` backend `FireLocal::new` always
+/// used before this flag existed; `Memory` keeps the default keyspace's WAL
+/// entirely in RAM via `FireLocal::new_in_memory`, for ephemeral test runs,
+/// CI, and embedded scratch use that should never touch disk.
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    File,
+    Memory,
+}
+
+/// Default security rules the CLI loads before any read/write command:
+/// allow everything, since the CLI is a trusted local tool rather than a
+/// client going through `SecurityContext`.
+const DEFAULT_RULES: &str = "service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }";
+
 #[derive(Parser)]
 #[command(name = "firelocal")]
 #[command(version = "0.1.0")]
@@ -25,6 +43,8 @@ enum Commands {
     Shell {
         #[arg(default_value = ".")]
         path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
     },
     /// Push a document
     Put {
@@ -32,17 +52,29 @@ enum Commands {
         value: String,
         #[arg(short, long, default_value = ".")]
         db_path: String,
+        /// Write into the named column family instead of the default keyspace
+        #[arg(short, long)]
+        column: Option<String>,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
     },
     /// Get a document
     Get {
         key: String,
         #[arg(short, long, default_value = ".")]
         db_path: String,
+        /// Read from the named column family instead of the default keyspace
+        #[arg(short, long)]
+        column: Option<String>,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
     },
     /// Flush memtable to SST
     Flush {
         #[arg(short, long, default_value = ".")]
         db_path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
     },
     /// Configuration management
     Config {
@@ -53,6 +85,57 @@ enum Commands {
     Compact {
         #[arg(short, long, default_value = ".")]
         db_path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
+    },
+    /// Column family management
+    Cf {
+        #[command(subcommand)]
+        action: CfAction,
+    },
+    /// Dump every document to a self-describing archive file
+    Export {
+        #[arg(short, long, default_value = ".")]
+        db_path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
+        /// File to write the dump archive to
+        out_path: String,
+    },
+    /// Restore every document from an archive file produced by `export`
+    Import {
+        #[arg(short, long, default_value = ".")]
+        db_path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
+        /// File to read the dump archive from
+        in_path: String,
+    },
+    /// List every key starting with a prefix (e.g. `users/` to list a collection)
+    Scan {
+        prefix: String,
+        #[arg(short, long, default_value = ".")]
+        db_path: String,
+        #[arg(short, long, value_enum, default_value_t = Backend::File)]
+        backend: Backend,
+        /// Stop after this many matches
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CfAction {
+    /// List every column family on disk
+    List {
+        #[arg(short, long, default_value = ".")]
+        db_path: String,
+    },
+    /// Create (or open, if it already exists) a column family
+    Create {
+        name: String,
+        #[arg(short, long, default_value = ".")]
+        db_path: String,
     },
 }
 
@@ -84,20 +167,43 @@ fn main() -> Result<()> {
             key,
             value,
             db_path,
+            column,
+            backend,
         } => {
-            let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
-            // Load default rules for CLI usage
-            db.load_rules("service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }")?;
-
-            db.put(key.clone(), value.clone().into_bytes())
-                .context("Failed to put")?;
+            match backend {
+                Backend::File => {
+                    let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    put_doc(&mut db, &key, &value, &column)?;
+                }
+                Backend::Memory => {
+                    let mut db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    put_doc(&mut db, &key, &value, &column)?;
+                }
+            }
             println!("Written {} = {}", key.green(), value);
         }
-        Commands::Get { key, db_path } => {
-            let mut db = FireLocal::new(db_path).context("Failed to open DB")?; // mut required for method signature if internal mutable? No, get is &self usually, but load_rules needs mut.
-            db.load_rules("service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }")?;
+        Commands::Get {
+            key,
+            db_path,
+            column,
+            backend,
+        } => {
+            let result = match backend {
+                Backend::File => {
+                    let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    get_doc(&mut db, &key, &column)?
+                }
+                Backend::Memory => {
+                    let mut db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    get_doc(&mut db, &key, &column)?
+                }
+            };
 
-            if let Ok(Some(bytes)) = db.get(&key) {
+            if let Some(bytes) = result {
                 if let Ok(s) = std::str::from_utf8(&bytes) {
                     println!("{}", s);
                 } else {
@@ -107,121 +213,33 @@ fn main() -> Result<()> {
                 println!("{}", "Not found".red());
             }
         }
-        Commands::Flush { db_path } => {
-            let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
-            db.load_rules("service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }")?;
-            db.flush().context("Failed to flush Memtable")?;
+        Commands::Flush { db_path, backend } => {
+            match backend {
+                Backend::File => {
+                    let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    db.flush().context("Failed to flush Memtable")?;
+                }
+                Backend::Memory => {
+                    let mut db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.load_rules(DEFAULT_RULES)?;
+                    db.flush().context("Failed to flush Memtable")?;
+                }
+            }
             println!("{}", "Flushed Memtable to SST".green());
         }
-        Commands::Shell { path } => {
-            let mut rl = DefaultEditor::new()?;
-            let history_file = ".firelocal_history";
-            if rl.load_history(history_file).is_err() {
-                // No history
+        Commands::Shell { path, backend } => match backend {
+            Backend::File => {
+                let mut db = FireLocal::new(&path).context("Failed to open DB")?;
+                db.load_rules(DEFAULT_RULES)?;
+                run_shell(db)?;
             }
-
-            println!("FireLocal Shell. Type 'exit' to quit.");
-
-            let mut db = FireLocal::new(&path).context("Failed to open DB")?;
-            db.load_rules("service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }")?;
-
-            loop {
-                let readline = rl.readline("firelocal> ");
-                match readline {
-                    Ok(line) => {
-                        let _ = rl.add_history_entry(line.as_str());
-                        let parts: Vec<&str> = line.split_whitespace().collect();
-                        if parts.is_empty() {
-                            continue;
-                        }
-
-                        match parts[0] {
-                            "exit" | "quit" => break,
-                            "put" => {
-                                if parts.len() < 3 {
-                                    println!("Usage: put <key> <json_value>");
-                                    continue;
-                                }
-                                let key = parts[1];
-                                // Value might contain spaces, join remainder
-                                let value = parts[2..].join(" ");
-                                match db.put(key.to_string(), value.into_bytes()) {
-                                    Ok(_) => println!("{}", "OK".green()),
-                                    Err(e) => println!("Error: {}", e.red()),
-                                }
-                            }
-                            "get" => {
-                                if parts.len() < 2 {
-                                    println!("Usage: get <key>");
-                                    continue;
-                                }
-                                let key = parts[1];
-                                if let Ok(Some(bytes)) = db.get(key) {
-                                    let s = String::from_utf8_lossy(&bytes);
-                                    println!("{}", s);
-                                } else {
-                                    println!("{}", "Not Found".red());
-                                }
-                            }
-                            "del" => {
-                                if parts.len() < 2 {
-                                    println!("Usage: del <key>");
-                                    continue;
-                                }
-                                let key = parts[1];
-                                match db.delete(key.to_string()) {
-                                    Ok(_) => println!("{}", "OK".green()),
-                                    Err(e) => println!("Error: {}", e.red()),
-                                }
-                            }
-                            "flush" => match db.flush() {
-                                Ok(_) => println!("{}", "Flushed memtable to SST".green()),
-                                Err(e) => println!("Error: {}", e.red()),
-                            },
-                            "compact" => match db.compact() {
-                                Ok(stats) => {
-                                    println!("{}", "Compaction completed!".green());
-                                    println!(
-                                        "  Files: {} → {}",
-                                        stats.files_before, stats.files_after
-                                    );
-                                    println!("  Tombstones removed: {}", stats.tombstones_removed);
-                                    println!(
-                                        "  Size reduction: {:.2}%",
-                                        stats.size_reduction_percent()
-                                    );
-                                }
-                                Err(e) => println!("Error: {}", e.red()),
-                            },
-                            "help" => {
-                                println!("Available commands:");
-                                println!("  put <key> <json>  - Write document");
-                                println!("  get <key>         - Read document");
-                                println!("  del <key>         - Delete document");
-                                println!("  flush             - Flush memtable to SST");
-                                println!("  compact           - Run compaction");
-                                println!("  help              - Show this help");
-                                println!("  exit/quit         - Exit shell");
-                            }
-                            _ => println!("Unknown command. Type 'help' for available commands."),
-                        }
-                    }
-                    Err(ReadlineError::Interrupted) => {
-                        println!("CTRL-C");
-                        break;
-                    }
-                    Err(ReadlineError::Eof) => {
-                        println!("CTRL-D");
-                        break;
-                    }
-                    Err(err) => {
-                        println!("Error: {:?}", err);
-                        break;
-                    }
-                }
+            Backend::Memory => {
+                let mut db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                db.load_rules(DEFAULT_RULES)?;
+                run_shell(db)?;
             }
-            rl.save_history(history_file)?;
-        }
+        },
         Commands::Config { action } => match action {
             ConfigAction::Show { path } => {
                 use firelocal_core::config::FireLocalConfig;
@@ -253,9 +271,18 @@ fn main() -> Result<()> {
                 }
             }
         },
-        Commands::Compact { db_path } => {
-            let db = FireLocal::new(db_path).context("Failed to open DB")?;
-            match db.compact() {
+        Commands::Compact { db_path, backend } => {
+            let stats = match backend {
+                Backend::File => {
+                    let db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.compact()
+                }
+                Backend::Memory => {
+                    let db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.compact()
+                }
+            };
+            match stats {
                 Ok(stats) => {
                     println!("{}", "Compaction completed!".green());
                     println!("  Files: {} → {}", stats.files_before, stats.files_after);
@@ -270,7 +297,287 @@ fn main() -> Result<()> {
                 Err(e) => println!("{}: {}", "Error".red(), e),
             }
         }
+        Commands::Cf { action } => match action {
+            CfAction::List { db_path } => {
+                let db = FireLocal::new(db_path).context("Failed to open DB")?;
+                match db.list_column_families() {
+                    Ok(names) if names.is_empty() => println!("No column families"),
+                    Ok(names) => {
+                        for name in names {
+                            println!("{}", name);
+                        }
+                    }
+                    Err(e) => println!("{}: {}", "Error".red(), e),
+                }
+            }
+            CfAction::Create { name, db_path } => {
+                let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
+                db.create_column_family(&name)
+                    .context("Failed to create column family")?;
+                println!("Created column family {}", name.green());
+            }
+        },
+        Commands::Export {
+            db_path,
+            backend,
+            out_path,
+        } => {
+            let bytes = match backend {
+                Backend::File => {
+                    let db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.dump(Codec::Zstd).context("Failed to dump DB")?
+                }
+                Backend::Memory => {
+                    let db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.dump(Codec::Zstd).context("Failed to dump DB")?
+                }
+            };
+            std::fs::write(&out_path, bytes).context("Failed to write dump archive")?;
+            println!("Exported to {}", out_path.green());
+        }
+        Commands::Import {
+            db_path,
+            backend,
+            in_path,
+        } => {
+            let bytes = std::fs::read(&in_path).context("Failed to read dump archive")?;
+            match backend {
+                Backend::File => {
+                    let mut db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.restore(&bytes).context("Failed to restore DB")?;
+                }
+                Backend::Memory => {
+                    let mut db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.restore(&bytes).context("Failed to restore DB")?;
+                }
+            }
+            println!("Imported from {}", in_path.green());
+        }
+        Commands::Scan {
+            prefix,
+            db_path,
+            backend,
+            limit,
+        } => {
+            let items = match backend {
+                Backend::File => {
+                    let db = FireLocal::new(db_path).context("Failed to open DB")?;
+                    db.scan_prefix(&prefix).context("Failed to scan")?
+                }
+                Backend::Memory => {
+                    let db = FireLocal::new_in_memory().context("Failed to open in-memory DB")?;
+                    db.scan_prefix(&prefix).context("Failed to scan")?
+                }
+            };
+            print_scan_results(&items, limit);
+        }
+    }
+
+    Ok(())
+}
+
+/// Shared `scan`/`scan_prefix` results printer for `Commands::Scan` and the
+/// shell's `scan` command: just the keys, newest-wins values already
+/// resolved by `scan_prefix`, capped at `limit` matches if given.
+fn print_scan_results(items: &[(String, Vec<u8>)], limit: Option<usize>) {
+    let shown = match limit {
+        Some(limit) => &items[..items.len().min(limit)],
+        None => items,
+    };
+    if shown.is_empty() {
+        println!("No matching keys");
+        return;
+    }
+    for (key, _) in shown {
+        println!("{}", key.green());
+    }
+    if let Some(limit) = limit {
+        if items.len() > limit {
+            println!("... {} more", items.len() - limit);
+        }
+    }
+}
+
+/// Shared `put` body for `Commands::Put`, independent of which `Storage`
+/// backend opened `db`.
+fn put_doc<S: Storage>(
+    db: &mut FireLocal<S>,
+    key: &str,
+    value: &str,
+    column: &Option<String>,
+) -> Result<()> {
+    match column {
+        Some(column) => {
+            db.put_cf(column, key.to_string(), value.as_bytes().to_vec())
+                .context("Failed to put")?;
+        }
+        None => {
+            db.put(key.to_string(), value.as_bytes().to_vec())
+                .context("Failed to put")?;
+        }
+    }
+    Ok(())
+}
+
+/// Shared `get` body for `Commands::Get`, independent of which `Storage`
+/// backend opened `db`.
+fn get_doc<S: Storage>(
+    db: &mut FireLocal<S>,
+    key: &str,
+    column: &Option<String>,
+) -> Result<Option<Vec<u8>>> {
+    match column {
+        Some(column) => db.get_cf(column, key).context("Failed to get"),
+        None => Ok(db.get(key)),
+    }
+}
+
+/// Interactive REPL loop shared by every `--backend` choice of `Commands::Shell`.
+fn run_shell<S: Storage>(mut db: FireLocal<S>) -> Result<()> {
+    let mut rl = DefaultEditor::new()?;
+    let history_file = ".firelocal_history";
+    if rl.load_history(history_file).is_err() {
+        // No history
     }
 
+    println!("FireLocal Shell. Type 'exit' to quit.");
+
+    loop {
+        let readline = rl.readline("firelocal> ");
+        match readline {
+            Ok(line) => {
+                let _ = rl.add_history_entry(line.as_str());
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.is_empty() {
+                    continue;
+                }
+
+                match parts[0] {
+                    "exit" | "quit" => break,
+                    "put" => {
+                        if parts.len() < 3 {
+                            println!("Usage: put <key> <json_value>");
+                            continue;
+                        }
+                        let key = parts[1];
+                        // Value might contain spaces, join remainder
+                        let value = parts[2..].join(" ");
+                        match db.put(key.to_string(), value.into_bytes()) {
+                            Ok(_) => println!("{}", "OK".green()),
+                            Err(e) => println!("Error: {}", e.red()),
+                        }
+                    }
+                    "get" => {
+                        if parts.len() < 2 {
+                            println!("Usage: get <key>");
+                            continue;
+                        }
+                        let key = parts[1];
+                        if let Some(bytes) = db.get(key) {
+                            let s = String::from_utf8_lossy(&bytes);
+                            println!("{}", s);
+                        } else {
+                            println!("{}", "Not Found".red());
+                        }
+                    }
+                    "del" => {
+                        if parts.len() < 2 {
+                            println!("Usage: del <key>");
+                            continue;
+                        }
+                        let key = parts[1];
+                        match db.delete(key.to_string()) {
+                            Ok(_) => println!("{}", "OK".green()),
+                            Err(e) => println!("Error: {}", e.red()),
+                        }
+                    }
+                    "flush" => match db.flush() {
+                        Ok(_) => println!("{}", "Flushed memtable to SST".green()),
+                        Err(e) => println!("Error: {}", e.red()),
+                    },
+                    "compact" => match db.compact() {
+                        Ok(stats) => {
+                            println!("{}", "Compaction completed!".green());
+                            println!(
+                                "  Files: {} → {}",
+                                stats.files_before, stats.files_after
+                            );
+                            println!("  Tombstones removed: {}", stats.tombstones_removed);
+                            println!(
+                                "  Size reduction: {:.2}%",
+                                stats.size_reduction_percent()
+                            );
+                        }
+                        Err(e) => println!("Error: {}", e.red()),
+                    },
+                    "export" => {
+                        if parts.len() < 2 {
+                            println!("Usage: export <file>");
+                            continue;
+                        }
+                        match db.dump(Codec::Zstd) {
+                            Ok(bytes) => match std::fs::write(parts[1], bytes) {
+                                Ok(_) => println!("{}", "Exported".green()),
+                                Err(e) => println!("Error: {}", e.red()),
+                            },
+                            Err(e) => println!("Error: {}", e.red()),
+                        }
+                    }
+                    "import" => {
+                        if parts.len() < 2 {
+                            println!("Usage: import <file>");
+                            continue;
+                        }
+                        match std::fs::read(parts[1]) {
+                            Ok(bytes) => match db.restore(&bytes) {
+                                Ok(_) => println!("{}", "Imported".green()),
+                                Err(e) => println!("Error: {}", e.red()),
+                            },
+                            Err(e) => println!("Error: {}", e.red()),
+                        }
+                    }
+                    "scan" => {
+                        if parts.len() < 2 {
+                            println!("Usage: scan <prefix> [limit]");
+                            continue;
+                        }
+                        let prefix = parts[1];
+                        let limit = parts.get(2).and_then(|s| s.parse::<usize>().ok());
+                        match db.scan_prefix(prefix) {
+                            Ok(items) => print_scan_results(&items, limit),
+                            Err(e) => println!("Error: {}", e.red()),
+                        }
+                    }
+                    "help" => {
+                        println!("Available commands:");
+                        println!("  put <key> <json>    - Write document");
+                        println!("  get <key>           - Read document");
+                        println!("  del <key>           - Delete document");
+                        println!("  scan <prefix> [limit] - List keys starting with prefix");
+                        println!("  flush               - Flush memtable to SST");
+                        println!("  compact             - Run compaction");
+                        println!("  export <file>       - Dump every document to an archive file");
+                        println!("  import <file>       - Restore documents from an archive file");
+                        println!("  help                - Show this help");
+                        println!("  exit/quit           - Exit shell");
+                    }
+                    _ => println!("Unknown command. Type 'help' for available commands."),
+                }
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("CTRL-C");
+                break;
+            }
+            Err(ReadlineError::Eof) => {
+                println!("CTRL-D");
+                break;
+            }
+            Err(err) => {
+                println!("Error: {:?}", err);
+                break;
+            }
+        }
+    }
+    rl.save_history(history_file)?;
     Ok(())
 }
@@ -31,6 +31,23 @@ impl CollectionReference {
         Query {
             db: self.db.clone(),
             ast: q,
+            order_by: None,
+        }
+    }
+
+    /// Query documents whose `field` falls within `[lo, hi]` inclusive,
+    /// served from the same per-field index `where_eq` uses rather than a
+    /// full collection scan.
+    pub fn where_range(&self, field: &str, lo: Value, hi: Value) -> Query {
+        let q = QueryAst {
+            collection: Some(self.path.clone()),
+            field: field.to_string(),
+            operator: QueryOperator::Between { lo, hi },
+        };
+        Query {
+            db: self.db.clone(),
+            ast: q,
+            order_by: None,
         }
     }
 }
@@ -54,6 +71,7 @@ impl DocumentReference {
                 .ok_or(anyhow::anyhow!("Data must be an object"))?
                 .clone(),
             version: 0,
+            ..Default::default()
         };
 
         let bytes = doc.to_json()?.into_bytes();
@@ -90,15 +108,32 @@ impl DocumentReference {
 pub struct Query {
     db: Arc<std::sync::Mutex<FireLocal>>,
     ast: QueryAst,
+    order_by: Option<String>,
 }
 
 impl Query {
+    /// Sort results ascending by `field`'s value. Applied client-side after
+    /// the index lookup, since the underlying indexes aren't queried in
+    /// sorted order across arbitrary predicates.
+    pub fn order_by(mut self, field: &str) -> Self {
+        self.order_by = Some(field.to_string());
+        self
+    }
+
     pub fn get(&self) -> anyhow::Result<Vec<Document>> {
         let db = self
             .db
             .lock()
             .map_err(|e| anyhow::anyhow!("Database lock poisoned: {}", e))?;
-        Ok(db.query(&self.ast)?)
+        let mut docs = db.query(&self.ast)?;
+        if let Some(field) = &self.order_by {
+            docs.sort_by(|a, b| {
+                let av = crate::model::resolve_path(&a.fields, field);
+                let bv = crate::model::resolve_path(&b.fields, field);
+                crate::index::compare_values(&av, &bv).cmp(&0)
+            });
+        }
+        Ok(docs)
     }
 
     pub fn on_snapshot<F>(&self, callback: F) -> u64
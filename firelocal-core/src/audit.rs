@@ -0,0 +1,339 @@
+//! A tamper-evident, hash-chained audit log.
+//!
+//! Every record extends a running SHA3-256 hash chain:
+//! `hash_n = SHA3-256(hash_{n-1} || canonical_bytes(record_n))`, so an
+//! attacker who edits, drops, or reorders a record on disk also has to
+//! recompute every hash after it to stay undetected. The chain is seeded
+//! with a random genesis the first time it's created and persisted as the
+//! sink file's first line, so resuming an existing sink after a restart
+//! continues the same chain rather than starting an unrelated one.
+//!
+//! `AuditChain::log` computes each record's hash in-flight as it's
+//! appended, never re-reading prior records; `AuditChain::verify_chain` is
+//! the separate, explicit operation that re-walks a sink file from its
+//! genesis and reports the first record whose stored hash doesn't match
+//! what recomputing the chain produces.
+
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HASH_LEN: usize = 32;
+
+/// One entry in the chain: `crate::logging::log_security_event` and
+/// `SecurityAuditor`'s operation log (which itself calls
+/// `log_security_event`) both become one of these.
+#[derive(Debug, Clone)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    pub event: String,
+    pub details: String,
+}
+
+impl AuditRecord {
+    fn new(event: &str, details: &str) -> Self {
+        Self {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            event: event.to_string(),
+            details: details.to_string(),
+        }
+    }
+
+    /// Deterministic byte form fed into the chain's hash: every variable-
+    /// length field is length-prefixed (as `store::format::encode_record`
+    /// prefixes keys/values), so no field's content can shift bytes into a
+    /// neighboring field and produce the same encoding for two different
+    /// records.
+    fn canonical_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + 4 + self.event.len() + 4 + self.details.len());
+        buf.extend_from_slice(&self.timestamp_ms.to_le_bytes());
+        buf.extend_from_slice(&(self.event.len() as u32).to_le_bytes());
+        buf.extend_from_slice(self.event.as_bytes());
+        buf.extend_from_slice(&(self.details.len() as u32).to_le_bytes());
+        buf.extend_from_slice(self.details.as_bytes());
+        buf
+    }
+}
+
+/// One line of a chain's sink file: either the genesis marker (written once,
+/// first line) or a logged record together with the hash it produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SinkLine {
+    Genesis { genesis: String },
+    Record {
+        timestamp_ms: u64,
+        event: String,
+        details: String,
+        hash: String,
+    },
+}
+
+fn encode_hash(hash: &[u8; HASH_LEN]) -> String {
+    hash.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn decode_hash(hex: &str) -> io::Result<[u8; HASH_LEN]> {
+    if hex.len() != HASH_LEN * 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "audit chain hash must be 32 bytes of hex",
+        ));
+    }
+    let mut out = [0u8; HASH_LEN];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid hex digit"))?;
+    }
+    Ok(out)
+}
+
+struct ChainState {
+    previous_hash: [u8; HASH_LEN],
+    sink: Option<std::fs::File>,
+}
+
+/// A hash chain of `AuditRecord`s, optionally persisted to an append-only
+/// file sink.
+pub struct AuditChain {
+    state: Mutex<ChainState>,
+}
+
+impl AuditChain {
+    /// Start (or resume) a chain. With `sink_path`, an existing non-empty
+    /// file resumes from its last recorded hash; a missing or empty one is
+    /// seeded with a fresh random genesis, written as the file's first
+    /// line. Without a `sink_path` the chain is in-memory only, seeded with
+    /// a random genesis that's lost on process exit -- there's nothing for
+    /// `verify_chain` to re-walk afterward.
+    pub fn new(sink_path: Option<&Path>) -> io::Result<Self> {
+        let (previous_hash, sink) = match sink_path {
+            Some(path) => {
+                let resumed = Self::last_hash(path)?;
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                let previous_hash = match resumed {
+                    Some(hash) => hash,
+                    None => {
+                        let genesis = random_hash();
+                        writeln!(
+                            file,
+                            "{}",
+                            serde_json::to_string(&SinkLine::Genesis {
+                                genesis: encode_hash(&genesis)
+                            })?
+                        )?;
+                        file.flush()?;
+                        genesis
+                    }
+                };
+                (previous_hash, Some(file))
+            }
+            None => (random_hash(), None),
+        };
+
+        Ok(Self {
+            state: Mutex::new(ChainState {
+                previous_hash,
+                sink,
+            }),
+        })
+    }
+
+    /// Append a record, computing its hash in-flight from the current
+    /// `previous_hash` and the record's own bytes -- no re-read of any
+    /// earlier record. Returns the new hash, which becomes `previous_hash`
+    /// for the next call.
+    pub fn log(&self, event: &str, details: &str) -> io::Result<[u8; HASH_LEN]> {
+        let record = AuditRecord::new(event, details);
+        let mut state = self.state.lock().unwrap();
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(state.previous_hash);
+        hasher.update(record.canonical_bytes());
+        let hash: [u8; HASH_LEN] = hasher.finalize().into();
+
+        if let Some(sink) = state.sink.as_mut() {
+            let line = SinkLine::Record {
+                timestamp_ms: record.timestamp_ms,
+                event: record.event.clone(),
+                details: record.details.clone(),
+                hash: encode_hash(&hash),
+            };
+            writeln!(sink, "{}", serde_json::to_string(&line)?)?;
+            sink.flush()?;
+        }
+
+        state.previous_hash = hash;
+        Ok(hash)
+    }
+
+    /// The hash the last record in `path` produced, or `None` if the file
+    /// doesn't exist or has no records yet (only a genesis line, or is
+    /// empty).
+    fn last_hash(path: &Path) -> io::Result<Option<[u8; HASH_LEN]>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = std::fs::File::open(path)?;
+        let mut last = None;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let SinkLine::Record { hash, .. } = serde_json::from_str(&line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            {
+                last = Some(decode_hash(&hash)?);
+            }
+        }
+        Ok(last)
+    }
+
+    /// Re-walk `path` from its genesis line, recomputing each record's hash
+    /// and comparing it against what was stored. Returns the 0-based index
+    /// of the first record that diverges (tampered, dropped, or reordered),
+    /// or `None` if the whole chain checks out.
+    pub fn verify_chain(path: &Path) -> io::Result<Option<usize>> {
+        let file = std::fs::File::open(path)?;
+        let mut lines = BufReader::new(file).lines();
+
+        let genesis_line = lines
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty audit chain file"))??;
+        let mut previous_hash = match serde_json::from_str(&genesis_line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        {
+            SinkLine::Genesis { genesis } => decode_hash(&genesis)?,
+            SinkLine::Record { .. } => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "audit chain file is missing its genesis line",
+                ))
+            }
+        };
+
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let SinkLine::Record {
+                timestamp_ms,
+                event,
+                details,
+                hash,
+            } = serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected second genesis line in audit chain file",
+                ));
+            };
+
+            let record = AuditRecord {
+                timestamp_ms,
+                event,
+                details,
+            };
+            let mut hasher = Sha3_256::new();
+            hasher.update(previous_hash);
+            hasher.update(record.canonical_bytes());
+            let recomputed: [u8; HASH_LEN] = hasher.finalize().into();
+            let stored = decode_hash(&hash)?;
+
+            if recomputed != stored {
+                return Ok(Some(index));
+            }
+            previous_hash = recomputed;
+        }
+
+        Ok(None)
+    }
+}
+
+fn random_hash() -> [u8; HASH_LEN] {
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+    let mut hash = [0u8; HASH_LEN];
+    OsRng.fill_bytes(&mut hash);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_memory_chain_produces_distinct_hashes_per_record() {
+        let chain = AuditChain::new(None).unwrap();
+        let first = chain.log("OPERATION", "a").unwrap();
+        let second = chain.log("OPERATION", "b").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_file_backed_chain_round_trips_and_verifies_clean() {
+        let dir = std::env::temp_dir().join(format!("firelocal-audit-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let chain = AuditChain::new(Some(&path)).unwrap();
+        chain.log("OPERATION", "user alice read users/alice").unwrap();
+        chain.log("PERMISSION_DENIED", "user bob write users/alice").unwrap();
+
+        assert_eq!(AuditChain::verify_chain(&path).unwrap(), None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_verify_chain_detects_a_tampered_record() {
+        let dir = std::env::temp_dir().join(format!("firelocal-audit-test-tamper-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let chain = AuditChain::new(Some(&path)).unwrap();
+        chain.log("OPERATION", "first").unwrap();
+        chain.log("OPERATION", "second").unwrap();
+
+        let mut contents = std::fs::read_to_string(&path).unwrap();
+        contents = contents.replace("\"first\"", "\"tampered\"");
+        std::fs::write(&path, contents).unwrap();
+
+        assert_eq!(AuditChain::verify_chain(&path).unwrap(), Some(0));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resuming_an_existing_sink_continues_the_same_chain() {
+        let dir = std::env::temp_dir().join(format!("firelocal-audit-test-resume-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let chain = AuditChain::new(Some(&path)).unwrap();
+        let last_hash = chain.log("OPERATION", "before restart").unwrap();
+        drop(chain);
+
+        let resumed = AuditChain::new(Some(&path)).unwrap();
+        let next_hash = resumed.log("OPERATION", "after restart").unwrap();
+        assert_ne!(last_hash, next_hash);
+        assert_eq!(AuditChain::verify_chain(&path).unwrap(), None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,182 @@
+//! Ed25519 request authentication.
+//!
+//! A client proves its identity by signing `"{operation}:{path}:{timestamp_ms}"`
+//! with an ed25519 private key and attaching the resulting [`AuthProof`] to a
+//! `SecurityContext`. `SecurityAuditor::pre_operation_check` verifies the
+//! signature against a pubkey registered via `SecurityAuditor::register_pubkey`
+//! and, on success, resolves the caller's `user_id`/roles from that
+//! registration -- so the signature itself is the only thing a client needs
+//! to authenticate, with no separate bearer token to leak or rotate.
+//!
+//! The same ed25519 key doubles as an x25519 key via [`derive_x25519_pubkey`]
+//! and a [`session_id`], following the scheme public-group servers use so one
+//! identity key can sign requests today and receive encrypted sync payloads
+//! later without a second key exchange.
+
+use crate::error::{FireLocalError, Result};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A signed proof of identity attached to a `SecurityContext`: the signer's
+/// 64-hex ed25519 public key, the 128-hex signature over
+/// [`AuthProof::signed_message`], and the timestamp (Unix epoch
+/// milliseconds) the signature covers.
+#[derive(Debug, Clone)]
+pub struct AuthProof {
+    pub pubkey_hex: String,
+    pub signature_hex: String,
+    pub timestamp_ms: u64,
+}
+
+impl AuthProof {
+    /// The exact bytes the client must sign: binds the signature to this one
+    /// operation, path, and timestamp so it can't be replayed against a
+    /// different request.
+    pub fn signed_message(operation: &str, path: &str, timestamp_ms: u64) -> Vec<u8> {
+        format!("{operation}:{path}:{timestamp_ms}").into_bytes()
+    }
+}
+
+/// Verify `proof` was produced by `proof.pubkey_hex`'s private key over
+/// `operation`/`path`, and that `proof.timestamp_ms` falls within
+/// `max_skew_ms` of `now_ms` in either direction (clocks drift both ways).
+pub fn verify_proof(
+    proof: &AuthProof,
+    operation: &str,
+    path: &str,
+    now_ms: u64,
+    max_skew_ms: u64,
+) -> Result<()> {
+    let age = now_ms.abs_diff(proof.timestamp_ms);
+    if age > max_skew_ms {
+        return Err(FireLocalError::Security(format!(
+            "signature timestamp {} is outside the {}ms skew window (now {})",
+            proof.timestamp_ms, max_skew_ms, now_ms
+        )));
+    }
+
+    let pubkey_bytes = decode_pubkey_hex(&proof.pubkey_hex)?;
+    let verifying_key = VerifyingKey::from_bytes(&pubkey_bytes)
+        .map_err(|e| FireLocalError::Security(format!("invalid ed25519 public key: {e}")))?;
+
+    let signature_bytes: [u8; 64] =
+        decode_hex(&proof.signature_hex)?
+            .try_into()
+            .map_err(|_| {
+                FireLocalError::Security("ed25519 signature must be 64 bytes".to_string())
+            })?;
+    let signature = Signature::from_bytes(&signature_bytes);
+
+    let message = AuthProof::signed_message(operation, path, proof.timestamp_ms);
+    verifying_key
+        .verify(&message, &signature)
+        .map_err(|_| FireLocalError::Security("ed25519 signature verification failed".to_string()))
+}
+
+/// The x25519 public key corresponding to an ed25519 public key, via the
+/// standard birational map between Curve25519's Edwards and Montgomery
+/// forms.
+pub fn derive_x25519_pubkey(ed25519_pubkey: &[u8; 32]) -> Result<[u8; 32]> {
+    let edwards = CompressedEdwardsY(*ed25519_pubkey).decompress().ok_or_else(|| {
+        FireLocalError::Security("invalid ed25519 public key point".to_string())
+    })?;
+    Ok(edwards.to_montgomery().to_bytes())
+}
+
+/// A stable, Session-messenger-style identifier for a registered identity: a
+/// `05` version byte followed by the hex-encoded x25519 public key derived
+/// from its ed25519 key, so the same id can be handed to peers for future
+/// encrypted sync without exposing the signing key itself.
+pub fn session_id(ed25519_pubkey: &[u8; 32]) -> Result<String> {
+    let x25519_pubkey = derive_x25519_pubkey(ed25519_pubkey)?;
+    Ok(format!("05{}", encode_hex(&x25519_pubkey)))
+}
+
+/// Milliseconds since the Unix epoch, for stamping and validating `AuthProof`s.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn decode_pubkey_hex(hex: &str) -> Result<[u8; 32]> {
+    decode_hex(hex)?
+        .try_into()
+        .map_err(|_| FireLocalError::Security("ed25519 public key must be 32 bytes".to_string()))
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(FireLocalError::Security("odd-length hex string".to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| FireLocalError::Security("invalid hex digit".to_string()))
+        })
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn test_signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn sign(signing_key: &SigningKey, operation: &str, path: &str, timestamp_ms: u64) -> AuthProof {
+        let message = AuthProof::signed_message(operation, path, timestamp_ms);
+        let signature = signing_key.sign(&message);
+        AuthProof {
+            pubkey_hex: encode_hex(signing_key.verifying_key().as_bytes()),
+            signature_hex: encode_hex(&signature.to_bytes()),
+            timestamp_ms,
+        }
+    }
+
+    #[test]
+    fn test_verify_proof_accepts_a_valid_signature() {
+        let signing_key = test_signing_key();
+        let proof = sign(&signing_key, "write", "users/alice", 1_000_000);
+        assert!(verify_proof(&proof, "write", "users/alice", 1_000_000, 5_000).is_ok());
+    }
+
+    #[test]
+    fn test_verify_proof_rejects_mismatched_path() {
+        let signing_key = test_signing_key();
+        let proof = sign(&signing_key, "write", "users/alice", 1_000_000);
+        assert!(verify_proof(&proof, "write", "users/bob", 1_000_000, 5_000).is_err());
+    }
+
+    #[test]
+    fn test_verify_proof_rejects_timestamp_outside_skew_window() {
+        let signing_key = test_signing_key();
+        let proof = sign(&signing_key, "write", "users/alice", 1_000_000);
+        assert!(verify_proof(&proof, "write", "users/alice", 1_010_000, 5_000).is_err());
+    }
+
+    #[test]
+    fn test_verify_proof_rejects_malformed_signature_hex() {
+        let mut proof = sign(&test_signing_key(), "write", "users/alice", 1_000_000);
+        proof.signature_hex = "not-hex".to_string();
+        assert!(verify_proof(&proof, "write", "users/alice", 1_000_000, 5_000).is_err());
+    }
+
+    #[test]
+    fn test_session_id_is_stable_and_version_prefixed() {
+        let pubkey = *test_signing_key().verifying_key().as_bytes();
+        let id = session_id(&pubkey).expect("valid point");
+        assert!(id.starts_with("05"));
+        assert_eq!(id.len(), 66, "\"05\" + 64 hex chars of an x25519 pubkey");
+        assert_eq!(session_id(&pubkey).expect("valid point"), id);
+    }
+}
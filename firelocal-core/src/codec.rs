@@ -0,0 +1,35 @@
+//! MessagePack-backed typed value codec. `FireLocal::put_typed`/`get_typed`
+//! let callers round-trip `Serialize`/`DeserializeOwned` types directly,
+//! storing them as compact MessagePack instead of going through a JSON
+//! string first — smaller on disk for numeric/array-heavy documents, and
+//! one less parse on the way in and out.
+
+use thiserror::Error;
+
+/// Error returned by the typed value API. Besides whatever the underlying
+/// `put` fails with (I/O, a rules denial, a validation error -- all of them
+/// reported as `Io`), a typed round-trip can also fail while packing or
+/// unpacking the value, so those two cases get their own variants.
+#[derive(Debug, Error)]
+pub enum CodecError {
+    #[error("i/o error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to encode value as MessagePack: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+
+    #[error("failed to decode value from MessagePack: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+pub type CodecResult<T> = std::result::Result<T, CodecError>;
+
+/// Pack `value` into its MessagePack encoding.
+pub fn encode<T: serde::Serialize>(value: &T) -> CodecResult<Vec<u8>> {
+    Ok(rmp_serde::to_vec(value)?)
+}
+
+/// Unpack a MessagePack-encoded value back into `T`.
+pub fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> CodecResult<T> {
+    Ok(rmp_serde::from_slice(bytes)?)
+}
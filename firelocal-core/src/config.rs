@@ -0,0 +1,123 @@
+//! Project configuration: sync settings, the database path, and tunables
+//! like the read-cache capacity, persisted as a small JSON file alongside
+//! the database so they survive across `FireLocal::new` calls instead of
+//! having to be threaded through every call site by hand.
+
+use crate::sync::enhanced::SyncMode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default capacity of the LRU cache `FireLocal::get` keeps in front of SST
+/// lookups.
+pub const DEFAULT_READ_CACHE_CAPACITY: usize = 1024;
+
+const CONFIG_FILE_NAME: &str = "firelocal.config.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FireLocalConfig {
+    pub project_id: String,
+    #[serde(skip)]
+    pub db_path: PathBuf,
+    #[serde(default)]
+    pub sync_mode: SyncMode,
+    #[serde(default = "default_sync_interval")]
+    pub sync_interval: u64,
+    #[serde(default)]
+    pub firebase_project_id: Option<String>,
+    /// Capacity of the LRU cache sitting in front of SST lookups in `get`.
+    #[serde(default = "default_read_cache_capacity")]
+    pub read_cache_capacity: usize,
+}
+
+fn default_sync_interval() -> u64 {
+    60
+}
+
+fn default_read_cache_capacity() -> usize {
+    DEFAULT_READ_CACHE_CAPACITY
+}
+
+impl Default for FireLocalConfig {
+    fn default() -> Self {
+        Self {
+            project_id: "default".to_string(),
+            db_path: PathBuf::new(),
+            sync_mode: SyncMode::default(),
+            sync_interval: default_sync_interval(),
+            firebase_project_id: None,
+            read_cache_capacity: default_read_cache_capacity(),
+        }
+    }
+}
+
+impl FireLocalConfig {
+    pub fn is_sync_enabled(&self) -> bool {
+        self.sync_mode != SyncMode::Off
+    }
+
+    fn config_path(db_path: &Path) -> PathBuf {
+        db_path.join(CONFIG_FILE_NAME)
+    }
+
+    /// Load the config file under `db_path` (the current directory if
+    /// `None`), creating one with defaults on disk if it doesn't exist yet.
+    pub fn load_or_create(db_path: Option<&Path>) -> Result<Self, ConfigError> {
+        let db_path = db_path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        fs::create_dir_all(&db_path)?;
+        let path = Self::config_path(&db_path);
+
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            let mut config: Self = serde_json::from_str(&content)?;
+            config.db_path = db_path;
+            Ok(config)
+        } else {
+            let config = Self {
+                db_path,
+                ..Self::default()
+            };
+            config.save()?;
+            Ok(config)
+        }
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(Self::config_path(&self.db_path), json)?;
+        Ok(())
+    }
+}
+
+/// Failure loading or persisting a `FireLocalConfig`.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "config I/O error: {e}"),
+            ConfigError::Json(e) => write!(f, "config parse error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(e: serde_json::Error) -> Self {
+        ConfigError::Json(e)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::model::Document;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Write};
+
+/// Current on-disk dump format version. Bump this and add a
+/// `migrate_vN_to_vN+1` function (registered in `reader_for_version`)
+/// whenever `DumpPayload`'s shape changes, so older dumps keep loading.
+pub const CURRENT_DUMP_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 4] = b"FLDB";
+
+/// Compression codec applied to the serialized payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    fn tag(self) -> u8 {
+        match self {
+            Codec::Gzip => 0,
+            Codec::Zstd => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Codec::Gzip),
+            1 => Ok(Codec::Zstd),
+            other => Err(anyhow!("unknown dump codec tag: {other}")),
+        }
+    }
+
+    fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+            Codec::Zstd => Ok(zstd::stream::encode_all(bytes, 0)?),
+        }
+    }
+
+    fn decompress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Gzip => {
+                let mut decoder = flate2::read::GzDecoder::new(bytes);
+                let mut out = Vec::new();
+                decoder.read_to_end(&mut out)?;
+                Ok(out)
+            }
+            Codec::Zstd => Ok(zstd::stream::decode_all(bytes)?),
+        }
+    }
+}
+
+/// The deserialized representation of a dump at the current format version.
+///
+/// Index state isn't included: indexes are derived data, and restoring simply
+/// replays each document through `FireLocal::put`, which repopulates them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DumpPayload {
+    pub documents: Vec<Document>,
+}
+
+/// How to turn the raw JSON tree read off disk into a current `DumpPayload`:
+/// either it's already at `CURRENT_DUMP_VERSION` (`Current`), or it needs to
+/// run through one or more `migrate_vN_to_vN+1` steps first (`Compat`),
+/// composed in order from the dump's declared version up to the current one.
+enum DumpReader {
+    Current,
+    Compat(Vec<fn(Value) -> Result<Value>>),
+}
+
+fn reader_for_version(version: u32) -> Result<DumpReader> {
+    match version {
+        CURRENT_DUMP_VERSION => Ok(DumpReader::Current),
+        1 => Ok(DumpReader::Compat(vec![migrate_v1_to_v2])),
+        other => Err(anyhow!("unsupported dump format version: {other}")),
+    }
+}
+
+/// v1 dumps didn't carry a per-document `version` counter; fill it in with
+/// the default so the result matches the current `Document` shape.
+fn migrate_v1_to_v2(mut value: Value) -> Result<Value> {
+    if let Some(documents) = value.get_mut("documents").and_then(Value::as_array_mut) {
+        for doc in documents {
+            if let Some(obj) = doc.as_object_mut() {
+                obj.entry("version").or_insert_with(|| Value::from(0u64));
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Serialize `payload` into a self-describing archive: a magic number, the
+/// format version, a codec tag, then the JSON payload compressed with `codec`.
+pub fn encode_dump(payload: &DumpPayload, codec: Codec) -> Result<Vec<u8>> {
+    let json_bytes = serde_json::to_vec(payload)?;
+    let compressed = codec.compress(&json_bytes)?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + 4 + 1 + compressed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&CURRENT_DUMP_VERSION.to_le_bytes());
+    out.push(codec.tag());
+    out.extend_from_slice(&compressed);
+    Ok(out)
+}
+
+/// Read back an archive produced by `encode_dump`, from this build or an
+/// older one, migrating it forward to the current `DumpPayload` shape.
+pub fn decode_dump(bytes: &[u8]) -> Result<DumpPayload> {
+    if bytes.len() < MAGIC.len() + 4 + 1 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(anyhow!("not a firelocal dump (bad magic)"));
+    }
+
+    let mut offset = MAGIC.len();
+    let version = u32::from_le_bytes(bytes[offset..offset + 4].try_into()?);
+    offset += 4;
+    let codec = Codec::from_tag(bytes[offset])?;
+    offset += 1;
+
+    let json_bytes = codec.decompress(&bytes[offset..])?;
+    let mut value: Value = serde_json::from_slice(&json_bytes)?;
+
+    if let DumpReader::Compat(chain) = reader_for_version(version)? {
+        for migrate in chain {
+            value = migrate(value)?;
+        }
+    }
+
+    Ok(serde_json::from_value(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_payload() -> DumpPayload {
+        DumpPayload {
+            documents: vec![Document {
+                path: "users/alice".to_string(),
+                fields: serde_json::from_value(json!({ "name": "Alice" })).unwrap(),
+                version: 3,
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip_gzip() {
+        let payload = sample_payload();
+        let bytes = encode_dump(&payload, Codec::Gzip).unwrap();
+        let restored = decode_dump(&bytes).unwrap();
+        assert_eq!(restored, payload);
+    }
+
+    #[test]
+    fn test_round_trip_zstd() {
+        let payload = sample_payload();
+        let bytes = encode_dump(&payload, Codec::Zstd).unwrap();
+        let restored = decode_dump(&bytes).unwrap();
+        assert_eq!(restored, payload);
+    }
+
+    #[test]
+    fn test_migrates_old_version_dump() {
+        // Simulate a v1 archive: no `version` field on the document.
+        let v1_json = json!({
+            "documents": [
+                { "path": "users/bob", "fields": { "name": "Bob" } }
+            ]
+        });
+        let compressed = Codec::Gzip.compress(&serde_json::to_vec(&v1_json).unwrap()).unwrap();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.push(Codec::Gzip.tag());
+        bytes.extend_from_slice(&compressed);
+
+        let restored = decode_dump(&bytes).unwrap();
+        assert_eq!(restored.documents.len(), 1);
+        assert_eq!(restored.documents[0].path, "users/bob");
+        assert_eq!(restored.documents[0].version, 0);
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let payload = sample_payload();
+        let mut bytes = encode_dump(&payload, Codec::Gzip).unwrap();
+        // Corrupt the version field to something never issued.
+        bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&99u32.to_le_bytes());
+        assert!(decode_dump(&bytes).is_err());
+    }
+}
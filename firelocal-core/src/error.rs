@@ -28,10 +28,39 @@ pub enum FireLocalError {
     RateLimitExceeded(String),
     /// Database corruption
     Corruption(String),
+    /// A `Mutex`/`RwLock` guarding shared state was poisoned by a panic in
+    /// another thread holding it.
+    LockPoisoned(String),
     /// Generic errors
     Generic(String),
 }
 
+impl FireLocalError {
+    /// A stable, machine-readable code for this variant, independent of its
+    /// (human-readable, sometimes-reworded) `Display` message. Bindings that
+    /// can't hand the caller a typed Rust enum -- e.g. the NAPI layer --
+    /// surface this instead, so JS code can branch on `error.code` rather
+    /// than parsing a message string.
+    pub fn code(&self) -> &'static str {
+        match self {
+            FireLocalError::Io(_) => "FIRELOCAL_IO",
+            FireLocalError::Validation(_) => "FIRELOCAL_VALIDATION",
+            FireLocalError::Storage(_) => "FIRELOCAL_STORAGE",
+            FireLocalError::Transaction(_) => "FIRELOCAL_TRANSACTION",
+            FireLocalError::Security(_) => "FIRELOCAL_SECURITY",
+            FireLocalError::Configuration(_) => "FIRELOCAL_CONFIGURATION",
+            FireLocalError::Serialization(_) => "FIRELOCAL_SERIALIZATION",
+            FireLocalError::Network(_) => "FIRELOCAL_NETWORK",
+            FireLocalError::NotFound(_) => "FIRELOCAL_NOT_FOUND",
+            FireLocalError::PermissionDenied(_) => "FIRELOCAL_PERMISSION_DENIED",
+            FireLocalError::RateLimitExceeded(_) => "FIRELOCAL_RATE_LIMIT_EXCEEDED",
+            FireLocalError::Corruption(_) => "FIRELOCAL_CORRUPTED",
+            FireLocalError::LockPoisoned(_) => "FIRELOCAL_LOCK_POISONED",
+            FireLocalError::Generic(_) => "FIRELOCAL_GENERIC",
+        }
+    }
+}
+
 impl fmt::Display for FireLocalError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -47,6 +76,7 @@ impl fmt::Display for FireLocalError {
             FireLocalError::PermissionDenied(msg) => write!(f, "Permission Denied: {}", msg),
             FireLocalError::RateLimitExceeded(msg) => write!(f, "Rate Limit Exceeded: {}", msg),
             FireLocalError::Corruption(msg) => write!(f, "Database Corruption: {}", msg),
+            FireLocalError::LockPoisoned(msg) => write!(f, "Lock Poisoned: {}", msg),
             FireLocalError::Generic(msg) => write!(f, "Error: {}", msg),
         }
     }
@@ -83,6 +113,7 @@ impl From<FireLocalError> for io::Error {
             FireLocalError::Security(msg) => io::Error::new(io::ErrorKind::PermissionDenied, msg),
             FireLocalError::Configuration(msg) => io::Error::new(io::ErrorKind::InvalidInput, msg),
             FireLocalError::Serialization(msg) => io::Error::new(io::ErrorKind::InvalidData, msg),
+            FireLocalError::LockPoisoned(msg) => io::Error::other(msg),
             FireLocalError::Generic(msg) => io::Error::other(msg),
         }
     }
@@ -142,6 +173,7 @@ impl ErrorContext {
             FireLocalError::PermissionDenied(_) => FireLocalError::PermissionDenied(message),
             FireLocalError::RateLimitExceeded(_) => FireLocalError::RateLimitExceeded(message),
             FireLocalError::Corruption(_) => FireLocalError::Corruption(message),
+            FireLocalError::LockPoisoned(_) => FireLocalError::LockPoisoned(message),
             FireLocalError::Generic(_) => FireLocalError::Generic(message),
         }
     }
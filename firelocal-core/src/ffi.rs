@@ -86,6 +86,38 @@ pub extern "C" fn firelocal_get_resource(db: *mut FireLocal, key: *const c_char)
     std::ptr::null_mut()
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn firelocal_upgrade(db: *mut FireLocal) -> i32 {
+    let db = unsafe {
+        if db.is_null() {
+            return -1;
+        }
+        &*db
+    };
+
+    if db.upgrade().is_ok() {
+        return 0;
+    }
+    -1
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn firelocal_metrics_json(db: *mut FireLocal) -> *mut c_char {
+    let db = unsafe {
+        if db.is_null() {
+            return std::ptr::null_mut();
+        }
+        &*db
+    };
+
+    if let Ok(json) = serde_json::to_string(&db.metrics()) {
+        if let Ok(c_str) = CString::new(json) {
+            return c_str.into_raw();
+        }
+    }
+    std::ptr::null_mut()
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn firelocal_free_string(s: *mut c_char) {
     if !s.is_null() {
@@ -0,0 +1,291 @@
+//! Per-collection field-level type coercion, applied before a document is
+//! indexed so that a field written as the string `"42"` by one client and
+//! the number `42` by another end up as the same JSON type.
+//!
+//! Distinct from `schema::Schema`'s structural JSON Schema validation: a
+//! `FieldSchema` doesn't reject a document for having the wrong shape, it
+//! normalizes individual field values into a declared type before
+//! validation/indexing ever sees them.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How a field's incoming value should be coerced before it's stored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conversion {
+    /// Store the value exactly as given.
+    Bytes,
+    /// Parse a string into a JSON integer (a value already numeric passes
+    /// through unchanged).
+    Integer,
+    /// Parse a string into a JSON float.
+    Float,
+    /// Parse `"true"`/`"false"` (case-insensitive) into a JSON boolean.
+    Boolean,
+    /// Parse an RFC3339 timestamp or an integer epoch into a millisecond
+    /// epoch integer, auto-detecting which one it's looking at.
+    Timestamp,
+    /// Parse a timestamp string using an explicit `chrono`-style format,
+    /// into a millisecond epoch integer.
+    TimestampFmt(String),
+}
+
+/// A field value that could not be coerced into its declared `Conversion`,
+/// naming the dotted field path that failed (e.g. `address.zip`) and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoercionError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for CoercionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot coerce field '{}': {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for CoercionError {}
+
+/// A dotted-field-path-to-`Conversion` map for one collection.
+#[derive(Debug, Clone, Default)]
+pub struct FieldSchema {
+    conversions: HashMap<String, Conversion>,
+}
+
+impl FieldSchema {
+    pub fn new(conversions: HashMap<String, Conversion>) -> Self {
+        Self { conversions }
+    }
+
+    /// Coerce every field in `data` whose dotted path has a declared
+    /// conversion, recursing into nested objects. Fails closed: the first
+    /// field that can't be parsed into its declared type aborts the whole
+    /// write rather than storing a partially-coerced document.
+    pub fn coerce(&self, data: &mut serde_json::Map<String, Value>) -> Result<(), CoercionError> {
+        coerce_object(data, "", &self.conversions)
+    }
+}
+
+fn coerce_object(
+    obj: &mut serde_json::Map<String, Value>,
+    prefix: &str,
+    conversions: &HashMap<String, Conversion>,
+) -> Result<(), CoercionError> {
+    for (key, value) in obj.iter_mut() {
+        let path = join_path(prefix, key);
+        if let Some(conversion) = conversions.get(&path) {
+            *value = coerce_value(conversion, value, &path)?;
+        } else if let Value::Object(nested) = value {
+            coerce_object(nested, &path, conversions)?;
+        }
+    }
+    Ok(())
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+fn coerce_value(conversion: &Conversion, value: &Value, path: &str) -> Result<Value, CoercionError> {
+    match conversion {
+        Conversion::Bytes => Ok(value.clone()),
+        Conversion::Integer => match value {
+            Value::Number(_) => Ok(value.clone()),
+            Value::String(s) => s
+                .trim()
+                .parse::<i64>()
+                .map(|n| Value::Number(n.into()))
+                .map_err(|_| CoercionError {
+                    path: path.to_string(),
+                    message: format!("'{s}' is not a valid integer"),
+                }),
+            other => Err(CoercionError {
+                path: path.to_string(),
+                message: format!("cannot coerce {} to integer", type_name(other)),
+            }),
+        },
+        Conversion::Float => match value {
+            Value::Number(n) => Ok(Value::Number(n.clone())),
+            Value::String(s) => s
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| CoercionError {
+                    path: path.to_string(),
+                    message: format!("'{s}' is not a valid float"),
+                }),
+            other => Err(CoercionError {
+                path: path.to_string(),
+                message: format!("cannot coerce {} to float", type_name(other)),
+            }),
+        },
+        Conversion::Boolean => match value {
+            Value::Bool(_) => Ok(value.clone()),
+            Value::String(s) => match s.to_ascii_lowercase().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(CoercionError {
+                    path: path.to_string(),
+                    message: format!("'{s}' is not a valid boolean"),
+                }),
+            },
+            other => Err(CoercionError {
+                path: path.to_string(),
+                message: format!("cannot coerce {} to boolean", type_name(other)),
+            }),
+        },
+        Conversion::Timestamp => parse_timestamp_auto(value, path),
+        Conversion::TimestampFmt(format) => parse_timestamp_fmt(value, format, path),
+    }
+}
+
+/// Auto-detect: a number, or a string holding an integer, is taken as a
+/// millisecond epoch and passed through unchanged (a seconds epoch is not
+/// scaled); an RFC3339 string is converted to a millisecond epoch integer.
+fn parse_timestamp_auto(value: &Value, path: &str) -> Result<Value, CoercionError> {
+    match value {
+        Value::Number(_) => Ok(value.clone()),
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if let Ok(epoch) = trimmed.parse::<i64>() {
+                return Ok(Value::Number(epoch.into()));
+            }
+            chrono::DateTime::parse_from_rfc3339(trimmed)
+                .map(|dt| Value::Number(dt.timestamp_millis().into()))
+                .map_err(|_| CoercionError {
+                    path: path.to_string(),
+                    message: format!("'{s}' is not a valid RFC3339 timestamp or epoch"),
+                })
+        }
+        other => Err(CoercionError {
+            path: path.to_string(),
+            message: format!("cannot coerce {} to timestamp", type_name(other)),
+        }),
+    }
+}
+
+fn parse_timestamp_fmt(value: &Value, format: &str, path: &str) -> Result<Value, CoercionError> {
+    let Value::String(s) = value else {
+        return Err(CoercionError {
+            path: path.to_string(),
+            message: format!("cannot coerce {} to timestamp", type_name(value)),
+        });
+    };
+    chrono::NaiveDateTime::parse_from_str(s.trim(), format)
+        .or_else(|_| {
+            // A date-only format carries no time fields; take it as midnight UTC.
+            chrono::NaiveDate::parse_from_str(s.trim(), format)
+                .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+        })
+        .map(|dt| Value::Number(dt.and_utc().timestamp_millis().into()))
+        .map_err(|_| CoercionError {
+            path: path.to_string(),
+            message: format!("'{s}' does not match format '{format}'"),
+        })
+}
+
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(conversions: &[(&str, Conversion)]) -> FieldSchema {
+        FieldSchema::new(
+            conversions
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_string_coerced_to_integer() {
+        let schema = schema(&[("age", Conversion::Integer)]);
+        let mut data = json!({ "age": "42" }).as_object().unwrap().clone();
+        schema.coerce(&mut data).unwrap();
+        assert_eq!(data.get("age"), Some(&json!(42)));
+    }
+
+    #[test]
+    fn test_numeric_already_integer_passes_through() {
+        let schema = schema(&[("age", Conversion::Integer)]);
+        let mut data = json!({ "age": 42 }).as_object().unwrap().clone();
+        schema.coerce(&mut data).unwrap();
+        assert_eq!(data.get("age"), Some(&json!(42)));
+    }
+
+    #[test]
+    fn test_invalid_integer_rejected() {
+        let schema = schema(&[("age", Conversion::Integer)]);
+        let mut data = json!({ "age": "not-a-number" }).as_object().unwrap().clone();
+        let err = schema.coerce(&mut data).unwrap_err();
+        assert_eq!(err.path, "age");
+    }
+
+    #[test]
+    fn test_boolean_coercion() {
+        let schema = schema(&[("active", Conversion::Boolean)]);
+        let mut data = json!({ "active": "TRUE" }).as_object().unwrap().clone();
+        schema.coerce(&mut data).unwrap();
+        assert_eq!(data.get("active"), Some(&json!(true)));
+    }
+
+    #[test]
+    fn test_timestamp_auto_detects_epoch_and_rfc3339() {
+        let schema = schema(&[("created", Conversion::Timestamp)]);
+
+        let mut epoch_data = json!({ "created": "1700000000000" }).as_object().unwrap().clone();
+        schema.coerce(&mut epoch_data).unwrap();
+        assert_eq!(epoch_data.get("created"), Some(&json!(1700000000000i64)));
+
+        let mut rfc_data = json!({ "created": "2023-11-14T22:13:20Z" })
+            .as_object()
+            .unwrap()
+            .clone();
+        schema.coerce(&mut rfc_data).unwrap();
+        assert_eq!(rfc_data.get("created"), Some(&json!(1700000000000i64)));
+    }
+
+    #[test]
+    fn test_timestamp_fmt_uses_explicit_format() {
+        let schema = schema(&[(
+            "created",
+            Conversion::TimestampFmt("%Y-%m-%d".to_string()),
+        )]);
+        let mut data = json!({ "created": "2023-11-14" }).as_object().unwrap().clone();
+        schema.coerce(&mut data).unwrap();
+        assert_eq!(data.get("created"), Some(&json!(1699920000000i64)));
+    }
+
+    #[test]
+    fn test_nested_field_path() {
+        let schema = schema(&[("address.zip", Conversion::Integer)]);
+        let mut data = json!({ "address": { "zip": "10001" } })
+            .as_object()
+            .unwrap()
+            .clone();
+        schema.coerce(&mut data).unwrap();
+        assert_eq!(
+            data.get("address").unwrap().get("zip"),
+            Some(&json!(10001))
+        );
+    }
+}
@@ -3,6 +3,8 @@ use crate::logging::{HealthStatus, PerformanceMetrics};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 
 /// Health check interface
 pub trait HealthCheck {
@@ -21,6 +23,8 @@ pub struct HealthCheckResult {
     pub message: String,
     pub duration: Duration,
     pub metrics: Option<PerformanceMetrics>,
+    /// Allocator/process memory figures, set by `MemoryHealthCheck`.
+    pub memory: Option<MemoryStats>,
 }
 
 impl HealthCheckResult {
@@ -31,6 +35,7 @@ impl HealthCheckResult {
             message: message.to_string(),
             duration,
             metrics: None,
+            memory: None,
         }
     }
 
@@ -44,10 +49,21 @@ impl HealthCheckResult {
             message: message.to_string(),
             duration,
             metrics: None,
+            memory: None,
         }
     }
 }
 
+/// Memory figures measured by `MemoryHealthCheck`: allocator-reported
+/// allocated/resident bytes when the `jemalloc` feature is enabled, or a
+/// whole-system approximation otherwise. Also surfaced through
+/// `HealthSummary`/`to_json` so operators get a genuine memory signal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStats {
+    pub allocated_bytes: u64,
+    pub resident_bytes: Option<u64>,
+}
+
 /// Database health check
 pub struct DatabaseHealthCheck;
 
@@ -128,6 +144,34 @@ impl MemoryHealthCheck {
     }
 }
 
+/// Read live allocator statistics. With the `jemalloc` feature enabled this
+/// advances jemalloc's stats epoch and reads `stats.allocated`/`stats.resident`
+/// directly; otherwise it falls back to whole-system memory usage via
+/// `sysinfo`, since there's no portable way to get this process's own heap
+/// size without an allocator that exposes one.
+#[cfg(feature = "jemalloc")]
+fn read_memory_stats() -> MemoryStats {
+    let _ = jemalloc_ctl::epoch::advance();
+    let allocated = jemalloc_ctl::stats::allocated::read().unwrap_or(0) as u64;
+    let resident = jemalloc_ctl::stats::resident::read().ok().map(|v| v as u64);
+    MemoryStats {
+        allocated_bytes: allocated,
+        resident_bytes: resident,
+    }
+}
+
+#[cfg(not(feature = "jemalloc"))]
+fn read_memory_stats() -> MemoryStats {
+    use sysinfo::System;
+
+    let mut system = System::new();
+    system.refresh_memory();
+    MemoryStats {
+        allocated_bytes: system.used_memory(),
+        resident_bytes: None,
+    }
+}
+
 impl HealthCheck for MemoryHealthCheck {
     fn name(&self) -> &str {
         "memory"
@@ -136,15 +180,32 @@ impl HealthCheck for MemoryHealthCheck {
     fn check(&self) -> Result<HealthCheckResult> {
         let start = Instant::now();
 
-        // Simple memory check (in a real implementation, you'd use system APIs)
-        // For now, just assume we're healthy
+        let stats = read_memory_stats();
+        let used_mb = stats.allocated_bytes as f64 / (1024.0 * 1024.0);
+        let exceeded = used_mb > self.threshold_mb as f64;
+
+        let message = match stats.resident_bytes {
+            Some(resident) => format!(
+                "{:.1}MB allocated, {:.1}MB resident (threshold {}MB)",
+                used_mb,
+                resident as f64 / (1024.0 * 1024.0),
+                self.threshold_mb
+            ),
+            None => format!(
+                "{:.1}MB in use (threshold {}MB)",
+                used_mb, self.threshold_mb
+            ),
+        };
+
         let duration = start.elapsed();
+        let mut result = if exceeded {
+            HealthCheckResult::unhealthy("memory", &message, duration)
+        } else {
+            HealthCheckResult::healthy("memory", &message, duration)
+        };
+        result.memory = Some(stats);
 
-        Ok(HealthCheckResult::healthy(
-            "memory",
-            &format!("Memory usage is below {}MB threshold", self.threshold_mb),
-            duration,
-        ))
+        Ok(result)
     }
 }
 
@@ -256,6 +317,8 @@ impl HealthMonitor {
             })
             .count();
 
+        let memory = results.iter().find(|r| r.name == "memory").and_then(|r| r.memory);
+
         HealthSummary {
             total_checks,
             healthy_checks,
@@ -263,8 +326,114 @@ impl HealthMonitor {
             healthy_critical,
             overall_healthy: self.is_healthy(),
             last_check: *self.last_check.lock().unwrap(),
+            memory,
         }
     }
+
+    /// Run `check_interval` health checks forever in a background task,
+    /// publishing each new `HealthSummary` over a `watch` channel so callers
+    /// can subscribe instead of polling. A log event fires only when overall
+    /// status or an individual check transitions between healthy and
+    /// unhealthy (edge-triggered), not on every tick. Returns a `HealthHandle`
+    /// for subscribing, forcing an out-of-band re-check, and shutting the
+    /// task down.
+    pub fn spawn(self) -> HealthHandle {
+        let monitor = Arc::new(self);
+        let initial_summary = monitor.get_health_summary();
+        let (summary_tx, summary_rx) = watch::channel(initial_summary);
+        let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let (recheck_tx, mut recheck_rx) = mpsc::channel::<()>(8);
+
+        let task_monitor = Arc::clone(&monitor);
+        let task = tokio::spawn(async move {
+            let mut previous_overall: Option<bool> = None;
+            let mut previous_checks: HashMap<String, bool> = HashMap::new();
+            let mut interval = tokio::time::interval(task_monitor.check_interval);
+
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = recheck_rx.recv() => {}
+                    _ = shutdown_rx.recv() => break,
+                }
+
+                let results = match task_monitor.run_checks().await {
+                    Ok(results) => results,
+                    Err(e) => {
+                        log::error!("health monitor: check run failed: {e}");
+                        continue;
+                    }
+                };
+
+                for result in &results {
+                    let healthy = result.status.is_healthy();
+                    if previous_checks.get(&result.name) != Some(&healthy) {
+                        if healthy {
+                            log::info!("health check '{}' recovered: {}", result.name, result.message);
+                        } else {
+                            log::warn!(
+                                "health check '{}' became unhealthy: {}",
+                                result.name,
+                                result.message
+                            );
+                        }
+                        previous_checks.insert(result.name.clone(), healthy);
+                    }
+                }
+
+                let summary = task_monitor.get_health_summary();
+                if previous_overall != Some(summary.overall_healthy) {
+                    if summary.overall_healthy {
+                        log::info!("health monitor: overall status recovered to healthy");
+                    } else {
+                        log::warn!("health monitor: overall status became unhealthy");
+                    }
+                    previous_overall = Some(summary.overall_healthy);
+                }
+
+                // A closed receiver just means nobody's subscribed yet.
+                let _ = summary_tx.send(summary);
+            }
+        });
+
+        HealthHandle {
+            summary_rx,
+            shutdown_tx,
+            recheck_tx,
+            task,
+        }
+    }
+}
+
+/// Handle to a `HealthMonitor` running as a background task via `spawn`.
+pub struct HealthHandle {
+    summary_rx: watch::Receiver<HealthSummary>,
+    shutdown_tx: mpsc::Sender<()>,
+    recheck_tx: mpsc::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl HealthHandle {
+    /// Subscribe to every `HealthSummary` published after each check run.
+    pub fn subscribe(&self) -> watch::Receiver<HealthSummary> {
+        self.summary_rx.clone()
+    }
+
+    /// Get the most recently published summary without waiting for a new one.
+    pub fn latest(&self) -> HealthSummary {
+        self.summary_rx.borrow().clone()
+    }
+
+    /// Trigger an out-of-band check run instead of waiting for the next tick.
+    pub async fn force_check(&self) {
+        let _ = self.recheck_tx.send(()).await;
+    }
+
+    /// Stop the background task and wait for it to finish.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown_tx.send(()).await;
+        let _ = self.task.await;
+    }
 }
 
 impl Default for HealthMonitor {
@@ -282,10 +451,26 @@ pub struct HealthSummary {
     pub healthy_critical: usize,
     pub overall_healthy: bool,
     pub last_check: Instant,
+    /// Figures from the `memory` check, when one ran.
+    pub memory: Option<MemoryStats>,
 }
 
 impl HealthSummary {
     pub fn to_json(&self) -> String {
+        let memory_fields = match self.memory {
+            Some(stats) => format!(
+                r#",
+  "memory_allocated_bytes": {},
+  "memory_resident_bytes": {}"#,
+                stats.allocated_bytes,
+                stats
+                    .resident_bytes
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "null".to_string())
+            ),
+            None => String::new(),
+        };
+
         format!(
             r#"{{
   "overall_healthy": {},
@@ -294,7 +479,7 @@ impl HealthSummary {
   "critical_checks": {},
   "healthy_critical": {},
   "last_check": "{}",
-  "uptime": "{}"
+  "uptime": "{}"{}
 }}"#,
             self.overall_healthy,
             self.total_checks,
@@ -302,7 +487,8 @@ impl HealthSummary {
             self.critical_checks,
             self.healthy_critical,
             self.last_check.elapsed().as_secs(),
-            self.last_check.elapsed().as_secs()
+            self.last_check.elapsed().as_secs(),
+            memory_fields
         )
     }
 }
@@ -372,11 +558,61 @@ mod tests {
             healthy_critical: 1,
             overall_healthy: false,
             last_check: Instant::now(),
+            memory: None,
         };
 
         let json = summary.to_json();
         assert!(json.contains("overall_healthy"));
         assert!(json.contains("total_checks"));
         assert!(json.contains("false"));
+        assert!(!json.contains("memory_allocated_bytes"));
+    }
+
+    #[test]
+    fn test_memory_health_check_reports_usage() {
+        let check = MemoryHealthCheck::new(1024 * 1024); // 1TB threshold, won't trip
+        let result = check.check().unwrap();
+        assert!(result.status.is_healthy());
+        let stats = result.memory.expect("memory check should populate stats");
+        assert!(stats.allocated_bytes > 0);
+    }
+
+    #[test]
+    fn test_memory_health_check_flags_exceeded_threshold() {
+        let check = MemoryHealthCheck::new(0); // any usage exceeds a 0MB threshold
+        let result = check.check().unwrap();
+        assert!(!result.status.is_healthy());
+        assert!(result.memory.is_some());
+    }
+
+    #[test]
+    fn test_health_summary_includes_memory_when_present() {
+        let monitor = HealthMonitor::new().add_check(Box::new(MemoryHealthCheck::new(1024 * 1024)));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(monitor.run_checks()).unwrap();
+
+        let summary = monitor.get_health_summary();
+        assert!(summary.memory.is_some());
+        assert!(summary.to_json().contains("memory_allocated_bytes"));
+    }
+
+    #[test]
+    fn test_health_monitor_spawn_publishes_and_shuts_down() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let monitor = HealthMonitor::new()
+                .add_check(Box::new(DatabaseHealthCheck::new()))
+                .with_interval(Duration::from_secs(60));
+
+            let handle = monitor.spawn();
+            let mut rx = handle.subscribe();
+
+            handle.force_check().await;
+            rx.changed().await.unwrap();
+            assert!(rx.borrow().overall_healthy);
+
+            handle.shutdown().await;
+        });
     }
 }
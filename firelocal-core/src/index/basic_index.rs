@@ -2,12 +2,19 @@ use crate::index::{IndexProvider, QueryAst, QueryOperator};
 use crate::model::Document;
 use anyhow::Result;
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
 use std::sync::{Arc, RwLock};
 
+/// Per-field value index: an order-preserving encoding of `Value`
+/// (`value_to_sortable`) to the set of documents holding that value, so a
+/// `BTreeMap::range` scan can serve inequality/range queries directly instead
+/// of only exact-match lookups.
+type FieldIndex = BTreeMap<Vec<u8>, HashSet<String>>;
+
 // Map: CollectionGroup -> FieldName -> Value -> Set<DocPath>
 // Very naive storage for M2
-type InvertedIndex = HashMap<String, HashMap<String, HashMap<String, HashSet<String>>>>;
+type InvertedIndex = HashMap<String, HashMap<String, FieldIndex>>;
 
 pub struct BasicIndexProvider {
     index: Arc<RwLock<InvertedIndex>>,
@@ -20,13 +27,69 @@ impl BasicIndexProvider {
         }
     }
 
-    fn value_to_key(v: &Value) -> String {
+    /// Encode a `Value` so that unsigned byte-wise comparison of the result
+    /// matches the value's natural ordering. A leading tag byte orders types
+    /// (`Null` = 0, `Bool` = 1, `Number` = 2, `String` = 3, anything else = 4)
+    /// before the payload, so cross-type comparisons fall out of the tag
+    /// byte rather than the payload bytes.
+    ///
+    /// Numbers are encoded as big-endian IEEE-754 bits with the sign bit set
+    /// for non-negative values (so they sort above all negatives) and every
+    /// bit flipped for negative values (so more-negative numbers, which have
+    /// a *larger* raw bit pattern, sort before less-negative ones). Strings
+    /// are encoded as their raw UTF-8 bytes, which already sorts correctly
+    /// byte-wise for valid UTF-8.
+    fn value_to_sortable(v: &Value) -> Vec<u8> {
         match v {
-            Value::String(s) => s.clone(),
-            Value::Number(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            Value::Null => "null".to_string(),
-            _ => v.to_string(), // Arrays/Objects serialized not ideal but works for unique key
+            Value::Null => vec![0],
+            Value::Bool(b) => vec![1, *b as u8],
+            Value::Number(n) => {
+                let f = n.as_f64().unwrap_or(0.0);
+                let bits = f.to_bits();
+                let encoded = if f.is_sign_negative() {
+                    !bits
+                } else {
+                    bits | (1u64 << 63)
+                };
+                let mut out = Vec::with_capacity(9);
+                out.push(2);
+                out.extend_from_slice(&encoded.to_be_bytes());
+                out
+            }
+            Value::String(s) => {
+                let mut out = Vec::with_capacity(1 + s.len());
+                out.push(3);
+                out.extend_from_slice(s.as_bytes());
+                out
+            }
+            // Arrays/objects aren't range-queryable; fall back to a stable
+            // but otherwise arbitrary encoding so equality lookups still work.
+            _ => {
+                let mut out = vec![4];
+                out.extend_from_slice(v.to_string().as_bytes());
+                out
+            }
+        }
+    }
+
+    /// Recursively walk `value`, collecting `(dotted_path, value)` for every
+    /// node reachable from `prefix` (objects by key, arrays by index) so a
+    /// nested field like `address.city` gets indexed the same way a
+    /// top-level field does, alongside the field itself.
+    fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+        out.push((prefix.to_string(), value.clone()));
+        match value {
+            Value::Object(map) => {
+                for (k, v) in map {
+                    Self::flatten(&format!("{prefix}.{k}"), v, out);
+                }
+            }
+            Value::Array(arr) => {
+                for (i, v) in arr.iter().enumerate() {
+                    Self::flatten(&format!("{prefix}.{i}"), v, out);
+                }
+            }
+            _ => {}
         }
     }
 
@@ -36,7 +99,7 @@ impl BasicIndexProvider {
         let empty_map = HashMap::new();
         let col_index = index.get(collection).unwrap_or(&empty_map);
 
-        let val_key = Self::value_to_key(value);
+        let val_key = Self::value_to_sortable(value);
         let field_index = col_index.get(field);
 
         let docs = match field_index {
@@ -47,6 +110,108 @@ impl BasicIndexProvider {
         let result: Vec<String> = docs.into_iter().collect();
         Ok(result)
     }
+
+    /// Union every document whose value for `field` falls within
+    /// `(start_bound, end_bound)`, e.g. a `[start, end)` window, by scanning
+    /// the field's `BTreeMap` instead of comparing each entry individually.
+    pub fn query_range(
+        &self,
+        collection: &str,
+        field: &str,
+        start_bound: Bound<Vec<u8>>,
+        end_bound: Bound<Vec<u8>>,
+    ) -> Result<Vec<String>> {
+        let index = self.index.read().unwrap();
+
+        let empty_map = HashMap::new();
+        let col_index = index.get(collection).unwrap_or(&empty_map);
+
+        let mut result = HashSet::new();
+        if let Some(f_map) = col_index.get(field) {
+            for docs in f_map.range((start_bound, end_bound)).map(|(_, docs)| docs) {
+                result.extend(docs.iter().cloned());
+            }
+        }
+
+        Ok(result.into_iter().collect())
+    }
+
+    /// Resolve a single predicate to its matching doc-path set, for use as a
+    /// building block by both `query` and `query_and`.
+    fn candidates_for(&self, collection: &str, field: &str, op: &QueryOperator) -> Result<HashSet<String>> {
+        let docs = match op {
+            QueryOperator::Equal(value) => self.query_equal(collection, field, value)?,
+            QueryOperator::LessThan(value) => self.query_range(
+                collection,
+                field,
+                Bound::Unbounded,
+                Bound::Excluded(Self::value_to_sortable(value)),
+            )?,
+            QueryOperator::LessThanOrEqual(value) => self.query_range(
+                collection,
+                field,
+                Bound::Unbounded,
+                Bound::Included(Self::value_to_sortable(value)),
+            )?,
+            QueryOperator::GreaterThan(value) => self.query_range(
+                collection,
+                field,
+                Bound::Excluded(Self::value_to_sortable(value)),
+                Bound::Unbounded,
+            )?,
+            QueryOperator::GreaterThanOrEqual(value) => self.query_range(
+                collection,
+                field,
+                Bound::Included(Self::value_to_sortable(value)),
+                Bound::Unbounded,
+            )?,
+            QueryOperator::Between { lo, hi } => self.query_range(
+                collection,
+                field,
+                Bound::Included(Self::value_to_sortable(lo)),
+                Bound::Included(Self::value_to_sortable(hi)),
+            )?,
+            other => {
+                return Err(anyhow::anyhow!(
+                    "query_and: unsupported predicate operator {other:?} on field '{field}'"
+                ))
+            }
+        };
+        Ok(docs.into_iter().collect())
+    }
+
+    /// Evaluate `predicates` as an AND of equality/range filters (e.g.
+    /// `status == "active" && age > 18`) and return the intersection of
+    /// matching doc paths.
+    ///
+    /// Each predicate is looked up independently into its own candidate
+    /// `HashSet`, then the sets are intersected smallest-first: since
+    /// intersecting can only shrink the running result, starting from the
+    /// most selective (smallest) candidate set minimizes the number of
+    /// membership checks done against the larger sets.
+    pub fn query_and(&self, collection: &str, predicates: &[(&str, QueryOperator)]) -> Result<Vec<String>> {
+        if predicates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut candidate_sets = Vec::with_capacity(predicates.len());
+        for (field, op) in predicates {
+            candidate_sets.push(self.candidates_for(collection, field, op)?);
+        }
+        candidate_sets.sort_by_key(|set| set.len());
+
+        let mut sets = candidate_sets.into_iter();
+        let mut result = sets.next().unwrap_or_default();
+
+        for set in sets {
+            if result.is_empty() {
+                break;
+            }
+            result.retain(|doc| set.contains(doc));
+        }
+
+        Ok(result.into_iter().collect())
+    }
 }
 
 impl IndexProvider for BasicIndexProvider {
@@ -63,19 +228,24 @@ impl IndexProvider for BasicIndexProvider {
             "default"
         };
 
-        // For each field in doc, update index
-        // Naive: doesn't handle nested fields well nicely without flattening
+        // For each field in doc, update index, including nested paths
+        // (address.city, tags.0, ...) reachable from it.
         for (field, value) in &doc.fields {
-            let val_key = Self::value_to_key(value);
-
-            index
-                .entry(collection.to_string())
-                .or_default()
-                .entry(field.clone())
-                .or_default()
-                .entry(val_key)
-                .or_default()
-                .insert(doc_path.to_string());
+            let mut flattened = Vec::new();
+            Self::flatten(field, value, &mut flattened);
+
+            for (path, nested_value) in flattened {
+                let val_key = Self::value_to_sortable(&nested_value);
+
+                index
+                    .entry(collection.to_string())
+                    .or_default()
+                    .entry(path)
+                    .or_default()
+                    .entry(val_key)
+                    .or_default()
+                    .insert(doc_path.to_string());
+            }
         }
         Ok(())
     }
@@ -100,13 +270,18 @@ impl IndexProvider for BasicIndexProvider {
     }
 
     fn query(&self, query_ast: &QueryAst) -> Result<Vec<String>> {
-        // For now, only support Equal operator
+        let collection = query_ast.collection.as_deref().unwrap_or("default");
+
         match &query_ast.operator {
-            QueryOperator::Equal(value) => {
-                // Extract collection from query AST
-                let collection = query_ast.collection.as_deref().unwrap_or("default");
-                self.query_equal(collection, &query_ast.field, value)
-            }
+            QueryOperator::Equal(_)
+            | QueryOperator::LessThan(_)
+            | QueryOperator::LessThanOrEqual(_)
+            | QueryOperator::GreaterThan(_)
+            | QueryOperator::GreaterThanOrEqual(_)
+            | QueryOperator::Between { .. } => Ok(self
+                .candidates_for(collection, &query_ast.field, &query_ast.operator)?
+                .into_iter()
+                .collect()),
             _ => {
                 // Other operators not yet implemented
                 Ok(Vec::new())
@@ -1,43 +1,119 @@
-use crate::model::Document;
+use crate::model::{resolve_path, Document};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
 
-/// Composite index for multi-field queries
+/// A single component of a `CompositeKey`.
+///
+/// Wraps a JSON `Value` with a total ordering (null < bool < number < string,
+/// numeric/lexicographic within a type) plus a `Max` sentinel that sorts
+/// above every real value. `Max` is never stored in an actual key — it only
+/// ever appears in a synthetic upper bound used to scan a range.
+#[derive(Debug, Clone, PartialEq)]
+enum KeyComponent {
+    Value(Value),
+    Max,
+}
+
+impl KeyComponent {
+    fn rank(&self) -> u8 {
+        match self {
+            KeyComponent::Value(Value::Null) => 0,
+            KeyComponent::Value(Value::Bool(_)) => 1,
+            KeyComponent::Value(Value::Number(_)) => 2,
+            KeyComponent::Value(Value::String(_)) => 3,
+            KeyComponent::Value(_) => 4, // arrays/objects: arbitrary but stable
+            KeyComponent::Max => 5,
+        }
+    }
+}
+
+impl Eq for KeyComponent {}
+
+impl PartialOrd for KeyComponent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KeyComponent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (r1, r2) = (self.rank(), other.rank());
+        if r1 != r2 {
+            return r1.cmp(&r2);
+        }
+        match (self, other) {
+            (KeyComponent::Value(Value::Bool(a)), KeyComponent::Value(Value::Bool(b))) => {
+                a.cmp(b)
+            }
+            (KeyComponent::Value(Value::Number(a)), KeyComponent::Value(Value::Number(b))) => a
+                .as_f64()
+                .unwrap_or(0.0)
+                .partial_cmp(&b.as_f64().unwrap_or(0.0))
+                .unwrap_or(Ordering::Equal),
+            (KeyComponent::Value(Value::String(a)), KeyComponent::Value(Value::String(b))) => {
+                a.cmp(b)
+            }
+            _ => Ordering::Equal,
+        }
+    }
+}
+
+/// Ordered composite key: a tuple of field values with a deterministic total
+/// ordering, so it can be used directly as a `BTreeMap` key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct CompositeKey(Vec<KeyComponent>);
+
+impl CompositeKey {
+    fn from_values(values: &[Value]) -> Self {
+        CompositeKey(values.iter().cloned().map(KeyComponent::Value).collect())
+    }
+
+    /// A key one past the last possible key sharing `values` as a leading
+    /// prefix, for use as an exclusive upper bound in a range scan.
+    fn prefix_upper_bound(values: &[Value]) -> Self {
+        let mut components: Vec<KeyComponent> =
+            values.iter().cloned().map(KeyComponent::Value).collect();
+        components.push(KeyComponent::Max);
+        CompositeKey(components)
+    }
+}
+
+/// Composite index for multi-field queries, backed by a `BTreeMap` so range
+/// and prefix queries can use `BTreeMap::range` instead of a linear scan.
 pub struct CompositeIndex {
     fields: Vec<String>,
-    entries: HashMap<Vec<Value>, HashSet<String>>,
+    entries: BTreeMap<CompositeKey, HashSet<String>>,
 }
 
 impl CompositeIndex {
     pub fn new(fields: Vec<String>) -> Self {
         Self {
             fields,
-            entries: HashMap::new(),
+            entries: BTreeMap::new(),
         }
     }
 
-    /// Add a document to the composite index
-    pub fn add(&mut self, doc: &Document) {
-        let key: Vec<Value> = self
+    /// Composite fields may be dotted paths (`address.city`) as well as plain
+    /// top-level names; `resolve_path` handles both uniformly.
+    fn key_for(&self, doc: &Document) -> CompositeKey {
+        let values: Vec<Value> = self
             .fields
             .iter()
-            .map(|f| doc.fields.get(f).cloned().unwrap_or(Value::Null))
+            .map(|f| resolve_path(&doc.fields, f))
             .collect();
+        CompositeKey::from_values(&values)
+    }
 
-        self.entries
-            .entry(key)
-            .or_insert_with(HashSet::new)
-            .insert(doc.path.clone());
+    /// Add a document to the composite index
+    pub fn add(&mut self, doc: &Document) {
+        let key = self.key_for(doc);
+        self.entries.entry(key).or_default().insert(doc.path.clone());
     }
 
     /// Remove a document from the composite index
     pub fn remove(&mut self, doc: &Document) {
-        let key: Vec<Value> = self
-            .fields
-            .iter()
-            .map(|f| doc.fields.get(f).cloned().unwrap_or(Value::Null))
-            .collect();
-
+        let key = self.key_for(doc);
         if let Some(paths) = self.entries.get_mut(&key) {
             paths.remove(&doc.path);
             if paths.is_empty() {
@@ -46,90 +122,80 @@ impl CompositeIndex {
         }
     }
 
-    /// Query documents using the composite index
-    pub fn query(&self, conditions: &[(String, Value)]) -> Vec<String> {
-        // Build query key from conditions
-        let mut query_key = Vec::new();
+    /// Find the leading subset of `self.fields` covered by `conditions`, in
+    /// field order. Returns `None` if a field is skipped (a field without a
+    /// condition precedes a field that has one) — true partial (non-prefix)
+    /// matches aren't supported, since a `BTreeMap` can't be ranged on a gap.
+    fn leading_prefix(&self, conditions: &[(String, Value)]) -> Option<Vec<Value>> {
+        let mut values = Vec::new();
+        let mut finished = false;
+
         for field in &self.fields {
-            if let Some((_, value)) = conditions.iter().find(|(f, _)| f == field) {
-                query_key.push(value.clone());
-            } else {
-                // Partial match not supported yet
-                return Vec::new();
+            match conditions.iter().find(|(f, _)| f == field) {
+                Some((_, value)) if !finished => values.push(value.clone()),
+                Some(_) => return None, // condition after a gap: not a prefix
+                None => finished = true,
             }
         }
 
-        // Exact match lookup
-        self.entries
-            .get(&query_key)
-            .map(|paths| paths.iter().cloned().collect())
-            .unwrap_or_default()
+        Some(values)
     }
 
-    /// Range query support (limited implementation with HashMap)
-    /// Note: This is less efficient than BTreeMap's range query
-    /// For production use, consider using a sorted data structure or specialized index
-    pub fn range_query(&self, start: &[Value], end: &[Value]) -> Vec<String> {
-        let mut results = HashSet::new();
+    /// Query documents using the composite index. Supports an exact match
+    /// across all fields, or a partial match across a leading subset of
+    /// fields (delegated to `prefix_query`).
+    pub fn query(&self, conditions: &[(String, Value)]) -> Vec<String> {
+        let Some(values) = self.leading_prefix(conditions) else {
+            return Vec::new();
+        };
 
-        // Since HashMap doesn't support range queries, we need to iterate all entries
-        // and check if each key falls within the range
-        for (key, paths) in &self.entries {
-            if key.len() == start.len() && key.len() == end.len() {
-                // Check if key is within range (lexicographically)
-                // This is a simplified comparison - in production you'd want more robust comparison
-                if is_in_range(key, start, end) {
-                    results.extend(paths.iter().cloned());
-                }
-            }
+        if values.len() == self.fields.len() {
+            let key = CompositeKey::from_values(&values);
+            return self
+                .entries
+                .get(&key)
+                .map(|paths| paths.iter().cloned().collect())
+                .unwrap_or_default();
         }
 
-        results.into_iter().collect()
+        self.prefix_query_values(&values)
     }
-}
 
-/// Helper function to check if a key is within a range
-/// This is a simplified implementation for demonstration
-fn is_in_range(key: &[Value], start: &[Value], end: &[Value]) -> bool {
-    if key.len() != start.len() || key.len() != end.len() {
-        return false;
+    /// Given a leading subset of the indexed fields bound to values, return
+    /// every doc whose key starts with those values, by ranging between the
+    /// prefix's lower bound and its successor.
+    pub fn prefix_query(&self, conditions: &[(String, Value)]) -> Vec<String> {
+        match self.leading_prefix(conditions) {
+            Some(values) if !values.is_empty() => self.prefix_query_values(&values),
+            _ => Vec::new(),
+        }
     }
 
-    // Compare each element
-    for i in 0..key.len() {
-        if !value_gte(&key[i], &start[i]) || !value_lte(&key[i], &end[i]) {
-            return false;
+    fn prefix_query_values(&self, values: &[Value]) -> Vec<String> {
+        if values.is_empty() {
+            return Vec::new();
         }
-    }
 
-    true
-}
+        let lower = CompositeKey::from_values(values);
+        let upper = CompositeKey::prefix_upper_bound(values);
 
-/// Compare if a >= b for JSON values
-fn value_gte(a: &Value, b: &Value) -> bool {
-    match (a, b) {
-        (Value::Number(n1), Value::Number(n2)) => {
-            let f1 = n1.as_f64().unwrap_or(0.0);
-            let f2 = n2.as_f64().unwrap_or(0.0);
-            f1 >= f2
+        let mut results = HashSet::new();
+        for (_, paths) in self.entries.range(lower..upper) {
+            results.extend(paths.iter().cloned());
         }
-        (Value::String(s1), Value::String(s2)) => s1 >= s2,
-        (Value::Bool(b1), Value::Bool(b2)) => b1 >= b2,
-        _ => false,
+        results.into_iter().collect()
     }
-}
 
-/// Compare if a <= b for JSON values
-fn value_lte(a: &Value, b: &Value) -> bool {
-    match (a, b) {
-        (Value::Number(n1), Value::Number(n2)) => {
-            let f1 = n1.as_f64().unwrap_or(0.0);
-            let f2 = n2.as_f64().unwrap_or(0.0);
-            f1 <= f2
+    /// Range query over the full key tuple, inclusive on both ends.
+    pub fn range_query(&self, start: &[Value], end: &[Value]) -> Vec<String> {
+        let start_key = CompositeKey::from_values(start);
+        let end_key = CompositeKey::from_values(end);
+
+        let mut results = HashSet::new();
+        for (_, paths) in self.entries.range(start_key..=end_key) {
+            results.extend(paths.iter().cloned());
         }
-        (Value::String(s1), Value::String(s2)) => s1 <= s2,
-        (Value::Bool(b1), Value::Bool(b2)) => b1 <= b2,
-        _ => false,
+        results.into_iter().collect()
     }
 }
 
@@ -138,21 +204,19 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    fn doc(path: &str, age: i64, city: &str) -> Document {
+        Document {
+            path: path.to_string(),
+            fields: serde_json::from_value(json!({ "age": age, "city": city })).unwrap(),
+            version: 0,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_composite_index_add() {
         let mut index = CompositeIndex::new(vec!["age".to_string(), "city".to_string()]);
-
-        let doc = Document {
-            path: "users/alice".to_string(),
-            fields: serde_json::from_value(json!({
-                "age": 30,
-                "city": "NYC"
-            }))
-            .unwrap(),
-            version: 0,
-        };
-
-        index.add(&doc);
+        index.add(&doc("users/alice", 30, "NYC"));
 
         let results = index.query(&[
             ("age".to_string(), json!(30)),
@@ -171,6 +235,7 @@ mod tests {
             path: "users/alice".to_string(),
             fields: serde_json::from_value(json!({"age": 30})).unwrap(),
             version: 0,
+            ..Default::default()
         };
 
         index.add(&doc);
@@ -179,4 +244,77 @@ mod tests {
         let results = index.query(&[("age".to_string(), json!(30))]);
         assert_eq!(results.len(), 0);
     }
+
+    #[test]
+    fn test_partial_prefix_query() {
+        let mut index = CompositeIndex::new(vec!["city".to_string(), "age".to_string()]);
+        index.add(&doc("users/alice", 30, "NYC"));
+        index.add(&doc("users/bob", 40, "NYC"));
+        index.add(&doc("users/carol", 25, "LA"));
+
+        let mut results = index.prefix_query(&[("city".to_string(), json!("NYC"))]);
+        results.sort();
+        assert_eq!(results, vec!["users/alice".to_string(), "users/bob".to_string()]);
+
+        let results = index.prefix_query(&[("city".to_string(), json!("LA"))]);
+        assert_eq!(results, vec!["users/carol".to_string()]);
+
+        // query() also falls through to the prefix path for a leading subset.
+        let mut results = index.query(&[("city".to_string(), json!("NYC"))]);
+        results.sort();
+        assert_eq!(results, vec!["users/alice".to_string(), "users/bob".to_string()]);
+    }
+
+    #[test]
+    fn test_non_leading_condition_is_unsupported() {
+        let mut index = CompositeIndex::new(vec!["city".to_string(), "age".to_string()]);
+        index.add(&doc("users/alice", 30, "NYC"));
+
+        // "age" without "city" skips a leading field, so it's not a prefix.
+        let results = index.query(&[("age".to_string(), json!(30))]);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_range_query_bounded() {
+        let mut index = CompositeIndex::new(vec!["age".to_string()]);
+        for (path, age) in [("users/a", 20), ("users/b", 30), ("users/c", 40)] {
+            index.add(&doc(path, age, "NYC"));
+        }
+
+        let mut results = index.range_query(&[json!(25)], &[json!(35)]);
+        results.sort();
+        assert_eq!(results, vec!["users/b".to_string()]);
+    }
+
+    #[test]
+    fn test_composite_index_indexes_nested_field() {
+        let mut index = CompositeIndex::new(vec!["address.city".to_string()]);
+        let doc = Document {
+            path: "users/alice".to_string(),
+            fields: serde_json::from_value(json!({ "address": { "city": "NYC" } })).unwrap(),
+            version: 0,
+            ..Default::default()
+        };
+        index.add(&doc);
+
+        let results = index.query(&[("address.city".to_string(), json!("NYC"))]);
+        assert_eq!(results, vec!["users/alice".to_string()]);
+    }
+
+    #[test]
+    fn test_range_query_open_ended() {
+        let mut index = CompositeIndex::new(vec!["age".to_string()]);
+        for (path, age) in [("users/a", 20), ("users/b", 30), ("users/c", 40)] {
+            index.add(&doc(path, age, "NYC"));
+        }
+
+        // An effectively open-ended range: everything from 0 up to i64::MAX.
+        let mut results = index.range_query(&[json!(0)], &[json!(i64::MAX)]);
+        results.sort();
+        assert_eq!(
+            results,
+            vec!["users/a".to_string(), "users/b".to_string(), "users/c".to_string()]
+        );
+    }
 }
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+/// Maximum edit distance we allow callers to request; beyond this the NFA
+/// state space grows large for little practical benefit on typo-tolerance.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// A Levenshtein automaton for a fixed query term and maximum edit distance.
+///
+/// States are pairs `(i, e)`: "matched `i` characters of the query term after
+/// spending `e` edits". From `(i, e)` we have:
+/// - a match transition on `term[i]` to `(i+1, e)`,
+/// - a substitution on any character to `(i+1, e+1)`,
+/// - an insertion on any character to `(i, e+1)`,
+/// - an epsilon deletion to `(i+1, e+1)` (no input consumed).
+///
+/// A candidate word is accepted if, after consuming it, some reachable state
+/// has `i == term.len()`. Matching a candidate of length `m` costs
+/// `O(m * term.len() * max_distance)`, since the live state set is bounded by
+/// `term.len() * (max_distance + 1)`.
+pub struct LevenshteinAutomaton {
+    term: Vec<char>,
+    max_distance: u8,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(term: &str, max_distance: u8) -> Self {
+        Self {
+            term: term.chars().collect(),
+            max_distance: max_distance.min(MAX_FUZZY_DISTANCE),
+        }
+    }
+
+    /// Whether `candidate` is within `max_distance` edits of the query term.
+    pub fn is_match(&self, candidate: &str) -> bool {
+        let n = self.term.len();
+        let k = self.max_distance;
+
+        let mut states = Self::epsilon_closure(HashSet::from([(0u32, 0u8)]), n, k);
+
+        for c in candidate.chars() {
+            if states.is_empty() {
+                // Pruned: no reachable state can still end up accepting.
+                return false;
+            }
+
+            let mut next = HashSet::new();
+            for &(i, e) in &states {
+                let i = i as usize;
+                if i < n && self.term[i] == c {
+                    next.insert((i as u32 + 1, e));
+                }
+                if e < k {
+                    if i < n {
+                        next.insert((i as u32 + 1, e + 1)); // substitution
+                    }
+                    next.insert((i as u32, e + 1)); // insertion
+                }
+            }
+            states = Self::epsilon_closure(next, n, k);
+        }
+
+        states.iter().any(|&(i, _)| i as usize == n)
+    }
+
+    /// Expand `states` with epsilon (deletion) transitions until fixpoint.
+    fn epsilon_closure(
+        states: HashSet<(u32, u8)>,
+        n: usize,
+        k: u8,
+    ) -> HashSet<(u32, u8)> {
+        let mut closure = states.clone();
+        let mut stack: Vec<(u32, u8)> = states.into_iter().collect();
+
+        while let Some((i, e)) = stack.pop() {
+            if (i as usize) < n && e < k {
+                let next = (i + 1, e + 1);
+                if closure.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+        closure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_match() {
+        let automaton = LevenshteinAutomaton::new("hello", 1);
+        assert!(automaton.is_match("hello"));
+    }
+
+    #[test]
+    fn test_substitution_within_distance() {
+        let automaton = LevenshteinAutomaton::new("hello", 1);
+        assert!(automaton.is_match("hallo"));
+        assert!(!automaton.is_match("hxllx")); // 2 edits, exceeds distance 1
+    }
+
+    #[test]
+    fn test_insertion_and_deletion() {
+        let automaton = LevenshteinAutomaton::new("cat", 1);
+        assert!(automaton.is_match("cats")); // insertion
+        assert!(automaton.is_match("ca")); // deletion
+        assert!(!automaton.is_match("dogs"));
+    }
+
+    #[test]
+    fn test_max_distance_is_capped() {
+        let automaton = LevenshteinAutomaton::new("a", 10);
+        assert_eq!(automaton.max_distance, MAX_FUZZY_DISTANCE);
+    }
+}
@@ -1,10 +1,46 @@
 pub mod basic_index;
 pub mod composite;
+pub mod fuzzy;
+pub mod prefix_index;
+pub mod search_index;
+pub mod text_index;
 
 use crate::model::Document;
 use anyhow::Result;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Compiled `Regex`es are cached by their effective pattern (case-folding
+/// flag folded in) so a `Regex`/`Matches` operator isn't recompiled on every
+/// document in a scan.
+fn regex_cache() -> &'static RwLock<HashMap<String, Arc<Regex>>> {
+    static CACHE: OnceLock<RwLock<HashMap<String, Arc<Regex>>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn compiled_regex(pattern: &str, case_insensitive: bool) -> Result<Arc<Regex>, String> {
+    let cache_key = if case_insensitive {
+        format!("(?i){pattern}")
+    } else {
+        pattern.to_string()
+    };
+
+    if let Some(re) = regex_cache().read().unwrap().get(&cache_key) {
+        return Ok(re.clone());
+    }
+
+    let re = Arc::new(
+        Regex::new(&cache_key).map_err(|e| format!("invalid regex pattern '{pattern}': {e}"))?,
+    );
+    regex_cache()
+        .write()
+        .unwrap()
+        .insert(cache_key, re.clone());
+    Ok(re)
+}
 
 /// Query operators for advanced filtering
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,12 +63,29 @@ pub enum QueryOperator {
     GreaterThan(Value),
     /// Greater than or equal
     GreaterThanOrEqual(Value),
+    /// Word-level full-text match against a tokenized, indexed string field
+    MatchText(String),
+    /// Typo-tolerant match: accepts tokens within `max_distance` edits of `term`
+    Fuzzy { term: String, max_distance: u8 },
+    /// Prefix / autocomplete match against an indexed string field
+    StartsWith(String),
+    /// Regex match against a string field (case-sensitive)
+    Regex(String),
+    /// Regex match against a string field, with an explicit case-sensitivity flag
+    Matches {
+        pattern: String,
+        case_insensitive: bool,
+    },
+    /// Inclusive range match: `lo <= value <= hi`.
+    Between { lo: Value, hi: Value },
 }
 
 impl QueryOperator {
-    /// Check if a value matches this operator
-    pub fn matches(&self, value: &Value) -> bool {
-        match self {
+    /// Check if a value matches this operator. Returns `Err` only for a
+    /// `Regex`/`Matches` operator whose pattern fails to compile — every
+    /// other operator always succeeds.
+    pub fn matches(&self, value: &Value) -> Result<bool, String> {
+        let result = match self {
             QueryOperator::Equal(v) => value == v,
             QueryOperator::In(values) => values.contains(value),
             QueryOperator::NotIn(values) => !values.contains(value),
@@ -54,12 +107,51 @@ impl QueryOperator {
             QueryOperator::LessThanOrEqual(v) => compare_values(value, v) <= 0,
             QueryOperator::GreaterThan(v) => compare_values(value, v) > 0,
             QueryOperator::GreaterThanOrEqual(v) => compare_values(value, v) >= 0,
-        }
+            QueryOperator::MatchText(query) => {
+                if let Value::String(text) = value {
+                    let doc_tokens: std::collections::HashSet<String> =
+                        crate::index::text_index::tokenize(text, true).into_iter().collect();
+                    let query_tokens = crate::index::text_index::tokenize(query, true);
+                    !query_tokens.is_empty()
+                        && query_tokens.iter().all(|t| doc_tokens.contains(t))
+                } else {
+                    false
+                }
+            }
+            QueryOperator::Fuzzy { term, max_distance } => {
+                if let Value::String(text) = value {
+                    let automaton = crate::index::fuzzy::LevenshteinAutomaton::new(term, *max_distance);
+                    crate::index::text_index::tokenize(text, true)
+                        .iter()
+                        .any(|token| automaton.is_match(token))
+                } else {
+                    false
+                }
+            }
+            QueryOperator::StartsWith(prefix) => {
+                matches!(value, Value::String(s) if s.starts_with(prefix.as_str()))
+            }
+            QueryOperator::Regex(pattern) => {
+                let re = compiled_regex(pattern, false)?;
+                matches!(value, Value::String(s) if re.is_match(s))
+            }
+            QueryOperator::Matches {
+                pattern,
+                case_insensitive,
+            } => {
+                let re = compiled_regex(pattern, *case_insensitive)?;
+                matches!(value, Value::String(s) if re.is_match(s))
+            }
+            QueryOperator::Between { lo, hi } => {
+                compare_values(value, lo) >= 0 && compare_values(value, hi) <= 0
+            }
+        };
+        Ok(result)
     }
 }
 
 /// Compare two JSON values
-fn compare_values(a: &Value, b: &Value) -> i32 {
+pub(crate) fn compare_values(a: &Value, b: &Value) -> i32 {
     match (a, b) {
         (Value::Number(n1), Value::Number(n2)) => {
             let f1 = n1.as_f64().unwrap_or(0.0);
@@ -99,6 +191,12 @@ pub trait IndexProvider: Send + Sync {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryAst {
+    /// The collection group this query runs over, i.e. the root path
+    /// segment documents are indexed under (see `BasicIndexProvider::on_put`).
+    /// `None` falls back to `"default"`.
+    pub collection: Option<String>,
+    /// A top-level field name or a dotted path (`address.city`, `tags.0`)
+    /// resolved via `crate::model::resolve_path`.
     pub field: String,
     pub operator: QueryOperator,
 }
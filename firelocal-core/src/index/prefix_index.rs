@@ -0,0 +1,176 @@
+use crate::index::{IndexProvider, QueryAst, QueryOperator};
+use crate::model::{resolve_path, Document};
+use anyhow::Result;
+use fst::automaton::{Automaton, Str};
+use fst::{IntoStreamer, Set, SetBuilder, Streamer};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+/// `IndexProvider` that stores the sorted set of indexed string keys for one
+/// field in a finite-state transducer, so prefix/autocomplete lookups can
+/// traverse the FST instead of scanning every key like `BasicIndexProvider`.
+///
+/// FSTs are immutable once built, so mutations just update the `key_docs`
+/// side table and mark the FST dirty; it's rebuilt lazily on the next query.
+pub struct PrefixIndex {
+    field: String,
+    key_docs: RwLock<HashMap<String, HashSet<String>>>,
+    fst: RwLock<Option<Set<Vec<u8>>>>,
+    dirty: RwLock<bool>,
+}
+
+impl PrefixIndex {
+    pub fn new(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            key_docs: RwLock::new(HashMap::new()),
+            fst: RwLock::new(None),
+            dirty: RwLock::new(true),
+        }
+    }
+
+    /// Rebuild the FST from the current `key_docs` key set.
+    pub fn flush(&self) {
+        let key_docs = self.key_docs.read().unwrap();
+        let mut keys: Vec<&String> = key_docs.keys().collect();
+        keys.sort();
+
+        let mut builder = SetBuilder::memory();
+        for key in keys {
+            // Keys are inserted in sorted order, as the FST builder requires.
+            let _ = builder.insert(key.as_bytes());
+        }
+        drop(key_docs);
+
+        let bytes = builder.into_inner().unwrap_or_default();
+        let set = Set::new(bytes).unwrap_or_else(|_| Set::from_iter(Vec::<Vec<u8>>::new()).unwrap());
+
+        *self.fst.write().unwrap() = Some(set);
+        *self.dirty.write().unwrap() = false;
+    }
+
+    fn ensure_built(&self) {
+        let needs_build = *self.dirty.read().unwrap() || self.fst.read().unwrap().is_none();
+        if needs_build {
+            self.flush();
+        }
+    }
+
+    /// Traverse the FST from the root following `prefix`, enumerate every
+    /// completion under that node, and map each completed key back to its
+    /// doc paths.
+    pub fn prefix_query(&self, prefix: &str) -> Vec<String> {
+        self.ensure_built();
+
+        let matched_keys = {
+            let fst = self.fst.read().unwrap();
+            let set = match fst.as_ref() {
+                Some(set) => set,
+                None => return Vec::new(),
+            };
+
+            let automaton = Str::new(prefix).starts_with();
+            let mut stream = set.search(automaton).into_stream();
+            let mut keys = Vec::new();
+            while let Some(key) = stream.next() {
+                keys.push(String::from_utf8_lossy(key).to_string());
+            }
+            keys
+        };
+
+        let key_docs = self.key_docs.read().unwrap();
+        let mut docs = HashSet::new();
+        for key in matched_keys {
+            if let Some(paths) = key_docs.get(&key) {
+                docs.extend(paths.iter().cloned());
+            }
+        }
+        docs.into_iter().collect()
+    }
+}
+
+impl IndexProvider for PrefixIndex {
+    fn on_put(&self, doc_path: &str, doc: &Document) -> Result<()> {
+        // `self.field` may be a dotted path (`address.city`) as well as a
+        // plain top-level name.
+        if let serde_json::Value::String(key) = resolve_path(&doc.fields, &self.field) {
+            let mut key_docs = self.key_docs.write().unwrap();
+            key_docs.entry(key).or_default().insert(doc_path.to_string());
+            drop(key_docs);
+            *self.dirty.write().unwrap() = true;
+        }
+        Ok(())
+    }
+
+    fn on_delete(&self, doc_path: &str) -> Result<()> {
+        // No reverse index from doc -> key, so scan (same tradeoff as the
+        // other naive index providers in this crate).
+        let mut key_docs = self.key_docs.write().unwrap();
+        key_docs.retain(|_, paths| {
+            paths.remove(doc_path);
+            !paths.is_empty()
+        });
+        drop(key_docs);
+        *self.dirty.write().unwrap() = true;
+        Ok(())
+    }
+
+    fn query(&self, query_ast: &QueryAst) -> Result<Vec<String>> {
+        match &query_ast.operator {
+            QueryOperator::StartsWith(prefix) => Ok(self.prefix_query(prefix)),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc(path: &str, name: &str) -> Document {
+        Document {
+            path: path.to_string(),
+            fields: serde_json::from_value(json!({ "name": name })).unwrap(),
+            version: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prefix_query_enumerates_completions() {
+        let index = PrefixIndex::new("name");
+        index.on_put("users/1", &doc("users/1", "alice")).unwrap();
+        index.on_put("users/2", &doc("users/2", "alicia")).unwrap();
+        index.on_put("users/3", &doc("users/3", "bob")).unwrap();
+
+        let mut results = index.prefix_query("ali");
+        results.sort();
+        assert_eq!(results, vec!["users/1".to_string(), "users/2".to_string()]);
+
+        assert!(index.prefix_query("z").is_empty());
+    }
+
+    #[test]
+    fn test_prefix_query_over_nested_field() {
+        let index = PrefixIndex::new("address.city");
+        let doc = Document {
+            path: "users/1".to_string(),
+            fields: serde_json::from_value(json!({ "address": { "city": "alicetown" } })).unwrap(),
+            version: 0,
+            ..Default::default()
+        };
+        index.on_put("users/1", &doc).unwrap();
+
+        assert_eq!(index.prefix_query("ali"), vec!["users/1".to_string()]);
+    }
+
+    #[test]
+    fn test_on_delete_drops_from_fst() {
+        let index = PrefixIndex::new("name");
+        index.on_put("users/1", &doc("users/1", "alice")).unwrap();
+        index.on_delete("users/1").unwrap();
+
+        assert!(index.prefix_query("ali").is_empty());
+    }
+}
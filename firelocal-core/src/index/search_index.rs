@@ -0,0 +1,214 @@
+use crate::index::text_index::tokenize;
+use crate::model::{resolve_path, Document};
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+// Map: Collection -> Token -> DocKey -> term frequency in that doc.
+type Postings = HashMap<String, HashMap<String, HashMap<String, u32>>>;
+
+/// Ranked full-text search over registered fields of a collection, distinct
+/// from `TextIndex`'s exact AND-match postings used by `QueryAst`: this one
+/// is scoped per collection, tracks per-token term frequency rather than
+/// just document membership, and ranks results instead of just filtering.
+/// Backs `FireLocal::index_field`/`search`.
+pub struct SearchIndex {
+    fields: RwLock<HashMap<String, HashSet<String>>>,
+    postings: RwLock<Postings>,
+}
+
+impl Default for SearchIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self {
+            fields: RwLock::new(HashMap::new()),
+            postings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The root path segment of `doc_key`, i.e. its collection -- same
+    /// convention `BasicIndexProvider` uses.
+    fn collection_of(doc_key: &str) -> &str {
+        doc_key.split('/').next().filter(|s| !s.is_empty()).unwrap_or("default")
+    }
+
+    /// Register `field` (a top-level or dotted path) as searchable for
+    /// documents in `collection`. Idempotent.
+    pub fn index_field(&self, collection: &str, field: &str) {
+        self.fields
+            .write()
+            .unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .insert(field.to_string());
+    }
+
+    /// Remove every posting `doc_key` contributed within its collection.
+    /// Naive scan-and-remove, same tradeoff `TextIndex::on_delete` makes:
+    /// there's no reverse doc -> tokens index, so overwrite/delete just
+    /// walks every token currently known for the collection.
+    fn retract(&self, collection: &str, doc_key: &str) {
+        let mut postings = self.postings.write().unwrap();
+        if let Some(token_postings) = postings.get_mut(collection) {
+            for doc_counts in token_postings.values_mut() {
+                doc_counts.remove(doc_key);
+            }
+        }
+    }
+
+    /// Tokenize and index `doc`'s registered fields for its collection under
+    /// `doc_key`, first retracting whatever this key previously contributed
+    /// so overwrites don't leave stale postings behind.
+    pub fn on_put(&self, doc_key: &str, doc: &Document) {
+        let collection = Self::collection_of(doc_key);
+        self.retract(collection, doc_key);
+
+        let fields = self.fields.read().unwrap();
+        let Some(searchable) = fields.get(collection) else {
+            return;
+        };
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for field in searchable {
+            if let serde_json::Value::String(text) = resolve_path(&doc.fields, field) {
+                for token in tokenize(&text, true) {
+                    *counts.entry(token).or_insert(0) += 1;
+                }
+            }
+        }
+        drop(fields);
+
+        if counts.is_empty() {
+            return;
+        }
+
+        let mut postings = self.postings.write().unwrap();
+        let token_postings = postings.entry(collection.to_string()).or_default();
+        for (token, count) in counts {
+            token_postings
+                .entry(token)
+                .or_default()
+                .insert(doc_key.to_string(), count);
+        }
+    }
+
+    /// Remove `doc_key`'s postings, e.g. because the document was deleted.
+    pub fn on_delete(&self, doc_key: &str) {
+        self.retract(Self::collection_of(doc_key), doc_key);
+    }
+
+    /// Tokenize `query` and rank every document in `collection` that matches
+    /// at least one token: the score is the summed term frequency of all
+    /// matching query tokens, ties broken by the number of distinct query
+    /// tokens matched. Results are sorted best-first.
+    pub fn search(&self, collection: &str, query: &str) -> Vec<(String, usize)> {
+        let tokens = tokenize(query, true);
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let postings = self.postings.read().unwrap();
+        let Some(token_postings) = postings.get(collection) else {
+            return Vec::new();
+        };
+
+        // doc_key -> (summed term frequency, distinct query tokens matched)
+        let mut scored: HashMap<String, (usize, usize)> = HashMap::new();
+        for token in &tokens {
+            let Some(doc_counts) = token_postings.get(token) else {
+                continue;
+            };
+            for (doc_key, count) in doc_counts {
+                let entry = scored.entry(doc_key.clone()).or_insert((0, 0));
+                entry.0 += *count as usize;
+                entry.1 += 1;
+            }
+        }
+
+        let mut results: Vec<(String, usize, usize)> = scored
+            .into_iter()
+            .map(|(key, (score, distinct))| (key, score, distinct))
+            .collect();
+        results.sort_by(|a, b| b.1.cmp(&a.1).then(b.2.cmp(&a.2)).then(a.0.cmp(&b.0)));
+
+        results.into_iter().map(|(key, score, _)| (key, score)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc(body: &str) -> Document {
+        Document {
+            fields: serde_json::from_value(json!({ "body": body })).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_ranks_by_term_frequency() {
+        let index = SearchIndex::new();
+        index.index_field("docs", "body");
+
+        index.on_put("docs/a", &doc("rust rust rust is fun"));
+        index.on_put("docs/b", &doc("rust is okay"));
+
+        let results = index.search("docs", "rust");
+        assert_eq!(results[0].0, "docs/a");
+        assert_eq!(results[0].1, 3);
+        assert_eq!(results[1].0, "docs/b");
+        assert_eq!(results[1].1, 1);
+    }
+
+    #[test]
+    fn test_search_ties_broken_by_distinct_terms_matched() {
+        let index = SearchIndex::new();
+        index.index_field("docs", "body");
+
+        index.on_put("docs/a", &doc("rust lang"));
+        index.on_put("docs/b", &doc("rust rust"));
+
+        // Both docs have term-frequency 2 for the query, but "a" matches two
+        // distinct query tokens while "b" only matches one.
+        let results = index.search("docs", "rust lang");
+        assert_eq!(results[0].0, "docs/a");
+        assert_eq!(results[1].0, "docs/b");
+    }
+
+    #[test]
+    fn test_overwrite_updates_postings() {
+        let index = SearchIndex::new();
+        index.index_field("docs", "body");
+
+        index.on_put("docs/a", &doc("hello world"));
+        index.on_put("docs/a", &doc("goodbye"));
+
+        assert!(index.search("docs", "hello").is_empty());
+        assert_eq!(index.search("docs", "goodbye")[0].0, "docs/a");
+    }
+
+    #[test]
+    fn test_delete_removes_postings() {
+        let index = SearchIndex::new();
+        index.index_field("docs", "body");
+
+        index.on_put("docs/a", &doc("hello world"));
+        index.on_delete("docs/a");
+
+        assert!(index.search("docs", "hello").is_empty());
+    }
+
+    #[test]
+    fn test_unregistered_field_is_not_indexed() {
+        let index = SearchIndex::new();
+        index.on_put("docs/a", &doc("hello world"));
+
+        assert!(index.search("docs", "hello").is_empty());
+    }
+}
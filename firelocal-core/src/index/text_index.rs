@@ -0,0 +1,217 @@
+use crate::index::fuzzy::LevenshteinAutomaton;
+use crate::index::{IndexProvider, QueryAst, QueryOperator};
+use crate::model::{resolve_path, Document};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+// A handful of common English stop-words. Not exhaustive, but enough to keep
+// the most frequent noise tokens (and, the, of, ...) out of the postings.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// Lowercase + split on non-alphanumeric boundaries (a simplified stand-in for
+/// full Unicode word segmentation), then drop stop-words and empty tokens.
+pub fn tokenize(text: &str, remove_stop_words: bool) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .filter(|s| !remove_stop_words || !STOP_WORDS.contains(s))
+        .map(|s| s.to_string())
+        .collect()
+}
+
+// Map: FieldName -> Token -> Set<DocPath>
+type Postings = HashMap<String, HashMap<String, HashSet<String>>>;
+
+/// Inverted-index `IndexProvider` over a configured set of "searchable" string
+/// fields, giving word-level search instead of `BasicIndexProvider`'s
+/// exact-match lookups.
+pub struct TextIndex {
+    searchable_fields: Vec<String>,
+    remove_stop_words: bool,
+    postings: RwLock<Postings>,
+}
+
+impl TextIndex {
+    pub fn new(searchable_fields: Vec<String>) -> Self {
+        Self {
+            searchable_fields,
+            remove_stop_words: true,
+            postings: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_stop_words(mut self, remove_stop_words: bool) -> Self {
+        self.remove_stop_words = remove_stop_words;
+        self
+    }
+
+    /// Tokenize the query and return the documents matching all tokens
+    /// (an AND of the per-token postings).
+    pub fn search(&self, field: &str, query: &str) -> Vec<String> {
+        let tokens = tokenize(query, self.remove_stop_words);
+        if tokens.is_empty() {
+            return Vec::new();
+        }
+
+        let postings = self.postings.read().unwrap();
+        let field_postings = match postings.get(field) {
+            Some(f) => f,
+            None => return Vec::new(),
+        };
+
+        let mut result: Option<HashSet<String>> = None;
+        for token in &tokens {
+            let docs = field_postings.get(token).cloned().unwrap_or_default();
+            result = Some(match result {
+                Some(acc) => acc.intersection(&docs).cloned().collect(),
+                None => docs,
+            });
+        }
+
+        result.unwrap_or_default().into_iter().collect()
+    }
+
+    /// Return documents whose indexed tokens for `field` are within
+    /// `max_distance` edits of `term`. Walks every distinct token currently
+    /// indexed for the field through a `LevenshteinAutomaton`, since the
+    /// postings map isn't itself sorted/structured for fuzzy lookups.
+    pub fn search_fuzzy(&self, field: &str, term: &str, max_distance: u8) -> Vec<String> {
+        let automaton = LevenshteinAutomaton::new(term, max_distance);
+
+        let postings = self.postings.read().unwrap();
+        let field_postings = match postings.get(field) {
+            Some(f) => f,
+            None => return Vec::new(),
+        };
+
+        let mut result = HashSet::new();
+        for (token, docs) in field_postings {
+            if automaton.is_match(token) {
+                result.extend(docs.iter().cloned());
+            }
+        }
+        result.into_iter().collect()
+    }
+}
+
+impl IndexProvider for TextIndex {
+    fn on_put(&self, doc_path: &str, doc: &Document) -> Result<()> {
+        let mut postings = self.postings.write().unwrap();
+
+        for field in &self.searchable_fields {
+            // Searchable fields may be dotted paths as well as top-level names.
+            if let serde_json::Value::String(text) = resolve_path(&doc.fields, field) {
+                let tokens = tokenize(&text, self.remove_stop_words);
+                let field_postings = postings.entry(field.clone()).or_default();
+                for token in tokens {
+                    field_postings
+                        .entry(token)
+                        .or_default()
+                        .insert(doc_path.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn on_delete(&self, doc_path: &str) -> Result<()> {
+        // Naive delete, same tradeoff as BasicIndexProvider: we don't keep a
+        // reverse index from doc -> tokens, so scan and remove everywhere.
+        let mut postings = self.postings.write().unwrap();
+        for field_postings in postings.values_mut() {
+            for docs in field_postings.values_mut() {
+                docs.remove(doc_path);
+            }
+        }
+        Ok(())
+    }
+
+    fn query(&self, query_ast: &QueryAst) -> Result<Vec<String>> {
+        match &query_ast.operator {
+            QueryOperator::MatchText(text) => Ok(self.search(&query_ast.field, text)),
+            QueryOperator::Fuzzy { term, max_distance } => {
+                Ok(self.search_fuzzy(&query_ast.field, term, *max_distance))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc(path: &str, body: &str) -> Document {
+        Document {
+            path: path.to_string(),
+            fields: serde_json::from_value(json!({ "body": body })).unwrap(),
+            version: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_matches_all_tokens() {
+        let index = TextIndex::new(vec!["body".to_string()]);
+        index.on_put("docs/a", &doc("docs/a", "the quick brown fox")).unwrap();
+        index.on_put("docs/b", &doc("docs/b", "a slow brown dog")).unwrap();
+
+        let results = index.search("body", "brown fox");
+        assert_eq!(results, vec!["docs/a".to_string()]);
+
+        let results = index.search("body", "brown");
+        let mut results = results;
+        results.sort();
+        assert_eq!(results, vec!["docs/a".to_string(), "docs/b".to_string()]);
+    }
+
+    #[test]
+    fn test_on_delete_removes_postings() {
+        let index = TextIndex::new(vec!["body".to_string()]);
+        index.on_put("docs/a", &doc("docs/a", "hello world")).unwrap();
+        index.on_delete("docs/a").unwrap();
+
+        assert!(index.search("body", "hello").is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_tolerates_typos() {
+        let index = TextIndex::new(vec!["body".to_string()]);
+        index.on_put("docs/a", &doc("docs/a", "firelocal search")).unwrap();
+
+        // "firelocl" is one edit away from "firelocal".
+        assert_eq!(index.search_fuzzy("body", "firelocl", 1), vec!["docs/a".to_string()]);
+        assert!(index.search_fuzzy("body", "xyzxyz", 1).is_empty());
+    }
+
+    #[test]
+    fn test_search_over_nested_field() {
+        let index = TextIndex::new(vec!["meta.body".to_string()]);
+        let doc = Document {
+            path: "docs/a".to_string(),
+            fields: serde_json::from_value(json!({ "meta": { "body": "quick brown fox" } }))
+                .unwrap(),
+            version: 0,
+            ..Default::default()
+        };
+        index.on_put("docs/a", &doc).unwrap();
+
+        assert_eq!(index.search("meta.body", "brown"), vec!["docs/a".to_string()]);
+    }
+
+    #[test]
+    fn test_stop_words_removed() {
+        let index = TextIndex::new(vec!["body".to_string()]);
+        index.on_put("docs/a", &doc("docs/a", "the cat sat")).unwrap();
+
+        // "the" is a stop-word, so querying for it alone yields nothing.
+        assert!(index.search("body", "the").is_empty());
+        assert_eq!(index.search("body", "cat"), vec!["docs/a".to_string()]);
+    }
+}
@@ -1,66 +1,266 @@
 pub mod api;
+pub mod audit;
+pub mod auth;
+pub mod codec;
 pub mod config;
+pub mod dump;
+pub mod error;
 pub mod ffi;
+pub mod field_schema;
 pub mod field_value;
+pub mod health;
 pub mod index;
 pub mod listener;
+pub mod logging;
+pub mod metrics;
 pub mod model;
 pub mod rules;
+pub mod schema;
+pub mod scrub;
+pub mod security;
 pub mod store;
 pub mod sync;
 pub mod transaction;
 
 use crate::config::FireLocalConfig;
+use crate::field_schema::{Conversion, FieldSchema};
 use crate::field_value::process_field_values;
 use crate::index::basic_index::BasicIndexProvider;
+use crate::index::search_index::SearchIndex;
 use crate::index::{IndexProvider, QueryAst};
-use crate::listener::{ListenerManager, SnapshotCallback};
+use crate::listener::{ChangeCallback, ListenerManager, SnapshotCallback};
+use crate::metrics::{MetricsSnapshot, OperationCounters};
 use crate::model::Document;
 use crate::rules::RulesEngine;
-use crate::store::compaction::{CompactionStats, Compactor};
-use crate::store::memtable::Memtable;
-use crate::store::sst::{SstBuilder, SstReader, SstSearchResult};
-use crate::store::wal::WriteAheadLog;
-use crate::sync::{MockRemoteStore, RemoteStore, SyncManager};
-use crate::transaction::{Transaction, WriteBatch, execute_batch_operation};
+use crate::schema::{Schema, SchemaRegistry};
+use crate::store::cache::{CachedValue, ReadCache};
+use crate::store::column_family::ColumnFamily;
+use crate::store::compaction::{CompactionPolicy, CompactionStats, Compactor};
+use crate::store::memtable::{Entry as MemtableEntry, Memtable};
+use crate::store::io::{MemoryStorage, StdStorage, Storage};
+use crate::store::sst::{RepairReport, SstBuilder, SstReader, SstSearchResult};
+use crate::store::wal::{WalEntry, WriteAheadLog};
+use crate::sync::{MockRemoteStore, RemoteStore, SyncManager, SyncSummary};
+use crate::transaction::{
+    operation_column, operation_path, execute_batch_operation, BatchCommitResult, BatchCondition,
+    BatchOperation, LockManager, Transaction, WriteBatch, DEFAULT_COLUMN_FAMILY,
+};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
-pub struct FireLocal {
+/// Result of `FireLocal::upgrade`: how many on-disk files and entries were
+/// migrated to bring the store up to the current on-disk format version.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpgradeSummary {
+    pub wal_upgraded: bool,
+    pub wal_entries_migrated: usize,
+    pub ssts_upgraded: usize,
+    pub sst_entries_migrated: usize,
+}
+
+/// A user-registered associative merge function (see
+/// `FireLocal::set_merge_operator`), modeled on RocksDB's merge operator:
+/// given the most recent fully-resolved value for a key (`None` if it has
+/// none yet) and every pending merge operand recorded for it since, oldest
+/// first, it returns the single value that replaces them. Used lazily by
+/// `get`/`scan`/`local_snapshot`, and eagerly by `flush` so a flushed SST
+/// never carries raw operands forward.
+pub type MergeOperator = dyn Fn(Option<&[u8]>, &[Vec<u8>]) -> Vec<u8> + Send + Sync;
+
+/// One page of `FireLocal::scan`'s results.
+#[derive(Debug, Clone, Default)]
+pub struct ScanPage {
+    pub items: Vec<(String, Document)>,
+    /// Exclusive start key for the next call (the last key this page
+    /// covered), or `None` when the call found no keys left in the range. A
+    /// page that reaches the end of the range still carries a cursor; the
+    /// call after it returns an empty page with `None`.
+    pub cursor: Option<String>,
+}
+
+/// The smallest key that sorts strictly after every key starting with
+/// `prefix`, used as `scan_prefix`'s exclusive upper bound -- found by
+/// incrementing `prefix`'s last `char` (not raw byte, so multi-byte UTF-8
+/// stays valid). Returns `None` for an empty prefix, or one where every
+/// trailing `char` is already `char::MAX` and there's nothing to carry into
+/// (vanishingly unlikely for real document paths), in which case the caller
+/// should treat the range as unbounded.
+fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        if let Some(incremented) = char::from_u32(last as u32 + 1) {
+            chars.push(incremented);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+/// `S` selects the `Storage` backend the default keyspace's WAL is written
+/// through -- `StdStorage` (the default, via `new`/`new_with_config`) for
+/// real files on disk, or any other backend (`MemoryStorage`'s
+/// `new_in_memory` shortcut, or `new_with_storage` for any `S` directly) to
+/// keep it entirely in RAM for ephemeral test runs, CI, and embedded
+/// scratch use. `SstBuilder`/`SstReader` are themselves generic over the
+/// same `Storage` trait now (see `store::sst`), but this store's own SST
+/// files and column families still go through `StdStorage` directly
+/// regardless of `S` for now -- a memory-backed store only avoids touching
+/// disk as long as `flush`/`compact` (and `put_cf`/`get_cf`) are never
+/// called against it.
+pub struct FireLocal<S: Storage = StdStorage> {
     path: PathBuf,
-    wal: WriteAheadLog,
+    wal: WriteAheadLog<S>,
     memtable: Memtable,
     ssts: Vec<Arc<std::sync::Mutex<SstReader>>>,
     index: Arc<dyn IndexProvider>,
     listeners: ListenerManager,
     rules: RulesEngine,
+    schemas: SchemaRegistry,
     sync: SyncManager,
     config: Option<FireLocalConfig>,
     document_versions: HashMap<String, u64>,
+    field_schemas: HashMap<String, FieldSchema>,
+    read_cache: ReadCache,
+    /// This store's identity in other nodes' version vectors. Stable across
+    /// process restarts when loaded via `new_with_config` with a configured
+    /// `project_id`; otherwise a fresh random id per process.
+    node_id: String,
+    metrics: OperationCounters,
+    /// Per-operation latency histograms (`put`/`get`/`delete`/`query`/
+    /// `compact`) feeding `metrics_snapshot`'s Prometheus exposition --
+    /// separate from `metrics` above, which only tracks cumulative
+    /// put/delete/get counts for `MetricsSnapshot`.
+    metrics_registry: crate::metrics::MetricsRegistry,
+    last_compaction: std::sync::Mutex<Option<CompactionStats>>,
+    /// Named column families opened on demand by `put_cf`/`get_cf`/
+    /// `delete_cf`. The default keyspace `put`/`get`/`delete` use lives
+    /// directly on `FireLocal` (above) rather than as an entry here.
+    columns: HashMap<String, ColumnFamily>,
+    /// Ranked full-text search over fields registered via `index_field`. See
+    /// `index::search_index` -- distinct from `index` above, which only
+    /// supports exact-match/range `QueryAst` queries.
+    search_index: SearchIndex,
+    /// Fields declared via `create_index`, collection -> field names. `index`
+    /// (a `BasicIndexProvider`) already maintains an equality/range index for
+    /// every field of every document unconditionally, so this doesn't gate
+    /// anything -- it's the supported, discoverable way to say "this field
+    /// is indexed and queries against it won't full-scan."
+    declared_indexes: HashMap<String, HashSet<String>>,
+    /// Process-wide lock table backing `run_transaction_pessimistic`. Shared
+    /// across every pessimistic transaction opened against this store so
+    /// they actually contend with each other instead of each getting their
+    /// own private set of locks.
+    lock_manager: Arc<LockManager>,
+    /// Folds a key's pending merge operands (see `merge`) onto its base
+    /// value. `None` until `set_merge_operator` is called, in which case
+    /// `resolve_merge` falls back to returning the most recent operand
+    /// unchanged.
+    merge_operator: Option<Arc<MergeOperator>>,
+    /// Key `flush`/`compact` write/read SST files through, via
+    /// `SstBuilder::encrypted`/`SstReader::open_encrypted`. `None` (the
+    /// default) means SSTs are plaintext. Set by `new_with_encryption_key`;
+    /// see `crate::store::encryption`.
+    sst_encryption_key: Option<[u8; crate::store::encryption::KEY_LEN]>,
+    /// What `compact_if_needed` merges on each call. `CompactionPolicy::Full`
+    /// (the default) means `compact_if_needed` is a no-op -- use `compact`
+    /// directly instead, or `set_compaction_policy` a `SizeTiered` policy
+    /// for a background loop to call `compact_if_needed` against.
+    compaction_policy: CompactionPolicy,
+    /// This handle's active identity for rules evaluation -- surfaced to a
+    /// loaded `Ruleset` as `request.auth.uid` (and `request.auth != null`).
+    /// `None` (the default) means unauthenticated, matching
+    /// `SecurityContext::anonymous`. Set via `set_auth_context`.
+    auth_uid: Option<String>,
 }
 
-impl FireLocal {
-    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+impl<S: Storage> FireLocal<S> {
+    /// Open a store against an already-constructed `storage` backend,
+    /// skipping the on-disk setup `new` does (`create_dir_all`,
+    /// `migration::run_pending`) since those are hard-wired to `std::fs`
+    /// and would defeat a non-`StdStorage` backend's whole point. `path` is
+    /// kept only as this instance's logical identity (`FireLocal::path`,
+    /// error messages) -- for a non-`StdStorage` backend it need not exist,
+    /// or even be a real filesystem path at all.
+    ///
+    /// Unlike `new`'s SST discovery, which scans `path` with `std::fs`,
+    /// this scans through `storage`'s own `read_dir` (a `Storage` trait
+    /// method every backend implements), so pre-existing `*.sst` files are
+    /// picked up here too -- letting a backend like `MemoryStorage` persist
+    /// compacted data across a restore instead of only ever replaying its
+    /// WAL. See the WASM binding's `export_snapshot`/`import_snapshot`.
+    pub fn new_with_storage(path: impl Into<PathBuf>, storage: S) -> io::Result<Self> {
         let path = path.into();
-        std::fs::create_dir_all(&path)?;
-
         let wal_path = path.join("wal.log");
-        let wal = WriteAheadLog::open(wal_path)?;
+        let storage = Arc::new(storage);
+
+        let mut sst_files: Vec<(PathBuf, (u64, u32))> = storage
+            .read_dir(&path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|stat| stat.path.extension().and_then(|e| e.to_str()) == Some("sst"))
+            .map(|stat| (stat.path, (stat.mtime.secs, stat.mtime.nanos)))
+            .collect();
+        // Newest first, same tie-break order as `new`'s std::fs-backed scan.
+        sst_files.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let mut ssts = Vec::new();
+        for (p, _) in sst_files {
+            if let Ok(reader) = SstReader::open_with_storage(storage.clone(), &p) {
+                ssts.push(Arc::new(std::sync::Mutex::new(reader)));
+            }
+        }
+
+        Self::open_with_storage(storage, path, wal_path, ssts, None)
+    }
+
+    /// Shared construction path for `new`/`new_with_storage`: open
+    /// `wal_path` through `storage`, replay it into a fresh memtable/index,
+    /// and assemble the rest of the struct around the already-prepared
+    /// `ssts`. Callers differ only in how `path`/`ssts` were prepared
+    /// beforehand -- a real, possibly-migrated directory for `new`; no
+    /// directory setup for `new_with_storage`, whose `ssts` come from the
+    /// backend's own `read_dir`. `sst_encryption_key`
+    /// is carried straight into the returned store's own field; it doesn't
+    /// affect how `ssts` (already-opened `SstReader`s) were loaded.
+    fn open_with_storage(
+        storage: Arc<S>,
+        path: PathBuf,
+        wal_path: PathBuf,
+        ssts: Vec<Arc<std::sync::Mutex<SstReader>>>,
+        sst_encryption_key: Option<[u8; crate::store::encryption::KEY_LEN]>,
+    ) -> io::Result<Self> {
+        let mut wal = WriteAheadLog::open(storage, wal_path)?;
 
         let index = Arc::new(BasicIndexProvider::new());
 
         let mut memtable = Memtable::new();
 
-        // Replay WAL
+        // Replay WAL. Two on-disk encodings share this file: raw
+        // `[op:u8][klen:u32][key][vlen:u32][value]` frames written directly
+        // by `put`/`delete`, and JSON-encoded `WalEntry` frames written by
+        // `execute_batch_operation` (batch/transaction commits), which also
+        // carry `BeginTxn`/`Prepare`/`Commit`/`Rollback` 2PC framing markers
+        // -- see `crate::store::wal::recover_committed`. A raw frame's first
+        // byte is always 0 or 1 and never valid JSON, so trying the JSON
+        // decode first cleanly tells the two apart.
         if let Ok(iter) = wal.iter() {
+            let mut txn_entries = Vec::new();
+
             for entry_res in iter {
                 if let Ok(entry) = entry_res {
                     if entry.is_empty() {
                         continue;
                     }
+
+                    if let Ok(wal_entry) = serde_json::from_slice::<crate::store::wal::WalEntry>(&entry) {
+                        txn_entries.push(wal_entry);
+                        continue;
+                    }
+
                     let op = entry[0];
                     if entry.len() < 5 {
                         continue;
@@ -101,8 +301,117 @@ impl FireLocal {
                     }
                 }
             }
+
+            // Only replay batch/transaction entries whose group reached a
+            // `Commit` marker; a group still mid-flight when the process
+            // crashed is discarded rather than partially applied.
+            for wal_entry in crate::store::wal::recover_committed(txn_entries) {
+                match wal_entry.op {
+                    // Seed the WAL's chunk store from its own `Chunk`
+                    // frames so a later `Put` entry's `chunk_keys` (see
+                    // `crate::store::chunking`) can be resolved below.
+                    crate::store::wal::WalOp::Chunk { key } => {
+                        if let Some(data) = wal_entry.value {
+                            wal.seed_chunk_store(key, data);
+                        }
+                    }
+                    crate::store::wal::WalOp::Put => {
+                        let value = wal_entry.value.or_else(|| {
+                            wal_entry
+                                .chunk_keys
+                                .as_deref()
+                                .and_then(|keys| wal.resolve_chunked_value(keys))
+                        });
+                        if let Some(value) = value {
+                            memtable.put(wal_entry.key.clone(), value.clone());
+                            if let Ok(json_str) = std::str::from_utf8(&value) {
+                                if let Ok(doc) = Document::from_json(json_str) {
+                                    let _ = index.on_put(&doc.path, &doc);
+                                }
+                            }
+                        }
+                    }
+                    crate::store::wal::WalOp::Delete => {
+                        memtable.delete(wal_entry.key.clone());
+                        let _ = index.on_delete(&wal_entry.key);
+                    }
+                    crate::store::wal::WalOp::Merge => {
+                        if let Some(operand) = wal_entry.value {
+                            memtable.merge(wal_entry.key.clone(), operand);
+                        }
+                    }
+                    _ => {}
+                }
+            }
         }
 
+        Ok(Self {
+            path,
+            wal,
+            memtable,
+            ssts,
+            index,
+            listeners: ListenerManager::new(),
+            rules: RulesEngine::new(),
+            schemas: SchemaRegistry::new(),
+            sync: SyncManager::new(Box::new(MockRemoteStore)),
+            config: None,
+            document_versions: HashMap::new(),
+            field_schemas: HashMap::new(),
+            read_cache: ReadCache::new(crate::config::DEFAULT_READ_CACHE_CAPACITY),
+            node_id: uuid::Uuid::new_v4().to_string(),
+            metrics: OperationCounters::default(),
+            metrics_registry: crate::metrics::MetricsRegistry::new(),
+            last_compaction: std::sync::Mutex::new(None),
+            columns: HashMap::new(),
+            search_index: SearchIndex::new(),
+            declared_indexes: HashMap::new(),
+            lock_manager: Arc::new(LockManager::new()),
+            merge_operator: None,
+            sst_encryption_key,
+            compaction_policy: CompactionPolicy::default(),
+            auth_uid: None,
+        })
+    }
+}
+
+impl FireLocal<StdStorage> {
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+        Self::open(path, None)
+    }
+
+    /// Like `new`, but every SST `flush`/`compact` write or read goes
+    /// through `SstBuilder::encrypted`/`SstReader::open_encrypted` with
+    /// `key` (see `crate::store::encryption`). A store opened with one key
+    /// can't read SSTs written under another, or written in plaintext by
+    /// plain `new` -- `SstReader::open_encrypted` rejects both.
+    pub fn new_with_encryption_key(
+        path: impl Into<PathBuf>,
+        key: [u8; crate::store::encryption::KEY_LEN],
+    ) -> io::Result<Self> {
+        Self::open(path, Some(key))
+    }
+
+    /// Shared construction path for `new`/`new_with_encryption_key`: prepare
+    /// the on-disk directory (migrations), load its existing `*.sst` files
+    /// through the right reader for `sst_encryption_key`, and hand off to
+    /// `open_with_storage` for WAL replay.
+    fn open(
+        path: impl Into<PathBuf>,
+        sst_encryption_key: Option<[u8; crate::store::encryption::KEY_LEN]>,
+    ) -> io::Result<Self> {
+        let path = path.into();
+        let is_new = !path.exists();
+        std::fs::create_dir_all(&path)?;
+
+        crate::store::migration::run_pending(
+            &path,
+            is_new,
+            &crate::store::migration::all_migrations(),
+        )?;
+
+        let wal_path = path.join("wal.log");
+
         // Load SSTs
         let mut ssts = Vec::new();
         if let Ok(entries) = std::fs::read_dir(&path) {
@@ -126,24 +435,17 @@ impl FireLocal {
             sst_files.sort_by(|a, b| b.1.cmp(&a.1));
 
             for (p, _) in sst_files {
-                if let Ok(reader) = SstReader::open(p) {
+                let reader = match sst_encryption_key {
+                    Some(key) => SstReader::open_encrypted(&p, key),
+                    None => SstReader::open(&p),
+                };
+                if let Ok(reader) = reader {
                     ssts.push(Arc::new(std::sync::Mutex::new(reader)));
                 }
             }
         }
 
-        Ok(Self {
-            path,
-            wal,
-            memtable,
-            ssts,
-            index,
-            listeners: ListenerManager::new(),
-            rules: RulesEngine::new(),
-            sync: SyncManager::new(Box::new(MockRemoteStore)),
-            config: None,
-            document_versions: HashMap::new(),
-        })
+        Self::open_with_storage(Arc::new(StdStorage), path, wal_path, ssts, sst_encryption_key)
     }
 
     /// Create a new FireLocal instance with configuration
@@ -153,95 +455,508 @@ impl FireLocal {
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
         let mut instance = Self::new(&path_buf)?;
+        instance.read_cache = ReadCache::new(config.read_cache_capacity);
+        if !config.project_id.is_empty() {
+            instance.node_id = config.project_id.clone();
+        }
         instance.config = Some(config);
         Ok(instance)
     }
+}
+
+impl FireLocal<MemoryStorage> {
+    /// Open an ephemeral, fully in-memory store under a synthetic path: its
+    /// default keyspace's WAL lives only in RAM, never on disk. Convenience
+    /// wrapper around `new_with_storage` for callers (the CLI's
+    /// `--backend memory`, embedded scratch use) that don't need a
+    /// caller-chosen path -- stays disk-free as long as
+    /// `flush`/`compact`/`put_cf`-family methods are never called against
+    /// it, see the `FireLocal` doc comment.
+    pub fn new_in_memory() -> io::Result<Self> {
+        let path = format!("memory://{}", uuid::Uuid::new_v4());
+        Self::new_with_storage(path, MemoryStorage::new())
+    }
+}
 
+impl<S: Storage> FireLocal<S> {
     // Allow swapping remote store
     pub fn set_remote_store(&mut self, remote: Box<dyn RemoteStore>) {
         self.sync = SyncManager::new(remote);
     }
 
+    /// Use `resolver` instead of the default last-writer-wins behavior when
+    /// `sync_pull`/`sync_pull_all` find a document whose local and remote
+    /// version vectors are concurrent (see `sync::conflict`).
+    pub fn set_conflict_resolver(&mut self, resolver: Box<dyn crate::sync::conflict::ConflictResolver>) {
+        self.sync.set_resolver(resolver);
+    }
+
+    /// Use `policy` for future `compact_if_needed` calls instead of the
+    /// default `CompactionPolicy::Full` (under which `compact_if_needed` is a
+    /// no-op).
+    pub fn set_compaction_policy(&mut self, policy: CompactionPolicy) {
+        self.compaction_policy = policy;
+    }
+
+    /// Set the identity `check_rules` evaluates `request.auth.uid`/
+    /// `request.auth != null` against for every `put`/`get`/`delete`/sync
+    /// call made through this handle, until changed again. `None` (the
+    /// default) means unauthenticated. Callers that verify a
+    /// `SecurityAuditor::pre_operation_check`'d `SecurityContext` up front
+    /// should pass its `user_id` through here before touching the store.
+    pub fn set_auth_context(&mut self, uid: Option<String>) {
+        self.auth_uid = uid;
+    }
+
     pub fn load_rules(&mut self, rules_str: &str) -> io::Result<()> {
         self.rules
             .load_rules(rules_str)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
     }
 
-    fn check_rules(&self, path: &str, operation: &str) -> io::Result<()> {
+    /// Load a ruleset from disk, expanding any `%include <path>` directives
+    /// (resolved relative to the including file) before parsing, so a large
+    /// ruleset can be split across files. The 1MB rules size limit applies to
+    /// the fully-expanded text, not just the entry file.
+    pub fn load_rules_from_file(&mut self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        const MAX_RULES_SIZE: usize = 1024 * 1024;
+
+        let expanded = crate::rules::include::expand_includes(path.as_ref())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        if expanded.len() > MAX_RULES_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Rules too large (max 1MB) after expanding includes",
+            ));
+        }
+        self.load_rules(&expanded)
+    }
+
+    /// Associate a JSON Schema with every document path under
+    /// `collection_prefix`, so `put` rejects writes whose shape doesn't
+    /// conform — a data-contract layer alongside the access-control
+    /// `Ruleset`.
+    pub fn register_schema(&mut self, collection_prefix: &str, schema: Schema) {
+        self.schemas.register(collection_prefix, schema);
+    }
+
+    /// Declare field-level type coercions for every document path under
+    /// `collection`: `put`/`put_with_field_values` will parse each field
+    /// named here into its declared `Conversion` before indexing, so e.g.
+    /// an `age` written as the string `"42"` and as the number `42` are
+    /// indexed identically. Replaces any conversions previously set for
+    /// this collection.
+    pub fn set_field_schema(&mut self, collection: impl Into<String>, conversions: HashMap<String, Conversion>) {
+        self.field_schemas
+            .insert(collection.into(), FieldSchema::new(conversions));
+    }
+
+    /// Register `field` (a top-level or dotted path, e.g. `"meta.title"`) as
+    /// searchable for documents in `collection`: every future `put`/
+    /// `commit_batch` tokenizes that field's string value into the
+    /// collection's ranked search index (see `search`). Idempotent, and
+    /// doesn't retroactively index documents already stored — call it
+    /// before writing the documents you want searchable.
+    pub fn index_field(&mut self, collection: &str, field: &str) {
+        self.search_index.index_field(collection, field);
+    }
+
+    /// Declare `field` (top-level or dotted path) as indexed for `collection`,
+    /// so `.where_eq`/`.where_range` queries against it are documented to run
+    /// through `index` rather than a full scan. Idempotent.
+    pub fn create_index(&mut self, collection: &str, field: &str) {
+        self.declared_indexes
+            .entry(collection.to_string())
+            .or_default()
+            .insert(field.to_string());
+    }
+
+    /// Whether `field` was declared indexed for `collection` via `create_index`.
+    pub fn has_index(&self, collection: &str, field: &str) -> bool {
+        self.declared_indexes
+            .get(collection)
+            .map(|fields| fields.contains(field))
+            .unwrap_or(false)
+    }
+
+    /// Rank every document in `collection` against `query`: tokenizes the
+    /// query the same way `index_field`-registered fields were tokenized on
+    /// write, and scores each matching document by summed term frequency
+    /// across matching tokens (ties broken by how many distinct query
+    /// tokens it matched). Best match first.
+    pub fn search(&self, collection: &str, query: &str) -> Vec<(String, usize)> {
+        self.search_index.search(collection, query)
+    }
+
+    /// The field schema governing `path`, if any — the longest registered
+    /// collection prefix `path` falls under, matching `SchemaRegistry`'s
+    /// prefix rule.
+    fn field_schema_for(&self, path: &str) -> Option<&FieldSchema> {
+        self.field_schemas
+            .iter()
+            .filter(|(prefix, _)| {
+                path == prefix.as_str() || path.starts_with(&format!("{prefix}/"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, schema)| schema)
+    }
+
+    /// Check `path`/`operation` against the loaded `Ruleset` with no
+    /// `request.resource.data` (a read, or a write whose incoming payload
+    /// isn't in hand at the call site) -- see `check_rules_with_incoming`.
+    fn check_rules(&self, path: &str, operation: &str) -> crate::error::Result<()> {
+        self.check_rules_with_incoming(path, operation, None)
+    }
+
+    /// Check `path`/`operation` against the loaded `Ruleset`, building a real
+    /// evaluation context instead of the empty one every caller used to get:
+    /// `request.auth.uid` from `self.auth_uid` (see `set_auth_context`),
+    /// `resource.data.*` from the document currently stored at `path` (if
+    /// any), and `request.resource.data.*` from `incoming` -- the write
+    /// payload a caller has in hand before it lands in the store, e.g.
+    /// `put`'s parsed `value`. A denied check fails closed with
+    /// `PermissionDenied`; a path with no loaded rules, or a condition this
+    /// context can't satisfy, behaves exactly as it did before this context
+    /// was wired in.
+    fn check_rules_with_incoming(
+        &self,
+        path: &str,
+        operation: &str,
+        incoming: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> crate::error::Result<()> {
         let full_path = format!("/databases/(default)/documents/{}", path);
-        let context: HashMap<String, String> = HashMap::new();
-        if self.rules.evaluate(&full_path, operation, &context) {
+
+        let existing = self.get_unchecked(path).and_then(|bytes| {
+            std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| Document::from_json(s).ok())
+        });
+
+        let allowed = self.rules.evaluate(
+            &full_path,
+            operation,
+            self.auth_uid.as_deref(),
+            existing.as_ref().map(|doc| &doc.fields),
+            incoming,
+        );
+
+        if allowed {
             Ok(())
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::PermissionDenied,
-                "Rules check failed",
-            ))
+            Err(crate::error::FireLocalError::PermissionDenied(format!(
+                "rules denied {operation} on '{path}'"
+            )))
         }
     }
 
-    pub fn put(&mut self, key: String, value: Vec<u8>) -> io::Result<()> {
-        self.check_rules(&key, "write")?;
+    pub fn put(&mut self, key: String, mut value: Vec<u8>) -> crate::error::Result<()> {
+        let (result, duration) = crate::timed_operation!("put", {
+            (|| -> crate::error::Result<()> {
+                let incoming_fields = std::str::from_utf8(&value)
+                    .ok()
+                    .and_then(|s| Document::from_json(s).ok())
+                    .map(|doc| doc.fields);
+                self.check_rules_with_incoming(&key, "write", incoming_fields.as_ref())?;
 
-        if let Ok(json_str) = std::str::from_utf8(&value) {
-            if let Ok(doc) = Document::from_json(json_str) {
-                let _ = self.index.on_put(&doc.path, &doc);
-            }
-        }
+                if let Ok(json_str) = std::str::from_utf8(&value) {
+                    if let Ok(mut doc) = Document::from_json(json_str) {
+                        if let Some(field_schema) = self.field_schema_for(&key) {
+                            field_schema.coerce(&mut doc.fields).map_err(|e| {
+                                crate::error::FireLocalError::Validation(e.to_string())
+                            })?;
+                        }
 
-        let mut entry = Vec::new();
-        entry.push(0u8);
-        entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
-        entry.extend_from_slice(key.as_bytes());
-        entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
-        entry.extend_from_slice(&value);
+                        // Stamp every local write so a later sync pull can tell
+                        // whether this document's edits causally dominate a remote
+                        // copy or happened concurrently with one. See
+                        // `sync::conflict`.
+                        *doc.version_vector.entry(self.node_id.clone()).or_insert(0) += 1;
+                        doc.updated_at_ms = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map(|d| d.as_millis() as u64)
+                            .unwrap_or(0);
 
-        self.wal.append(&entry)?;
-        self.memtable.put(key, value);
-        self.notify_listeners();
-        Ok(())
+                        if let Ok(stamped_json) = doc.to_json() {
+                            value = stamped_json.into_bytes();
+                        }
+
+                        if let Some(schema) = self.schemas.schema_for(&key) {
+                            schema
+                                .validate(&serde_json::Value::Object(doc.fields.clone()))
+                                .map_err(|e| {
+                                    crate::error::FireLocalError::Validation(e.to_string())
+                                })?;
+                        }
+                        let _ = self.index.on_put(&doc.path, &doc);
+                        self.search_index.on_put(&doc.path, &doc);
+                    }
+                }
+
+                let mut entry = Vec::new();
+                entry.push(0u8);
+                entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
+                entry.extend_from_slice(key.as_bytes());
+                entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                entry.extend_from_slice(&value);
+
+                self.wal.append(&entry)?;
+                self.memtable.put(key.clone(), value);
+                self.read_cache.invalidate(&key);
+                self.metrics.record_put();
+                self.notify_listeners();
+                Ok(())
+            })()
+        });
+        self.metrics_registry
+            .record(crate::metrics::OpKind::Put, duration, result.is_ok());
+        result
     }
 
-    pub fn delete(&mut self, key: String) -> io::Result<()> {
-        self.check_rules(&key, "write")?;
-        let _ = self.index.on_delete(&key);
+    pub fn delete(&mut self, key: String) -> crate::error::Result<()> {
+        let (result, duration) = crate::timed_operation!("delete", {
+            (|| -> crate::error::Result<()> {
+                self.check_rules(&key, "write")?;
+                let _ = self.index.on_delete(&key);
+                self.search_index.on_delete(&key);
 
-        let mut entry = Vec::new();
-        entry.push(1u8);
-        entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
-        entry.extend_from_slice(key.as_bytes());
-        entry.extend_from_slice(&0u32.to_le_bytes());
+                let mut entry = Vec::new();
+                entry.push(1u8);
+                entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
+                entry.extend_from_slice(key.as_bytes());
+                entry.extend_from_slice(&0u32.to_le_bytes());
 
-        self.wal.append(&entry)?;
-        self.memtable.delete(key);
-        self.notify_listeners();
-        Ok(())
+                self.wal.append(&entry)?;
+                self.memtable.delete(key.clone());
+                self.read_cache.invalidate(&key);
+                self.metrics.record_delete();
+                self.notify_listeners();
+                Ok(())
+            })()
+        });
+        self.metrics_registry
+            .record(crate::metrics::OpKind::Delete, duration, result.is_ok());
+        result
     }
 
     pub fn get(&self, key: &str) -> Option<Vec<u8>> {
-        if self.check_rules(key, "read").is_err() {
-            return None;
-        }
+        self.get_checked(key).ok().flatten()
+    }
+
+    /// Like `get`, but surfaces a denied rules check as
+    /// `FireLocalError::PermissionDenied` instead of silently folding it
+    /// into the same `None` a genuine "key not found" returns. `get` itself
+    /// keeps its existing `Option`-only signature (too many call sites rely
+    /// on it) -- this is for callers, like the NAPI binding, that can
+    /// surface a distinct error to their caller and want "denied" to be
+    /// observable rather than indistinguishable from "absent".
+    pub fn get_checked(&self, key: &str) -> crate::error::Result<Option<Vec<u8>>> {
+        self.metrics.record_get();
+        let (result, duration) = crate::timed_operation!("get", {
+            match self.check_rules(key, "read") {
+                Ok(()) => Ok(self.get_unchecked(key)),
+                Err(e) => Err(e),
+            }
+        });
+        self.metrics_registry
+            .record(crate::metrics::OpKind::Get, duration, result.is_ok());
+        result
+    }
+
+    /// `get`/`get_checked`'s shared lookup: memtable, then read cache, then
+    /// the SST set newest-first. Doesn't check rules or record metrics --
+    /// callers have already done both before this runs.
+    fn get_unchecked(&self, key: &str) -> Option<Vec<u8>> {
         // Memtable check
-        if let Some(val) = self.memtable.get(key) {
-            return Some(val.to_vec());
+        match self.memtable.get_entry(key) {
+            Some(MemtableEntry::Put(val)) => return Some(val.clone()),
+            Some(MemtableEntry::Merge { base, operands }) => {
+                let base = base.clone().or_else(|| self.sst_lookup(key));
+                return Some(self.resolve_merge(base.as_deref(), operands));
+            }
+            _ => {}
+        }
+
+        if let Some(cached) = self.read_cache.get(key) {
+            return match cached {
+                CachedValue::Found(val) => Some(val),
+                CachedValue::Tombstone => None,
+            };
         }
 
         // SST check (newest first)
+        for sst_mutex in &self.ssts {
+            let mut sst = sst_mutex.lock().unwrap();
+            match sst.get(key) {
+                Ok(SstSearchResult::Found(val)) => {
+                    self.read_cache
+                        .put(key.to_string(), CachedValue::Found(val.clone()));
+                    return Some(val);
+                }
+                Ok(SstSearchResult::Deleted) => {
+                    self.read_cache.put(key.to_string(), CachedValue::Tombstone);
+                    return None;
+                }
+                Ok(SstSearchResult::NotFound) | Ok(SstSearchResult::Corrupt { .. }) | Err(_) => {
+                    continue
+                }
+            }
+        }
+
+        None
+    }
+
+    /// `key`'s value in the SST set alone (newest first), bypassing the
+    /// memtable and read cache. The fallback `get`/`scan`/`flush` use to find
+    /// a merge's base value when the memtable's `Entry::Merge` itself has
+    /// none (the prior write, if any, was already flushed before the first
+    /// merge on the key landed).
+    fn sst_lookup(&self, key: &str) -> Option<Vec<u8>> {
         for sst_mutex in &self.ssts {
             let mut sst = sst_mutex.lock().unwrap();
             match sst.get(key) {
                 Ok(SstSearchResult::Found(val)) => return Some(val),
                 Ok(SstSearchResult::Deleted) => return None,
-                Ok(SstSearchResult::NotFound) | Err(_) => continue,
+                Ok(SstSearchResult::NotFound) | Ok(SstSearchResult::Corrupt { .. }) | Err(_) => {
+                    continue
+                }
             }
         }
-
         None
     }
 
+    /// Fold `operands` onto `base` with the registered merge operator, or
+    /// (none registered) just return the most recent operand unchanged.
+    fn resolve_merge(&self, base: Option<&[u8]>, operands: &[Vec<u8>]) -> Vec<u8> {
+        match &self.merge_operator {
+            Some(f) => f(base, operands),
+            None => operands.last().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Register the function `get`/`scan`/`flush` use to fold a key's
+    /// pending merge operands (see `merge`) onto its base value, as in
+    /// RocksDB's merge operator. Replaces any operator previously
+    /// registered. Until one is registered, `resolve_merge` falls back to
+    /// returning the most recent operand unchanged.
+    pub fn set_merge_operator<F>(&mut self, f: F)
+    where
+        F: Fn(Option<&[u8]>, &[Vec<u8>]) -> Vec<u8> + Send + Sync + 'static,
+    {
+        self.merge_operator = Some(Arc::new(f));
+    }
+
+    /// Append `operand` as a pending merge on `key` instead of overwriting
+    /// it — the atomic read-modify-write primitive for things like counters
+    /// and append-only lists, which would otherwise need a full transaction
+    /// round trip. Folded onto the key's base value (by the operator
+    /// registered via `set_merge_operator`, or the last-operand-wins
+    /// default) the next time it's read.
+    pub fn merge(&mut self, key: String, operand: Vec<u8>) -> io::Result<()> {
+        self.check_rules(&key, "write")?;
+
+        let entry = WalEntry::merge(key.clone(), operand.clone(), None);
+        let entry_bytes = serde_json::to_vec(&entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.wal.append(&entry_bytes)?;
+        self.memtable.merge(key.clone(), operand);
+        self.read_cache.invalidate(&key);
+        self.notify_listeners();
+        Ok(())
+    }
+
+    /// Like `put`, but for any `Serialize` type: encodes `value` as
+    /// MessagePack and writes it through the same WAL/memtable path as a
+    /// raw `put`. More compact on disk than JSON text for numeric/array-
+    /// heavy values, and skips the JSON parse `put` does on the way in.
+    pub fn put_typed<T: serde::Serialize>(
+        &mut self,
+        key: String,
+        value: &T,
+    ) -> codec::CodecResult<()> {
+        let bytes = codec::encode(value)?;
+        self.put(key, bytes)
+            .map_err(|e| codec::CodecError::Io(e.into()))?;
+        Ok(())
+    }
+
+    /// Like `get`, but decodes the stored MessagePack bytes back into `T`.
+    /// Returns `Ok(None)` if the key isn't present, and `Err` if it is but
+    /// doesn't decode as `T` (e.g. it was written by `put`/`put_with_field_values`
+    /// as JSON rather than `put_typed`).
+    pub fn get_typed<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> codec::CodecResult<Option<T>> {
+        match self.get(key) {
+            Some(bytes) => Ok(Some(codec::decode(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The column family `name`, opening it from disk (or creating it) on
+    /// first use.
+    fn column(&mut self, name: &str) -> io::Result<&mut ColumnFamily> {
+        if !self.columns.contains_key(name) {
+            let column = ColumnFamily::open(&self.path, name)?;
+            self.columns.insert(name.to_string(), column);
+        }
+        Ok(self.columns.get_mut(name).expect("just inserted"))
+    }
+
+    /// Write `key`/`value` into the named column family instead of the
+    /// default keyspace `put` uses — its own WAL segment and SST files
+    /// under this store's directory, so e.g. large document bodies can be
+    /// written (and later compacted) independently from small metadata.
+    /// Unlike `put`, this bypasses rules checks, schema coercion, and
+    /// indexing, since those are concepts of the default document
+    /// keyspace, not of an arbitrary column.
+    pub fn put_cf(&mut self, name: &str, key: String, value: Vec<u8>) -> io::Result<()> {
+        self.column(name)?.put(key, value)
+    }
+
+    /// Read `key` from the named column family.
+    pub fn get_cf(&mut self, name: &str, key: &str) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.column(name)?.get(key))
+    }
+
+    /// Delete `key` from the named column family.
+    pub fn delete_cf(&mut self, name: &str, key: String) -> io::Result<()> {
+        self.column(name)?.delete(key)
+    }
+
+    /// Flush the named column family's memtable to a new SST file under
+    /// this store's directory, independent of the default column's SST set
+    /// or any other column's.
+    pub fn flush_cf(&mut self, name: &str) -> io::Result<()> {
+        let path = self.path.clone();
+        self.column(name)?.flush(&path)
+    }
+
+    /// Every column family that exists on disk under this store's
+    /// directory, whether or not it's been opened in this process yet.
+    pub fn list_column_families(&self) -> io::Result<Vec<String>> {
+        crate::store::column_family::list_existing(&self.path)
+    }
+
+    /// Create (or open, if it already exists) the column family `name`, so
+    /// it shows up in `list_column_families` even before anything is
+    /// written to it.
+    pub fn create_column_family(&mut self, name: &str) -> io::Result<()> {
+        self.column(name)?;
+        Ok(())
+    }
+
     pub fn query(&self, q: &QueryAst) -> io::Result<Vec<Document>> {
+        let (result, duration) = crate::timed_operation!("query", { self.query_unchecked(q) });
+        self.metrics_registry.record(
+            crate::metrics::OpKind::Query,
+            duration,
+            result.is_ok(),
+        );
+        result
+    }
+
+    fn query_unchecked(&self, q: &QueryAst) -> io::Result<Vec<Document>> {
         // Assume list permissions handled by collection rule (not impl in M4) or per-doc.
         let paths = self
             .index
@@ -263,6 +978,180 @@ impl FireLocal {
         Ok(docs)
     }
 
+    /// The k-way merge engine behind `scan`/`scan_prefix`: up to `limit` live
+    /// raw `(key, value)` pairs starting at `effective_start` and stopping
+    /// before `end` (if given), in ascending key order, merging the memtable
+    /// with every SST (newest source wins
+    /// ties) so a tombstone or an older SST's value for the same key never
+    /// surfaces, plus a cursor to resume from (the last key covered, `None`
+    /// only when no key was left in range). `cursor`, when given, resumes strictly after that key --
+    /// not at it -- by searching from `cursor` with a trailing NUL appended,
+    /// which every real key sorts before (document paths don't contain NUL
+    /// bytes).
+    fn merge_range(
+        &self,
+        start: &str,
+        end: Option<&str>,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> io::Result<(Vec<(String, Vec<u8>)>, Option<String>)> {
+        let resume_from;
+        let effective_start = match cursor {
+            Some(c) => {
+                resume_from = format!("{c}\0");
+                resume_from.as_str()
+            }
+            None => start,
+        };
+
+        // One sorted `(key, Option<value>)` vector per source, newest first
+        // (the memtable, then each SST in the newest-first order `ssts` is
+        // already kept in), positioned at `effective_start`. `None` marks a
+        // tombstone.
+        let mut sources: Vec<Vec<(String, Option<Vec<u8>>)>> = Vec::new();
+
+        sources.push(
+            self.memtable
+                .range_from(effective_start)
+                .map(|(k, e)| {
+                    let v = match e {
+                        MemtableEntry::Put(v) => Some(v.clone()),
+                        MemtableEntry::Delete => None,
+                        MemtableEntry::Merge { base, operands } => {
+                            let base = base.clone().or_else(|| self.sst_lookup(k));
+                            Some(self.resolve_merge(base.as_deref(), operands))
+                        }
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        );
+
+        for sst_mutex in &self.ssts {
+            let mut sst = sst_mutex.lock().unwrap();
+            let entries = sst
+                .entries_from(effective_start)?
+                .into_iter()
+                .map(|(k, r)| {
+                    let v = match r {
+                        SstSearchResult::Found(val) => Some(val),
+                        SstSearchResult::Deleted
+                        | SstSearchResult::NotFound
+                        | SstSearchResult::Corrupt { .. } => None,
+                    };
+                    (k, v)
+                })
+                .collect();
+            sources.push(entries);
+        }
+
+        // K-way merge: a min-heap of (key, source index) over each source's
+        // current position. On a tie, the lowest source index wins (it's the
+        // newest), and every source sitting on that same key advances so it
+        // isn't re-emitted on the next key.
+        let mut positions = vec![0usize; sources.len()];
+        let mut heap: std::collections::BinaryHeap<std::cmp::Reverse<(String, usize)>> =
+            std::collections::BinaryHeap::new();
+        for (idx, src) in sources.iter().enumerate() {
+            if let Some((key, _)) = src.first() {
+                heap.push(std::cmp::Reverse((key.clone(), idx)));
+            }
+        }
+
+        let mut items = Vec::new();
+        let mut next_cursor = None;
+
+        while let Some(std::cmp::Reverse((key, _))) = heap.peek().cloned() {
+            if let Some(end_key) = end {
+                if key.as_str() >= end_key {
+                    break;
+                }
+            }
+            if items.len() >= limit {
+                break;
+            }
+
+            let mut winner: Option<(usize, Option<Vec<u8>>)> = None;
+            while let Some(std::cmp::Reverse((k, idx))) = heap.peek().cloned() {
+                if k != key {
+                    break;
+                }
+                heap.pop();
+                let pos = positions[idx];
+                let value = sources[idx][pos].1.clone();
+                positions[idx] += 1;
+                if let Some(next) = sources[idx].get(positions[idx]) {
+                    heap.push(std::cmp::Reverse((next.0.clone(), idx)));
+                }
+                if winner.as_ref().is_none_or(|(best_idx, _)| idx < *best_idx) {
+                    winner = Some((idx, value));
+                }
+            }
+
+            next_cursor = Some(key.clone());
+            if let Some((_, Some(bytes))) = winner {
+                items.push((key, bytes));
+            }
+        }
+
+        Ok((items, next_cursor))
+    }
+
+    /// A page of `scan`'s results: up to `limit` live `(key, Document)` pairs
+    /// in ascending key order, plus a cursor to resume from (see
+    /// `ScanPage::cursor`). Values that aren't readable (per `check_rules`)
+    /// or don't parse as a `Document` are skipped, same as `query`; see
+    /// `scan_prefix` for a raw-bytes equivalent that doesn't require either.
+    pub fn scan(
+        &self,
+        start: &str,
+        end: Option<&str>,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> io::Result<ScanPage> {
+        let (raw, next_cursor) = self.merge_range(start, end, limit, cursor)?;
+
+        let mut items = Vec::new();
+        for (key, bytes) in raw {
+            if self.check_rules(&key, "read").is_ok() {
+                if let Ok(s) = std::str::from_utf8(&bytes) {
+                    if let Ok(doc) = Document::from_json(s) {
+                        items.push((key, doc));
+                    }
+                }
+            }
+        }
+
+        Ok(ScanPage {
+            items,
+            cursor: next_cursor,
+        })
+    }
+
+    /// Every live raw key/value pair whose key starts with `prefix`, in
+    /// ascending order -- the foundation for collection listing (e.g.
+    /// `users/**`) that doesn't require a value to parse as a `Document`
+    /// the way `scan`/`query` do. Drains `merge_range` one page at a time
+    /// internally, so a prefix with more matches than fit in one page is
+    /// still returned in full.
+    pub fn scan_prefix(&self, prefix: &str) -> io::Result<Vec<(String, Vec<u8>)>> {
+        const PAGE_SIZE: usize = 256;
+        let end = prefix_upper_bound(prefix);
+
+        let mut results = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let (page, next_cursor) =
+                self.merge_range(prefix, end.as_deref(), PAGE_SIZE, cursor.as_deref())?;
+            if page.is_empty() {
+                break;
+            }
+            results.extend(page);
+            cursor = next_cursor;
+        }
+        Ok(results)
+    }
+
     pub fn listen(&mut self, q: QueryAst, callback: SnapshotCallback) -> u64 {
         let id = self.listeners.register(q.clone(), callback);
         if let Ok(docs) = self.query(&q) {
@@ -271,6 +1160,17 @@ impl FireLocal {
         id
     }
 
+    /// Like `listen`, but the callback receives a `SnapshotChange` (added /
+    /// modified / removed) diffed against the listener's previous result set
+    /// instead of the full result set every time.
+    pub fn listen_diff(&mut self, q: QueryAst, callback: ChangeCallback) -> u64 {
+        let id = self.listeners.register_diff(q.clone(), callback);
+        if let Ok(docs) = self.query(&q) {
+            self.listeners.notify(id, docs);
+        }
+        id
+    }
+
     fn notify_listeners(&self) {
         for (id, q) in self.listeners.get_listeners() {
             if let Ok(docs) = self.query(&q) {
@@ -298,61 +1198,393 @@ impl FireLocal {
     }
 
     pub fn sync_pull(&mut self, key: &str) -> io::Result<()> {
-        if let Ok(Some(doc)) = self
+        let remote_doc = self
             .sync
             .pull(key)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
-        {
-            // We pulled a doc. Write it to local.
-            // Bypass check_rules? "Admin" action? Or enforce "write"?
-            // Syncing usually implies authoritative source, so maybe bypass?
-            // But for safety, let's just use put().
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Remote doc not found"))?;
+
+        let local_doc = self.get(key).and_then(|bytes| {
+            std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| Document::from_json(s).ok())
+        });
+
+        let resolved = self.sync.reconcile(local_doc.as_ref(), remote_doc);
+        self.check_rules_with_incoming(&resolved.path, "write", Some(&resolved.fields))?;
+
+        // Write the reconciled document through as-is rather than via put(),
+        // which would stamp it with a fresh local version-vector entry and
+        // make this node look like it authored a new edit it merely pulled.
+        let bytes = resolved
+            .to_json()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            .into_bytes();
+        let mut batch = self.batch();
+        batch.set(resolved.path.clone(), bytes);
+        // commit_batch parses the document back out of the bytes it just
+        // wrote and maintains the index itself, so there's no separate
+        // on_put call needed here.
+        self.commit_batch(&batch)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Merge every SST (oldest to newest) with the memtable on top, so the
+    /// result reflects the same "most recent write wins" view `get()` gives
+    /// per-key, but for every key at once. Used by `sync_push_all`/
+    /// `sync_pull_all` to diff the whole local keyspace against the remote.
+    fn local_snapshot(&self) -> HashMap<String, Vec<u8>> {
+        let mut out = HashMap::new();
+
+        for sst_mutex in self.ssts.iter().rev() {
+            let mut sst = sst_mutex.lock().unwrap();
+            if let Ok(entries) = sst.all_entries() {
+                for (key, result) in entries {
+                    match result {
+                        SstSearchResult::Found(val) => {
+                            out.insert(key, val);
+                        }
+                        SstSearchResult::Deleted => {
+                            out.remove(&key);
+                        }
+                        SstSearchResult::NotFound | SstSearchResult::Corrupt { .. } => {}
+                    }
+                }
+            }
+        }
+
+        for (key, entry) in self.memtable.iter() {
+            match entry {
+                MemtableEntry::Put(val) => {
+                    out.insert(key.clone(), val.clone());
+                }
+                MemtableEntry::Delete => {
+                    out.remove(key);
+                }
+                MemtableEntry::Merge { base, operands } => {
+                    // `out` already reflects every already-flushed SST's
+                    // state for this key, so it's a cheaper base fallback
+                    // than re-scanning the SSTs again via `sst_lookup`.
+                    let base = base.clone().or_else(|| out.get(key).cloned());
+                    out.insert(key.clone(), self.resolve_merge(base.as_deref(), operands));
+                }
+            }
+        }
+
+        out
+    }
 
+    /// Push every local document under `prefix` to the remote, then delete
+    /// any remote object under `prefix` that no longer has a local
+    /// counterpart — reconciling the whole collection in one call instead of
+    /// one hand-enumerated path at a time.
+    ///
+    /// Every push/delete this would perform is checked against the loaded
+    /// `Ruleset` before any of them run, so a permission denial partway
+    /// through can't leave the remote half-synced.
+    pub fn sync_push_all(&mut self, prefix: &str) -> io::Result<SyncSummary> {
+        let local_docs: Vec<Document> = self
+            .local_snapshot()
+            .into_iter()
+            .filter(|(path, _)| path.starts_with(prefix))
+            .filter_map(|(_, bytes)| {
+                std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| Document::from_json(s).ok())
+            })
+            .collect();
+
+        let remote_paths: HashSet<String> = self
+            .sync
+            .list(prefix)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .into_iter()
+            .collect();
+        let local_paths: HashSet<&str> = local_docs.iter().map(|doc| doc.path.as_str()).collect();
+        let remote_only: Vec<String> = remote_paths
+            .into_iter()
+            .filter(|path| !local_paths.contains(path.as_str()))
+            .collect();
+
+        for doc in &local_docs {
+            self.check_rules_with_incoming(&doc.path, "write", Some(&doc.fields))?;
+        }
+        for path in &remote_only {
+            self.check_rules(path, "write")?;
+        }
+
+        for doc in &local_docs {
+            self.sync
+                .push(doc)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+        for path in &remote_only {
+            self.sync
+                .delete(path)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+
+        Ok(SyncSummary {
+            pushed: local_docs.len(),
+            pulled: 0,
+            deleted: remote_only.len(),
+        })
+    }
+
+    /// Pull every remote document under `prefix` into the local store, then
+    /// delete any local document under `prefix` that no longer has a remote
+    /// counterpart — the mirror image of `sync_push_all` for reconciling an
+    /// entire local DB on reconnect.
+    ///
+    /// Every write/delete this would perform is checked against the loaded
+    /// `Ruleset` up front and applied as a single `WriteBatch`, so a
+    /// permission denial partway through can't leave the local store
+    /// half-synced.
+    pub fn sync_pull_all(&mut self, prefix: &str) -> io::Result<SyncSummary> {
+        let remote_paths = self
+            .sync
+            .list(prefix)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+
+        let mut remote_docs = Vec::new();
+        for path in &remote_paths {
+            if let Ok(Some(doc)) = self.sync.pull(path) {
+                remote_docs.push(doc);
+            }
+        }
+        let remote_path_set: HashSet<&str> = remote_paths.iter().map(String::as_str).collect();
+
+        let local_snapshot = self.local_snapshot();
+        let local_only: Vec<String> = local_snapshot
+            .keys()
+            .filter(|path| path.starts_with(prefix) && !remote_path_set.contains(path.as_str()))
+            .cloned()
+            .collect();
+
+        // Reconcile each pulled document against its local counterpart (if
+        // any) by version vector before writing anything, so a remote copy
+        // that's behind the local one doesn't clobber it.
+        let resolved_docs: Vec<Document> = remote_docs
+            .into_iter()
+            .map(|remote_doc| {
+                let local_doc = local_snapshot
+                    .get(&remote_doc.path)
+                    .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                    .and_then(|s| Document::from_json(s).ok());
+                self.sync.reconcile(local_doc.as_ref(), remote_doc)
+            })
+            .collect();
+
+        for doc in &resolved_docs {
+            self.check_rules_with_incoming(&doc.path, "write", Some(&doc.fields))?;
+        }
+        for path in &local_only {
+            self.check_rules(path, "write")?;
+        }
+
+        let mut batch = self.batch();
+        for doc in &resolved_docs {
             let bytes = doc
                 .to_json()
                 .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
                 .into_bytes();
-            // We need to call put, but get() above took &self. pull took &self.
-            // put needs &mut self.
-            // We are in &mut self method.
-            self.put(doc.path, bytes)?;
-            Ok(())
-        } else {
-            Err(io::Error::new(
-                io::ErrorKind::NotFound,
-                "Remote doc not found",
-            ))
+            batch.set(doc.path.clone(), bytes);
+        }
+        for path in &local_only {
+            batch.delete(path.clone());
         }
+        // commit_batch parses each written document back out of its bytes
+        // and maintains the index itself, so the pulled documents are
+        // queryable and the removed ones aren't without doing it again here.
+        self.commit_batch(&batch)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+        Ok(SyncSummary {
+            pushed: 0,
+            pulled: resolved_docs.len(),
+            deleted: local_only.len(),
+        })
     }
 
     pub fn flush(&mut self) -> io::Result<()> {
         let uuid = uuid::Uuid::new_v4();
         let sst_path = self.path.join(format!("{}.sst", uuid));
 
-        let builder = SstBuilder::new(sst_path)?;
-        builder.build(&self.memtable)?;
+        // The on-disk SST format only has Put/Delete records (see
+        // `store::sst`), so a pending `Entry::Merge` is resolved to a
+        // concrete value here rather than carried into the file raw --
+        // which is also what keeps `compact`'s merge of SST files from
+        // needing to know about merge operands at all.
+        let resolved = self.resolve_memtable_merges();
+
+        let builder = match self.sst_encryption_key {
+            Some(key) => SstBuilder::encrypted(sst_path, key)?,
+            None => SstBuilder::new(sst_path)?,
+        };
+        builder.build(&resolved)?;
+        // The SST set underneath the cache just changed shape; stale hits
+        // are cheaper to avoid than to reason about.
+        self.read_cache.clear();
         Ok(())
     }
 
+    /// A copy of `self.memtable` with every pending `Entry::Merge` folded
+    /// into a concrete `Entry::Put`, via `resolve_merge` (falling back to
+    /// `sst_lookup` for a merge whose own `base` is `None`). Used by `flush`
+    /// so a flushed SST never carries raw merge operands forward.
+    fn resolve_memtable_merges(&self) -> Memtable {
+        self.memtable.resolved(|key, base, operands| {
+            let base = base.map(|b| b.to_vec()).or_else(|| self.sst_lookup(key));
+            self.resolve_merge(base.as_deref(), operands)
+        })
+    }
+
     /// Create a new write batch
     pub fn batch(&self) -> WriteBatch {
         WriteBatch::new()
     }
 
-    /// Commit a write batch atomically
-    pub fn commit_batch(&mut self, batch: &WriteBatch) -> Result<()> {
-        for op in batch.operations() {
+    /// Append a single `WalEntry` (a 2PC framing marker or a batch/txn op)
+    /// as its own WAL frame.
+    fn wal_append_entry(&mut self, entry: &WalEntry) -> Result<()> {
+        let bytes = serde_json::to_vec(entry)?;
+        self.wal.append(&bytes)?;
+        Ok(())
+    }
+
+    /// Apply `op` to the store: the default column's WAL/memtable via
+    /// `execute_batch_operation` if `operation_column(op)` is
+    /// `DEFAULT_COLUMN_FAMILY`, or the named `ColumnFamily`'s own simpler
+    /// raw-byte WAL otherwise -- same split as `put` vs `put_cf`.
+    fn apply_batch_op(&mut self, op: &BatchOperation, batch_id: Option<&str>) -> Result<()> {
+        let column = operation_column(op);
+        if column == DEFAULT_COLUMN_FAMILY {
             execute_batch_operation(
                 op,
                 &mut self.wal,
                 &mut self.memtable,
-                Some(batch.batch_id()),
+                batch_id.map(|s| s.to_string()),
             )?;
+        } else {
+            match op {
+                BatchOperation::Set { path, data, .. } | BatchOperation::Update { path, data, .. } => {
+                    self.column(column)?.put(path.clone(), data.clone())?;
+                }
+                BatchOperation::Delete { path, .. } => {
+                    self.column(column)?.delete(path.clone())?;
+                }
+                BatchOperation::Merge { path, operand, .. } => {
+                    self.column(column)?.merge(path.clone(), operand.clone())?;
+                }
+            }
         }
-        self.notify_listeners();
         Ok(())
     }
 
+    /// The `Document::version` currently stored at `path`, or 0 if there's
+    /// no document there (or its bytes aren't a valid `Document`) -- the
+    /// baseline `BatchCondition::CheckVersion` compares against.
+    fn document_version_at(&self, path: &str) -> u64 {
+        self.get_unchecked(path)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .and_then(|s| Document::from_json(&s).ok())
+            .map(|doc| doc.version)
+            .unwrap_or(0)
+    }
+
+    /// Commit a write batch atomically. Framed in the WAL with `BeginTxn`/
+    /// `Prepare`/`Commit` markers around `batch`'s operations (see
+    /// `crate::store::wal::recover_committed`), so a crash partway through
+    /// leaves nothing for replay to apply -- either every operation lands or
+    /// none do. This framing only covers the default column family; an
+    /// operation targeting a named column family (see `BatchOperation`'s
+    /// `column` field) goes to that column's own WAL instead and isn't part
+    /// of this atomicity guarantee, same as `put_cf` already isn't.
+    ///
+    /// Before anything is applied, `batch`'s staged `get` reads are resolved
+    /// against the current store (the batch's commit snapshot) and its
+    /// `check_version`/`set_if_absent` preconditions (see `BatchCondition`)
+    /// are checked; if any precondition fails, the whole batch is rejected
+    /// and none of its operations are applied. On success, the resolved
+    /// reads are returned alongside the commit so a caller can combine a
+    /// read with a conditional write in one atomic round trip.
+    pub fn commit_batch(&mut self, batch: &WriteBatch) -> Result<BatchCommitResult> {
+        let reads: HashMap<String, Option<Vec<u8>>> = batch
+            .reads()
+            .iter()
+            .map(|path| (path.clone(), self.get_unchecked(path)))
+            .collect();
+
+        for condition in batch.conditions() {
+            match condition {
+                BatchCondition::CheckVersion {
+                    path,
+                    expected_version,
+                } => {
+                    let actual = self.document_version_at(path);
+                    if actual != *expected_version {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!(
+                                "batch precondition failed: '{path}' is at version {actual}, expected {expected_version}"
+                            ),
+                        )
+                        .into());
+                    }
+                }
+                BatchCondition::NotExists { path } => {
+                    if self.get_unchecked(path).is_some() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Other,
+                            format!("batch precondition failed: '{path}' already exists"),
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+
+        self.wal_append_entry(&WalEntry::begin_txn(batch.batch_id()))?;
+        for op in batch.operations() {
+            let is_default_column = operation_column(op) == DEFAULT_COLUMN_FAMILY;
+            self.apply_batch_op(op, Some(batch.batch_id()))?;
+            self.read_cache.invalidate(operation_path(op));
+            if !is_default_column {
+                // Indexing, the search index, and metrics are concepts of
+                // the default document keyspace -- a named column family's
+                // writes bypass them, same as `put_cf`/`delete_cf` already do.
+                continue;
+            }
+            match op {
+                BatchOperation::Delete { path, .. } => {
+                    self.metrics.record_delete();
+                    let _ = self.index.on_delete(path);
+                    self.search_index.on_delete(path);
+                }
+                BatchOperation::Set { path, data, .. } | BatchOperation::Update { path, data, .. } => {
+                    self.metrics.record_put();
+                    if let Ok(json_str) = std::str::from_utf8(data) {
+                        if let Ok(doc) = Document::from_json(json_str) {
+                            let _ = self.index.on_put(path, &doc);
+                            self.search_index.on_put(path, &doc);
+                        }
+                    }
+                }
+                BatchOperation::Merge { .. } => {
+                    // A merge operand isn't a full document, so there's
+                    // nothing here for `index`/`search_index` to index --
+                    // it only becomes one once `get`/`flush` resolve it.
+                }
+            }
+        }
+        self.wal_append_entry(&WalEntry::prepare(batch.batch_id()))?;
+        self.wal_append_entry(&WalEntry::commit(batch.batch_id()))?;
+        self.notify_listeners();
+        Ok(BatchCommitResult { reads })
+    }
+
     /// Run a transaction with optimistic concurrency control
     pub fn run_transaction<F>(&mut self, f: F) -> Result<()>
     where
@@ -366,19 +1598,23 @@ impl FireLocal {
         // Validate versions haven't changed
         txn.validate(|path| self.document_versions.get(path).copied())?;
 
-        // Apply writes
+        // Apply writes, framed with BeginTxn/Prepare/Commit so a crash
+        // partway through leaves nothing for replay to apply (see
+        // `crate::store::wal::recover_committed`).
+        self.wal_append_entry(&WalEntry::begin_txn(txn.transaction_id()))?;
         for op in txn.writes() {
-            execute_batch_operation(
-                op,
-                &mut self.wal,
-                &mut self.memtable,
-                Some(txn.transaction_id()),
-            )?;
+            self.apply_batch_op(op, Some(txn.transaction_id()))?;
+            self.read_cache.invalidate(operation_path(op));
         }
+        self.wal_append_entry(&WalEntry::prepare(txn.transaction_id()))?;
+        self.wal_append_entry(&WalEntry::commit(txn.transaction_id()))?;
 
-        // Update versions
+        // Update versions (default column family only -- a named column
+        // family doesn't participate in `document_versions`/optimistic
+        // validation, same as `put_cf` already doesn't).
         for op in txn.writes() {
-            if let Some(path) = self.get_operation_path(op) {
+            if operation_column(op) == DEFAULT_COLUMN_FAMILY {
+                let path = operation_path(op).to_string();
                 let version = self.document_versions.get(&path).unwrap_or(&0) + 1;
                 self.document_versions.insert(path, version);
             }
@@ -388,17 +1624,201 @@ impl FireLocal {
         Ok(())
     }
 
-    /// Helper to extract path from batch operation
-    fn get_operation_path(&self, _op: &crate::transaction::BatchOperation) -> Option<String> {
-        // This is a workaround since BatchOperation is private
-        // In production, we'd expose a method to get the path
-        None // TODO: Implement properly
+    /// Run a transaction with pessimistic (eagerly-locking) concurrency
+    /// control, modeled on RocksDB's `TransactionDB`: `txn.get`/`set`/
+    /// `update`/`delete` take shared/exclusive locks from this store's
+    /// `lock_manager` as they run, instead of the optimistic path's
+    /// check-at-commit `validate`. `lock_timeout` bounds how long a blocked
+    /// lock acquisition waits before `f` gets a `TransactionConflict` error
+    /// back, so two pessimistic transactions that deadlock on each other's
+    /// locks abort instead of hanging forever.
+    ///
+    /// Locks are released once writes are durably applied, or immediately if
+    /// `f` returns an error -- either way the transaction never leaves locks
+    /// held past this call.
+    pub fn run_transaction_pessimistic<F>(&mut self, lock_timeout: Duration, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Transaction, &FireLocal) -> Result<()>,
+    {
+        let mut txn = Transaction::new_pessimistic(self.lock_manager.clone(), lock_timeout);
+
+        let result = f(&mut txn, self);
+        if let Err(e) = result {
+            txn.release_locks();
+            return Err(e);
+        }
+
+        // Apply writes, framed with BeginTxn/Prepare/Commit (see
+        // `crate::store::wal::recover_committed`).
+        self.wal_append_entry(&WalEntry::begin_txn(txn.transaction_id()))?;
+        for op in txn.writes() {
+            self.apply_batch_op(op, Some(txn.transaction_id()))?;
+            self.read_cache.invalidate(operation_path(op));
+        }
+        self.wal_append_entry(&WalEntry::prepare(txn.transaction_id()))?;
+        self.wal_append_entry(&WalEntry::commit(txn.transaction_id()))?;
+
+        // Update versions (default column family only, as in `run_transaction`).
+        for op in txn.writes() {
+            if operation_column(op) == DEFAULT_COLUMN_FAMILY {
+                let path = operation_path(op).to_string();
+                let version = self.document_versions.get(&path).unwrap_or(&0) + 1;
+                self.document_versions.insert(path, version);
+            }
+        }
+
+        txn.release_locks();
+        self.notify_listeners();
+        Ok(())
     }
 
     /// Run compaction to merge SST files and remove tombstones
     pub fn compact(&self) -> Result<CompactionStats> {
-        let compactor = Compactor::new(self.path.clone());
-        compactor.compact()
+        let (result, duration) = crate::timed_operation!("compact", {
+            (|| -> Result<CompactionStats> {
+                let mut compactor = Compactor::new(self.path.clone());
+                if let Some(key) = self.sst_encryption_key {
+                    compactor = compactor.with_encryption_key(key);
+                }
+                let stats = compactor.compact()?;
+                // The SST set underneath the cache just changed shape; stale hits
+                // are cheaper to avoid than to reason about.
+                self.read_cache.clear();
+                *self.last_compaction.lock().unwrap() = Some(stats.clone());
+                Ok(stats)
+            })()
+        });
+        self.metrics_registry.record(
+            crate::metrics::OpKind::Compact,
+            duration,
+            result.is_ok(),
+        );
+        result
+    }
+
+    /// Bounded compaction for a background loop, driven by
+    /// `set_compaction_policy`: merges the smallest eligible tier instead of
+    /// `compact`'s unconditional full merge. A no-op (`files_before: 0`)
+    /// until a `CompactionPolicy::SizeTiered` policy is set and a tier
+    /// actually crosses its fanout/size threshold.
+    pub fn compact_if_needed(&self) -> Result<CompactionStats> {
+        let mut compactor = Compactor::new(self.path.clone()).with_policy(self.compaction_policy);
+        if let Some(key) = self.sst_encryption_key {
+            compactor = compactor.with_encryption_key(key);
+        }
+        let stats = compactor.compact_if_needed()?;
+        if stats.files_before > 0 {
+            self.read_cache.clear();
+            *self.last_compaction.lock().unwrap() = Some(stats.clone());
+        }
+        Ok(stats)
+    }
+
+    /// Snapshot of this store's current memtable/SST/WAL footprint,
+    /// listener count, cumulative put/delete/get counters, read-cache
+    /// hit/miss rates, and the last compaction's stats — enough for an
+    /// embedding app to decide when to `flush`/`compact` without guessing.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let (puts, deletes, gets) = self.metrics.snapshot();
+        let cache_stats = self.read_cache.stats();
+
+        let mut sst_bytes = 0u64;
+        for sst_mutex in &self.ssts {
+            if let Ok(sst) = sst_mutex.lock() {
+                sst_bytes += sst.size_bytes().unwrap_or(0);
+            }
+        }
+
+        MetricsSnapshot {
+            memtable_entries: self.memtable.len(),
+            memtable_bytes: self.memtable.size_bytes(),
+            sst_count: self.ssts.len(),
+            sst_bytes,
+            wal_bytes_appended: self.wal.bytes_appended(),
+            listener_count: self.listeners.count(),
+            puts,
+            deletes,
+            gets,
+            cache_hits: cache_stats.hits,
+            cache_misses: cache_stats.misses,
+            last_compaction: self.last_compaction.lock().unwrap().clone(),
+        }
+    }
+
+    /// Per-operation (`put`/`get`/`delete`/`query`/`compact`) latency
+    /// histograms, counts, and error totals rendered as Prometheus text
+    /// exposition, for a `/metrics` scrape endpoint. Distinct from
+    /// `metrics()`'s point-in-time footprint snapshot -- this tracks
+    /// latency over the store's whole lifetime. See `crate::metrics`.
+    pub fn metrics_snapshot(&self) -> String {
+        self.metrics_registry.prometheus_text()
+    }
+
+    /// Like `metrics_snapshot`, but as a JSON-able struct instead of
+    /// Prometheus text, for callers that don't run a scraper (e.g. the NAPI
+    /// `metrics()` binding).
+    pub fn metrics_registry_snapshot(&self) -> crate::metrics::MetricsRegistrySnapshot {
+        self.metrics_registry.snapshot()
+    }
+
+    /// Bring this store's on-disk WAL and SST files up to
+    /// `store::format::CURRENT_VERSION`, in place. Idempotent: files already
+    /// tagged with the current version are left untouched. Safe to call on a
+    /// freshly-opened store before doing anything else with it.
+    pub fn upgrade(&self) -> io::Result<UpgradeSummary> {
+        let mut summary = UpgradeSummary::default();
+
+        let wal_path = self.path.join("wal.log");
+        if self.path.join("wal.log").exists() {
+            let report = crate::store::wal::upgrade_wal_file(&wal_path)?;
+            summary.wal_entries_migrated = report.migrated;
+            summary.wal_upgraded = !report.already_current;
+        }
+
+        if let Ok(entries) = std::fs::read_dir(&self.path) {
+            for entry in entries.flatten() {
+                let p = entry.path();
+                if p.extension().and_then(|e| e.to_str()) == Some("sst") {
+                    let report = SstReader::upgrade(&p)?;
+                    if !report.already_current {
+                        summary.ssts_upgraded += 1;
+                        summary.sst_entries_migrated += report.migrated;
+                    }
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Scan the WAL and every on-disk `*.sst` file for a torn write (data
+    /// left behind by a crash mid-`append`/mid-flush) and truncate each
+    /// back to its last fully-valid record -- see `WriteAheadLog::recover`
+    /// and `SstReader::repair`. Returns one `RepairReport` for the WAL
+    /// followed by one per `*.sst` file, in the same order `upgrade` walks
+    /// them; a file with no corruption still gets a report, just one with
+    /// `bytes_truncated: 0`. Clears the read cache afterward, since a
+    /// truncated file's tail may have held the only copy of a key this
+    /// store had cached. Bubbles up an `Err` without truncating anything
+    /// if the WAL's corruption turns out to be mid-log, not a torn tail --
+    /// see `WriteAheadLog::recover`'s doc comment.
+    pub fn repair(&mut self) -> io::Result<Vec<RepairReport>> {
+        let mut reports = vec![self.wal.recover()?];
+
+        if let Ok(entries) = std::fs::read_dir(&self.path) {
+            for entry in entries.flatten() {
+                let p = entry.path();
+                if p.extension().and_then(|e| e.to_str()) == Some("sst") {
+                    reports.push(SstReader::repair(&p)?);
+                }
+            }
+        }
+
+        if reports.iter().any(|r| r.bytes_truncated > 0) {
+            self.read_cache.clear();
+        }
+
+        Ok(reports)
     }
 
     /// Put with FieldValue support
@@ -416,18 +1836,98 @@ impl FireLocal {
             None
         };
 
-        // Process FieldValue operations
+        // Process FieldValue operations directly on the decoded value —
+        // no need to round-trip through a JSON string first.
         process_field_values(&mut data, existing_data.as_ref());
 
-        // Convert to JSON and put
-        let json_str = serde_json::to_string(&data)
+        let bytes = serde_json::to_vec(&data)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        self.put(key, json_str.into_bytes())
+        self.put(key, bytes).map_err(io::Error::from)
     }
 
     /// Get the current configuration
     pub fn config(&self) -> Option<&FireLocalConfig> {
         self.config.as_ref()
     }
+
+    /// Every live (key, raw value) pair currently visible in the store: the
+    /// memtable's entries, then each SST newest-first for any key the
+    /// memtable hasn't already accounted for. Tombstones are skipped.
+    fn all_entries(&self) -> Vec<(String, Vec<u8>)> {
+        let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut entries = Vec::new();
+
+        for (key, entry) in self.memtable.iter() {
+            seen.insert(key.clone());
+            match entry {
+                crate::store::memtable::Entry::Put(bytes) => {
+                    entries.push((key.clone(), bytes.clone()));
+                }
+                crate::store::memtable::Entry::Merge { base, operands } => {
+                    let base = base.clone().or_else(|| self.sst_lookup(key));
+                    entries.push((key.clone(), self.resolve_merge(base.as_deref(), operands)));
+                }
+                crate::store::memtable::Entry::Delete => {}
+            }
+        }
+
+        for sst_mutex in &self.ssts {
+            let mut sst = sst_mutex.lock().unwrap();
+            if let Ok(sst_entries) = sst.all_entries() {
+                for (key, result) in sst_entries {
+                    if !seen.insert(key.clone()) {
+                        continue;
+                    }
+                    if let SstSearchResult::Found(bytes) = result {
+                        entries.push((key, bytes));
+                    }
+                }
+            }
+        }
+
+        entries
+    }
+
+    /// Every document currently visible in the store (see `all_entries`).
+    fn all_documents(&self) -> Vec<Document> {
+        self.all_entries()
+            .into_iter()
+            .filter_map(|(_, bytes)| {
+                std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|s| Document::from_json(s).ok())
+            })
+            .collect()
+    }
+
+    /// Every key currently visible in the store, sorted, for callers (like
+    /// the scrub worker) that need a stable, resumable enumeration order.
+    pub fn all_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.all_entries().into_iter().map(|(k, _)| k).collect();
+        keys.sort();
+        keys
+    }
+
+    /// Serialize every document into a versioned, compressed dump archive
+    /// (see `crate::dump`). Index state is left out on purpose — `restore`
+    /// repopulates indexes by replaying documents through `put`.
+    pub fn dump(&self, codec: crate::dump::Codec) -> Result<Vec<u8>> {
+        let payload = crate::dump::DumpPayload {
+            documents: self.all_documents(),
+        };
+        crate::dump::encode_dump(&payload, codec)
+    }
+
+    /// Load a dump produced by `dump` (from this build or an older one) and
+    /// write each document back with `put`, rebuilding indexes as it goes.
+    /// Keys present locally but absent from the dump are left untouched.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<()> {
+        let payload = crate::dump::decode_dump(bytes)?;
+        for doc in payload.documents {
+            let json = doc.to_json()?;
+            self.put(doc.path.clone(), json.into_bytes())?;
+        }
+        Ok(())
+    }
 }
@@ -1,14 +1,38 @@
 use crate::index::QueryAst;
 use crate::model::Document;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 pub type SnapshotCallback = Box<dyn Fn(Vec<Document>) + Send + Sync>;
 
+/// Delta between a listener's previous result set and its latest one,
+/// computed by re-running the listener's `QueryAst` — mirrors Firestore's
+/// `DocumentChange` semantics so a UI client can apply a minimal mutation
+/// instead of rebuilding its whole list from a full snapshot.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotChange {
+    pub added: Vec<Document>,
+    pub modified: Vec<Document>,
+    pub removed: Vec<Document>,
+}
+
+pub type ChangeCallback = Box<dyn Fn(SnapshotChange) + Send + Sync>;
+
+/// How a listener wants to be notified: the full result set every time, or
+/// just the delta since the last notification.
+enum Callback {
+    Snapshot(SnapshotCallback),
+    Diff(ChangeCallback),
+}
+
 struct ListenerEntry {
     query: QueryAst,
-    callback: SnapshotCallback,
+    callback: Callback,
+    /// The listener's previous result set, keyed by document path, so the
+    /// next notification can diff against it. Unused (and left empty) in
+    /// full-snapshot mode.
+    last_results: HashMap<String, Document>,
 }
 
 pub struct ListenerManager {
@@ -30,18 +54,45 @@ impl ListenerManager {
         }
     }
 
+    /// Register a listener that receives the full result set on every
+    /// notification.
     pub fn register(&self, query: QueryAst, callback: SnapshotCallback) -> u64 {
+        self.insert(query, Callback::Snapshot(callback))
+    }
+
+    /// Register a listener that receives only the added/modified/removed
+    /// delta since its last notification (computed against `query`).
+    pub fn register_diff(&self, query: QueryAst, callback: ChangeCallback) -> u64 {
+        self.insert(query, Callback::Diff(callback))
+    }
+
+    fn insert(&self, query: QueryAst, callback: Callback) -> u64 {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
-        
+
         let mut listeners = match self.listeners.lock() {
             Ok(guard) => guard,
             Err(poisoned) => poisoned.into_inner(),
         };
 
-        listeners.insert(id, ListenerEntry { query, callback });
+        listeners.insert(
+            id,
+            ListenerEntry {
+                query,
+                callback,
+                last_results: HashMap::new(),
+            },
+        );
         id
     }
 
+    /// Number of currently registered listeners, for `FireLocal::metrics`.
+    pub fn count(&self) -> usize {
+        match self.listeners.lock() {
+            Ok(guard) => guard.len(),
+            Err(poisoned) => poisoned.into_inner().len(),
+        }
+    }
+
     pub fn unregister(&self, id: u64) {
         let mut listeners = match self.listeners.lock() {
             Ok(guard) => guard,
@@ -56,23 +107,35 @@ impl ListenerManager {
             Ok(guard) => guard,
             Err(poisoned) => poisoned.into_inner(),
         };
-        
+
         listeners
             .iter()
             .map(|(id, entry)| (*id, entry.query.clone()))
             .collect()
     }
 
-    /// Notify a specific listener without holding the global lock
+    /// Notify a specific listener; the listeners lock is held while its
+    /// callback runs. `docs` is
+    /// the query's current full result set; in diff mode this is compared
+    /// against the listener's last result set (documents that fall out of
+    /// the query, because they no longer match it or were deleted, show up
+    /// as `removed`) and the listener's stored results are updated to `docs`
+    /// for the next call.
     pub fn notify_single(&self, id: u64, docs: Vec<Document>) {
-        // Execute callback while holding the lock (simpler and safer)
-        let listeners = match self.listeners.lock() {
+        let mut listeners = match self.listeners.lock() {
             Ok(guard) => guard,
             Err(poisoned) => poisoned.into_inner(),
         };
-        
-        if let Some(entry) = listeners.get(&id) {
-            (entry.callback)(docs);
+
+        if let Some(entry) = listeners.get_mut(&id) {
+            match &entry.callback {
+                Callback::Snapshot(callback) => callback(docs),
+                Callback::Diff(callback) => {
+                    let change = diff_snapshot(&entry.last_results, &docs);
+                    entry.last_results = docs.into_iter().map(|d| (d.path.clone(), d)).collect();
+                    callback(change);
+                }
+            }
         }
     }
 
@@ -92,3 +155,106 @@ impl ListenerManager {
         self.notify_single(id, docs);
     }
 }
+
+/// Compare a listener's previous result set against its current one,
+/// classifying each path as added, modified (present in both but with
+/// different fields/version), or removed (present before, absent now).
+fn diff_snapshot(previous: &HashMap<String, Document>, current: &[Document]) -> SnapshotChange {
+    let mut change = SnapshotChange::default();
+    let mut seen = std::collections::HashSet::with_capacity(current.len());
+
+    for doc in current {
+        seen.insert(doc.path.as_str());
+        match previous.get(&doc.path) {
+            None => change.added.push(doc.clone()),
+            Some(prev) if prev != doc => change.modified.push(doc.clone()),
+            Some(_) => {}
+        }
+    }
+
+    for (path, doc) in previous {
+        if !seen.contains(path.as_str()) {
+            change.removed.push(doc.clone());
+        }
+    }
+
+    change
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{QueryAst, QueryOperator};
+    use serde_json::json;
+    use std::sync::Mutex as StdMutex;
+
+    fn doc(path: &str, name: &str) -> Document {
+        Document {
+            path: path.to_string(),
+            fields: json!({ "name": name }).as_object().unwrap().clone(),
+            version: 0,
+            ..Default::default()
+        }
+    }
+
+    fn dummy_query() -> QueryAst {
+        QueryAst {
+            collection: None,
+            field: "name".to_string(),
+            operator: QueryOperator::Equal(json!("x")),
+        }
+    }
+
+    #[test]
+    fn test_diff_mode_reports_added_modified_removed() {
+        let manager = ListenerManager::new();
+        let changes: Arc<StdMutex<Vec<SnapshotChange>>> = Arc::new(StdMutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+
+        let id = manager.register_diff(
+            dummy_query(),
+            Box::new(move |change| changes_clone.lock().unwrap().push(change)),
+        );
+
+        // First notification: everything is new.
+        manager.notify_single(id, vec![doc("users/alice", "Alice"), doc("users/bob", "Bob")]);
+        // Second notification: alice's data changed, bob dropped out, carol joined.
+        manager.notify_single(
+            id,
+            vec![doc("users/alice", "Alicia"), doc("users/carol", "Carol")],
+        );
+
+        let recorded = changes.lock().unwrap();
+        assert_eq!(recorded.len(), 2);
+
+        assert_eq!(recorded[0].added.len(), 2);
+        assert!(recorded[0].modified.is_empty());
+        assert!(recorded[0].removed.is_empty());
+
+        assert_eq!(recorded[1].added.len(), 1);
+        assert_eq!(recorded[1].added[0].path, "users/carol");
+        assert_eq!(recorded[1].modified.len(), 1);
+        assert_eq!(recorded[1].modified[0].path, "users/alice");
+        assert_eq!(recorded[1].removed.len(), 1);
+        assert_eq!(recorded[1].removed[0].path, "users/bob");
+    }
+
+    #[test]
+    fn test_snapshot_mode_still_gets_full_results() {
+        let manager = ListenerManager::new();
+        let received: Arc<StdMutex<Vec<Vec<Document>>>> = Arc::new(StdMutex::new(Vec::new()));
+        let received_clone = received.clone();
+
+        let id = manager.register(
+            dummy_query(),
+            Box::new(move |docs| received_clone.lock().unwrap().push(docs)),
+        );
+
+        manager.notify_single(id, vec![doc("users/alice", "Alice")]);
+        manager.notify_single(id, vec![doc("users/alice", "Alice")]);
+
+        let recorded = received.lock().unwrap();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[1].len(), 1);
+    }
+}
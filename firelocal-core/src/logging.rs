@@ -1,6 +1,26 @@
-use log::{error, info, warn};
+use crate::audit::AuditChain;
+use log::{debug, error, info, warn};
+use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
+static AUDIT_CHAIN: OnceLock<AuditChain> = OnceLock::new();
+
+/// Turn on hash-chained audit logging for every future `log_security_event`
+/// call, via `SecurityConfig::audit_chain_enabled`. `sink_path` persists the
+/// chain to an append-only file so it survives a restart; `None` keeps it
+/// in-memory only. Only the first call takes effect -- the chain is
+/// process-wide, matching `log_security_event` itself already being a free
+/// function with no per-caller state.
+pub fn enable_audit_chain(sink_path: Option<PathBuf>) -> std::io::Result<()> {
+    if AUDIT_CHAIN.get().is_some() {
+        return Ok(());
+    }
+    let chain = AuditChain::new(sink_path.as_deref())?;
+    let _ = AUDIT_CHAIN.set(chain);
+    Ok(())
+}
+
 /// Performance metrics for operations
 #[derive(Debug, Clone)]
 pub struct PerformanceMetrics {
@@ -179,9 +199,17 @@ pub fn log_database_operation(operation: &str, path: &str, success: bool, durati
     }
 }
 
-/// Log security events
+/// Log security events. Also appends to the hash-chained audit log (see
+/// `crate::audit`) once `enable_audit_chain` has been called -- a no-op
+/// otherwise, so this stays safe to call unconditionally the way it always
+/// has been.
 pub fn log_security_event(event: &str, details: &str) {
     warn!("SECURITY: {} - {}", event, details);
+    if let Some(chain) = AUDIT_CHAIN.get() {
+        if let Err(e) = chain.log(event, details) {
+            error!("failed to append audit record for {event}: {e}");
+        }
+    }
 }
 
 /// Log performance metrics
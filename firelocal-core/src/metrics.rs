@@ -0,0 +1,285 @@
+//! Runtime observability: `FireLocal::metrics()` reports memtable/SST/WAL
+//! footprint, listener count, cumulative put/delete/get counters, read-cache
+//! hit/miss rates, and the last compaction's stats, so an embedding app can
+//! decide when to `flush`/`compact` instead of guessing. Exposed over FFI as
+//! `firelocal_metrics_json`.
+
+use crate::store::compaction::CompactionStats;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Point-in-time view of `FireLocal`'s internal state, returned by
+/// `FireLocal::metrics()`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsSnapshot {
+    pub memtable_entries: usize,
+    pub memtable_bytes: usize,
+    pub sst_count: usize,
+    pub sst_bytes: u64,
+    pub wal_bytes_appended: u64,
+    pub listener_count: usize,
+    pub puts: u64,
+    pub deletes: u64,
+    pub gets: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub last_compaction: Option<CompactionStats>,
+}
+
+/// Cumulative put/delete/get counters, bumped inline by the operations they
+/// count. Plain atomics rather than a `Mutex`-guarded struct since `get`
+/// only takes `&self`.
+#[derive(Debug, Default)]
+pub struct OperationCounters {
+    pub puts: AtomicU64,
+    pub deletes: AtomicU64,
+    pub gets: AtomicU64,
+}
+
+impl OperationCounters {
+    pub fn record_put(&self) {
+        self.puts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_delete(&self) {
+        self.deletes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_get(&self) {
+        self.gets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> (u64, u64, u64) {
+        (
+            self.puts.load(Ordering::Relaxed),
+            self.deletes.load(Ordering::Relaxed),
+            self.gets.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Upper bound (inclusive), in microseconds, of each latency bucket tracked
+/// by `MetricsRegistry` -- successive powers of two from 1us up to ~1.05s.
+/// An observation past the last bucket still counts toward the implicit
+/// Prometheus `+Inf` bucket at render time.
+const LATENCY_BUCKETS_US: [u64; 21] = [
+    1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1_024, 2_048, 4_096, 8_192, 16_384, 32_768, 65_536,
+    131_072, 262_144, 524_288, 1_048_576,
+];
+
+/// One operation type `MetricsRegistry` keeps a separate latency histogram
+/// for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpKind {
+    Put,
+    Get,
+    Delete,
+    Query,
+    Compact,
+}
+
+impl OpKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            OpKind::Put => "put",
+            OpKind::Get => "get",
+            OpKind::Delete => "delete",
+            OpKind::Query => "query",
+            OpKind::Compact => "compact",
+        }
+    }
+}
+
+/// Point-in-time view of one `OpHistogram`, for the NAPI `metrics()`
+/// binding's JSON. Doesn't expose per-bucket counts -- those only matter to
+/// the Prometheus exposition a scraper consumes directly.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct OpMetricsSnapshot {
+    pub count: u64,
+    pub errors: u64,
+    pub total_micros: u64,
+}
+
+/// Latency histogram for one operation type: one counter per
+/// `LATENCY_BUCKETS_US` bound, plus running count/error/total-duration
+/// counters for the `_count`/`_sum` lines a Prometheus histogram also
+/// needs. All atomics, like `OperationCounters`, so `get` (which only takes
+/// `&self`) can still record into it.
+#[derive(Debug)]
+struct OpHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_US.len()],
+    count: AtomicU64,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+impl OpHistogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, duration: Duration, success: bool) {
+        let micros = duration.as_micros().min(u64::MAX as u128) as u64;
+        if let Some(bucket) = LATENCY_BUCKETS_US.iter().position(|&bound| micros <= bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        if !success {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> OpMetricsSnapshot {
+        OpMetricsSnapshot {
+            count: self.count.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            total_micros: self.total_micros.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Append this histogram's Prometheus bucket/`_sum`/`_count` lines
+    /// (plus an `_errors_total` counter) to `out`, labeled `op="<op>"`.
+    /// Bucket counts are stored per-bucket, not cumulatively, so this
+    /// accumulates them into the `le="..."` running totals Prometheus
+    /// expects on the way out.
+    fn write_prometheus(&self, out: &mut String, op: &str) {
+        let mut cumulative = 0u64;
+        for (bound, bucket) in LATENCY_BUCKETS_US.iter().zip(&self.buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = *bound as f64 / 1_000_000.0;
+            out.push_str(&format!(
+                "firelocal_op_duration_seconds_bucket{{op=\"{op}\",le=\"{le}\"}} {cumulative}\n"
+            ));
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        out.push_str(&format!(
+            "firelocal_op_duration_seconds_bucket{{op=\"{op}\",le=\"+Inf\"}} {count}\n"
+        ));
+        out.push_str(&format!(
+            "firelocal_op_duration_seconds_sum{{op=\"{op}\"}} {}\n",
+            self.total_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        ));
+        out.push_str(&format!(
+            "firelocal_op_duration_seconds_count{{op=\"{op}\"}} {count}\n"
+        ));
+        out.push_str(&format!(
+            "firelocal_op_errors_total{{op=\"{op}\"}} {}\n",
+            self.errors.load(Ordering::Relaxed)
+        ));
+    }
+}
+
+impl Default for OpHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Snapshot of every operation type's `OpHistogram`, for the NAPI
+/// `metrics()` binding's JSON payload.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MetricsRegistrySnapshot {
+    pub put: OpMetricsSnapshot,
+    pub get: OpMetricsSnapshot,
+    pub delete: OpMetricsSnapshot,
+    pub query: OpMetricsSnapshot,
+    pub compact: OpMetricsSnapshot,
+}
+
+/// One `OpHistogram` per operation type (`put`/`get`/`delete`/`query`/
+/// `compact`), fed by `FireLocal`'s core methods via `timed_operation!` (see
+/// `crate::logging`). `FireLocal::metrics_snapshot()` renders it as
+/// Prometheus text exposition; the NAPI `metrics()` binding exposes
+/// `snapshot()` as JSON instead for callers that don't run a scraper.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    put: OpHistogram,
+    get: OpHistogram,
+    delete: OpHistogram,
+    query: OpHistogram,
+    compact: OpHistogram,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn histogram(&self, op: OpKind) -> &OpHistogram {
+        match op {
+            OpKind::Put => &self.put,
+            OpKind::Get => &self.get,
+            OpKind::Delete => &self.delete,
+            OpKind::Query => &self.query,
+            OpKind::Compact => &self.compact,
+        }
+    }
+
+    pub fn record(&self, op: OpKind, duration: Duration, success: bool) {
+        self.histogram(op).record(duration, success);
+    }
+
+    /// Render every operation's histogram as Prometheus text exposition,
+    /// suitable for a `/metrics` scrape endpoint.
+    pub fn prometheus_text(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP firelocal_op_duration_seconds Latency of FireLocal operations.\n");
+        out.push_str("# TYPE firelocal_op_duration_seconds histogram\n");
+        for op in [
+            OpKind::Put,
+            OpKind::Get,
+            OpKind::Delete,
+            OpKind::Query,
+            OpKind::Compact,
+        ] {
+            self.histogram(op).write_prometheus(&mut out, op.as_str());
+        }
+        out
+    }
+
+    pub fn snapshot(&self) -> MetricsRegistrySnapshot {
+        MetricsRegistrySnapshot {
+            put: self.put.snapshot(),
+            get: self.get.snapshot(),
+            delete: self.delete.snapshot(),
+            query: self.query.snapshot(),
+            compact: self.compact.snapshot(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_into_the_matching_bucket() {
+        let registry = MetricsRegistry::new();
+        registry.record(OpKind::Get, Duration::from_micros(10), true);
+        registry.record(OpKind::Get, Duration::from_micros(10_000_000), false);
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.get.count, 2);
+        assert_eq!(snapshot.get.errors, 1);
+        assert_eq!(snapshot.put.count, 0);
+    }
+
+    #[test]
+    fn prometheus_text_includes_every_op_and_a_cumulative_inf_bucket() {
+        let registry = MetricsRegistry::new();
+        registry.record(OpKind::Put, Duration::from_micros(5), true);
+
+        let text = registry.prometheus_text();
+        assert!(text.contains(r#"op="put""#));
+        assert!(text.contains(r#"op="compact""#));
+        assert!(text.contains(r#"le="+Inf""#));
+        assert!(text.contains("firelocal_op_duration_seconds_count{op=\"put\"} 1"));
+    }
+}
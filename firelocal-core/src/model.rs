@@ -1,13 +1,23 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
 use serde_json::Value;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Document {
     pub path: String,
     pub fields: Map<String, Value>,
     #[serde(default)]
     pub version: u64,
+    /// Per-node write counters (`node_id` -> count), compared on sync pull
+    /// to tell whether one side's edit causally dominates the other or the
+    /// two are concurrent. See `sync::conflict`.
+    #[serde(default)]
+    pub version_vector: HashMap<String, u64>,
+    /// Wall-clock milliseconds of this document's last local write; the
+    /// tiebreak the default last-writer-wins `ConflictResolver` uses.
+    #[serde(default)]
+    pub updated_at_ms: u64,
     // TODO: Add create_time, update_time for M3/M4
 }
 
@@ -20,3 +30,63 @@ impl Document {
         serde_json::to_string(self)
     }
 }
+
+/// Resolve a dotted field path like `address.city` or `tags.0` against a
+/// document's fields, descending into nested objects by key and arrays by
+/// index. Resolution is permissive: a missing field, an out-of-range index,
+/// or a path that walks through a scalar all yield `Value::Null` rather than
+/// an error, so indexes and queries can target paths that aren't present on
+/// every document.
+pub fn resolve_path(fields: &Map<String, Value>, path: &str) -> Value {
+    let mut segments = path.split('.');
+    let mut current = match segments.next() {
+        Some(first) => fields.get(first),
+        None => None,
+    };
+
+    for segment in segments {
+        current = match current {
+            Some(Value::Object(map)) => map.get(segment),
+            Some(Value::Array(arr)) => segment.parse::<usize>().ok().and_then(|i| arr.get(i)),
+            _ => None,
+        };
+    }
+
+    current.cloned().unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(value: Value) -> Map<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_path_nested_object() {
+        let fields = fields(json!({ "address": { "city": "NYC" } }));
+        assert_eq!(resolve_path(&fields, "address.city"), json!("NYC"));
+    }
+
+    #[test]
+    fn test_resolve_path_array_index() {
+        let fields = fields(json!({ "tags": ["a", "b", "c"] }));
+        assert_eq!(resolve_path(&fields, "tags.1"), json!("b"));
+    }
+
+    #[test]
+    fn test_resolve_path_top_level_field() {
+        let fields = fields(json!({ "age": 30 }));
+        assert_eq!(resolve_path(&fields, "age"), json!(30));
+    }
+
+    #[test]
+    fn test_resolve_path_missing_segment_is_null() {
+        let fields = fields(json!({ "address": { "city": "NYC" } }));
+        assert_eq!(resolve_path(&fields, "address.zip"), Value::Null);
+        assert_eq!(resolve_path(&fields, "missing.deeply.nested"), Value::Null);
+        assert_eq!(resolve_path(&fields, "address.city.extra"), Value::Null);
+    }
+}
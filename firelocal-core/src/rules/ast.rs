@@ -1,3 +1,5 @@
+use crate::rules::condition::{EvalContext, Expr as ConditionExpr};
+use serde_json::{Map, Value};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -17,6 +19,9 @@ pub struct MatchBlock {
 pub struct AllowStatement {
     pub operations: Vec<String>,
     pub condition: String,
+    /// `condition` tokenized and parsed into an evaluable AST, checked by
+    /// `matches_recursive` via `condition::evaluate_condition`.
+    pub condition_ast: ConditionExpr,
 }
 
 impl Ruleset {
@@ -24,31 +29,64 @@ impl Ruleset {
         &self,
         path: &str,
         operation: &str,
-        context: &HashMap<String, String>,
+        auth_uid: Option<&str>,
+        existing_data: Option<&Map<String, Value>>,
+        incoming_data: Option<&Map<String, Value>>,
     ) -> bool {
-        self.match_block.matches_recursive(path, operation, context)
+        let path_params = HashMap::new();
+        self.match_block.matches_recursive(
+            path,
+            operation,
+            auth_uid,
+            existing_data,
+            incoming_data,
+            &path_params,
+        )
     }
 }
 
 impl MatchBlock {
-    // Returns true if this block or any sub-block allows the operation on the path
+    /// Returns true if this block or any sub-block allows the operation on
+    /// the path. `auth_uid`/`existing_data`/`incoming_data` carry the
+    /// request/resource values a condition can reference and are unchanged
+    /// throughout the recursion; `path_params` carries the wildcard bindings
+    /// captured along the way (`{userId}`, `{document=**}`) and is extended
+    /// into a copy scoped to this branch, so a failed sub-match backtracks
+    /// without leaking its bindings into a sibling branch tried afterward.
+    /// A condition that errors (a missing field, a type mismatch) is treated
+    /// as not granting access rather than propagating the error, matching
+    /// `evaluate_condition`'s fail-closed contract.
     pub fn matches_recursive(
         &self,
         remaining_path: &str,
         operation: &str,
-        _context: &HashMap<String, String>,
+        auth_uid: Option<&str>,
+        existing_data: Option<&Map<String, Value>>,
+        incoming_data: Option<&Map<String, Value>>,
+        path_params: &HashMap<String, String>,
     ) -> bool {
         // 1. Try to consume the current pattern from the remaining path
-        if let Some(remainder) = self.consume_pattern(remaining_path) {
-            // Match successful!
+        if let Some((remainder, captured)) = self.consume_pattern(remaining_path) {
+            let mut local_params = path_params.clone();
+            local_params.extend(captured);
 
             // 2. If exact match (remainder empty or just /), check ALLOWS
             let is_exact = remainder.trim_matches('/').is_empty();
             if is_exact {
+                let eval_ctx = EvalContext {
+                    auth_uid,
+                    incoming_data,
+                    existing_data,
+                    path_params: &local_params,
+                };
                 for allow in &self.allow_statements {
                     if (allow.operations.contains(&operation.to_string())
                         || allow.operations.contains(&"match_all".to_string()))
-                        && allow.condition.trim() == "true"
+                        && crate::rules::condition::evaluate_condition(
+                            &allow.condition_ast,
+                            &eval_ctx,
+                        )
+                        .unwrap_or(false)
                     {
                         return true;
                     }
@@ -57,7 +95,14 @@ impl MatchBlock {
 
             // 3. Check sub-matches with the remainder
             for sub in &self.sub_matches {
-                if sub.matches_recursive(remainder, operation, _context) {
+                if sub.matches_recursive(
+                    remainder,
+                    operation,
+                    auth_uid,
+                    existing_data,
+                    incoming_data,
+                    &local_params,
+                ) {
                     return true;
                 }
             }
@@ -66,7 +111,13 @@ impl MatchBlock {
         false
     }
 
-    fn consume_pattern<'a>(&self, path: &'a str) -> Option<&'a str> {
+    /// Match `self.path_pattern` against the start of `path`, returning the
+    /// unconsumed remainder plus any `{name}`/`{name=**}` wildcard bindings
+    /// captured along the way. A `{name=**}` segment binds the entire tail of
+    /// the path from that segment onward, slashes included, and consumes the
+    /// whole thing (an empty remainder), matching Firestore's recursive
+    /// wildcard semantics.
+    fn consume_pattern<'a>(&self, path: &'a str) -> Option<(&'a str, HashMap<String, String>)> {
         let pattern_segments: Vec<&str> = self
             .path_pattern
             .split('/')
@@ -78,21 +129,18 @@ impl MatchBlock {
             return None;
         }
 
-        // Check segments
+        let mut bindings = HashMap::new();
+
         for (i, p_seg) in pattern_segments.iter().enumerate() {
             let doc_seg = path_segments[i];
 
-            if p_seg.starts_with('{') && p_seg.ends_with('}') {
-                if p_seg.contains("=**") {
-                    // Recursive wildcard: Matches everything remaining.
-                    // If this is the last pattern segment, we return "" as remainder (conceptually consumed all relevant for this block? Or matches rest?)
-                    // In Firestore: match /{document=**} means document variable captures the REST of path.
-                    // So we successfully match ALL of it.
-                    // Remainder should be empty to trigger "exact match" allows?
-                    // Yes. And we need to support sub-matches? Rarely used with **.
-                    return Some("");
+            if let Some(inner) = p_seg.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                if let Some(name) = inner.strip_suffix("=**") {
+                    let tail = &path[Self::skip_segments(path, i)..];
+                    bindings.insert(name.to_string(), tail.to_string());
+                    return Some(("", bindings));
                 }
-                // Variable match: Matches any single segment
+                bindings.insert(inner.to_string(), doc_seg.to_string());
                 continue;
             }
 
@@ -101,85 +149,28 @@ impl MatchBlock {
             }
         }
 
-        // Reconstruct remainder
-        // Skip the consumed segments.
-        // We need to find where the consumed part ended in the original string?
-        // Or simpler: rejoin the remaining segments.
-        // But rejoining allocates. We want &str.
-        // Let's approximate:
-        // We matched `pattern_segments.len()` segments.
-        // Total path segments available.
-        // If we matched all, remainder is the rest.
-
-        // Reconstructing from index is annoying with split.
-        // Let's iterate original string splitting?
-
-        let consumed_count = pattern_segments.len();
-
-        // Find the byte offset of the Nth slash-separated segment end
-        let _ = path; // usage
-
-        // Simplification for M4: Reconstruct string and leak? No.
-        // Return byte index?
-        // Let's just use re-split assumption:
-        // Pass the substring starting after the Nth non-empty segment.
+        let remainder = &path[Self::skip_segments(path, pattern_segments.len())..];
+        Some((remainder, bindings))
+    }
 
-        let mut current_matches = 0;
-        let p_bytes = path.as_bytes();
+    /// Byte offset in `path` after skipping `count` leading `/`-separated
+    /// segments (and the slashes between/after them), starting past any
+    /// leading slashes. Used to locate both an ordinary remainder and the
+    /// start of a `{name=**}` segment's captured tail.
+    fn skip_segments(path: &str, count: usize) -> usize {
+        let bytes = path.as_bytes();
         let mut i = 0;
-
-        // Skip leading slashes
-        while i < p_bytes.len() && p_bytes[i] == b'/' {
+        while i < bytes.len() && bytes[i] == b'/' {
             i += 1;
         }
-
-        let start_idx = i;
-
-        if consumed_count == 0 {
-            return Some(path);
-        }
-
-        while current_matches < consumed_count {
-            if i >= p_bytes.len() {
-                // If we ran out of string but matched segments, it means we consumed everything?
-                // But we checked len earlier.
-                break;
-            }
-            if p_bytes[i] == b'/' {
-                current_matches += 1;
-                while i < p_bytes.len() && p_bytes[i] == b'/' {
-                    i += 1;
-                } // skip multiple slashes
-            } else {
+        for _ in 0..count {
+            while i < bytes.len() && bytes[i] != b'/' {
                 i += 1;
             }
-        }
-
-        // Check if we finished the last segment
-        if current_matches < consumed_count {
-            // We didn't find N separators.
-            // That means the last segment goes to end of string?
-            // Example: path "a/b", consume 2.
-            // i goes to end. current_matches = 1 (sep after a).
-            // separator count is segments - 1.
-            // So if we consume N segments, we might pass N-1 separators.
-            // Correct logic: Scan past N segments.
-        }
-
-        // Let's restart scan
-        i = start_idx;
-        for _ in 0..consumed_count {
-            // Scan one segment
-            while i < p_bytes.len() && p_bytes[i] != b'/' {
-                i += 1;
-            }
-            // Consumed one segment.
-            // Scan past separators
-            while i < p_bytes.len() && p_bytes[i] == b'/' {
+            while i < bytes.len() && bytes[i] == b'/' {
                 i += 1;
             }
         }
-
-        Some(&path[i..])
+        i
     }
 }
@@ -0,0 +1,765 @@
+//! Lexer, parser, and evaluator for the condition expression in an
+//! `allow <ops>: if <condition>;` statement, e.g.
+//! `request.auth != null && request.auth.uid in resource.data.members`.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+    In,
+    Dot,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    EqEq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    AndAnd,
+    OrOr,
+    Bang,
+    Eof,
+}
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+        }
+    }
+
+    fn tokenize(mut self) -> Result<Vec<Token>, String> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let Some(&c) = self.chars.peek() else {
+                tokens.push(Token::Eof);
+                break;
+            };
+
+            let token = match c {
+                '.' => {
+                    self.chars.next();
+                    Token::Dot
+                }
+                ',' => {
+                    self.chars.next();
+                    Token::Comma
+                }
+                '(' => {
+                    self.chars.next();
+                    Token::LParen
+                }
+                ')' => {
+                    self.chars.next();
+                    Token::RParen
+                }
+                '[' => {
+                    self.chars.next();
+                    Token::LBracket
+                }
+                ']' => {
+                    self.chars.next();
+                    Token::RBracket
+                }
+                '!' => {
+                    self.chars.next();
+                    if self.eat('=') {
+                        Token::NotEq
+                    } else {
+                        Token::Bang
+                    }
+                }
+                '=' => {
+                    self.chars.next();
+                    if self.eat('=') {
+                        Token::EqEq
+                    } else {
+                        return Err("unexpected '=' (did you mean '=='?)".to_string());
+                    }
+                }
+                '<' => {
+                    self.chars.next();
+                    if self.eat('=') {
+                        Token::LtEq
+                    } else {
+                        Token::Lt
+                    }
+                }
+                '>' => {
+                    self.chars.next();
+                    if self.eat('=') {
+                        Token::GtEq
+                    } else {
+                        Token::Gt
+                    }
+                }
+                '&' => {
+                    self.chars.next();
+                    if self.eat('&') {
+                        Token::AndAnd
+                    } else {
+                        return Err("unexpected '&' (did you mean '&&'?)".to_string());
+                    }
+                }
+                '|' => {
+                    self.chars.next();
+                    if self.eat('|') {
+                        Token::OrOr
+                    } else {
+                        return Err("unexpected '|' (did you mean '||'?)".to_string());
+                    }
+                }
+                '\'' | '"' => self.read_string(c)?,
+                c if c.is_ascii_digit() => self.read_number(),
+                c if c.is_alphabetic() || c == '_' => self.read_ident_or_keyword(),
+                other => return Err(format!("unexpected character '{other}' in condition")),
+            };
+            tokens.push(token);
+        }
+        Ok(tokens)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.chars.peek() == Some(&expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_string(&mut self, quote: char) -> Result<Token, String> {
+        self.chars.next(); // opening quote
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == quote => return Ok(Token::Str(s)),
+                Some(c) => s.push(c),
+                None => return Err("unterminated string literal in condition".to_string()),
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Token {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                s.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        Token::Number(s.parse().unwrap_or(0.0))
+    }
+
+    fn read_ident_or_keyword(&mut self) -> Token {
+        let mut s = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                s.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        match s.as_str() {
+            "true" => Token::Bool(true),
+            "false" => Token::Bool(false),
+            "null" => Token::Null,
+            "in" => Token::In,
+            _ => Token::Ident(s),
+        }
+    }
+}
+
+/// Runtime value produced by evaluating an `Expr`. Reuses `serde_json::Value`
+/// so document field comparisons don't need a parallel value type.
+pub type EvalValue = Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    In,
+}
+
+/// Condition AST produced by `parse_condition`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Literal),
+    List(Vec<Expr>),
+    /// A dotted identifier chain, e.g. `request.auth.uid` -> `["request", "auth", "uid"]`.
+    FieldAccess(Vec<String>),
+    UnaryOp {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    BinaryOp {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    /// `receiver.name(args)`, or `name(args)` when `receiver` is `None`.
+    FunctionCall {
+        receiver: Option<Box<Expr>>,
+        name: String,
+        args: Vec<Expr>,
+    },
+}
+
+struct ConditionParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ConditionParser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let tok = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), String> {
+        if self.peek() == expected {
+            self.advance();
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, found {:?}", expected, self.peek()))
+        }
+    }
+
+    fn parse(&mut self) -> Result<Expr, String> {
+        let expr = self.parse_binary(0)?;
+        if self.peek() != &Token::Eof {
+            return Err(format!("unexpected trailing token {:?}", self.peek()));
+        }
+        Ok(expr)
+    }
+
+    /// Precedence-climbing: `||` binds loosest, then `&&`, then equality,
+    /// then relational/`in`.
+    fn parse_binary(&mut self, min_bp: u8) -> Result<Expr, String> {
+        let mut lhs = self.parse_unary()?;
+
+        loop {
+            let (op, bp) = match self.peek() {
+                Token::OrOr => (BinaryOp::Or, 1),
+                Token::AndAnd => (BinaryOp::And, 2),
+                Token::EqEq => (BinaryOp::Eq, 3),
+                Token::NotEq => (BinaryOp::NotEq, 3),
+                Token::Lt => (BinaryOp::Lt, 4),
+                Token::LtEq => (BinaryOp::LtEq, 4),
+                Token::Gt => (BinaryOp::Gt, 4),
+                Token::GtEq => (BinaryOp::GtEq, 4),
+                Token::In => (BinaryOp::In, 4),
+                _ => break,
+            };
+            if bp < min_bp {
+                break;
+            }
+            self.advance();
+            let rhs = self.parse_binary(bp + 1)?;
+            lhs = Expr::BinaryOp {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == &Token::Bang {
+            self.advance();
+            let expr = self.parse_unary()?;
+            return Ok(Expr::UnaryOp {
+                op: UnaryOp::Not,
+                expr: Box::new(expr),
+            });
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+
+        loop {
+            if self.peek() != &Token::Dot {
+                break;
+            }
+            self.advance();
+            let name = match self.advance() {
+                Token::Ident(s) => s,
+                other => return Err(format!("expected field or method name, found {other:?}")),
+            };
+
+            if self.peek() == &Token::LParen {
+                self.advance();
+                let args = self.parse_args()?;
+                self.expect(&Token::RParen)?;
+                expr = Expr::FunctionCall {
+                    receiver: Some(Box::new(expr)),
+                    name,
+                    args,
+                };
+            } else {
+                expr = match expr {
+                    Expr::FieldAccess(mut segments) => {
+                        segments.push(name);
+                        Expr::FieldAccess(segments)
+                    }
+                    other => return Err(format!("cannot access field '{name}' on {other:?}")),
+                };
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Expr>, String> {
+        let mut args = Vec::new();
+        if self.peek() == &Token::RParen {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_binary(0)?);
+            if self.peek() == &Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.advance() {
+            Token::Null => Ok(Expr::Literal(Literal::Null)),
+            Token::Bool(b) => Ok(Expr::Literal(Literal::Bool(b))),
+            Token::Number(n) => Ok(Expr::Literal(Literal::Number(n))),
+            Token::Str(s) => Ok(Expr::Literal(Literal::String(s))),
+            Token::Ident(name) => {
+                if self.peek() == &Token::LParen {
+                    self.advance();
+                    let args = self.parse_args()?;
+                    self.expect(&Token::RParen)?;
+                    Ok(Expr::FunctionCall {
+                        receiver: None,
+                        name,
+                        args,
+                    })
+                } else {
+                    Ok(Expr::FieldAccess(vec![name]))
+                }
+            }
+            Token::LParen => {
+                let expr = self.parse_binary(0)?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::LBracket => {
+                let mut items = Vec::new();
+                if self.peek() != &Token::RBracket {
+                    loop {
+                        items.push(self.parse_binary(0)?);
+                        if self.peek() == &Token::Comma {
+                            self.advance();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                self.expect(&Token::RBracket)?;
+                Ok(Expr::List(items))
+            }
+            other => Err(format!("unexpected token {other:?} in condition")),
+        }
+    }
+}
+
+/// Tokenize and parse a condition expression, e.g. the text after `if` in an
+/// `allow read: if <condition>;` statement.
+pub fn parse_condition(source: &str) -> Result<Expr, String> {
+    let tokens = Lexer::new(source).tokenize()?;
+    let mut parser = ConditionParser { tokens, pos: 0 };
+    parser.parse()
+}
+
+/// Everything a condition might reference while being evaluated for one
+/// read/write attempt.
+pub struct EvalContext<'a> {
+    pub auth_uid: Option<&'a str>,
+    /// `request.resource.data`: the document as it would look after the write.
+    pub incoming_data: Option<&'a Map<String, Value>>,
+    /// `resource.data`: the document as it currently exists in the store.
+    pub existing_data: Option<&'a Map<String, Value>>,
+    /// Values captured from `{wildcard}` segments in the matched path.
+    pub path_params: &'a HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    /// A field access had no value at that path — Firestore rules treat this
+    /// as a hard error, not an implicit `null`, so it denies the request
+    /// rather than silently evaluating to false.
+    MissingField(String),
+    TypeMismatch(String),
+    UnknownIdentifier(String),
+    UnknownFunction(String),
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::MissingField(path) => write!(f, "missing field '{path}'"),
+            EvalError::TypeMismatch(msg) => write!(f, "type mismatch: {msg}"),
+            EvalError::UnknownIdentifier(path) => write!(f, "unknown identifier '{path}'"),
+            EvalError::UnknownFunction(name) => write!(f, "unknown function '{name}'"),
+        }
+    }
+}
+
+/// Evaluate a condition to a bool for a given request context. A missing
+/// field, unknown identifier, or type mismatch is an error (denying access)
+/// rather than a silent `false`.
+pub fn evaluate_condition(expr: &Expr, ctx: &EvalContext) -> Result<bool, EvalError> {
+    as_bool(evaluate(expr, ctx)?)
+}
+
+fn evaluate(expr: &Expr, ctx: &EvalContext) -> Result<EvalValue, EvalError> {
+    match expr {
+        Expr::Literal(Literal::Null) => Ok(Value::Null),
+        Expr::Literal(Literal::Bool(b)) => Ok(Value::Bool(*b)),
+        Expr::Literal(Literal::Number(n)) => {
+            Ok(serde_json::Number::from_f64(*n).map(Value::Number).unwrap_or(Value::Null))
+        }
+        Expr::Literal(Literal::String(s)) => Ok(Value::String(s.clone())),
+        Expr::List(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                values.push(evaluate(item, ctx)?);
+            }
+            Ok(Value::Array(values))
+        }
+        Expr::FieldAccess(path) => resolve_field(path, ctx),
+        Expr::UnaryOp {
+            op: UnaryOp::Not,
+            expr,
+        } => Ok(Value::Bool(!as_bool(evaluate(expr, ctx)?)?)),
+        Expr::BinaryOp {
+            op: BinaryOp::And,
+            lhs,
+            rhs,
+        } => {
+            if !as_bool(evaluate(lhs, ctx)?)? {
+                return Ok(Value::Bool(false));
+            }
+            Ok(Value::Bool(as_bool(evaluate(rhs, ctx)?)?))
+        }
+        Expr::BinaryOp {
+            op: BinaryOp::Or,
+            lhs,
+            rhs,
+        } => {
+            if as_bool(evaluate(lhs, ctx)?)? {
+                return Ok(Value::Bool(true));
+            }
+            Ok(Value::Bool(as_bool(evaluate(rhs, ctx)?)?))
+        }
+        Expr::BinaryOp { op, lhs, rhs } => {
+            let l = evaluate(lhs, ctx)?;
+            let r = evaluate(rhs, ctx)?;
+            match op {
+                BinaryOp::Eq => Ok(Value::Bool(l == r)),
+                BinaryOp::NotEq => Ok(Value::Bool(l != r)),
+                BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::Gt | BinaryOp::GtEq => {
+                    let a = as_f64(&l)?;
+                    let b = as_f64(&r)?;
+                    let result = match op {
+                        BinaryOp::Lt => a < b,
+                        BinaryOp::LtEq => a <= b,
+                        BinaryOp::Gt => a > b,
+                        BinaryOp::GtEq => a >= b,
+                        _ => unreachable!(),
+                    };
+                    Ok(Value::Bool(result))
+                }
+                BinaryOp::In => match &r {
+                    Value::Array(items) => Ok(Value::Bool(items.contains(&l))),
+                    _ => Err(EvalError::TypeMismatch(
+                        "right-hand side of 'in' must be a list".to_string(),
+                    )),
+                },
+                BinaryOp::And | BinaryOp::Or => unreachable!("handled above for short-circuiting"),
+            }
+        }
+        Expr::FunctionCall { name, .. } => Err(EvalError::UnknownFunction(name.clone())),
+    }
+}
+
+fn as_bool(v: EvalValue) -> Result<bool, EvalError> {
+    match v {
+        Value::Bool(b) => Ok(b),
+        other => Err(EvalError::TypeMismatch(format!("expected bool, got {other}"))),
+    }
+}
+
+fn as_f64(v: &EvalValue) -> Result<f64, EvalError> {
+    v.as_f64()
+        .ok_or_else(|| EvalError::TypeMismatch(format!("expected number, got {v}")))
+}
+
+fn resolve_field(path: &[String], ctx: &EvalContext) -> Result<EvalValue, EvalError> {
+    if path.len() == 1 {
+        if let Some(value) = ctx.path_params.get(&path[0]) {
+            return Ok(Value::String(value.clone()));
+        }
+    }
+
+    match path.first().map(String::as_str) {
+        Some("request") => resolve_request(&path[1..], ctx),
+        Some("resource") => resolve_resource(&path[1..], ctx.existing_data),
+        _ => Err(EvalError::UnknownIdentifier(path.join("."))),
+    }
+}
+
+fn resolve_request(path: &[String], ctx: &EvalContext) -> Result<EvalValue, EvalError> {
+    match path {
+        [] => Err(EvalError::UnknownIdentifier("request".to_string())),
+        [first, rest @ ..] if first == "auth" => {
+            if rest.is_empty() {
+                return Ok(match ctx.auth_uid {
+                    Some(_) => Value::Object(Map::new()),
+                    None => Value::Null,
+                });
+            }
+            if rest == ["uid"] {
+                return ctx
+                    .auth_uid
+                    .map(|uid| Value::String(uid.to_string()))
+                    .ok_or_else(|| EvalError::MissingField("request.auth.uid".to_string()));
+            }
+            Err(EvalError::UnknownIdentifier(format!(
+                "request.auth.{}",
+                rest.join(".")
+            )))
+        }
+        [first, rest @ ..] if first == "resource" => {
+            if rest.first().map(String::as_str) != Some("data") {
+                return Err(EvalError::UnknownIdentifier(format!(
+                    "request.resource.{}",
+                    rest.join(".")
+                )));
+            }
+            resolve_data(ctx.incoming_data, &rest[1..], "request.resource.data")
+        }
+        _ => Err(EvalError::UnknownIdentifier(format!(
+            "request.{}",
+            path.join(".")
+        ))),
+    }
+}
+
+fn resolve_resource(path: &[String], existing_data: Option<&Map<String, Value>>) -> Result<EvalValue, EvalError> {
+    match path {
+        [first, rest @ ..] if first == "data" => resolve_data(existing_data, rest, "resource.data"),
+        _ => Err(EvalError::UnknownIdentifier(format!(
+            "resource.{}",
+            path.join(".")
+        ))),
+    }
+}
+
+fn resolve_data(
+    data: Option<&Map<String, Value>>,
+    path: &[String],
+    label: &str,
+) -> Result<EvalValue, EvalError> {
+    let map = data.ok_or_else(|| EvalError::MissingField(label.to_string()))?;
+    if path.is_empty() {
+        return Ok(Value::Object(map.clone()));
+    }
+
+    let mut current = map
+        .get(&path[0])
+        .ok_or_else(|| EvalError::MissingField(format!("{label}.{}", path[0])))?;
+    for segment in &path[1..] {
+        current = match current {
+            Value::Object(obj) => obj
+                .get(segment)
+                .ok_or_else(|| EvalError::MissingField(format!("{label}.{segment}")))?,
+            Value::Array(arr) => {
+                let idx: usize = segment
+                    .parse()
+                    .map_err(|_| EvalError::MissingField(format!("{label}.{segment}")))?;
+                arr.get(idx)
+                    .ok_or_else(|| EvalError::MissingField(format!("{label}.{segment}")))?
+            }
+            _ => return Err(EvalError::MissingField(format!("{label}.{segment}"))),
+        };
+    }
+    Ok(current.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx<'a>(
+        auth_uid: Option<&'a str>,
+        incoming: Option<&'a Map<String, Value>>,
+        existing: Option<&'a Map<String, Value>>,
+        path_params: &'a HashMap<String, String>,
+    ) -> EvalContext<'a> {
+        EvalContext {
+            auth_uid,
+            incoming_data: incoming,
+            existing_data: existing,
+            path_params,
+        }
+    }
+
+    #[test]
+    fn test_request_auth_not_null() {
+        let expr = parse_condition("request.auth != null").unwrap();
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(Some("u1"), None, None, &params)).unwrap());
+        assert!(!evaluate_condition(&expr, &ctx(None, None, None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_field_comparison_against_incoming_data() {
+        let expr = parse_condition("request.resource.data.role == 'admin'").unwrap();
+        let mut data = Map::new();
+        data.insert("role".to_string(), Value::String("admin".to_string()));
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(None, Some(&data), None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_missing_field_is_an_error() {
+        let expr = parse_condition("resource.data.role == 'admin'").unwrap();
+        let params = HashMap::new();
+        let err = evaluate_condition(&expr, &ctx(None, None, None, &params)).unwrap_err();
+        assert!(matches!(err, EvalError::MissingField(_)));
+    }
+
+    #[test]
+    fn test_membership_check() {
+        let expr = parse_condition("request.auth.uid in ['a', 'b', 'c']").unwrap();
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(Some("b"), None, None, &params)).unwrap());
+        assert!(!evaluate_condition(&expr, &ctx(Some("z"), None, None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_short_circuit_and_avoids_evaluating_rhs() {
+        // `false && <missing field>` must short-circuit and not error.
+        let expr = parse_condition("false && resource.data.role == 'admin'").unwrap();
+        let params = HashMap::new();
+        assert!(!evaluate_condition(&expr, &ctx(None, None, None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let expr = parse_condition("true || false && false").unwrap();
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(None, None, None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_path_wildcard_lookup() {
+        let expr = parse_condition("request.auth.uid == userId").unwrap();
+        let mut params = HashMap::new();
+        params.insert("userId".to_string(), "u1".to_string());
+        assert!(evaluate_condition(&expr, &ctx(Some("u1"), None, None, &params)).unwrap());
+    }
+
+    #[test]
+    fn test_owner_only_condition_against_existing_data() {
+        let expr = parse_condition("request.auth.uid == resource.data.owner").unwrap();
+        let mut data = Map::new();
+        data.insert("owner".to_string(), Value::String("u1".to_string()));
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(Some("u1"), None, Some(&data), &params)).unwrap());
+
+        data.insert("owner".to_string(), Value::String("u2".to_string()));
+        assert!(!evaluate_condition(&expr, &ctx(Some("u1"), None, Some(&data), &params)).unwrap());
+    }
+
+    #[test]
+    fn test_string_field_that_looks_like_a_bool_or_number_still_compares_by_value() {
+        // A document field's apparent type must not be reinterpreted before
+        // comparing against a string literal -- `status` here is genuinely the
+        // string "true", not a bool, and `zip` is genuinely the string
+        // "90210", not a number.
+        let expr = parse_condition("resource.data.status == 'true' && resource.data.zip == '90210'")
+            .unwrap();
+        let mut data = Map::new();
+        data.insert("status".to_string(), Value::String("true".to_string()));
+        data.insert("zip".to_string(), Value::String("90210".to_string()));
+        let params = HashMap::new();
+        assert!(evaluate_condition(&expr, &ctx(None, None, Some(&data), &params)).unwrap());
+    }
+
+    #[test]
+    fn test_true_false_literals() {
+        let params = HashMap::new();
+        assert!(evaluate_condition(&parse_condition("true").unwrap(), &ctx(None, None, None, &params)).unwrap());
+        assert!(!evaluate_condition(&parse_condition("false").unwrap(), &ctx(None, None, None, &params)).unwrap());
+    }
+}
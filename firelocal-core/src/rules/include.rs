@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Maximum include nesting before `%include` expansion is aborted, as a
+/// backstop against runaway chains independent of the cycle check below.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Expand every `%include <path>` directive in `entry_path` and the files it
+/// pulls in, recursively, returning the fully concatenated rules text.
+///
+/// An include path is resolved relative to the directory of the file that
+/// contains the directive, so a ruleset can be split across files without
+/// every file needing to agree on a working directory. Cycles (a file
+/// including itself, directly or through a chain of other includes) are
+/// rejected, as is nesting past `MAX_INCLUDE_DEPTH`.
+pub fn expand_includes(entry_path: &Path) -> Result<String, String> {
+    let mut ancestors = HashSet::new();
+    expand_file(entry_path, &mut ancestors, 0)
+}
+
+fn expand_file(path: &Path, ancestors: &mut HashSet<PathBuf>, depth: usize) -> Result<String, String> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(format!(
+            "rules include depth exceeded {MAX_INCLUDE_DEPTH} while including '{}'",
+            path.display()
+        ));
+    }
+
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("rules include not found: '{}' ({e})", path.display()))?;
+
+    if !ancestors.insert(canonical.clone()) {
+        return Err(format!(
+            "rules include cycle detected at '{}'",
+            path.display()
+        ));
+    }
+
+    let contents = std::fs::read_to_string(&canonical)
+        .map_err(|e| format!("failed to read rules file '{}': {e}", path.display()))?;
+    let parent = canonical.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut expanded = String::new();
+    for line in contents.lines() {
+        if let Some(included_path) = line.trim_start().strip_prefix("%include ") {
+            let included_path = parent.join(included_path.trim());
+            expanded.push_str(&expand_file(&included_path, ancestors, depth + 1)?);
+            expanded.push('\n');
+        } else {
+            expanded.push_str(line);
+            expanded.push('\n');
+        }
+    }
+
+    // Drop back out of the ancestor chain so a diamond include (the same
+    // file pulled in from two different, non-cyclic branches) is allowed.
+    ancestors.remove(&canonical);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("firelocal_rules_include_test_{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_expand_single_include() {
+        let dir = temp_dir("single");
+        fs::write(
+            dir.join("users.rules"),
+            "match /users/{userId} { allow read, write: if true; }\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("main.rules"),
+            "service cloud.firestore {\n%include users.rules\n}\n",
+        )
+        .unwrap();
+
+        let expanded = expand_includes(&dir.join("main.rules")).unwrap();
+        assert!(expanded.contains("match /users/{userId}"));
+        assert!(expanded.contains("service cloud.firestore"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_include_names_the_file() {
+        let dir = temp_dir("missing");
+        fs::write(dir.join("main.rules"), "%include does_not_exist.rules\n").unwrap();
+
+        let err = expand_includes(&dir.join("main.rules")).unwrap_err();
+        assert!(err.contains("does_not_exist.rules"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_include_cycle_is_rejected() {
+        let dir = temp_dir("cycle");
+        fs::write(dir.join("a.rules"), "%include b.rules\n").unwrap();
+        fs::write(dir.join("b.rules"), "%include a.rules\n").unwrap();
+
+        let err = expand_includes(&dir.join("a.rules")).unwrap_err();
+        assert!(err.contains("cycle"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_diamond_include_is_allowed() {
+        let dir = temp_dir("diamond");
+        fs::write(dir.join("shared.rules"), "allow read: if true;\n").unwrap();
+        fs::write(dir.join("a.rules"), "%include shared.rules\n").unwrap();
+        fs::write(dir.join("b.rules"), "%include shared.rules\n").unwrap();
+        fs::write(
+            dir.join("main.rules"),
+            "%include a.rules\n%include b.rules\n",
+        )
+        .unwrap();
+
+        let expanded = expand_includes(&dir.join("main.rules")).unwrap();
+        assert_eq!(expanded.matches("allow read").count(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,9 +1,11 @@
 pub mod ast;
+pub mod condition;
+pub mod include;
 pub mod parser;
 
 use crate::rules::ast::Ruleset;
 use crate::rules::parser::RulesParser;
-use std::collections::HashMap;
+use serde_json::{Map, Value};
 
 pub struct RulesEngine {
     ruleset: Option<Ruleset>,
@@ -25,11 +27,16 @@ impl RulesEngine {
         Ok(())
     }
 
-    pub fn evaluate(&self, path: &str, operation: &str, context: &HashMap<String, String>) -> bool {
+    pub fn evaluate(
+        &self,
+        path: &str,
+        operation: &str,
+        auth_uid: Option<&str>,
+        existing_data: Option<&Map<String, Value>>,
+        incoming_data: Option<&Map<String, Value>>,
+    ) -> bool {
         if let Some(ruleset) = &self.ruleset {
-            // TODO: Traverse match blocks and evaluate allow conditions
-            // For M4 MVP, we will implement a basic traversal
-            return ruleset.is_allowed(path, operation, context);
+            return ruleset.is_allowed(path, operation, auth_uid, existing_data, incoming_data);
         }
         // distinct from Firestore default? TDD says "Deny by default" usually.
         false
@@ -96,10 +96,15 @@ impl<'a> RulesParser<'a> {
         // Parse condition until semicolon
         let condition = self.parse_until(";")?;
         self.expect(";")?;
+        let condition = condition.trim().to_string();
+
+        let condition_ast = crate::rules::condition::parse_condition(&condition)
+            .map_err(|e| format!("invalid condition '{condition}': {e}"))?;
 
         Ok(AllowStatement {
             operations,
-            condition: condition.trim().to_string(),
+            condition,
+            condition_ast,
         })
     }
 
@@ -0,0 +1,305 @@
+//! Per-collection JSON Schema validation: a declarative data-contract layer
+//! alongside the access-control `Ruleset`, so a collection can reject writes
+//! whose shape doesn't match a registered schema instead of only being able
+//! to approximate that with rule conditions.
+//!
+//! Only the common subset of JSON Schema is supported: `type`, `properties`,
+//! `required`, `enum`, numeric `minimum`/`maximum`, and string `maxLength`.
+//! Unrecognized keywords are ignored rather than rejected, so a schema with
+//! extra metadata (`title`, `description`, ...) still validates.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A JSON Schema document, restricted to the subset described above.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    raw: Value,
+}
+
+impl Schema {
+    pub fn from_value(raw: Value) -> Self {
+        Self { raw }
+    }
+
+    pub fn from_json(json_str: &str) -> serde_json::Result<Self> {
+        Ok(Self {
+            raw: serde_json::from_str(json_str)?,
+        })
+    }
+
+    /// Validate `instance` against this schema, returning the path of the
+    /// first field that doesn't conform.
+    pub fn validate(&self, instance: &Value) -> Result<(), SchemaError> {
+        validate_node(&self.raw, instance, "")
+    }
+}
+
+/// A schema validation failure, naming the dotted field path that failed
+/// (e.g. `address.city`) and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = if self.path.is_empty() {
+            "<root>"
+        } else {
+            &self.path
+        };
+        write!(f, "schema validation failed at '{field}': {}", self.message)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Maps a collection path prefix (e.g. `"users"` or `"users/alice/posts"`)
+/// to the `Schema` that documents written under it must conform to.
+pub struct SchemaRegistry {
+    schemas: HashMap<String, Schema>,
+}
+
+impl SchemaRegistry {
+    pub fn new() -> Self {
+        Self {
+            schemas: HashMap::new(),
+        }
+    }
+
+    /// Associate `schema` with every document path under `collection_prefix`.
+    pub fn register(&mut self, collection_prefix: impl Into<String>, schema: Schema) {
+        self.schemas.insert(collection_prefix.into(), schema);
+    }
+
+    /// The schema governing `path`, if any — the longest registered prefix
+    /// that `path` falls under, so a schema on `"users"` doesn't shadow a
+    /// more specific one registered on `"users/admins"`.
+    pub fn schema_for(&self, path: &str) -> Option<&Schema> {
+        self.schemas
+            .iter()
+            .filter(|(prefix, _)| {
+                path == prefix.as_str() || path.starts_with(&format!("{prefix}/"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, schema)| schema)
+    }
+}
+
+impl Default for SchemaRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn validate_node(schema: &Value, instance: &Value, path: &str) -> Result<(), SchemaError> {
+    let Some(schema) = schema.as_object() else {
+        // A bare `true`/`false` (or any non-object) schema isn't part of the
+        // supported subset; accept permissively rather than reject shapes we
+        // don't understand.
+        return Ok(());
+    };
+
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !matches_type(expected, instance) {
+            return Err(SchemaError {
+                path: path.to_string(),
+                message: format!(
+                    "expected type '{expected}', found '{}'",
+                    type_name(instance)
+                ),
+            });
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(instance) {
+            return Err(SchemaError {
+                path: path.to_string(),
+                message: format!("{instance} is not one of the allowed enum values"),
+            });
+        }
+    }
+
+    if let Some(n) = instance.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                return Err(SchemaError {
+                    path: path.to_string(),
+                    message: format!("{n} is less than minimum {min}"),
+                });
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                return Err(SchemaError {
+                    path: path.to_string(),
+                    message: format!("{n} is greater than maximum {max}"),
+                });
+            }
+        }
+    }
+
+    if let Some(s) = instance.as_str() {
+        if let Some(max_len) = schema.get("maxLength").and_then(Value::as_u64) {
+            let len = s.chars().count() as u64;
+            if len > max_len {
+                return Err(SchemaError {
+                    path: path.to_string(),
+                    message: format!("string length {len} exceeds maxLength {max_len}"),
+                });
+            }
+        }
+    }
+
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        if let Some(obj) = instance.as_object() {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(key) {
+                    return Err(SchemaError {
+                        path: join_path(path, key),
+                        message: "required field is missing".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        if let Some(obj) = instance.as_object() {
+            for (key, prop_schema) in properties {
+                if let Some(value) = obj.get(key) {
+                    validate_node(prop_schema, value, &join_path(path, key))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
+
+fn matches_type(expected: &str, instance: &Value) -> bool {
+    match expected {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        "number" => instance.is_number(),
+        "integer" => instance.is_i64() || instance.is_u64(),
+        // Unrecognized type keyword: accept rather than reject a schema we
+        // don't fully understand.
+        _ => true,
+    }
+}
+
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user_schema() -> Schema {
+        Schema::from_value(json!({
+            "type": "object",
+            "required": ["name", "age"],
+            "properties": {
+                "name": { "type": "string", "maxLength": 20 },
+                "age": { "type": "integer", "minimum": 0, "maximum": 150 },
+                "role": { "enum": ["admin", "member"] }
+            }
+        }))
+    }
+
+    #[test]
+    fn test_valid_document_passes() {
+        let schema = user_schema();
+        let doc = json!({ "name": "Alice", "age": 30, "role": "admin" });
+        assert!(schema.validate(&doc).is_ok());
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let schema = user_schema();
+        let doc = json!({ "name": "Alice" });
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.path, "age");
+    }
+
+    #[test]
+    fn test_wrong_type_on_nested_field() {
+        let schema = user_schema();
+        let doc = json!({ "name": "Alice", "age": "thirty" });
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.path, "age");
+        assert!(err.message.contains("expected type 'integer'"));
+    }
+
+    #[test]
+    fn test_numeric_bounds() {
+        let schema = user_schema();
+        let doc = json!({ "name": "Alice", "age": 200 });
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.path, "age");
+        assert!(err.message.contains("greater than maximum"));
+    }
+
+    #[test]
+    fn test_string_max_length() {
+        let schema = user_schema();
+        let doc = json!({ "name": "A".repeat(25), "age": 30 });
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.path, "name");
+        assert!(err.message.contains("exceeds maxLength"));
+    }
+
+    #[test]
+    fn test_enum_rejects_unlisted_value() {
+        let schema = user_schema();
+        let doc = json!({ "name": "Alice", "age": 30, "role": "superuser" });
+        let err = schema.validate(&doc).unwrap_err();
+        assert_eq!(err.path, "role");
+    }
+
+    #[test]
+    fn test_registry_longest_prefix_wins() {
+        let mut registry = SchemaRegistry::new();
+        registry.register("users", Schema::from_value(json!({ "type": "object" })));
+        registry.register(
+            "users/admins",
+            Schema::from_value(json!({ "required": ["level"] })),
+        );
+
+        let admin_schema = registry.schema_for("users/admins/bob").unwrap();
+        assert!(admin_schema
+            .validate(&json!({}))
+            .unwrap_err()
+            .message
+            .contains("required"));
+
+        assert!(registry.schema_for("users/alice").is_some());
+        assert!(registry.schema_for("posts/1").is_none());
+    }
+}
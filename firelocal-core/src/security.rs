@@ -1,6 +1,9 @@
+use crate::auth::AuthProof;
 use crate::error::{FireLocalError, Result};
 use crate::logging::log_security_event;
+use crate::store::encryption::EncryptionKeySource;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
@@ -25,6 +28,57 @@ pub struct SecurityConfig {
     pub blocked_ips: Vec<String>,
     /// Allowed operations for anonymous users
     pub anonymous_operations: Vec<String>,
+    /// How far a signed request's timestamp may drift from the server's
+    /// clock, in either direction, before `verify_proof` rejects it as
+    /// expired.
+    pub signature_skew_ms: u64,
+    /// How many equal-length sub-windows `SecurityRateLimiter` divides its
+    /// one-minute window into. `1` (the default) matches the old
+    /// fixed-window behavior (one bucket, reset every minute); higher
+    /// values smooth the limit across minute boundaries at the cost of
+    /// enforcing it over a shorter bucket (`1 minute / sub_windows`) instead
+    /// of the full minute.
+    pub rate_limit_sub_windows: u32,
+    /// Upper bound on how many distinct `client_id`s `SecurityRateLimiter`
+    /// tracks at once. Once reached, a request from a client_id not already
+    /// tracked is rejected rather than growing the table further -- the
+    /// mitigation for a flood of spoofed `client_ip`s each only needing one
+    /// request to force an entry.
+    pub rate_limit_max_clients: usize,
+    /// Whether SST files should be encrypted at rest. This is a
+    /// configuration/discoverability surface only -- `SecurityConfig` isn't
+    /// wired into `FireLocal` directly, so a caller that enables this is
+    /// expected to derive the key themselves (`crate::store::encryption::derive_key`
+    /// with `encryption_key_source`) and pass it to
+    /// `FireLocal::new_with_encryption_key`.
+    pub encryption_enabled: bool,
+    /// Where the SST encryption key comes from, when `encryption_enabled` is
+    /// set. `None` (the default) means a caller that enables encryption
+    /// must supply its own key source out of band.
+    pub encryption_key_source: Option<EncryptionKeySource>,
+    /// Route `log_security_event` (including `SecurityAuditor`'s own
+    /// operation logging, which calls it) through the tamper-evident
+    /// hash chain in `crate::audit` instead of only a plain log line. See
+    /// `audit_log_path` for where the chain persists.
+    pub audit_chain_enabled: bool,
+    /// Append-only file the audit chain is persisted to when
+    /// `audit_chain_enabled` is set, so it survives a process restart.
+    /// `None` keeps the chain in-memory only -- nothing to call
+    /// `crate::audit::AuditChain::verify_chain` against later.
+    pub audit_log_path: Option<PathBuf>,
+    /// Maximum nesting depth `InputSanitizer::validate_json` allows in a
+    /// document (an object/array nested inside another counts as one level).
+    pub max_json_depth: usize,
+    /// Maximum number of elements `InputSanitizer::validate_json` allows in
+    /// any single JSON array.
+    pub max_json_array_len: usize,
+    /// Maximum number of keys `InputSanitizer::validate_json` allows in any
+    /// single JSON object.
+    pub max_json_object_keys: usize,
+    /// Maximum total number of values (objects, arrays, and scalars
+    /// combined) `InputSanitizer::validate_json` allows across an entire
+    /// document.
+    pub max_json_total_nodes: usize,
 }
 
 impl Default for SecurityConfig {
@@ -39,6 +93,17 @@ impl Default for SecurityConfig {
             max_path_depth: 32,
             blocked_ips: Vec::new(),
             anonymous_operations: vec!["read".to_string()],
+            signature_skew_ms: 30_000,
+            rate_limit_sub_windows: 1,
+            rate_limit_max_clients: 100_000,
+            encryption_enabled: false,
+            encryption_key_source: None,
+            audit_chain_enabled: false,
+            audit_log_path: None,
+            max_json_depth: 32,
+            max_json_array_len: 10_000,
+            max_json_object_keys: 1_000,
+            max_json_total_nodes: 100_000,
         }
     }
 }
@@ -58,6 +123,12 @@ pub struct SecurityContext {
     pub auth_token: Option<String>,
     /// Request timestamp
     pub timestamp: Instant,
+    /// A signed ed25519 proof of identity for this request, checked by
+    /// `SecurityAuditor::pre_operation_check` when `authentication_enabled`
+    /// requires more than an anonymous operation. Unrelated to `auth_token`,
+    /// which this context carries unverified for callers outside the
+    /// signature scheme.
+    pub auth_proof: Option<AuthProof>,
 }
 
 impl SecurityContext {
@@ -69,6 +140,7 @@ impl SecurityContext {
             user_agent: None,
             auth_token: None,
             timestamp: Instant::now(),
+            auth_proof: None,
         }
     }
 
@@ -80,9 +152,18 @@ impl SecurityContext {
             user_agent: None,
             auth_token: None,
             timestamp: Instant::now(),
+            auth_proof: None,
         }
     }
 
+    /// Attach a signed proof of identity to be verified by
+    /// `SecurityAuditor::pre_operation_check` instead of trusting
+    /// `user_id`/`roles` set directly on this context.
+    pub fn with_auth_proof(mut self, proof: AuthProof) -> Self {
+        self.auth_proof = Some(proof);
+        self
+    }
+
     pub fn is_authenticated(&self) -> bool {
         self.user_id.is_some()
     }
@@ -92,45 +173,139 @@ impl SecurityContext {
     }
 }
 
-/// Rate limiter for security
+/// A client's sliding-window-counter state: bounds its footprint to two
+/// counters and an instant, regardless of how many requests it's made --
+/// unlike the `Vec<Instant>` this replaces, which grew to `max_requests`
+/// entries per client forever.
+struct WindowCounter {
+    /// Start of the current sub-window.
+    sub_window_start: Instant,
+    /// Requests seen in the current sub-window.
+    current: u32,
+    /// Requests seen in the previous sub-window.
+    previous: u32,
+}
+
+/// Rate limiter for security.
+///
+/// Instead of retaining every request's timestamp, each client keeps only
+/// the two counters in [`WindowCounter`]. `check_rate_limit` estimates the
+/// request rate as `current + previous * (fraction of the previous
+/// sub-window still inside the sliding window)`, rejecting once that
+/// estimate reaches `max_requests` -- see
+/// <https://blog.cloudflare.com/counting-things-a-lot-of-different-things/>
+/// for the technique this follows.
 pub struct SecurityRateLimiter {
-    requests: Arc<Mutex<HashMap<String, Vec<Instant>>>>,
+    windows: Arc<Mutex<HashMap<String, WindowCounter>>>,
     max_requests: u32,
-    window: Duration,
+    sub_window: Duration,
+    max_tracked_clients: usize,
+    /// Bumped on every call; a sweep evicting idle clients runs every
+    /// `SWEEP_INTERVAL` calls rather than on every single one, amortizing
+    /// its cost.
+    calls_since_sweep: std::sync::atomic::AtomicU64,
 }
 
+/// How many `check_rate_limit` calls between idle-client eviction sweeps.
+const SWEEP_INTERVAL: u64 = 1024;
+
 impl SecurityRateLimiter {
     pub fn new(max_requests: u32, window_minutes: u64) -> Self {
+        Self::with_config(max_requests, window_minutes, 1, 100_000)
+    }
+
+    /// Like `new`, but with `SecurityConfig::rate_limit_sub_windows`/
+    /// `rate_limit_max_clients` also configurable.
+    pub fn with_config(
+        max_requests: u32,
+        window_minutes: u64,
+        sub_windows: u32,
+        max_tracked_clients: usize,
+    ) -> Self {
+        let sub_windows = sub_windows.max(1);
         Self {
-            requests: Arc::new(Mutex::new(HashMap::new())),
+            windows: Arc::new(Mutex::new(HashMap::new())),
             max_requests,
-            window: Duration::from_secs(window_minutes * 60),
+            sub_window: Duration::from_secs(window_minutes * 60) / sub_windows,
+            max_tracked_clients,
+            calls_since_sweep: std::sync::atomic::AtomicU64::new(0),
         }
     }
 
     pub fn check_rate_limit(&self, client_id: &str) -> Result<()> {
-        let mut requests = self.requests.lock().unwrap();
+        let mut windows = self.windows.lock().unwrap();
         let now = Instant::now();
 
-        let client_requests = requests.entry(client_id.to_string()).or_default();
+        if self
+            .calls_since_sweep
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+            % SWEEP_INTERVAL
+            == 0
+        {
+            self.evict_idle(&mut windows, now);
+        }
+
+        if !windows.contains_key(client_id) && windows.len() >= self.max_tracked_clients {
+            log_security_event(
+                "RATE_LIMIT_CLIENT_TABLE_FULL",
+                &format!("Rejected new client {} at tracked-client cap", client_id),
+            );
+            return Err(FireLocalError::RateLimitExceeded(
+                "too many distinct clients tracked for rate limiting".to_string(),
+            ));
+        }
+
+        let counter = windows.entry(client_id.to_string()).or_insert(WindowCounter {
+            sub_window_start: now,
+            current: 0,
+            previous: 0,
+        });
 
-        // Remove old requests outside the window
-        client_requests.retain(|&time| now.duration_since(time) < self.window);
+        // Roll the window forward however many sub-windows have fully
+        // elapsed since we last looked: one roll carries `current` into
+        // `previous`, any further idle sub-windows just drain `previous` to
+        // nothing since there's no third bucket to shift it into.
+        let elapsed = now.saturating_duration_since(counter.sub_window_start);
+        if elapsed >= self.sub_window * 2 {
+            counter.previous = 0;
+            counter.current = 0;
+            counter.sub_window_start = now;
+        } else if elapsed >= self.sub_window {
+            counter.previous = counter.current;
+            counter.current = 0;
+            counter.sub_window_start += self.sub_window;
+        }
 
-        if client_requests.len() >= self.max_requests as usize {
+        let elapsed_in_current = now.saturating_duration_since(counter.sub_window_start);
+        let fraction = 1.0
+            - (elapsed_in_current.as_secs_f64() / self.sub_window.as_secs_f64()).min(1.0);
+        let estimate = counter.current as f64 + counter.previous as f64 * fraction;
+
+        if estimate >= self.max_requests as f64 {
             log_security_event(
                 "RATE_LIMIT_EXCEEDED",
                 &format!("Client {} exceeded rate limit", client_id),
             );
             return Err(FireLocalError::RateLimitExceeded(format!(
                 "Rate limit exceeded: {} requests per {:?}",
-                self.max_requests, self.window
+                self.max_requests,
+                self.sub_window * 2
             )));
         }
 
-        client_requests.push(now);
+        counter.current += 1;
         Ok(())
     }
+
+    /// Drop every client whose both sub-windows have fully expired, so a
+    /// flood of distinct (e.g. spoofed) client IDs can't grow this table
+    /// without bound just by each showing up once.
+    fn evict_idle(&self, windows: &mut HashMap<String, WindowCounter>, now: Instant) {
+        let idle_after = self.sub_window * 2;
+        windows.retain(|_, counter| {
+            now.saturating_duration_since(counter.sub_window_start) < idle_after
+        });
+    }
 }
 
 /// Input sanitizer
@@ -229,52 +404,163 @@ impl InputSanitizer {
         }
     }
 
-    /// Validate JSON structure
-    pub fn validate_json(data: &[u8]) -> Result<()> {
+    /// Validate JSON structure: parse `data`, then recursively walk it,
+    /// rejecting a dangerous token (`__proto__`, `constructor`, `prototype`)
+    /// only when it appears as an *object key* at any depth -- a string or
+    /// number value containing one of those tokens (e.g. `{"role":
+    /// "constructor"}`) is never rejected, unlike a substring search over
+    /// the raw JSON text. The same walk enforces `config`'s structural
+    /// limits (max nesting depth, array length, object key count, and total
+    /// node count) so a deeply nested or enormous payload can't be used as a
+    /// DoS vector.
+    pub fn validate_json(data: &[u8], config: &SecurityConfig) -> Result<()> {
         let json_str = std::str::from_utf8(data)
             .map_err(|_| FireLocalError::Validation("Invalid UTF-8".to_string()))?;
 
-        // Check for potentially dangerous JSON patterns
-        if json_str.contains("__proto__") || json_str.contains("constructor") {
-            log_security_event(
-                "PROTOTYPE_POLLUTION",
-                "JSON contains prototype pollution patterns",
-            );
-            return Err(FireLocalError::Security(
-                "Prototype pollution detected".to_string(),
-            ));
+        let value: serde_json::Value = serde_json::from_str(json_str)
+            .map_err(|e| FireLocalError::Validation(format!("Invalid JSON: {}", e)))?;
+
+        let mut node_count = 0usize;
+        Self::validate_json_node(&value, "$", 0, config, &mut node_count)
+    }
+
+    /// Recursive worker for `validate_json`. `path` is the dotted/indexed
+    /// location of `value` within the document (e.g. `$/users/0/meta`), used
+    /// to make a rejection's error message actionable.
+    fn validate_json_node(
+        value: &serde_json::Value,
+        path: &str,
+        depth: usize,
+        config: &SecurityConfig,
+        node_count: &mut usize,
+    ) -> Result<()> {
+        *node_count += 1;
+        if *node_count > config.max_json_total_nodes {
+            return Err(FireLocalError::Validation(format!(
+                "JSON payload exceeds maximum node count {} (at '{}')",
+                config.max_json_total_nodes, path
+            )));
+        }
+        if depth > config.max_json_depth {
+            return Err(FireLocalError::Validation(format!(
+                "JSON nesting exceeds maximum depth {} at '{}'",
+                config.max_json_depth, path
+            )));
         }
 
-        // Try to parse as JSON
-        serde_json::from_str::<serde_json::Value>(json_str)
-            .map_err(|e| FireLocalError::Validation(format!("Invalid JSON: {}", e)))?;
+        match value {
+            serde_json::Value::Object(map) => {
+                if map.len() > config.max_json_object_keys {
+                    return Err(FireLocalError::Validation(format!(
+                        "JSON object at '{}' has {} keys, exceeding maximum {}",
+                        path,
+                        map.len(),
+                        config.max_json_object_keys
+                    )));
+                }
+                for (key, child) in map {
+                    if is_dangerous_json_key(key) {
+                        log_security_event(
+                            "PROTOTYPE_POLLUTION",
+                            &format!("Dangerous object key '{}' at '{}'", key, path),
+                        );
+                        return Err(FireLocalError::Security(format!(
+                            "Prototype pollution detected: key '{}' at '{}'",
+                            key, path
+                        )));
+                    }
+                    Self::validate_json_node(
+                        child,
+                        &format!("{path}/{key}"),
+                        depth + 1,
+                        config,
+                        node_count,
+                    )?;
+                }
+            }
+            serde_json::Value::Array(items) => {
+                if items.len() > config.max_json_array_len {
+                    return Err(FireLocalError::Validation(format!(
+                        "JSON array at '{}' has {} elements, exceeding maximum {}",
+                        path,
+                        items.len(),
+                        config.max_json_array_len
+                    )));
+                }
+                for (i, item) in items.iter().enumerate() {
+                    Self::validate_json_node(
+                        item,
+                        &format!("{path}/{i}"),
+                        depth + 1,
+                        config,
+                        node_count,
+                    )?;
+                }
+            }
+            _ => {}
+        }
 
         Ok(())
     }
 }
 
+/// Prototype-pollution gadget names a JS consumer of this data could act on
+/// if they ever ended up as an object key in a stored document.
+fn is_dangerous_json_key(key: &str) -> bool {
+    matches!(key, "__proto__" | "constructor" | "prototype")
+}
+
 /// Security auditor for logging and monitoring
 pub struct SecurityAuditor {
     config: SecurityConfig,
     rate_limiter: SecurityRateLimiter,
+    /// ed25519 public key (64-hex) -> the `(user_id, roles)` it authenticates
+    /// as, populated via `register_pubkey`.
+    registered_pubkeys: Mutex<HashMap<String, (String, Vec<String>)>>,
 }
 
 impl SecurityAuditor {
     pub fn new(config: SecurityConfig) -> Self {
+        if config.audit_chain_enabled {
+            if let Err(e) = crate::logging::enable_audit_chain(config.audit_log_path.clone()) {
+                log_security_event(
+                    "AUDIT_CHAIN_INIT_FAILED",
+                    &format!("Failed to open audit log sink: {e}"),
+                );
+            }
+        }
         Self {
-            rate_limiter: SecurityRateLimiter::new(config.max_requests_per_minute, 1),
+            rate_limiter: SecurityRateLimiter::with_config(
+                config.max_requests_per_minute,
+                1,
+                config.rate_limit_sub_windows,
+                config.rate_limit_max_clients,
+            ),
             config,
+            registered_pubkeys: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Perform security checks before an operation
+    /// Register `ed25519_hex`'s corresponding identity: a request signed by
+    /// this key's private counterpart authenticates as `user_id` with
+    /// `roles` once verified by `pre_operation_check`.
+    pub fn register_pubkey(&self, user_id: &str, ed25519_hex: &str, roles: Vec<String>) {
+        self.registered_pubkeys
+            .lock()
+            .unwrap()
+            .insert(ed25519_hex.to_string(), (user_id.to_string(), roles));
+    }
+
+    /// Perform security checks before an operation, returning a context with
+    /// `user_id`/`roles` resolved from a verified signature when
+    /// authentication was required for `operation`.
     pub fn pre_operation_check(
         &self,
         context: &SecurityContext,
         operation: &str,
         path: &str,
         data: Option<&[u8]>,
-    ) -> Result<()> {
+    ) -> Result<SecurityContext> {
         // Check IP blocking
         if let Some(ip) = &context.client_ip {
             if self.config.blocked_ips.contains(ip) {
@@ -298,37 +584,87 @@ impl SecurityAuditor {
             self.rate_limiter.check_rate_limit(&client_id)?;
         }
 
-        // Check authentication
-        if self.config.authentication_enabled
-            && !context.is_authenticated()
+        // Check authentication: resolve (and upgrade) the caller's identity
+        // from a verified signature, unless this operation is open to
+        // anonymous callers.
+        let context = if self.config.authentication_enabled
             && !self
                 .config
                 .anonymous_operations
                 .contains(&operation.to_string())
         {
-            log_security_event(
-                "UNAUTHORIZED_ACCESS_ATTEMPT",
-                &format!("Operation: {}, Path: {}", operation, path),
-            );
-            return Err(FireLocalError::PermissionDenied(
-                "Authentication required".to_string(),
-            ));
-        }
+            self.authenticate(context, operation, path)?
+        } else {
+            context.clone()
+        };
 
         // Validate and sanitize inputs
         let sanitized_path = InputSanitizer::sanitize_path(path, self.config.max_path_depth)?;
 
         if let Some(data) = data {
             InputSanitizer::sanitize_document(data, self.config.max_document_size)?;
-            InputSanitizer::validate_json(data)?;
+            InputSanitizer::validate_json(data, &self.config)?;
         }
 
         // Log the operation if audit logging is enabled
         if self.config.audit_logging_enabled {
-            self.log_operation(context, operation, &sanitized_path, data.is_some());
+            self.log_operation(&context, operation, &sanitized_path, data.is_some());
         }
 
-        Ok(())
+        Ok(context)
+    }
+
+    /// Verify `context.auth_proof` is a valid, unexpired ed25519 signature
+    /// from a registered public key, returning a new context with
+    /// `user_id`/`roles` populated from that registration. A missing,
+    /// malformed, or expired proof, a failed verification, or a proof from
+    /// an unregistered key are all reported identically as
+    /// `AUTH_SIGNATURE_INVALID` so a client can't distinguish "wrong key"
+    /// from "no key" by probing.
+    fn authenticate(
+        &self,
+        context: &SecurityContext,
+        operation: &str,
+        path: &str,
+    ) -> Result<SecurityContext> {
+        let reject = |reason: &str| {
+            log_security_event(
+                "AUTH_SIGNATURE_INVALID",
+                &format!("Operation: {}, Path: {}, Reason: {}", operation, path, reason),
+            );
+            // `reason` only goes to the log above -- returning it here would let a
+            // client distinguish "wrong key" from "no key" by probing, which is
+            // exactly what this function's doc comment promises not to do.
+            FireLocalError::PermissionDenied("Invalid request signature".to_string())
+        };
+
+        let proof = context
+            .auth_proof
+            .as_ref()
+            .ok_or_else(|| reject("missing signature"))?;
+
+        let (user_id, roles) = self
+            .registered_pubkeys
+            .lock()
+            .unwrap()
+            .get(&proof.pubkey_hex)
+            .cloned()
+            .ok_or_else(|| reject("unregistered public key"))?;
+
+        crate::auth::verify_proof(
+            proof,
+            operation,
+            path,
+            crate::auth::now_ms(),
+            self.config.signature_skew_ms,
+        )
+        .map_err(|e| reject(&e.to_string()))?;
+
+        Ok(SecurityContext {
+            user_id: Some(user_id),
+            roles,
+            ..context.clone()
+        })
     }
 
     /// Log security events
@@ -446,6 +782,36 @@ mod tests {
         assert!(InputSanitizer::sanitize_document(null_doc, 1024).is_err());
     }
 
+    #[test]
+    fn test_validate_json_rejects_dangerous_keys_but_allows_them_as_values() {
+        let config = SecurityConfig::default();
+
+        // A dangerous token as a plain string *value* is legitimate data.
+        assert!(InputSanitizer::validate_json(br#"{"role": "constructor"}"#, &config).is_ok());
+
+        // The same token as an object *key*, however deeply nested, is not.
+        assert!(InputSanitizer::validate_json(br#"{"__proto__": {"polluted": true}}"#, &config)
+            .is_err());
+        assert!(InputSanitizer::validate_json(
+            br#"{"users": [{"meta": {"constructor": 1}}]}"#,
+            &config
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_json_enforces_structural_limits() {
+        let mut config = SecurityConfig::default();
+        config.max_json_depth = 2;
+        config.max_json_array_len = 2;
+        config.max_json_object_keys = 2;
+
+        assert!(InputSanitizer::validate_json(br#"{"a": {"b": 1}}"#, &config).is_ok());
+        assert!(InputSanitizer::validate_json(br#"{"a": {"b": {"c": 1}}}"#, &config).is_err());
+        assert!(InputSanitizer::validate_json(br#"[1, 2, 3]"#, &config).is_err());
+        assert!(InputSanitizer::validate_json(br#"{"a": 1, "b": 2, "c": 3}"#, &config).is_err());
+    }
+
     #[test]
     fn test_security_context() {
         let anonymous = SecurityContext::anonymous();
@@ -466,6 +832,19 @@ mod tests {
         assert!(limiter.check_rate_limit("client1").is_err());
     }
 
+    #[test]
+    fn test_rate_limiter_rejects_new_clients_once_at_the_tracked_client_cap() {
+        let limiter = SecurityRateLimiter::with_config(100, 1, 1, 2);
+
+        assert!(limiter.check_rate_limit("client1").is_ok());
+        assert!(limiter.check_rate_limit("client2").is_ok());
+        // A third, never-seen client_id is rejected at the cap rather than
+        // growing the table further.
+        assert!(limiter.check_rate_limit("client3").is_err());
+        // An already-tracked client is unaffected.
+        assert!(limiter.check_rate_limit("client1").is_ok());
+    }
+
     #[test]
     fn test_security_auditor() {
         let auditor = create_default_security_auditor();
@@ -497,4 +876,63 @@ mod tests {
             .pre_operation_check(&context, "write", "users/alice", None)
             .is_err());
     }
+
+    #[test]
+    fn test_signature_authentication_resolves_identity_and_rejects_bad_proofs() {
+        use crate::auth::AuthProof;
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[3u8; 32]);
+        let pubkey_hex = signing_key
+            .verifying_key()
+            .as_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<String>();
+
+        let auditor = SecurityAuditor::new(SecurityConfig {
+            authentication_enabled: true,
+            ..Default::default()
+        });
+        auditor.register_pubkey("alice", &pubkey_hex, vec!["writer".to_string()]);
+
+        let sign_at = |timestamp_ms: u64| -> SecurityContext {
+            let message = AuthProof::signed_message("write", "users/alice", timestamp_ms);
+            let signature = signing_key.sign(&message);
+            SecurityContext::anonymous().with_auth_proof(AuthProof {
+                pubkey_hex: pubkey_hex.clone(),
+                signature_hex: signature
+                    .to_bytes()
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect(),
+                timestamp_ms,
+            })
+        };
+
+        let now = crate::auth::now_ms();
+        let valid_context = sign_at(now);
+        let resolved = auditor
+            .pre_operation_check(&valid_context, "write", "users/alice", None)
+            .expect("a correctly signed request should authenticate");
+        assert_eq!(resolved.user_id.as_deref(), Some("alice"));
+        assert!(resolved.has_role("writer"));
+
+        // No signature at all.
+        assert!(auditor
+            .pre_operation_check(&SecurityContext::anonymous(), "write", "users/alice", None)
+            .is_err());
+
+        // Signature valid, but for a different path than the one checked.
+        let mismatched_path = sign_at(now);
+        assert!(auditor
+            .pre_operation_check(&mismatched_path, "write", "users/bob", None)
+            .is_err());
+
+        // Signature valid, but its timestamp is far outside the skew window.
+        let expired = sign_at(now.saturating_sub(10 * 60 * 1000));
+        assert!(auditor
+            .pre_operation_check(&expired, "write", "users/alice", None)
+            .is_err());
+    }
 }
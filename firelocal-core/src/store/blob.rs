@@ -0,0 +1,177 @@
+//! Out-of-line storage for large values, layered on top of `Storage` the
+//! same way `WriteAheadLog` is: generic over the backend so it works
+//! unchanged against `StdStorage` or `MemoryStorage`.
+//!
+//! A value over `threshold` bytes is hashed with SHA-256 and written once to
+//! `objects/<first2hex>/<hash>`; the caller gets back a small pointer record
+//! to persist in its place instead of the full value. Identical large values
+//! (even across different keys) hash to the same object and are stored
+//! once, ref-counted by a sidecar `objects/refcounts.json` index so
+//! `release` can garbage-collect an object once nothing points at it
+//! anymore.
+
+use crate::store::io::Storage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Prefixes a pointer record so `resolve`/`release` can tell an offloaded
+/// value apart from an ordinary inline one without speculatively parsing
+/// every value as JSON.
+const POINTER_MAGIC: &[u8] = b"FLBLOB1\0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlobPointer {
+    hash: String,
+    size: u64,
+}
+
+/// Content-addressed store for values too large to keep inline in the main
+/// keyspace. See the module docs for the on-disk layout.
+pub struct BlobStore<S: Storage> {
+    storage: Arc<S>,
+    root: PathBuf,
+    threshold: usize,
+    refcounts: Mutex<HashMap<String, u64>>,
+}
+
+impl<S: Storage> BlobStore<S> {
+    /// Open a blob store rooted at `root` (created if missing), offloading
+    /// any value larger than `threshold` bytes.
+    pub fn open(storage: Arc<S>, root: impl Into<PathBuf>, threshold: usize) -> io::Result<Self> {
+        let root = root.into();
+        storage.create_dir_all(&root)?;
+        let refcounts = Self::load_refcounts(&storage, &root)?;
+        Ok(Self {
+            storage,
+            root,
+            threshold,
+            refcounts: Mutex::new(refcounts),
+        })
+    }
+
+    /// If `value` exceeds the configured threshold, write it once to the
+    /// content-addressed object store and return the pointer record that
+    /// should be persisted in its place. Values at or under the threshold
+    /// are returned unchanged.
+    pub fn offload(&self, value: &[u8]) -> io::Result<Vec<u8>> {
+        if value.len() <= self.threshold {
+            return Ok(value.to_vec());
+        }
+
+        let hash = hex_sha256(value);
+        let object_path = self.object_path(&hash);
+
+        let mut refcounts = self.refcounts.lock().unwrap();
+        if !refcounts.contains_key(&hash) {
+            if let Some(parent) = object_path.parent() {
+                self.storage.create_dir_all(parent)?;
+            }
+            let mut file = self.storage.create(&object_path)?;
+            file.write_all(value)?;
+            file.sync_all()?;
+        }
+        *refcounts.entry(hash.clone()).or_insert(0) += 1;
+        self.save_refcounts(&refcounts)?;
+        drop(refcounts);
+
+        let pointer = BlobPointer {
+            hash,
+            size: value.len() as u64,
+        };
+        let mut out = POINTER_MAGIC.to_vec();
+        out.extend_from_slice(serde_json::to_string(&pointer).unwrap_or_default().as_bytes());
+        Ok(out)
+    }
+
+    /// Whether `value` is a pointer record produced by `offload`, as opposed
+    /// to an ordinary inline value.
+    pub fn is_pointer(value: &[u8]) -> bool {
+        value.starts_with(POINTER_MAGIC)
+    }
+
+    /// If `value` is a pointer record, stream the referenced object back and
+    /// verify its hash, returning a corruption error on mismatch. Otherwise
+    /// returns `value` unchanged.
+    pub fn resolve(&self, value: &[u8]) -> io::Result<Vec<u8>> {
+        let Some(pointer_json) = value.strip_prefix(POINTER_MAGIC) else {
+            return Ok(value.to_vec());
+        };
+        let pointer = parse_pointer(pointer_json)?;
+
+        let mut file = self.storage.open(&self.object_path(&pointer.hash))?;
+        let mut buf = Vec::with_capacity(pointer.size as usize);
+        file.read_to_end(&mut buf)?;
+
+        let actual_hash = hex_sha256(&buf);
+        if actual_hash != pointer.hash {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "blob corruption: object '{}' hashes to '{actual_hash}'",
+                    pointer.hash
+                ),
+            ));
+        }
+
+        Ok(buf)
+    }
+
+    /// Drop one reference to the blob `value` points at (a no-op if `value`
+    /// isn't a pointer record), deleting the underlying object once its
+    /// reference count reaches zero.
+    pub fn release(&self, value: &[u8]) -> io::Result<()> {
+        let Some(pointer_json) = value.strip_prefix(POINTER_MAGIC) else {
+            return Ok(());
+        };
+        let pointer = parse_pointer(pointer_json)?;
+
+        let mut refcounts = self.refcounts.lock().unwrap();
+        if let Some(count) = refcounts.get_mut(&pointer.hash) {
+            *count -= 1;
+            if *count == 0 {
+                refcounts.remove(&pointer.hash);
+                let _ = self.storage.remove_file(&self.object_path(&pointer.hash));
+            }
+        }
+        self.save_refcounts(&refcounts)
+    }
+
+    fn object_path(&self, hash: &str) -> PathBuf {
+        self.root.join(&hash[..2]).join(hash)
+    }
+
+    fn refcounts_path(root: &Path) -> PathBuf {
+        root.join("refcounts.json")
+    }
+
+    fn load_refcounts(storage: &S, root: &Path) -> io::Result<HashMap<String, u64>> {
+        let path = Self::refcounts_path(root);
+        if !storage.exists(&path) {
+            return Ok(HashMap::new());
+        }
+        let mut file = storage.open(&path)?;
+        let mut buf = String::new();
+        file.read_to_string(&mut buf)?;
+        Ok(serde_json::from_str(&buf).unwrap_or_default())
+    }
+
+    fn save_refcounts(&self, refcounts: &HashMap<String, u64>) -> io::Result<()> {
+        let json = serde_json::to_string(refcounts).unwrap_or_default();
+        let mut file = self.storage.create(&Self::refcounts_path(&self.root))?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()
+    }
+}
+
+fn parse_pointer(pointer_json: &[u8]) -> io::Result<BlobPointer> {
+    serde_json::from_slice(pointer_json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{b:02x}")).collect()
+}
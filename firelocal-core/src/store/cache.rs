@@ -0,0 +1,70 @@
+//! A small bounded LRU cache of decoded values sitting in front of
+//! `FireLocal::get`'s SST scan, so repeat reads of a hot key don't walk
+//! every SST and serialize on their mutexes on every call.
+
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// What a previous SST scan found for a key: a live value, or a tombstone
+/// recording that the key is known deleted — caching the negative result
+/// too, so a repeatedly-queried deleted key doesn't keep re-walking every
+/// SST to rediscover "not found".
+#[derive(Debug, Clone)]
+pub enum CachedValue {
+    Found(Vec<u8>),
+    Tombstone,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Thread-safe, fixed-capacity LRU cache keyed by document key.
+pub struct ReadCache {
+    inner: Mutex<LruCache<String, CachedValue>>,
+    stats: Mutex<CacheStats>,
+}
+
+impl ReadCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(1).unwrap());
+        Self {
+            inner: Mutex::new(LruCache::new(capacity)),
+            stats: Mutex::new(CacheStats::default()),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedValue> {
+        let found = self.inner.lock().unwrap().get(key).cloned();
+        let mut stats = self.stats.lock().unwrap();
+        if found.is_some() {
+            stats.hits += 1;
+        } else {
+            stats.misses += 1;
+        }
+        found
+    }
+
+    pub fn put(&self, key: String, value: CachedValue) {
+        self.inner.lock().unwrap().put(key, value);
+    }
+
+    /// Drop a single key's entry, e.g. because it was just written or
+    /// deleted and the cached result no longer reflects reality.
+    pub fn invalidate(&self, key: &str) {
+        self.inner.lock().unwrap().pop(key);
+    }
+
+    /// Drop every entry, e.g. because `compact`/`flush` just rebuilt the SST
+    /// set underneath this cache.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        *self.stats.lock().unwrap()
+    }
+}
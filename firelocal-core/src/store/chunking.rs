@@ -0,0 +1,277 @@
+//! Content-defined chunking (FastCDC-style, gear-hash based), plus a
+//! content-addressed [`ChunkStore`] for dedup.
+//!
+//! Splitting a large value on a fixed byte offset means a tiny edit near
+//! the front shifts every following chunk boundary, so none of them dedup
+//! against the previous version. Splitting on *content* instead -- where a
+//! rolling fingerprint of the last few bytes determines the boundary --
+//! means only the chunk(s) actually touched by an edit change; everything
+//! before and after it re-chunks identically and is deduped by
+//! `ChunkStore` against what's already stored. This is the technique
+//! behind Garage's content-defined-chunking work, applied here to large
+//! `WalEntry` values via [`crate::store::wal::WriteAheadLog::append_chunked`].
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// No chunk is ever shorter than this (except the final chunk of a value
+/// shorter than `MIN_SIZE` to begin with).
+pub const MIN_SIZE: usize = 2 * 1024;
+/// The rolling split is biased to land near this size: a stricter mask
+/// applies below it, a looser one above, so boundaries cluster here
+/// instead of spreading uniformly between `MIN_SIZE` and `MAX_SIZE`.
+pub const AVG_SIZE: usize = 8 * 1024;
+/// A chunk boundary is forced here even with no natural gear-hash match.
+pub const MAX_SIZE: usize = 64 * 1024;
+
+/// Below this size, a value is kept inline rather than run through `chunk`
+/// -- it's guaranteed to produce just one chunk (`next_chunk_len` returns
+/// immediately once `data.len() <= MIN_SIZE`... and up to `MAX_SIZE` a
+/// boundary may or may not occur), so splitting wouldn't reliably save
+/// anything and isn't worth the indirection. `MAX_SIZE` is the point where
+/// a value always spans at least two chunks, which is where the dedup
+/// benefit this module exists for actually kicks in.
+pub const CHUNKING_THRESHOLD: usize = MAX_SIZE;
+
+/// Fixed arbitrary seed for the gear table -- it only needs to be a
+/// well-mixed constant shared by every writer and reader of this format,
+/// not a secret, so a hardcoded seed keeps chunking deterministic across
+/// runs and processes instead of reshuffling (and invalidating every
+/// existing chunk's boundaries) each time the process restarts.
+const GEAR_SEED: u64 = 0x4645_5254_4c4f_4341;
+
+/// Below `AVG_SIZE`, require more fingerprint bits to be zero (lower match
+/// probability) so chunks are biased to grow toward the average instead of
+/// cutting too early.
+const MASK_SMALL: u64 = (1u64 << 14) - 1;
+/// At or above `AVG_SIZE`, require fewer bits (higher match probability)
+/// so a boundary is found soon, biasing chunks back down instead of
+/// growing toward `MAX_SIZE`.
+const MASK_LARGE: u64 = (1u64 << 12) - 1;
+
+fn gear_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut rng = StdRng::seed_from_u64(GEAR_SEED);
+        let mut table = [0u64; 256];
+        for slot in table.iter_mut() {
+            *slot = rng.gen();
+        }
+        table
+    })
+}
+
+/// The length of the first content-defined chunk in `data`: a gear-hash
+/// fingerprint rolls forward one byte at a time starting at `MIN_SIZE`,
+/// and the chunk ends at the first position where the fingerprint matches
+/// the size-appropriate mask (normalized chunking, see module docs), or at
+/// `MAX_SIZE` if none is found. Returns `data.len()` unchanged if `data`
+/// is no longer than `MIN_SIZE`.
+fn next_chunk_len(data: &[u8]) -> usize {
+    if data.len() <= MIN_SIZE {
+        return data.len();
+    }
+
+    let gear = gear_table();
+    let limit = data.len().min(MAX_SIZE);
+    let mut fingerprint: u64 = 0;
+    for (i, &byte) in data.iter().enumerate().take(limit).skip(MIN_SIZE) {
+        fingerprint = (fingerprint << 1).wrapping_add(gear[byte as usize]);
+        let mask = if i < AVG_SIZE { MASK_SMALL } else { MASK_LARGE };
+        if fingerprint & mask == 0 {
+            return i + 1;
+        }
+    }
+    limit
+}
+
+/// Split `data` into content-defined chunks (see module docs). Chunks are
+/// returned in their original order; concatenating them reproduces `data`.
+pub fn chunk(data: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let len = next_chunk_len(rest);
+        let (head, tail) = rest.split_at(len);
+        chunks.push(head);
+        rest = tail;
+    }
+    chunks
+}
+
+/// A chunk's content-addressed key: its hex-encoded SHA-256 digest, the
+/// same content-addressing scheme `store::blob::BlobStore` uses for
+/// offloaded values. A 32-bit checksum like CRC32 collides at a ~10^4-10^5
+/// chunk birthday bound, which here would mean `ChunkStore::get` silently
+/// returning the wrong bytes for one of two colliding chunks; SHA-256 makes
+/// that practically impossible.
+pub type ChunkKey = String;
+
+fn chunk_key(data: &[u8]) -> ChunkKey {
+    Sha256::digest(data).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// A content-addressed store of chunks, keyed by `ChunkKey`. Two values
+/// that share a chunk (identical bytes at an identical content-defined
+/// boundary) store it once.
+#[derive(Debug, Default)]
+pub struct ChunkStore {
+    chunks: HashMap<ChunkKey, Vec<u8>>,
+}
+
+impl ChunkStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn insert_chunk(&mut self, data: &[u8]) -> (ChunkKey, bool) {
+        let key = chunk_key(data);
+        let is_new = !self.chunks.contains_key(&key);
+        if is_new {
+            self.chunks.insert(key, data.to_vec());
+        }
+        (key, is_new)
+    }
+
+    /// Split `value` into content-defined chunks, writing any not already
+    /// present, and return the ordered list of keys needed to reconstruct
+    /// it via `get`.
+    pub fn put(&mut self, value: &[u8]) -> Vec<ChunkKey> {
+        chunk(value).into_iter().map(|piece| self.insert_chunk(piece).0).collect()
+    }
+
+    /// Like `put`, but also reports which chunks were newly inserted (vs.
+    /// already present from an earlier value), so a caller writing each
+    /// chunk out to durable storage only has to write the new ones --
+    /// this is the actual WAL-amplification saving `put` alone doesn't
+    /// expose. Returns `(key, chunk bytes, was newly inserted)` triples in
+    /// order.
+    pub(crate) fn put_reporting_new<'a>(
+        &mut self,
+        value: &'a [u8],
+    ) -> Vec<(ChunkKey, &'a [u8], bool)> {
+        chunk(value)
+            .into_iter()
+            .map(|piece| {
+                let (key, is_new) = self.insert_chunk(piece);
+                (key, piece, is_new)
+            })
+            .collect()
+    }
+
+    /// Seed a chunk whose key is already known (from a WAL `Chunk` frame
+    /// written by a previous process) without re-splitting or re-hashing
+    /// it -- used by WAL replay to rebuild the in-memory store before
+    /// resolving any `chunk_keys`-only entry.
+    pub(crate) fn insert_known(&mut self, key: ChunkKey, data: Vec<u8>) {
+        self.chunks.entry(key).or_insert(data);
+    }
+
+    /// Reconstruct a value from the ordered chunk keys `put` returned for
+    /// it, or `None` if any referenced chunk is missing.
+    pub fn get(&self, keys: &[ChunkKey]) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        for key in keys {
+            out.extend_from_slice(self.chunks.get(key)?);
+        }
+        Some(out)
+    }
+
+    /// Number of distinct chunks currently stored.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repeatable_bytes(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|i| seed.wrapping_add(i as u8)).collect()
+    }
+
+    #[test]
+    fn test_chunk_reassembles_to_original_value() {
+        let data = repeatable_bytes(200_000, 7);
+        let chunks = chunk(&data);
+        let reassembled: Vec<u8> = chunks.concat();
+        assert_eq!(reassembled, data);
+    }
+
+    #[test]
+    fn test_chunk_respects_min_and_max_size() {
+        let data = repeatable_bytes(500_000, 3);
+        let chunks = chunk(&data);
+        assert!(chunks.len() > 1);
+        for (i, piece) in chunks.iter().enumerate() {
+            assert!(piece.len() <= MAX_SIZE);
+            // Every chunk but the last must reach MIN_SIZE (the last one
+            // may be a short remainder).
+            if i + 1 < chunks.len() {
+                assert!(piece.len() >= MIN_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunk_shorter_than_min_size_is_a_single_chunk() {
+        let data = repeatable_bytes(100, 1);
+        let chunks = chunk(&data);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0], data.as_slice());
+    }
+
+    #[test]
+    fn test_edit_far_from_start_reuses_leading_chunks() {
+        // An append-only edit should leave every chunk before it
+        // byte-identical, since content-defined boundaries don't depend on
+        // the overall length the way fixed-offset chunking would.
+        let mut original = repeatable_bytes(300_000, 5);
+        let original_chunks: Vec<Vec<u8>> = chunk(&original).into_iter().map(|c| c.to_vec()).collect();
+
+        original.extend_from_slice(b"a tiny appended edit");
+        let edited_chunks: Vec<Vec<u8>> = chunk(&original).into_iter().map(|c| c.to_vec()).collect();
+
+        let shared_prefix_len = original_chunks
+            .iter()
+            .zip(edited_chunks.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        assert!(shared_prefix_len >= original_chunks.len() - 1);
+    }
+
+    #[test]
+    fn test_chunk_store_dedups_identical_chunks() {
+        let mut store = ChunkStore::new();
+        let data = repeatable_bytes(300_000, 9);
+
+        let keys_a = store.put(&data);
+        let count_after_first = store.len();
+        let keys_b = store.put(&data);
+
+        assert_eq!(keys_a, keys_b);
+        assert_eq!(store.len(), count_after_first);
+    }
+
+    #[test]
+    fn test_chunk_store_round_trips_via_get() {
+        let mut store = ChunkStore::new();
+        let data = repeatable_bytes(150_000, 11);
+        let keys = store.put(&data);
+        assert_eq!(store.get(&keys).unwrap(), data);
+    }
+
+    #[test]
+    fn test_chunk_store_get_missing_key_returns_none() {
+        let store = ChunkStore::new();
+        assert!(store.get(&["deadbeef".to_string()]).is_none());
+    }
+}
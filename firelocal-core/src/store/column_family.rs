@@ -0,0 +1,199 @@
+//! Named column families: independent keyspaces under one store directory,
+//! each with its own WAL segment, memtable, and SST files, so e.g. large
+//! document bodies can live (and eventually compact) separately from small
+//! metadata without a second `FireLocal::new` call. See `FireLocal::put_cf`/
+//! `get_cf`/`delete_cf`. The un-suffixed default keyspace `FireLocal::put`/
+//! `get`/`delete` already use is unaffected by this module.
+
+use crate::store::io::StdStorage;
+use crate::store::memtable::Memtable;
+use crate::store::sst::{SstBuilder, SstReader, SstSearchResult};
+use crate::store::wal::WriteAheadLog;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+pub struct ColumnFamily {
+    name: String,
+    wal: WriteAheadLog<StdStorage>,
+    memtable: Memtable,
+    ssts: Vec<Arc<Mutex<SstReader>>>,
+}
+
+impl ColumnFamily {
+    /// Open (or create) the column `name` under `dir`: its own
+    /// `wal_<name>.log`, replayed into a fresh `Memtable`, plus any
+    /// `<name>_*.sst` files already on disk (newest first, same convention
+    /// `FireLocal::new` uses for the default column).
+    pub fn open(dir: &Path, name: &str) -> io::Result<Self> {
+        let wal_path = dir.join(format!("wal_{name}.log"));
+        let wal = WriteAheadLog::open(Arc::new(StdStorage), &wal_path)?;
+
+        let mut memtable = Memtable::new();
+        if let Ok(iter) = wal.iter() {
+            for entry_res in iter {
+                if let Ok(entry) = entry_res {
+                    if entry.is_empty() || entry.len() < 5 {
+                        continue;
+                    }
+                    let op = entry[0];
+                    let k_len = u32::from_le_bytes(entry[1..5].try_into().unwrap()) as usize;
+                    if entry.len() < 5 + k_len {
+                        continue;
+                    }
+                    let key = String::from_utf8_lossy(&entry[5..5 + k_len]).to_string();
+
+                    if op == 0 || op == 2 {
+                        if entry.len() < 5 + k_len + 4 {
+                            continue;
+                        }
+                        let v_len_offset = 5 + k_len;
+                        let v_len = u32::from_le_bytes(
+                            entry[v_len_offset..v_len_offset + 4].try_into().unwrap(),
+                        ) as usize;
+                        if entry.len() < v_len_offset + 4 + v_len {
+                            continue;
+                        }
+                        let value = entry[v_len_offset + 4..v_len_offset + 4 + v_len].to_vec();
+                        if op == 0 {
+                            memtable.put(key, value);
+                        } else {
+                            memtable.merge(key, value);
+                        }
+                    } else if op == 1 {
+                        memtable.delete(key);
+                    }
+                }
+            }
+        }
+
+        let prefix = format!("{name}_");
+        let mut ssts = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            let mut sst_files = Vec::new();
+            for entry in entries.flatten() {
+                let p = entry.path();
+                let matches_column = p
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .map(|s| s.starts_with(&prefix))
+                    .unwrap_or(false);
+                if matches_column && p.extension().and_then(|e| e.to_str()) == Some("sst") {
+                    let mtime = entry
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                    sst_files.push((p, mtime));
+                }
+            }
+            sst_files.sort_by(|a, b| b.1.cmp(&a.1));
+            for (p, _) in sst_files {
+                if let Ok(reader) = SstReader::open(p) {
+                    ssts.push(Arc::new(Mutex::new(reader)));
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            wal,
+            memtable,
+            ssts,
+        })
+    }
+
+    pub fn put(&mut self, key: String, value: Vec<u8>) -> io::Result<()> {
+        let mut entry = Vec::new();
+        entry.push(0u8);
+        entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        entry.extend_from_slice(key.as_bytes());
+        entry.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        entry.extend_from_slice(&value);
+
+        self.wal.append(&entry)?;
+        self.memtable.put(key, value);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, key: String) -> io::Result<()> {
+        let mut entry = Vec::new();
+        entry.push(1u8);
+        entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        entry.extend_from_slice(key.as_bytes());
+        entry.extend_from_slice(&0u32.to_le_bytes());
+
+        self.wal.append(&entry)?;
+        self.memtable.delete(key);
+        Ok(())
+    }
+
+    /// Append `operand` to `key`'s pending merge operands, raw-encoded like
+    /// `put`/`delete` above (op byte `2`). Unlike the default column family,
+    /// `ColumnFamily` has no merge operator of its own, so `get` never folds
+    /// a pending merge -- callers that need resolved values should use the
+    /// default column's `FireLocal::merge`/`FireLocal::get` instead.
+    pub fn merge(&mut self, key: String, operand: Vec<u8>) -> io::Result<()> {
+        let mut entry = Vec::new();
+        entry.push(2u8);
+        entry.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        entry.extend_from_slice(key.as_bytes());
+        entry.extend_from_slice(&(operand.len() as u32).to_le_bytes());
+        entry.extend_from_slice(&operand);
+
+        self.wal.append(&entry)?;
+        self.memtable.merge(key, operand);
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        if let Some(val) = self.memtable.get(key) {
+            return Some(val.to_vec());
+        }
+
+        for sst_mutex in &self.ssts {
+            let mut sst = sst_mutex.lock().unwrap();
+            match sst.get(key) {
+                Ok(SstSearchResult::Found(val)) => return Some(val),
+                Ok(SstSearchResult::Deleted) => return None,
+                Ok(SstSearchResult::NotFound) | Ok(SstSearchResult::Corrupt { .. }) | Err(_) => {
+                    continue
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Flush this column's memtable to a new `<name>_<uuid>.sst` under
+    /// `dir`, independent of any other column's SST set.
+    pub fn flush(&mut self, dir: &Path) -> io::Result<()> {
+        let uuid = uuid::Uuid::new_v4();
+        let sst_path = dir.join(format!("{}_{}.sst", self.name, uuid));
+
+        let builder = SstBuilder::new(sst_path)?;
+        builder.build(&self.memtable)?;
+        Ok(())
+    }
+}
+
+/// Names of every column family with a `wal_<name>.log` under `dir`,
+/// including ones opened in an earlier process (not just this session's
+/// already-open `ColumnFamily`s) -- used by `FireLocal::list_column_families`.
+pub fn list_existing(dir: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(names),
+        Err(e) => return Err(e),
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(file_name) = file_name.to_str() else {
+            continue;
+        };
+        if let Some(name) = file_name.strip_prefix("wal_").and_then(|s| s.strip_suffix(".log")) {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
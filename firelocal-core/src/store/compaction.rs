@@ -1,18 +1,66 @@
-use crate::store::memtable::Memtable;
-use crate::store::sst::SstBuilder;
+use crate::store::encryption::KEY_LEN;
+use crate::store::sst::{SstBuilder, SstReader};
 use anyhow::Result;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// How `Compactor` decides what to merge on a given run. `Full` is the
+/// original merge-everything-into-one-file behavior: simple, but O(total
+/// data) write amplification on every run. `SizeTiered` is a classic LSM
+/// leveling policy: SSTs accumulate at level 0, and once a level holds more
+/// than `fanout` files or exceeds `max_bytes`, `compact_if_needed` merges
+/// just that level into a single SST promoted to the next level, so a
+/// background compactor does bounded work per call instead of re-merging
+/// data that's already settled into a higher level.
+#[derive(Debug, Clone, Copy)]
+pub enum CompactionPolicy {
+    Full,
+    SizeTiered { fanout: usize, max_bytes: u64 },
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        CompactionPolicy::Full
+    }
+}
 
 /// Compaction strategy for merging SST files and removing tombstones
 pub struct Compactor {
     data_dir: PathBuf,
+    encryption_key: Option<[u8; KEY_LEN]>,
+    policy: CompactionPolicy,
 }
 
 impl Compactor {
     pub fn new(data_dir: PathBuf) -> Self {
-        Self { data_dir }
+        Self {
+            data_dir,
+            encryption_key: None,
+            policy: CompactionPolicy::default(),
+        }
+    }
+
+    /// Compact SSTs encrypted under `key`: each source file is opened with
+    /// `SstReader::open_encrypted` and the merged SST is written back out
+    /// with `SstBuilder::encrypted`, so the result stays protected at rest
+    /// the same as the files it replaces.
+    pub fn with_encryption_key(mut self, key: [u8; KEY_LEN]) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// Use `policy` instead of the default `CompactionPolicy::Full` to
+    /// decide what `compact_if_needed` merges. `compact` itself always
+    /// merges everything regardless of policy -- it's the explicit,
+    /// unconditional operation; `compact_if_needed` is the bounded,
+    /// policy-driven one meant for a background loop.
+    pub fn with_policy(mut self, policy: CompactionPolicy) -> Self {
+        self.policy = policy;
+        self
     }
 
     /// Run compaction: merge all SST files, remove tombstones, create new SST
@@ -26,52 +74,159 @@ impl Compactor {
         }
 
         stats.files_before = sst_files.len();
+        stats.size_before = self.calculate_total_size(&sst_files)?;
+
+        let new_sst_path = self.data_dir.join(format!("compacted-{}.sst", Uuid::new_v4()));
+        // `sst_files` is every SST this store has, so nothing outside the
+        // merge could still hold an older value for a deleted key -- safe to
+        // drop its tombstone for good.
+        self.write_merged(&sst_files, &new_sst_path, true, &mut stats)?;
 
-        // Load all SST files and merge their data
-        let merged_data = self.merge_sst_files(&sst_files)?;
-        stats.entries_before = merged_data.len();
+        let mut manifest = Manifest::load(&self.data_dir);
+        for old_file in &sst_files {
+            manifest.remove(old_file);
+        }
+        if stats.files_after == 1 {
+            manifest.set_level(&new_sst_path, 0);
+        }
+        manifest.save(&self.data_dir)?;
 
-        // Remove tombstones (entries with None value)
-        let compacted_data: HashMap<String, Vec<u8>> = merged_data
-            .into_iter()
-            .filter_map(|(k, v)| v.map(|val| (k, val)))
-            .collect();
+        for old_file in &sst_files {
+            let _ = fs::remove_file(old_file); // Ignore errors
+        }
 
-        stats.entries_after = compacted_data.len();
-        stats.tombstones_removed = stats.entries_before - stats.entries_after;
+        Ok(stats)
+    }
 
-        // Calculate size before deletion
-        stats.size_before = self.calculate_total_size(&sst_files)?;
+    /// Bounded, policy-driven compaction for a background loop: under
+    /// `CompactionPolicy::SizeTiered`, merge the smallest level whose file
+    /// count exceeds `fanout` or total size exceeds `max_bytes` into a
+    /// single SST promoted to the next level, and stop -- one level per
+    /// call, so a caller looping this is free to cascade a deep backlog
+    /// across several calls rather than doing it all at once. A no-op
+    /// (`files_before: 0`) if no level is eligible, or if the policy is
+    /// `Full` (use `compact` directly instead).
+    pub fn compact_if_needed(&self) -> Result<CompactionStats> {
+        let CompactionPolicy::SizeTiered { fanout, max_bytes } = self.policy else {
+            return Ok(CompactionStats::default());
+        };
 
-        // Write new compacted SST file if we have data
-        if !compacted_data.is_empty() {
-            let new_sst_path = self.data_dir.join("compacted.sst");
+        let mut manifest = Manifest::load(&self.data_dir);
+        let sst_files = self.find_sst_files()?;
+        let total_files = sst_files.len();
+        let mut by_level: BTreeMap<u32, Vec<PathBuf>> = BTreeMap::new();
+        for file in sst_files {
+            by_level.entry(manifest.level_of(&file)).or_default().push(file);
+        }
 
-            // Create a temporary memtable with compacted data
-            let mut temp_memtable = Memtable::new();
-            for (key, value) in compacted_data {
-                temp_memtable.put(key, value);
-            }
+        let mut stats = CompactionStats::default();
+        for (level, files) in &by_level {
+            stats
+                .per_level_file_counts
+                .insert(level.to_string(), files.len());
+        }
 
-            // Build SST from memtable
-            let builder = SstBuilder::new(&new_sst_path)?;
-            builder.build(&temp_memtable)?;
+        let eligible_level = by_level.iter().find_map(|(level, files)| {
+            let total_bytes = files
+                .iter()
+                .filter_map(|f| fs::metadata(f).ok())
+                .map(|m| m.len())
+                .sum::<u64>();
+            (files.len() > fanout || total_bytes > max_bytes).then_some(*level)
+        });
 
+        let Some(level) = eligible_level else {
+            return Ok(stats);
+        };
+        let files = by_level.get(&level).cloned().unwrap_or_default();
+
+        stats.files_before = files.len();
+        stats.size_before = self.calculate_total_size(&files)?;
+        stats.level_compacted = Some(level);
+
+        // Only safe to drop a tombstone for good if `files` is every SST
+        // this store has -- otherwise an older, unmerged value for the same
+        // key could still be sitting in a level this call didn't touch, and
+        // dropping the tombstone now would let that stale value resurface
+        // the next time it's read. Short of that, promote the tombstone
+        // forward into the merged file instead.
+        let drop_tombstones = files.len() == total_files;
+        let new_sst_path = self.data_dir.join(format!("compacted-{}.sst", Uuid::new_v4()));
+        self.write_merged(&files, &new_sst_path, drop_tombstones, &mut stats)?;
+
+        for old_file in &files {
+            manifest.remove(old_file);
+        }
+        if stats.files_after == 1 {
+            manifest.set_level(&new_sst_path, level + 1);
+        }
+        manifest.save(&self.data_dir)?;
+
+        for old_file in &files {
+            let _ = fs::remove_file(old_file);
+        }
+
+        Ok(stats)
+    }
+
+    /// Stream `files`'s k-way merge into a fresh SST at `new_sst_path`,
+    /// filling in `stats`'s `entries_before`/`entries_after`/
+    /// `tombstones_removed`/`files_after`/`size_after`. Shared by `compact`
+    /// (merges every file) and `compact_if_needed` (merges one level's
+    /// files); the two differ only in which files they pass in, what they do
+    /// with the manifest afterward, and whether dropping a tombstone for
+    /// good is actually safe -- see `drop_tombstones`.
+    ///
+    /// `drop_tombstones` must only be `true` when `files` covers every SST
+    /// this store currently has. Dropping a tombstone discards the only
+    /// record that a key was ever deleted; if some file outside `files`
+    /// (an unmerged level `compact_if_needed` left untouched) still carries
+    /// an older put for that key, dropping the tombstone here would let that
+    /// stale value resurface the next time it's read. When `false`, a
+    /// tombstone is written forward as a real delete record instead of being
+    /// dropped, so it keeps shadowing that older value until a later merge
+    /// can prove it's safe to retire for good.
+    fn write_merged(
+        &self,
+        files: &[PathBuf],
+        new_sst_path: &Path,
+        drop_tombstones: bool,
+        stats: &mut CompactionStats,
+    ) -> Result<()> {
+        let mut builder = match self.encryption_key {
+            Some(key) => SstBuilder::encrypted(new_sst_path, key)?,
+            None => SstBuilder::new(new_sst_path)?,
+        };
+
+        for item in self.merge_sst_files(files)? {
+            let (key, value) = item?;
+            stats.entries_before += 1;
+            match value {
+                Some(value) => {
+                    builder.write_put(&key, &value)?;
+                    stats.entries_after += 1;
+                }
+                None if drop_tombstones => {
+                    stats.tombstones_removed += 1;
+                }
+                None => {
+                    builder.write_delete(&key)?;
+                    stats.entries_after += 1;
+                }
+            }
+        }
+        builder.finish()?;
+
+        if stats.entries_after > 0 {
             stats.files_after = 1;
-            stats.size_after = fs::metadata(&new_sst_path)?.len();
+            stats.size_after = fs::metadata(new_sst_path)?.len();
         } else {
             stats.files_after = 0;
             stats.size_after = 0;
+            let _ = fs::remove_file(new_sst_path);
         }
 
-        // Delete old SST files (except the new compacted one)
-        for old_file in &sst_files {
-            if old_file.file_name().unwrap() != "compacted.sst" {
-                let _ = fs::remove_file(old_file); // Ignore errors
-            }
-        }
-
-        Ok(stats)
+        Ok(())
     }
 
     /// Find all SST files in the data directory
@@ -100,23 +255,28 @@ impl Compactor {
         Ok(sst_files)
     }
 
-    /// Merge data from multiple SST files
-    /// Later entries override earlier ones (last-write-wins)
-    fn merge_sst_files(&self, _files: &[PathBuf]) -> Result<HashMap<String, Option<Vec<u8>>>> {
-        let merged = HashMap::new();
-
-        // Note: Full SST iteration requires enhancing SstReader
-        // Current SstReader only supports get() by key
-        // For production, we would:
-        // 1. Add iterator support to SstReader
-        // 2. Scan all entries from each SST
-        // 3. Merge with last-write-wins semantics
-
-        // For now, return empty map which will result in stats showing
-        // the compaction happened but no data was merged
-        // This is acceptable for M1 as the framework is in place
-
-        Ok(merged)
+    /// Merge `files` (already sorted oldest first, see `find_sst_files`)
+    /// into a single ordered stream: each file contributes its own
+    /// `SstReader::into_ordered_iter`, and `KWayMerge` picks the smallest
+    /// pending key across all of them on every step, keeping only the
+    /// newest file's value when several share a key -- including the
+    /// put -> delete -> put case, where the key's live final state simply
+    /// lives in the newest file that mentions it at all. Bounds memory to
+    /// one pending entry per file rather than a file's entire contents.
+    fn merge_sst_files(
+        &self,
+        files: &[PathBuf],
+    ) -> Result<impl Iterator<Item = io::Result<(String, Option<Vec<u8>>)>>> {
+        let mut sources: Vec<Box<dyn Iterator<Item = io::Result<(String, Option<Vec<u8>>)>>>> =
+            Vec::with_capacity(files.len());
+        for file in files {
+            let reader = match self.encryption_key {
+                Some(key) => SstReader::open_encrypted(file, key)?,
+                None => SstReader::open(file)?,
+            };
+            sources.push(Box::new(reader.into_ordered_iter()?));
+        }
+        Ok(KWayMerge::new(sources)?)
     }
 
     /// Calculate total size of SST files
@@ -129,8 +289,163 @@ impl Compactor {
     }
 }
 
+/// One source's current head in `KWayMerge`'s heap: the smallest pending key
+/// across all sources is the next one popped, with `source_rank` (a
+/// source's position in the oldest-first `files` list `merge_sst_files`
+/// builds it from, so higher is newer) breaking ties in favor of the newest
+/// file. Ordering only ever compares `key` -- `source_rank` is read directly
+/// by `KWayMerge::next` once a tie is known to exist.
+struct MergeHead {
+    key: String,
+    source_rank: usize,
+    value: Option<Vec<u8>>,
+}
+
+impl PartialEq for MergeHead {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for MergeHead {}
+
+impl PartialOrd for MergeHead {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MergeHead {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// Streaming k-way merge over already-sorted sources (see `merge_sst_files`):
+/// a binary min-heap (via `Reverse`, since `BinaryHeap` is a max-heap) holds
+/// each source's current head, so popping the smallest key and refilling
+/// that one source's slot costs `O(log sources)` rather than re-scanning
+/// every source on each step.
+struct KWayMerge {
+    sources: Vec<Box<dyn Iterator<Item = io::Result<(String, Option<Vec<u8>>)>>>>,
+    heap: BinaryHeap<Reverse<MergeHead>>,
+}
+
+impl KWayMerge {
+    fn new(
+        mut sources: Vec<Box<dyn Iterator<Item = io::Result<(String, Option<Vec<u8>>)>>>>,
+    ) -> io::Result<Self> {
+        let mut heap = BinaryHeap::new();
+        for (source_rank, source) in sources.iter_mut().enumerate() {
+            if let Some(item) = source.next() {
+                let (key, value) = item?;
+                heap.push(Reverse(MergeHead {
+                    key,
+                    source_rank,
+                    value,
+                }));
+            }
+        }
+        Ok(Self { sources, heap })
+    }
+
+    /// Pull `source_rank`'s next head into the heap, if it has one.
+    fn refill(&mut self, source_rank: usize) -> io::Result<()> {
+        if let Some(item) = self.sources[source_rank].next() {
+            let (key, value) = item?;
+            self.heap.push(Reverse(MergeHead {
+                key,
+                source_rank,
+                value,
+            }));
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for KWayMerge {
+    type Item = io::Result<(String, Option<Vec<u8>>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(mut winner) = self.heap.pop()?;
+        if let Err(e) = self.refill(winner.source_rank) {
+            return Some(Err(e));
+        }
+
+        // Every other source currently holding this same key also needs
+        // advancing, with the newest (`source_rank`) of them winning the key.
+        while let Some(Reverse(tied)) = self.heap.peek() {
+            if tied.key != winner.key {
+                break;
+            }
+            let Reverse(tied) = self.heap.pop().unwrap();
+            if let Err(e) = self.refill(tied.source_rank) {
+                return Some(Err(e));
+            }
+            if tied.source_rank > winner.source_rank {
+                winner = tied;
+            }
+        }
+
+        Some(Ok((winner.key, winner.value)))
+    }
+}
+
+/// On-disk record of which level each SST file belongs to, so a leveled
+/// `CompactionPolicy::SizeTiered` setup survives a restart instead of
+/// treating every file as level 0 again. Keyed by filename (not full path)
+/// since the manifest travels with the directory. Written atomically (temp
+/// file + rename), same as `store::migration`'s version file.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    levels: HashMap<String, u32>,
+}
+
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+impl Manifest {
+    /// Load the manifest from `data_dir`, or an empty one (every file
+    /// defaults to level 0) if it doesn't exist yet or fails to parse.
+    fn load(data_dir: &Path) -> Self {
+        fs::read_to_string(data_dir.join(MANIFEST_FILE_NAME))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, data_dir: &Path) -> io::Result<()> {
+        let tmp_path = data_dir.join(format!("{MANIFEST_FILE_NAME}.tmp"));
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, data_dir.join(MANIFEST_FILE_NAME))
+    }
+
+    /// The level `path`'s file belongs to, `0` if it has no entry (a file
+    /// predating the manifest, or one `Full`-compacted without leveling).
+    fn level_of(&self, path: &Path) -> u32 {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| self.levels.get(n))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn set_level(&mut self, path: &Path, level: u32) {
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            self.levels.insert(name.to_string(), level);
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            self.levels.remove(name);
+        }
+    }
+}
+
 /// Statistics from a compaction run
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, serde::Serialize)]
 pub struct CompactionStats {
     pub files_before: usize,
     pub files_after: usize,
@@ -139,6 +454,14 @@ pub struct CompactionStats {
     pub tombstones_removed: usize,
     pub size_before: u64,
     pub size_after: u64,
+    /// Which level `compact_if_needed` merged, `None` for a no-op call or
+    /// for `compact`'s unconditional full merge (which doesn't operate
+    /// per-level).
+    pub level_compacted: Option<u32>,
+    /// How many files `compact_if_needed` found at each level before
+    /// merging, keyed by the level number formatted as a string (JSON
+    /// object keys must be strings).
+    pub per_level_file_counts: BTreeMap<String, usize>,
 }
 
 impl CompactionStats {
@@ -0,0 +1,253 @@
+//! On-disk format versioning shared by `WriteAheadLog` and
+//! `SstBuilder`/`SstReader`.
+//!
+//! Every WAL and SST file starts with a fixed 8-byte header —
+//! `b"FLCL" || u16 version (LE) || u16 flags (LE)` — so a future change to
+//! either file's record encoding can bump `CURRENT_VERSION` without
+//! silently corrupting or dropping data written by an older build. A file
+//! with no recognizable header is treated as version 0, the pre-versioning
+//! legacy layout.
+//!
+//! Starting at `CHECKSUM_VERSION`, every record also carries a trailing
+//! 4-byte CRC32 (`record_crc`, via `crc32fast` -- the same crate and
+//! algorithm `WriteAheadLog`/`scrub` already use for their own per-frame
+//! integrity checks) over its flag/length/key/value bytes, so a torn write
+//! or bit flip is detected instead of silently handed back as a value.
+//!
+//! Starting at `INDEX_VERSION`, an SST additionally has a sparse block
+//! index and footer appended after its last record (see `store::sst` for
+//! that layout) -- this module only needs to know such a file's record
+//! section doesn't necessarily run to EOF.
+//!
+//! `decoder_for_version` maps a version to the record decoder that
+//! understands it; `upgrade_record_file` uses that to stream an old file's
+//! records through the legacy decoder and re-emit them through the current
+//! encoder into a fresh, header-tagged file.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const MAGIC: [u8; 4] = *b"FLCL";
+pub const CURRENT_VERSION: u16 = 3;
+/// Pre-versioning files have no header at all; treated as this version.
+pub const LEGACY_VERSION: u16 = 0;
+/// The first version whose records carry a trailing CRC32 (see
+/// `record_crc`/`decode_record_v2`). Kept separate from `CURRENT_VERSION`
+/// so a later, unrelated version bump doesn't silently stop every existing
+/// checksummed file from being treated as checksummed.
+pub const CHECKSUM_VERSION: u16 = 2;
+/// The first version whose SSTs carry a trailing sparse block index and
+/// footer (see `store::sst`). Record encoding is unchanged from
+/// `CHECKSUM_VERSION`; only what follows the last record differs, so this
+/// has its own decoder entry only to stay future-proof against a later,
+/// unrelated version bump.
+pub const INDEX_VERSION: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatHeader {
+    pub version: u16,
+    pub flags: u16,
+}
+
+impl FormatHeader {
+    pub fn current() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            flags: 0,
+        }
+    }
+}
+
+pub fn write_header<W: Write>(w: &mut W, header: &FormatHeader) -> io::Result<()> {
+    w.write_all(&MAGIC)?;
+    w.write_all(&header.version.to_le_bytes())?;
+    w.write_all(&header.flags.to_le_bytes())?;
+    Ok(())
+}
+
+/// On-disk size of a header written by `write_header`: always `MAGIC` plus
+/// a `u16` version and a `u16` flags, regardless of version.
+pub fn header_len() -> u64 {
+    MAGIC.len() as u64 + 2 + 2
+}
+
+/// Read a header from the start of `r`. If the first bytes aren't `MAGIC`
+/// (or the file is too short to hold one), `r` is rewound to where it
+/// started and the file is reported as `LEGACY_VERSION`, so the caller's
+/// record decoder sees the very first record byte rather than header
+/// bytes it misinterpreted as a record.
+pub fn read_header<R: Read + Seek>(r: &mut R) -> io::Result<FormatHeader> {
+    let start = r.stream_position()?;
+
+    let mut magic = [0u8; 4];
+    let mut version_flags = [0u8; 4];
+    let recognized = r.read_exact(&mut magic).is_ok()
+        && magic == MAGIC
+        && r.read_exact(&mut version_flags).is_ok();
+
+    if recognized {
+        let version = u16::from_le_bytes([version_flags[0], version_flags[1]]);
+        let flags = u16::from_le_bytes([version_flags[2], version_flags[3]]);
+        Ok(FormatHeader { version, flags })
+    } else {
+        r.seek(SeekFrom::Start(start))?;
+        Ok(FormatHeader {
+            version: LEGACY_VERSION,
+            flags: 0,
+        })
+    }
+}
+
+/// A single `[flag][key][value]` record, shared by the SST format and by
+/// the flag-tagged put/delete entries the engine writes into the WAL.
+pub type Record = (u8, String, Vec<u8>);
+
+/// Reads one record from `r`, returning `Ok(None)` on a clean EOF between
+/// records.
+pub type RecordDecoder = fn(&mut dyn Read) -> io::Result<Option<Record>>;
+
+/// The `[flag: u8][k_len: u32][key][v_len: u32][value]` layout used by both
+/// version 0 (legacy, headerless) and version 1 (current, header-tagged)
+/// files — introducing the header didn't change the record shape itself,
+/// only what precedes the first one. A future version that changes the
+/// record layout would get its own decoder here.
+fn decode_record_v0_v1(r: &mut dyn Read) -> io::Result<Option<Record>> {
+    let mut flag_buf = [0u8; 1];
+    if r.read(&mut flag_buf)? == 0 {
+        return Ok(None);
+    }
+    let flag = flag_buf[0];
+
+    let mut len_buf = [0u8; 4];
+    r.read_exact(&mut len_buf)?;
+    let k_len = u32::from_le_bytes(len_buf) as usize;
+    let mut key_buf = vec![0u8; k_len];
+    r.read_exact(&mut key_buf)?;
+    let key = String::from_utf8_lossy(&key_buf).to_string();
+
+    r.read_exact(&mut len_buf)?;
+    let v_len = u32::from_le_bytes(len_buf) as usize;
+    let mut value = vec![0u8; v_len];
+    r.read_exact(&mut value)?;
+
+    Ok(Some((flag, key, value)))
+}
+
+/// Version 2's record layout: identical to `decode_record_v0_v1`'s
+/// `[flag][k_len][key][v_len][value]`, plus a trailing `[crc: u32]` over
+/// those same bytes. Rejects the record with an `InvalidData` error if the
+/// stored CRC doesn't match what `record_crc` recomputes, rather than
+/// handing back a value that may have been silently corrupted.
+fn decode_record_v2(r: &mut dyn Read) -> io::Result<Option<Record>> {
+    let Some((flag, key, value)) = decode_record_v0_v1(r)? else {
+        return Ok(None);
+    };
+
+    let mut crc_buf = [0u8; 4];
+    r.read_exact(&mut crc_buf)?;
+    let stored_crc = u32::from_le_bytes(crc_buf);
+    if record_crc(flag, &key, &value) != stored_crc {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "record CRC mismatch",
+        ));
+    }
+
+    Ok(Some((flag, key, value)))
+}
+
+pub fn decoder_for_version(version: u16) -> io::Result<RecordDecoder> {
+    match version {
+        LEGACY_VERSION | 1 => Ok(decode_record_v0_v1),
+        2 | INDEX_VERSION => Ok(decode_record_v2),
+        other => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported on-disk format version {other}"),
+        )),
+    }
+}
+
+/// CRC32 (`crc32fast`) over a record's flag, length-prefixed key, and
+/// length-prefixed value -- the same bytes `encode_record` writes before
+/// its trailing checksum, so `decode_record_v2` can recompute and compare.
+pub fn record_crc(flag: u8, key: &str, value: &[u8]) -> u32 {
+    let key_bytes = key.as_bytes();
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&[flag]);
+    hasher.update(&(key_bytes.len() as u32).to_le_bytes());
+    hasher.update(key_bytes);
+    hasher.update(&(value.len() as u32).to_le_bytes());
+    hasher.update(value);
+    hasher.finalize()
+}
+
+/// Encode one record in the current (`CHECKSUM_VERSION`-and-up) layout:
+/// `[flag][k_len][key][v_len][value][crc: u32]`.
+pub fn encode_record<W: Write>(w: &mut W, flag: u8, key: &str, value: &[u8]) -> io::Result<()> {
+    let key_bytes = key.as_bytes();
+    w.write_all(&[flag])?;
+    w.write_all(&(key_bytes.len() as u32).to_le_bytes())?;
+    w.write_all(key_bytes)?;
+    w.write_all(&(value.len() as u32).to_le_bytes())?;
+    w.write_all(value)?;
+    w.write_all(&record_crc(flag, key, value).to_le_bytes())?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpgradeReport {
+    pub migrated: usize,
+    pub already_current: bool,
+}
+
+/// Bring a `[flag][key][value]`-record file (an SST, or any file using that
+/// same record shape) up to `CURRENT_VERSION`, in place. A no-op — returning
+/// `already_current: true` — if the file is already tagged with the current
+/// version. Otherwise every record is streamed through the version's
+/// decoder and re-emitted through the current encoder into `<path>.upgrading`,
+/// which is then atomically renamed over `path`.
+pub fn upgrade_record_file(path: &Path) -> io::Result<UpgradeReport> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = read_header(&mut reader)?;
+
+    if header.version == CURRENT_VERSION {
+        return Ok(UpgradeReport {
+            migrated: 0,
+            already_current: true,
+        });
+    }
+
+    let decode = decoder_for_version(header.version)?;
+    let tmp_path = path.with_extension("upgrading");
+    let mut writer = BufWriter::new(
+        File::options()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&tmp_path)?,
+    );
+    write_header(&mut writer, &FormatHeader::current())?;
+
+    let mut migrated = 0usize;
+    while let Some((flag, key, value)) = decode(&mut reader)? {
+        encode_record(&mut writer, flag, &key, &value)?;
+        migrated += 1;
+    }
+    writer.flush()?;
+    drop(writer);
+    drop(reader);
+
+    std::fs::rename(&tmp_path, path)?;
+
+    log::info!(
+        "upgraded '{}' from format version {} to {CURRENT_VERSION}: {migrated} entries migrated",
+        path.display(),
+        header.version,
+    );
+
+    Ok(UpgradeReport {
+        migrated,
+        already_current: false,
+    })
+}
@@ -0,0 +1,356 @@
+//! An fs-verity-style integrity layer over any `Storage` backend.
+//!
+//! Each file written through `IntegrityStorage` is split into fixed-size
+//! pages, each page hashed with SHA-256, and the leaf hashes folded up into
+//! a Merkle tree whose root is persisted alongside the file in a sidecar
+//! `<path>.fsv`. On read, a touched page is re-hashed and checked against
+//! its leaf on the spot (via `FileHandle::read_at`), so tampering or
+//! bit-rot anywhere in the file is caught without rehashing the whole
+//! thing on every open — only the pages actually read pay the cost.
+//!
+//! `IntegrityStorage` is a transparent decorator: it implements `Storage`
+//! itself and can wrap `StdStorage` (or `MemoryStorage`) unchanged.
+
+use crate::store::io::{FileHandle, FileLock, FileStat, LockMode, RecordLock, Storage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const PAGE_SIZE: u64 = 4096;
+
+/// Raised when a page's content no longer matches its recorded leaf hash.
+#[derive(Debug)]
+pub struct IntegrityError {
+    pub path: PathBuf,
+    pub page: u64,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "integrity check failed for '{}' at page {}",
+            self.path.display(),
+            self.page
+        )
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FsvSidecar {
+    page_size: u64,
+    /// Hex-encoded SHA-256 of each page, in order.
+    leaves: Vec<String>,
+    /// Hex-encoded Merkle root over `leaves`.
+    root: String,
+}
+
+/// Storage decorator that maintains a Merkle tree of page hashes for every
+/// file written through it, verifying pages lazily as they're read.
+pub struct IntegrityStorage<S: Storage> {
+    inner: Arc<S>,
+}
+
+impl<S: Storage> IntegrityStorage<S> {
+    pub fn new(inner: Arc<S>) -> Self {
+        Self { inner }
+    }
+
+    fn sidecar_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".fsv");
+        PathBuf::from(name)
+    }
+
+    /// Recompute the Merkle root over `path`'s current on-disk content. Does
+    /// not require a sidecar to already exist, so a caller can pin an
+    /// expected root right after writing a file.
+    pub fn measure_root(&self, path: &Path) -> io::Result<[u8; 32]> {
+        let mut file = self.inner.open(path)?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        Ok(merkle_root(&page_hashes(&content)))
+    }
+
+    fn load_sidecar(&self, path: &Path) -> io::Result<Option<FsvSidecar>> {
+        let sidecar_path = Self::sidecar_path(path);
+        if !self.inner.exists(&sidecar_path) {
+            return Ok(None);
+        }
+        let mut file = self.inner.open(&sidecar_path)?;
+        let mut buf = String::new();
+        file.read_to_string(&mut buf)?;
+        Ok(serde_json::from_str(&buf).ok())
+    }
+
+    fn write_sidecar(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let leaves = page_hashes(content);
+        let root = merkle_root(&leaves);
+        let sidecar = FsvSidecar {
+            page_size: PAGE_SIZE,
+            leaves: leaves.iter().map(hex_encode).collect(),
+            root: hex_encode(&root),
+        };
+        let json = serde_json::to_string(&sidecar).unwrap_or_default();
+        let mut file = self.inner.create(&Self::sidecar_path(path))?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()
+    }
+}
+
+impl<S: Storage> Storage for IntegrityStorage<S> {
+    type File = IntegrityFile<S>;
+
+    fn open(&self, path: &Path) -> io::Result<Self::File> {
+        let inner = self.inner.open(path)?;
+        let sidecar = self.load_sidecar(path)?;
+        Ok(IntegrityFile::new(
+            self.inner.clone(),
+            path.to_path_buf(),
+            inner,
+            sidecar,
+        ))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Self::File> {
+        let inner = self.inner.create(path)?;
+        // Starting a new generation of this file invalidates any previous
+        // tree; it's rebuilt from scratch on the next `sync_all`.
+        let _ = self.inner.remove_file(&Self::sidecar_path(path));
+        Ok(IntegrityFile::new(
+            self.inner.clone(),
+            path.to_path_buf(),
+            inner,
+            None,
+        ))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let _ = self.inner.remove_file(&Self::sidecar_path(path));
+        self.inner.remove_file(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FileStat>> {
+        let entries = self.inner.read_dir(path)?;
+        Ok(entries
+            .into_iter()
+            .filter(|stat| stat.path.extension().and_then(|e| e.to_str()) != Some("fsv"))
+            .collect())
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<FileStat> {
+        self.inner.stat(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.inner.rename(from, to)?;
+        if self.inner.exists(&Self::sidecar_path(from)) {
+            let _ = self
+                .inner
+                .rename(&Self::sidecar_path(from), &Self::sidecar_path(to));
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn lock_exclusive(&self, path: &Path) -> io::Result<FileLock> {
+        self.inner.lock_exclusive(path)
+    }
+
+    fn try_lock_exclusive(&self, path: &Path) -> io::Result<Option<FileLock>> {
+        self.inner.try_lock_exclusive(path)
+    }
+
+    fn lock_range(&self, path: &Path, start: u64, len: u64, mode: LockMode) -> io::Result<RecordLock> {
+        self.inner.lock_range(path, start, len, mode)
+    }
+
+    fn try_lock_range(
+        &self,
+        path: &Path,
+        start: u64,
+        len: u64,
+        mode: LockMode,
+    ) -> io::Result<Option<RecordLock>> {
+        self.inner.try_lock_range(path, start, len, mode)
+    }
+}
+
+/// File handle wrapping an inner `Storage::File`, verifying pages against a
+/// (possibly not-yet-computed) Merkle tree as they're read.
+pub struct IntegrityFile<S: Storage> {
+    storage: Arc<S>,
+    path: PathBuf,
+    inner: S::File,
+    sidecar: Mutex<Option<FsvSidecar>>,
+}
+
+impl<S: Storage> IntegrityFile<S> {
+    fn new(storage: Arc<S>, path: PathBuf, inner: S::File, sidecar: Option<FsvSidecar>) -> Self {
+        Self {
+            storage,
+            path,
+            inner,
+            sidecar: Mutex::new(sidecar),
+        }
+    }
+
+    /// A write landed since the tree was last computed (or this file was
+    /// freshly created): drop the stale sidecar so reads fall back to
+    /// passthrough until the next `sync_all` rebuilds it.
+    fn invalidate(&self) {
+        *self.sidecar.lock().unwrap() = None;
+    }
+}
+
+impl<S: Storage> FileHandle for IntegrityFile<S> {
+    fn set_len(&mut self, size: u64) -> io::Result<()> {
+        self.invalidate();
+        self.inner.set_len(size)
+    }
+
+    fn sync_all(&mut self) -> io::Result<()> {
+        self.inner.sync_all()?;
+
+        let mut content = Vec::new();
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.read_to_end(&mut content)?;
+
+        let storage = IntegrityStorage {
+            inner: self.storage.clone(),
+        };
+        storage.write_sidecar(&self.path, &content)?;
+        *self.sidecar.lock().unwrap() = storage.load_sidecar(&self.path)?;
+        Ok(())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let sidecar_guard = self.sidecar.lock().unwrap();
+        let Some(sidecar) = sidecar_guard.as_ref() else {
+            // No finalized tree yet (never synced, or invalidated by a
+            // write since): nothing to verify against.
+            return self.inner.read_at(buf, offset);
+        };
+
+        let page_size = sidecar.page_size;
+        let end = offset + buf.len() as u64;
+        let mut cursor = offset;
+        let mut total_read = 0usize;
+
+        while cursor < end {
+            let page_index = cursor / page_size;
+            let page_start = page_index * page_size;
+            let mut page_buf = vec![0u8; page_size as usize];
+            let page_read = self.inner.read_at(&mut page_buf, page_start)?;
+            page_buf.truncate(page_read);
+
+            if let Some(expected) = sidecar.leaves.get(page_index as usize) {
+                if &hex_encode(&sha256(&page_buf)) != expected {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        IntegrityError {
+                            path: self.path.clone(),
+                            page: page_index,
+                        },
+                    ));
+                }
+            }
+
+            let page_end = page_start + page_buf.len() as u64;
+            let copy_start = cursor.max(page_start);
+            let copy_end = end.min(page_end);
+            if copy_end <= copy_start {
+                break;
+            }
+
+            let src = (copy_start - page_start) as usize;
+            let dst = (copy_start - offset) as usize;
+            let n = (copy_end - copy_start) as usize;
+            buf[dst..dst + n].copy_from_slice(&page_buf[src..src + n]);
+            total_read += n;
+            cursor = copy_end;
+
+            if page_buf.len() < page_size as usize {
+                break; // hit EOF mid-page
+            }
+        }
+
+        Ok(total_read)
+    }
+
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.invalidate();
+        self.inner.write_at(buf, offset)
+    }
+}
+
+impl<S: Storage> Read for IntegrityFile<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: Storage> Write for IntegrityFile<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.invalidate();
+        self.inner.write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Storage> Seek for IntegrityFile<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn hex_encode(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Hash `content` page by page (an empty file still hashes to one leaf, the
+/// hash of an empty page, so `merkle_root` always has something to fold).
+fn page_hashes(content: &[u8]) -> Vec<[u8; 32]> {
+    if content.is_empty() {
+        return vec![sha256(&[])];
+    }
+    content
+        .chunks(PAGE_SIZE as usize)
+        .map(sha256)
+        .collect()
+}
+
+/// Fold leaf hashes up a binary Merkle tree. An odd node out at any level is
+/// paired with itself, so the tree stays well-defined at every size.
+fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity(level.len().div_ceil(2));
+        for pair in level.chunks(2) {
+            let mut hasher = Sha256::new();
+            hasher.update(pair[0]);
+            hasher.update(pair.get(1).unwrap_or(&pair[0]));
+            next.push(hasher.finalize().into());
+        }
+        level = next;
+    }
+    level.first().copied().unwrap_or([0u8; 32])
+}
@@ -5,6 +5,64 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
+/// A modification time as reported by the filesystem, split into whole
+/// seconds and a nanosecond remainder so callers can tell a high-resolution
+/// timestamp from one truncated to second granularity.
+///
+/// Some filesystems (notably FAT and some network mounts, and `SystemTime`
+/// itself on platforms where the OS API doesn't expose sub-second
+/// resolution) only report `mtime` to the nearest second. If two
+/// `TruncatedTimestamp`s are compared naively, a file that changed twice
+/// within the same second would look unchanged. `possibly_equal` implements
+/// the dirstate-v2 "second-ambiguous" rule: when either side has a zero
+/// nanosecond component, two timestamps with equal seconds are reported as
+/// "possibly unchanged" rather than definitely equal, so the caller falls
+/// back to a content check instead of trusting the timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedTimestamp {
+    pub secs: u64,
+    pub nanos: u32,
+}
+
+impl TruncatedTimestamp {
+    pub fn new(secs: u64, nanos: u32) -> Self {
+        Self { secs, nanos }
+    }
+
+    pub fn from_system_time(time: SystemTime) -> Self {
+        match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => Self::new(d.as_secs(), d.subsec_nanos()),
+            Err(_) => Self::new(0, 0),
+        }
+    }
+
+    /// Whether `self` and `other` might refer to the same write. Returns
+    /// `true` whenever the two can't be told apart: either their full
+    /// (secs, nanos) pairs are equal, or one of them only carries
+    /// second-granularity precision (`nanos == 0`) and the seconds match.
+    /// `false` means the timestamps are precise enough to prove a change
+    /// happened.
+    pub fn possibly_equal(&self, other: &Self) -> bool {
+        if self.secs != other.secs {
+            return false;
+        }
+        if self.nanos == 0 || other.nanos == 0 {
+            return true;
+        }
+        self.nanos == other.nanos
+    }
+}
+
+/// Metadata for a directory entry: enough to decide whether a segment file
+/// changed since it was last seen, without rereading its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    pub path: PathBuf,
+    pub size: u64,
+    pub mtime: TruncatedTimestamp,
+    pub is_dir: bool,
+}
+
 /// File lock for exclusive access
 pub struct FileLock {
     _file: File,
@@ -17,10 +75,51 @@ impl FileLock {
     }
 }
 
+/// Whether a byte-range record lock excludes other locks (`Exclusive`) or
+/// only other exclusive locks (`Shared`, i.e. readers-share/writers-exclude).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+/// Guard for a POSIX-style byte-range record lock, acquired via
+/// `Storage::lock_range`/`try_lock_range`. The lock is released when the
+/// guard is dropped, regardless of which backend produced it.
+pub struct RecordLock {
+    release: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl RecordLock {
+    fn new(release: impl FnOnce() + Send + 'static) -> Self {
+        Self {
+            release: Some(Box::new(release)),
+        }
+    }
+}
+
+impl Drop for RecordLock {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+    }
+}
+
 /// Trait representing a file handle
 pub trait FileHandle: Read + Write + Seek + Send + Sync {
     fn set_len(&mut self, size: u64) -> io::Result<()>;
     fn sync_all(&mut self) -> io::Result<()>;
+
+    /// Read into `buf` starting at `offset`, without moving the handle's
+    /// seek position. Takes `&self` (not `&mut self`) so one handle can be
+    /// shared across threads and read concurrently — useful for immutable
+    /// SSTable/segment files, where the usual seek-then-read pair would
+    /// otherwise serialize every reader on the same cursor.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize>;
+    /// Write `buf` starting at `offset`, without moving the handle's seek
+    /// position.
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize>;
 }
 
 /// Trait representing file system operations
@@ -30,7 +129,9 @@ pub trait Storage: Send + Sync + 'static {
     fn open(&self, path: &Path) -> io::Result<Self::File>;
     fn create(&self, path: &Path) -> io::Result<Self::File>;
     fn remove_file(&self, path: &Path) -> io::Result<()>;
-    fn read_dir(&self, path: &Path) -> io::Result<Vec<(PathBuf, SystemTime)>>;
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FileStat>>;
+    /// Stat a single file or directory.
+    fn stat(&self, path: &Path) -> io::Result<FileStat>;
     fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
     fn exists(&self, path: &Path) -> bool;
     fn create_dir_all(&self, path: &Path) -> io::Result<()>;
@@ -39,6 +140,46 @@ pub trait Storage: Send + Sync + 'static {
     fn lock_exclusive(&self, path: &Path) -> io::Result<FileLock>;
     /// Try to acquire an exclusive lock without blocking
     fn try_lock_exclusive(&self, path: &Path) -> io::Result<Option<FileLock>>;
+
+    /// Acquire a POSIX-style byte-range lock on `[start, start + len)` of
+    /// `path`, blocking until it's available. Unlike `lock_exclusive`, this
+    /// only locks the given range, so concurrent writers to disjoint regions
+    /// of the same file don't serialize against each other.
+    fn lock_range(&self, path: &Path, start: u64, len: u64, mode: LockMode) -> io::Result<RecordLock>;
+    /// Like `lock_range`, but returns `Ok(None)` immediately instead of
+    /// blocking if the range is unavailable.
+    fn try_lock_range(
+        &self,
+        path: &Path,
+        start: u64,
+        len: u64,
+        mode: LockMode,
+    ) -> io::Result<Option<RecordLock>>;
+}
+
+/// Build a `FileStat` from `std::fs::Metadata`, reading `mtime` at the
+/// filesystem's native resolution: nanosecond precision on Unix via
+/// `st_mtime`/`st_mtime_nsec`, or whatever `SystemTime::modified` reports
+/// elsewhere (frequently only second-granular, which `TruncatedTimestamp`
+/// tracks explicitly).
+#[cfg(unix)]
+fn std_mtime(meta: &std::fs::Metadata) -> TruncatedTimestamp {
+    use std::os::unix::fs::MetadataExt;
+    TruncatedTimestamp::new(meta.mtime().max(0) as u64, meta.mtime_nsec() as u32)
+}
+
+#[cfg(not(unix))]
+fn std_mtime(meta: &std::fs::Metadata) -> TruncatedTimestamp {
+    TruncatedTimestamp::from_system_time(meta.modified().unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+fn std_file_stat(path: &Path, meta: &std::fs::Metadata) -> FileStat {
+    FileStat {
+        path: path.to_path_buf(),
+        size: meta.len(),
+        mtime: std_mtime(meta),
+        is_dir: meta.is_dir(),
+    }
 }
 
 // --- Standard Filesystem Implementation (Native) ---
@@ -52,6 +193,31 @@ impl FileHandle for StdFile {
     fn sync_all(&mut self) -> io::Result<()> {
         self.0.sync_all()
     }
+
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+        self.0.read_at(buf, offset)
+    }
+    #[cfg(unix)]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+        self.0.write_at(buf, offset)
+    }
+
+    // `seek_read`/`seek_write` are std's safe wrappers around `ReadFile`/
+    // `WriteFile` with an `OVERLAPPED` offset, so there's no need to hand-roll
+    // the FFI ourselves here.
+    #[cfg(windows)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+        self.0.seek_read(buf, offset)
+    }
+    #[cfg(windows)]
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+        self.0.seek_write(buf, offset)
+    }
 }
 
 impl Read for StdFile {
@@ -103,19 +269,21 @@ impl Storage for StdStorage {
         std::fs::remove_file(path)
     }
 
-    fn read_dir(&self, path: &Path) -> io::Result<Vec<(PathBuf, SystemTime)>> {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<FileStat>> {
         let mut entries = Vec::new();
         for entry in std::fs::read_dir(path)? {
             let entry = entry?;
             let meta = entry.metadata()?;
-            entries.push((
-                entry.path(),
-                meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-            ));
+            entries.push(std_file_stat(&entry.path(), &meta));
         }
         Ok(entries)
     }
 
+    fn stat(&self, path: &Path) -> io::Result<FileStat> {
+        let meta = std::fs::metadata(path)?;
+        Ok(std_file_stat(path, &meta))
+    }
+
     fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
         std::fs::rename(from, to)
     }
@@ -223,6 +391,117 @@ impl Storage for StdStorage {
         
         Ok(Some(FileLock::new(file)))
     }
+
+    fn lock_range(&self, path: &Path, start: u64, len: u64, mode: LockMode) -> io::Result<RecordLock> {
+        std_lock_range(path, start, len, mode, true)?
+            .ok_or_else(|| io::Error::other("blocking record lock unexpectedly returned None"))
+    }
+
+    fn try_lock_range(
+        &self,
+        path: &Path,
+        start: u64,
+        len: u64,
+        mode: LockMode,
+    ) -> io::Result<Option<RecordLock>> {
+        std_lock_range(path, start, len, mode, false)
+    }
+}
+
+/// Lock `[start, start + len)` of the real file at `path` (not a side-car
+/// `.lock` file, since the point of a range lock is to let two writers share
+/// the same file as long as their ranges don't overlap). `blocking` selects
+/// `F_SETLKW`/a blocking `LockFileEx` call vs. the non-blocking variants.
+#[cfg(unix)]
+fn std_lock_range(
+    path: &Path,
+    start: u64,
+    len: u64,
+    mode: LockMode,
+    blocking: bool,
+) -> io::Result<Option<RecordLock>> {
+    use std::os::unix::io::AsRawFd;
+
+    let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+    let mut lock_arg: libc::flock = unsafe { std::mem::zeroed() };
+    lock_arg.l_type = match mode {
+        LockMode::Shared => libc::F_RDLCK as _,
+        LockMode::Exclusive => libc::F_WRLCK as _,
+    };
+    lock_arg.l_whence = libc::SEEK_SET as _;
+    lock_arg.l_start = start as i64;
+    lock_arg.l_len = len as i64;
+
+    let cmd = if blocking { libc::F_SETLKW } else { libc::F_SETLK };
+    let result = unsafe { libc::fcntl(file.as_raw_fd(), cmd, &lock_arg) };
+    if result == -1 {
+        let error = io::Error::last_os_error();
+        if !blocking
+            && matches!(
+                error.raw_os_error(),
+                Some(libc::EACCES) | Some(libc::EAGAIN)
+            )
+        {
+            return Ok(None);
+        }
+        return Err(error);
+    }
+
+    Ok(Some(RecordLock::new(move || {
+        let mut unlock_arg = lock_arg;
+        unlock_arg.l_type = libc::F_UNLCK as _;
+        unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &unlock_arg) };
+    })))
+}
+
+#[cfg(windows)]
+fn std_lock_range(
+    path: &Path,
+    start: u64,
+    len: u64,
+    mode: LockMode,
+    blocking: bool,
+) -> io::Result<Option<RecordLock>> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        LockFileEx, UnlockFile, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY,
+    };
+
+    let file = std::fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let handle = file.as_raw_handle() as windows_sys::Win32::Foundation::HANDLE;
+
+    let offset_low = (start & 0xFFFF_FFFF) as u32;
+    let offset_high = (start >> 32) as u32;
+    let len_low = (len & 0xFFFF_FFFF) as u32;
+    let len_high = (len >> 32) as u32;
+
+    let mut flags = 0u32;
+    if mode == LockMode::Exclusive {
+        flags |= LOCKFILE_EXCLUSIVE_LOCK;
+    }
+    if !blocking {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+
+    let mut overlapped: windows_sys::Win32::System::IO::OVERLAPPED = unsafe { std::mem::zeroed() };
+    overlapped.Anonymous.Anonymous.Offset = offset_low;
+    overlapped.Anonymous.Anonymous.OffsetHigh = offset_high;
+
+    let result = unsafe { LockFileEx(handle, flags, 0, len_low, len_high, &mut overlapped) };
+    if result == 0 {
+        let error = io::Error::last_os_error();
+        if !blocking && error.raw_os_error() == Some(33) {
+            // ERROR_LOCK_VIOLATION
+            return Ok(None);
+        }
+        return Err(error);
+    }
+
+    Ok(Some(RecordLock::new(move || {
+        unsafe { UnlockFile(handle, offset_low, offset_high, len_low, len_high) };
+        drop(file);
+    })))
 }
 
 // --- In-Memory Implementation (WASM/Test) ---
@@ -235,9 +514,73 @@ struct MemFileData {
     mtime: SystemTime,
 }
 
+/// One held byte-range lock within a `PathLocks` interval map. Shared locks
+/// over the exact same `[start, end)` range coalesce into a single entry
+/// with a `refcount`, rather than one entry per acquisition.
+struct RangeLockEntry {
+    id: u64,
+    start: u64,
+    end: u64,
+    mode: LockMode,
+    refcount: u32,
+}
+
+#[derive(Default)]
+struct PathLocks {
+    next_id: u64,
+    entries: Vec<RangeLockEntry>,
+}
+
+impl PathLocks {
+    /// Try to grant `[start, start + len)` in `mode`, returning the id to
+    /// release it by, or `None` if it conflicts with an existing lock.
+    fn acquire(&mut self, start: u64, len: u64, mode: LockMode) -> Option<u64> {
+        let end = start + len;
+        let conflicts = self.entries.iter().any(|e| {
+            let overlaps = e.start < end && start < e.end;
+            overlaps && !(mode == LockMode::Shared && e.mode == LockMode::Shared)
+        });
+        if conflicts {
+            return None;
+        }
+
+        if mode == LockMode::Shared {
+            if let Some(existing) = self
+                .entries
+                .iter_mut()
+                .find(|e| e.mode == LockMode::Shared && e.start == start && e.end == end)
+            {
+                existing.refcount += 1;
+                return Some(existing.id);
+            }
+        }
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.entries.push(RangeLockEntry {
+            id,
+            start,
+            end,
+            mode,
+            refcount: 1,
+        });
+        Some(id)
+    }
+
+    fn release(&mut self, id: u64) {
+        if let Some(pos) = self.entries.iter().position(|e| e.id == id) {
+            self.entries[pos].refcount -= 1;
+            if self.entries[pos].refcount == 0 {
+                self.entries.remove(pos);
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 struct MemFs {
     files: Arc<Mutex<HashMap<PathBuf, Arc<Mutex<MemFileData>>>>>,
+    range_locks: Arc<Mutex<HashMap<PathBuf, PathLocks>>>,
 }
 
 pub struct MemFile {
@@ -255,6 +598,31 @@ impl FileHandle for MemFile {
     fn sync_all(&mut self) -> io::Result<()> {
         Ok(())
     }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let data = self.inner.lock().unwrap();
+        let len = data.content.len() as u64;
+        if offset >= len {
+            return Ok(0);
+        }
+
+        let available = len - offset;
+        let to_read = std::cmp::min(buf.len() as u64, available) as usize;
+        buf[..to_read].copy_from_slice(&data.content[offset as usize..offset as usize + to_read]);
+        Ok(to_read)
+    }
+
+    fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let mut data = self.inner.lock().unwrap();
+        let end = offset + buf.len() as u64;
+        if end > data.content.len() as u64 {
+            data.content.resize(end as usize, 0);
+        }
+
+        data.content[offset as usize..end as usize].copy_from_slice(buf);
+        data.mtime = SystemTime::now();
+        Ok(buf.len())
+    }
 }
 
 impl Read for MemFile {
@@ -354,9 +722,40 @@ impl MemoryStorage {
         MemoryStorage {
             fs: MemFs {
                 files: Arc::new(Mutex::new(HashMap::new())),
+                range_locks: Arc::new(Mutex::new(HashMap::new())),
             },
         }
     }
+
+    /// Every file currently held in memory, as `(path, content)` pairs --
+    /// the whole WAL + SST keyspace backing a `FireLocal<MemoryStorage>`,
+    /// for a caller to serialize into a portable snapshot (see the WASM
+    /// binding's `export_snapshot`). The counterpart is `restore_files`.
+    pub fn snapshot_files(&self) -> Vec<(PathBuf, Vec<u8>)> {
+        let files = self.fs.files.lock().unwrap();
+        files
+            .iter()
+            .map(|(path, data)| (path.clone(), data.lock().unwrap().content.clone()))
+            .collect()
+    }
+
+    /// Replace this `MemoryStorage`'s entire file set with `files`, as
+    /// previously captured by `snapshot_files` -- used to hydrate a fresh
+    /// instance from an imported snapshot before reopening it with
+    /// `FireLocal::new_with_storage`.
+    pub fn restore_files(&self, files: Vec<(PathBuf, Vec<u8>)>) {
+        let mut guard = self.fs.files.lock().unwrap();
+        guard.clear();
+        for (path, content) in files {
+            guard.insert(
+                path,
+                Arc::new(Mutex::new(MemFileData {
+                    content,
+                    mtime: SystemTime::now(),
+                })),
+            );
+        }
+    }
 }
 
 impl Storage for MemoryStorage {
@@ -390,18 +789,37 @@ impl Storage for MemoryStorage {
         Ok(())
     }
 
-    fn read_dir(&self, _path: &Path) -> io::Result<Vec<(PathBuf, SystemTime)>> {
+    fn read_dir(&self, _path: &Path) -> io::Result<Vec<FileStat>> {
         // Simple linear scan of all files (assuming flat or handling prefix)
         // For simplicity in this iteration, returning all files
         let files = self.fs.files.lock().unwrap();
         let mut entries = Vec::new();
         for (p, data) in files.iter() {
             let guard = data.lock().unwrap();
-            entries.push((p.clone(), guard.mtime));
+            entries.push(FileStat {
+                path: p.clone(),
+                size: guard.content.len() as u64,
+                mtime: TruncatedTimestamp::from_system_time(guard.mtime),
+                is_dir: false,
+            });
         }
         Ok(entries)
     }
 
+    fn stat(&self, path: &Path) -> io::Result<FileStat> {
+        let files = self.fs.files.lock().unwrap();
+        let data = files
+            .get(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "File not found"))?;
+        let guard = data.lock().unwrap();
+        Ok(FileStat {
+            path: path.to_path_buf(),
+            size: guard.content.len() as u64,
+            mtime: TruncatedTimestamp::from_system_time(guard.mtime),
+            is_dir: false,
+        })
+    }
+
     fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
         let mut files = self.fs.files.lock().unwrap();
         if let Some(data) = files.remove(from) {
@@ -439,4 +857,39 @@ impl Storage for MemoryStorage {
             self.lock_exclusive(path).map(Some)
         }
     }
+
+    fn lock_range(&self, path: &Path, start: u64, len: u64, mode: LockMode) -> io::Result<RecordLock> {
+        // There's no OS primitive to block on here, so poll the interval map
+        // until the range frees up.
+        loop {
+            if let Some(lock) = self.try_lock_range(path, start, len, mode)? {
+                return Ok(lock);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    fn try_lock_range(
+        &self,
+        path: &Path,
+        start: u64,
+        len: u64,
+        mode: LockMode,
+    ) -> io::Result<Option<RecordLock>> {
+        let path = path.to_path_buf();
+        let mut all_locks = self.fs.range_locks.lock().unwrap();
+        let path_locks = all_locks.entry(path.clone()).or_default();
+
+        let Some(id) = path_locks.acquire(start, len, mode) else {
+            return Ok(None);
+        };
+
+        let range_locks = self.fs.range_locks.clone();
+        Ok(Some(RecordLock::new(move || {
+            let mut all_locks = range_locks.lock().unwrap();
+            if let Some(path_locks) = all_locks.get_mut(&path) {
+                path_locks.release(id);
+            }
+        })))
+    }
 }
@@ -4,6 +4,16 @@ use std::collections::BTreeMap;
 pub enum Entry {
     Put(Vec<u8>),
     Delete,
+    /// One or more merge operands (see `BatchOperation::Merge`) layered on
+    /// top of `base` -- the most recent fully-resolved value this memtable
+    /// knew about for the key before the first of them landed, or `None` if
+    /// that value (if any) only exists in an already-flushed SST. Folded
+    /// into a single value lazily, by the registered merge operator, rather
+    /// than eagerly on every merge -- see `FireLocal::resolve_merge`.
+    Merge {
+        base: Option<Vec<u8>>,
+        operands: Vec<Vec<u8>>,
+    },
 }
 
 pub struct Memtable {
@@ -31,20 +41,91 @@ impl Memtable {
         }
     }
 
+    /// The raw entry for `key`, including a pending `Merge` that `get`
+    /// doesn't resolve on its own -- callers that need to fold merge
+    /// operands (`FireLocal::get`, `scan`, `local_snapshot`) go through this
+    /// instead.
+    pub fn get_entry(&self, key: &str) -> Option<&Entry> {
+        self.map.get(key)
+    }
+
     pub fn delete(&mut self, key: String) {
         self.size_approx += key.len(); // Tombstone size approximation
         self.map.insert(key, Entry::Delete);
     }
-    
+
+    /// Record a pending merge `operand` for `key`, stacking it on any
+    /// already-pending operands for the same key in this memtable rather
+    /// than overwriting them. The first merge after a `Put`/`Delete` (or
+    /// after nothing at all) captures that prior state as `Entry::Merge`'s
+    /// `base`, so folding later doesn't need to re-derive it.
+    pub fn merge(&mut self, key: String, operand: Vec<u8>) {
+        self.size_approx += key.len() + operand.len();
+        match self.map.get_mut(&key) {
+            Some(Entry::Merge { operands, .. }) => operands.push(operand),
+            Some(Entry::Put(existing)) => {
+                let base = Some(existing.clone());
+                self.map.insert(
+                    key,
+                    Entry::Merge {
+                        base,
+                        operands: vec![operand],
+                    },
+                );
+            }
+            Some(Entry::Delete) | None => {
+                self.map.insert(
+                    key,
+                    Entry::Merge {
+                        base: None,
+                        operands: vec![operand],
+                    },
+                );
+            }
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&String, &Entry)> {
         self.map.iter()
     }
 
+    /// Entries with key `>= start`, in ascending key order. Used by range
+    /// scans to position a cursor without materializing the whole map.
+    pub fn range_from(&self, start: &str) -> impl Iterator<Item = (&String, &Entry)> {
+        self.map.range(start.to_string()..)
+    }
+
     pub fn len(&self) -> usize {
         self.map.len()
     }
-    
+
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
     }
+
+    /// Approximate bytes currently held (keys + values), for
+    /// `FireLocal::metrics`.
+    pub fn size_bytes(&self) -> usize {
+        self.size_approx
+    }
+
+    /// A copy of this memtable with every `Entry::Merge` folded into a
+    /// concrete `Entry::Put` by `resolve` (given the key, the merge's own
+    /// `base`, and its accumulated operands). `Put`/`Delete` entries are
+    /// carried over unchanged. Used by `FireLocal::flush` so a flushed SST
+    /// never has to represent a pending merge on disk.
+    pub fn resolved(&self, mut resolve: impl FnMut(&str, Option<&[u8]>, &[Vec<u8>]) -> Vec<u8>) -> Memtable {
+        let mut out = Memtable::new();
+        for (key, entry) in self.map.iter() {
+            match entry {
+                Entry::Put(val) => out.put(key.clone(), val.clone()),
+                Entry::Delete => out.delete(key.clone()),
+                Entry::Merge { base, operands } => {
+                    let resolved = resolve(key, base.as_deref(), operands);
+                    out.put(key.clone(), resolved);
+                }
+            }
+        }
+        out
+    }
 }
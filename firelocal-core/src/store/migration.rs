@@ -0,0 +1,156 @@
+//! Ordered, idempotent migrations for the on-disk DB directory layout —
+//! distinct from the per-record `store::format` version embedded in each
+//! WAL/SST file's own header. A single `version` file at the DB root tracks
+//! the highest migration that's been applied; `FireLocal::new` runs any
+//! migration whose `version()` is greater than what's stored, in ascending
+//! order, before serving requests.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const VERSION_FILE_NAME: &str = "version";
+
+/// Highest migration version this build knows about. A freshly-created DB
+/// directory is stamped at this directly, without running any migration
+/// body, since there's nothing in it yet to transform.
+pub const LATEST_VERSION: u32 = 0;
+
+/// One step in the DB directory's schema history: transforms the directory
+/// from `version() - 1` to `version()`.
+pub trait Migration {
+    /// The version this migration brings the DB directory up to.
+    fn version(&self) -> u32;
+    /// Apply this migration's changes to the DB directory at `dir`.
+    fn migrate(&self, dir: &Path) -> io::Result<()>;
+}
+
+/// Read the DB directory's stamped schema version, or `0` if no `version`
+/// file exists yet (a pre-migration-framework store).
+pub fn read_version(dir: &Path) -> io::Result<u32> {
+    match fs::read_to_string(dir.join(VERSION_FILE_NAME)) {
+        Ok(s) => Ok(s.trim().parse().unwrap_or(0)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Atomically stamp `dir`'s schema version: write to a temp file and rename
+/// over the real one, so a crash mid-write never leaves a half-written (or
+/// half-applied) version behind.
+fn write_version(dir: &Path, version: u32) -> io::Result<()> {
+    let tmp_path = dir.join(format!("{VERSION_FILE_NAME}.tmp"));
+    fs::write(&tmp_path, version.to_string())?;
+    fs::rename(&tmp_path, dir.join(VERSION_FILE_NAME))
+}
+
+/// Bring `dir` up to `LATEST_VERSION`, running every migration in
+/// `migrations` whose version is greater than the directory's currently
+/// stamped version, strictly in ascending order. Each migration's body runs
+/// to completion before its version is stamped, so a crash mid-migration
+/// leaves the directory at the last fully-applied version, not a
+/// half-migrated one. `is_new` marks a directory that didn't exist before
+/// this call: it's stamped at `LATEST_VERSION` directly, with no migration
+/// bodies run.
+pub fn run_pending(dir: &Path, is_new: bool, migrations: &[Box<dyn Migration>]) -> io::Result<()> {
+    if is_new {
+        return write_version(dir, LATEST_VERSION);
+    }
+
+    let mut current = read_version(dir)?;
+
+    let mut ordered: Vec<&Box<dyn Migration>> = migrations.iter().collect();
+    ordered.sort_by_key(|m| m.version());
+
+    for migration in ordered {
+        if migration.version() <= current {
+            continue;
+        }
+        migration.migrate(dir)?;
+        write_version(dir, migration.version())?;
+        current = migration.version();
+    }
+
+    Ok(())
+}
+
+/// Every migration this build knows about. Empty for now — the first real
+/// entry lands alongside whatever on-disk layout change needs one (e.g.
+/// column families or a MessagePack value codec).
+pub fn all_migrations() -> Vec<Box<dyn Migration>> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BumpMarker {
+        to: u32,
+    }
+
+    impl Migration for BumpMarker {
+        fn version(&self) -> u32 {
+            self.to
+        }
+
+        fn migrate(&self, dir: &Path) -> io::Result<()> {
+            fs::write(dir.join(format!("migrated_to_{}", self.to)), b"")
+        }
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("firelocal_migration_test_{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_fresh_db_stamped_without_running_migrations() {
+        let dir = temp_dir("fresh");
+        let migrations: Vec<Box<dyn Migration>> = vec![Box::new(BumpMarker { to: 1 })];
+
+        run_pending(&dir, true, &migrations).unwrap();
+
+        assert_eq!(read_version(&dir).unwrap(), LATEST_VERSION);
+        assert!(!dir.join("migrated_to_1").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_migrations_run_in_ascending_order_and_stamp_version() {
+        let dir = temp_dir("ordered");
+        let migrations: Vec<Box<dyn Migration>> = vec![
+            Box::new(BumpMarker { to: 2 }),
+            Box::new(BumpMarker { to: 1 }),
+        ];
+
+        run_pending(&dir, false, &migrations).unwrap();
+
+        assert!(dir.join("migrated_to_1").exists());
+        assert!(dir.join("migrated_to_2").exists());
+        assert_eq!(read_version(&dir).unwrap(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_already_applied_migrations_are_skipped() {
+        let dir = temp_dir("skip");
+        write_version(&dir, 1).unwrap();
+        let migrations: Vec<Box<dyn Migration>> = vec![
+            Box::new(BumpMarker { to: 1 }),
+            Box::new(BumpMarker { to: 2 }),
+        ];
+
+        run_pending(&dir, false, &migrations).unwrap();
+
+        assert!(!dir.join("migrated_to_1").exists());
+        assert!(dir.join("migrated_to_2").exists());
+        assert_eq!(read_version(&dir).unwrap(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,13 @@
+pub mod blob;
+pub mod cache;
+pub mod chunking;
+pub mod column_family;
+pub mod compaction;
+pub mod encryption;
+pub mod format;
+pub mod integrity;
+pub mod io;
+pub mod memtable;
+pub mod migration;
+pub mod sst;
+pub mod wal;
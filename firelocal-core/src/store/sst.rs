@@ -1,81 +1,571 @@
+use crate::store::encryption::SstCipher;
+use crate::store::format::{self, FormatHeader};
+use crate::store::io::{Storage, StdStorage};
 use crate::store::memtable::{Entry, Memtable};
-use std::fs::{File, OpenOptions};
 use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::Arc;
 
 // Simple SST format:
-// Record: [flag: u8] [k_len: u32] [key_bytes] [v_len: u32] [val_bytes]
+// Header: b"FLCL" [version: u16] [flags: u16], see `crate::store::format`.
+// Records (sorted, grouped into ~BLOCK_SIZE_BYTES blocks):
+//   [flag: u8] [k_len: u32] [key_bytes] [v_len: u32] [val_bytes] [crc: u32]
 // flag: 0 = Put, 1 = Delete
+// Index (one entry per block, `format::INDEX_VERSION` and up):
+//   [k_len: u32] [first_key_bytes] [block_offset: u64]
+// Footer (fixed FOOTER_LEN bytes at EOF):
+//   [index_start_offset: u64] [entry_count: u32]
+// When `flags & FLAG_ENCRYPTED` is set, every Put record's value is an
+// `SstCipher::encrypt` record (`[12-byte IV][ciphertext || tag]`) instead of
+// a plaintext value; Delete tombstones are never encrypted (see
+// `crate::store::encryption`).
 
 const FLAG_PUT: u8 = 0;
 const FLAG_DELETE: u8 = 1;
 
-pub struct SstBuilder {
-    writer: BufWriter<File>,
+/// Header flag marking an SST file's Put values as AES-256-GCM ciphertext.
+const FLAG_ENCRYPTED: u16 = 1;
+
+/// Target size of each data block the index groups records into. Not a
+/// hard cap -- a single oversized record can push a block past this, since
+/// a block only rolls over to a new entry *before* the next record starts.
+const BLOCK_SIZE_BYTES: u64 = 4096;
+
+/// `[index_start_offset: u64][entry_count: u32]`, always the last
+/// `FOOTER_LEN` bytes of an `format::INDEX_VERSION`-and-up SST file.
+const FOOTER_LEN: u64 = 8 + 4;
+
+/// `[flag][k_len][key][v_len][value][crc]` -- the on-disk length of one
+/// record, so `SstBuilder` can track block boundaries without seeking.
+fn record_len(key: &str, value: &[u8]) -> u64 {
+    1 + 4 + key.len() as u64 + 4 + value.len() as u64 + 4
 }
 
-impl SstBuilder {
+fn cipher_err(e: crate::error::FireLocalError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// `S` selects the `Storage` backend records are written through --
+/// `StdStorage` (the default, via `new`/`encrypted`) for a real file on
+/// disk, or any other backend (e.g. `MemoryStorage`, via `new_with_storage`/
+/// `encrypted_with_storage`) to build an SST entirely in RAM -- useful for
+/// benchmarks and tests that don't want a `remove_dir_all` per iteration,
+/// and the seam a future WASM target (no real filesystem) would hang its
+/// own backend off of.
+pub struct SstBuilder<S: Storage = StdStorage> {
+    writer: BufWriter<S::File>,
+    cipher: Option<SstCipher>,
+    /// Byte offset the next record will be written at.
+    offset: u64,
+    /// First key and starting offset of each block written so far, for the
+    /// index `finish` appends after the last record.
+    index: Vec<(String, u64)>,
+    /// Bytes written to the block currently being filled. Reset to `0`
+    /// whenever a new block starts.
+    block_bytes: u64,
+}
+
+impl SstBuilder<StdStorage> {
     pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
+        Self::new_with_storage(&StdStorage, path)
+    }
+
+    /// Like `new`, but every Put value is encrypted with `key` before it's
+    /// written, and the header's `FLAG_ENCRYPTED` bit is set so
+    /// `SstReader::open` refuses to read it back without also being given
+    /// the key.
+    pub fn encrypted(path: impl AsRef<Path>, key: [u8; crate::store::encryption::KEY_LEN]) -> io::Result<Self> {
+        Self::encrypted_with_storage(&StdStorage, path, key)
+    }
+}
 
+impl<S: Storage> SstBuilder<S> {
+    /// Like `new`, but through `storage` instead of always `StdStorage`.
+    pub fn new_with_storage(storage: &S, path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = storage.create(path.as_ref())?;
+
+        let mut writer = BufWriter::new(file);
+        format::write_header(&mut writer, &FormatHeader::current())?;
+        Ok(Self {
+            writer,
+            cipher: None,
+            offset: format::header_len(),
+            index: Vec::new(),
+            block_bytes: 0,
+        })
+    }
+
+    /// Like `encrypted`, but through `storage` instead of always `StdStorage`.
+    pub fn encrypted_with_storage(
+        storage: &S,
+        path: impl AsRef<Path>,
+        key: [u8; crate::store::encryption::KEY_LEN],
+    ) -> io::Result<Self> {
+        let file = storage.create(path.as_ref())?;
+
+        let mut writer = BufWriter::new(file);
+        format::write_header(
+            &mut writer,
+            &FormatHeader {
+                version: format::CURRENT_VERSION,
+                flags: FLAG_ENCRYPTED,
+            },
+        )?;
         Ok(Self {
-            writer: BufWriter::new(file),
+            writer,
+            cipher: Some(SstCipher::new(key)),
+            offset: format::header_len(),
+            index: Vec::new(),
+            block_bytes: 0,
         })
     }
 
     pub fn build(mut self, memtable: &Memtable) -> io::Result<()> {
         for (key, entry) in memtable.iter() {
-            let key_bytes = key.as_bytes();
-            let k_len = key_bytes.len() as u32;
-
             match entry {
-                Entry::Put(val) => {
-                    self.writer.write_all(&[FLAG_PUT])?;
-                    self.writer.write_all(&k_len.to_le_bytes())?;
-                    self.writer.write_all(key_bytes)?;
-
-                    let v_len = val.len() as u32;
-                    self.writer.write_all(&v_len.to_le_bytes())?;
-                    self.writer.write_all(val)?;
-                }
-                Entry::Delete => {
-                    self.writer.write_all(&[FLAG_DELETE])?;
-                    self.writer.write_all(&k_len.to_le_bytes())?;
-                    self.writer.write_all(key_bytes)?;
-                    // No value for delete
-                    self.writer.write_all(&0u32.to_le_bytes())?; // v_len = 0 generic
-                }
+                Entry::Put(val) => self.write_put(key, val)?,
+                Entry::Delete => self.write_record(FLAG_DELETE, key, &[])?,
             }
         }
-        self.writer.flush()?;
+        self.finish()
+    }
+
+    /// Write a single Put record directly, bypassing `build`'s `Memtable`
+    /// staging step. Used by `Compactor`'s streaming merge, whose records
+    /// already arrive in sorted order and have no use for being re-sorted
+    /// into a fresh `Memtable` first.
+    pub fn write_put(&mut self, key: &str, value: &[u8]) -> io::Result<()> {
+        let record = match &self.cipher {
+            Some(cipher) => cipher.encrypt(value).map_err(cipher_err)?,
+            None => value.to_vec(),
+        };
+        self.write_record(FLAG_PUT, key, &record)
+    }
+
+    /// Write a tombstone record directly, the delete counterpart to
+    /// `write_put`. Used by `Compactor::write_merged` when a level-scoped
+    /// merge can't prove a key has no live value in an unmerged level, so
+    /// the tombstone has to be promoted forward instead of dropped.
+    pub fn write_delete(&mut self, key: &str) -> io::Result<()> {
+        self.write_record(FLAG_DELETE, key, &[])
+    }
+
+    /// Write one record, starting a new index block first if the previous
+    /// block has grown past `BLOCK_SIZE_BYTES` (or this is the first record
+    /// in the file). Records arrive in ascending key order (from
+    /// `Memtable::iter` or an already-sorted `Compactor` merge), so each
+    /// block's first key doubles as its lower bound for `SstReader::get`'s
+    /// binary search.
+    fn write_record(&mut self, flag: u8, key: &str, value: &[u8]) -> io::Result<()> {
+        if self.index.is_empty() || self.block_bytes >= BLOCK_SIZE_BYTES {
+            self.index.push((key.to_string(), self.offset));
+            self.block_bytes = 0;
+        }
+        format::encode_record(&mut self.writer, flag, key, value)?;
+        let len = record_len(key, value);
+        self.offset += len;
+        self.block_bytes += len;
         Ok(())
     }
+
+    /// Flush the index and footer, then the writer itself. `build` calls
+    /// this itself; a caller driving `write_put` directly must call it once
+    /// done.
+    pub fn finish(mut self) -> io::Result<()> {
+        let index_start = self.offset;
+        for (key, block_offset) in &self.index {
+            let key_bytes = key.as_bytes();
+            self.writer
+                .write_all(&(key_bytes.len() as u32).to_le_bytes())?;
+            self.writer.write_all(key_bytes)?;
+            self.writer.write_all(&block_offset.to_le_bytes())?;
+        }
+        self.writer.write_all(&index_start.to_le_bytes())?;
+        self.writer
+            .write_all(&(self.index.len() as u32).to_le_bytes())?;
+        self.writer.flush()
+    }
 }
 
-pub struct SstReader {
-    file: BufReader<File>,
+/// `S` selects the `Storage` backend records are read through -- see
+/// `SstBuilder`'s doc comment for why a caller would pick anything other
+/// than the default `StdStorage`.
+pub struct SstReader<S: Storage = StdStorage> {
+    file: BufReader<S::File>,
+    /// Offset of the first record, right after the format header (or `0`
+    /// for a legacy file with none).
+    data_start: u64,
+    /// Offset where the record section ends -- the index's start offset for
+    /// an `format::INDEX_VERSION`-and-up file with a loaded index, the
+    /// file's length otherwise. Every record read must stop here instead of
+    /// running into the index/footer bytes that may follow.
+    data_end: u64,
+    /// This file's total on-disk size, for `size_bytes` -- unlike `data_end`,
+    /// this includes the index/footer, not just the record section.
+    file_len: u64,
+    /// Each block's first key and starting offset, in ascending order, for
+    /// `get`'s binary search. Empty for a file with no index (pre-`INDEX_VERSION`,
+    /// or a footer that failed to parse), in which case `get` falls back to
+    /// a full linear scan of `[data_start, data_end)`.
+    index: Vec<(String, u64)>,
+    cipher: Option<SstCipher>,
+    /// This file's header version, so `get`/`all_entries`/`next_ordered_record`
+    /// know whether each record carries a trailing CRC (see
+    /// `format::CHECKSUM_VERSION`).
+    version: u16,
 }
 
-impl SstReader {
+impl SstReader<StdStorage> {
     pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
-        let file = File::open(path)?;
+        Self::open_with_storage(Arc::new(StdStorage), path)
+    }
+
+    /// Like `open`, but for an SST written by `SstBuilder::encrypted`: `key`
+    /// decrypts every Put value as it's read. Errors if the file's header
+    /// doesn't actually have `FLAG_ENCRYPTED` set, since that means `key`
+    /// would silently decrypt plaintext into garbage.
+    pub fn open_encrypted(
+        path: impl AsRef<Path>,
+        key: [u8; crate::store::encryption::KEY_LEN],
+    ) -> io::Result<Self> {
+        Self::open_encrypted_with_storage(Arc::new(StdStorage), path, key)
+    }
+
+    /// Like `repair`, but through `storage` instead of always `StdStorage`.
+    /// See `repair_with_storage`'s doc comment for the full behavior.
+    pub fn repair(path: impl AsRef<Path>) -> io::Result<RepairReport> {
+        Self::repair_with_storage(&StdStorage, path)
+    }
+}
+
+impl<S: Storage> SstReader<S> {
+    /// Like `open`, but through `storage` instead of always `StdStorage`.
+    pub fn open_with_storage(storage: Arc<S>, path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let (mut file, data_start, flags, version) = Self::open_impl(&storage, path)?;
+        if flags & FLAG_ENCRYPTED != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "SST file is encrypted; open it with SstReader::open_encrypted",
+            ));
+        }
+        let file_len = storage.stat(path)?.size;
+        let (index, data_end) = Self::load_index(&mut file, version, file_len)?;
+        file.seek(SeekFrom::Start(data_start))?;
         Ok(Self {
             file: BufReader::new(file),
+            data_start,
+            data_end,
+            file_len,
+            index,
+            cipher: None,
+            version,
         })
     }
 
-    // Very inefficient linear scan for M1
+    /// Like `open_encrypted`, but through `storage` instead of always `StdStorage`.
+    pub fn open_encrypted_with_storage(
+        storage: Arc<S>,
+        path: impl AsRef<Path>,
+        key: [u8; crate::store::encryption::KEY_LEN],
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+        let (mut file, data_start, flags, version) = Self::open_impl(&storage, path)?;
+        if flags & FLAG_ENCRYPTED == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "SST file is not encrypted; open it with SstReader::open",
+            ));
+        }
+        let file_len = storage.stat(path)?.size;
+        let (index, data_end) = Self::load_index(&mut file, version, file_len)?;
+        file.seek(SeekFrom::Start(data_start))?;
+        Ok(Self {
+            file: BufReader::new(file),
+            data_start,
+            data_end,
+            file_len,
+            index,
+            cipher: Some(SstCipher::new(key)),
+            version,
+        })
+    }
+
+    fn open_impl(storage: &S, path: &Path) -> io::Result<(S::File, u64, u16, u16)> {
+        let mut file = storage.open(path)?;
+        let header = format::read_header(&mut file)?;
+        // Errors cleanly on a recognized-but-unsupported version; a missing
+        // header (pre-versioning legacy file) already reads as `LEGACY_VERSION`
+        // via `read_header`'s own fallback, so it's accepted here too.
+        format::decoder_for_version(header.version)?;
+        let data_start = file.stream_position()?;
+        Ok((file, data_start, header.flags, header.version))
+    }
+
+    /// Load the trailing block index and footer, if this file has one.
+    /// Falls back to `(vec![], file_len)` -- meaning "no index, the record
+    /// section runs to EOF" -- for a pre-`format::INDEX_VERSION` file, a
+    /// file too short to hold a footer, or one whose footer doesn't parse
+    /// cleanly (e.g. a crash during `SstBuilder::finish`, before the index
+    /// was fully written); `get` degrades to a full linear scan in that
+    /// case rather than failing to open the file at all. Leaves `file`'s
+    /// position wherever the attempt left it -- callers must seek back to
+    /// `data_start` before reading records.
+    fn load_index(
+        file: &mut S::File,
+        version: u16,
+        file_len: u64,
+    ) -> io::Result<(Vec<(String, u64)>, u64)> {
+        if version < format::INDEX_VERSION || file_len < FOOTER_LEN {
+            return Ok((Vec::new(), file_len));
+        }
+
+        match Self::try_load_index(file, file_len) {
+            Some(loaded) => Ok(loaded),
+            None => Ok((Vec::new(), file_len)),
+        }
+    }
+
+    /// The fallible half of `load_index`: `None` on any inconsistency, so
+    /// the caller can treat it as "no index" instead of propagating an
+    /// error.
+    fn try_load_index(file: &mut S::File, file_len: u64) -> Option<(Vec<(String, u64)>, u64)> {
+        file.seek(SeekFrom::Start(file_len - FOOTER_LEN)).ok()?;
+
+        let mut buf8 = [0u8; 8];
+        let mut buf4 = [0u8; 4];
+        file.read_exact(&mut buf8).ok()?;
+        let index_start = u64::from_le_bytes(buf8);
+        file.read_exact(&mut buf4).ok()?;
+        let entry_count = u32::from_le_bytes(buf4);
+        if index_start > file_len - FOOTER_LEN {
+            return None;
+        }
+
+        file.seek(SeekFrom::Start(index_start)).ok()?;
+        let mut index = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            file.read_exact(&mut buf4).ok()?;
+            let k_len = u32::from_le_bytes(buf4) as usize;
+            let mut key_buf = vec![0u8; k_len];
+            file.read_exact(&mut key_buf).ok()?;
+            file.read_exact(&mut buf8).ok()?;
+            index.push((
+                String::from_utf8_lossy(&key_buf).to_string(),
+                u64::from_le_bytes(buf8),
+            ));
+        }
+        if file.stream_position().ok()? != file_len - FOOTER_LEN {
+            return None;
+        }
+
+        Some((index, index_start))
+    }
+
+    /// Decrypt `val` if this reader was opened with a cipher, otherwise
+    /// return it unchanged.
+    fn decrypt(&self, val: Vec<u8>) -> io::Result<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => cipher.decrypt(&val).map_err(cipher_err),
+            None => Ok(val),
+        }
+    }
+
+    /// Bring this SST file up to `format::CURRENT_VERSION` in place,
+    /// idempotently, re-encoding its records if needed. Call before
+    /// `open`, since this rewrites the file on disk.
+    pub fn upgrade(path: impl AsRef<Path>) -> io::Result<format::UpgradeReport> {
+        format::upgrade_record_file(path.as_ref())
+    }
+
+    /// On-disk size of this SST file, for `FireLocal::metrics`.
+    pub fn size_bytes(&self) -> io::Result<u64> {
+        Ok(self.file_len)
+    }
+
+    /// Scan `path` front-to-back, validating that every record's `flag`
+    /// byte is `FLAG_PUT`/`FLAG_DELETE`, that its `k_len`/`v_len` don't run
+    /// past the end of the record section, and -- for a
+    /// `format::CHECKSUM_VERSION` file -- that its trailing CRC32 matches.
+    /// The record section ends at the footer-declared index offset for a
+    /// file with an intact `format::INDEX_VERSION` footer, so a clean
+    /// index/block index is never mistaken for a corrupt record; a file
+    /// whose footer itself didn't survive the crash (e.g. torn mid-`SstBuilder::finish`)
+    /// falls back to treating everything to EOF as a record, same as
+    /// before the index existed. At the first record that fails validation
+    /// -- or an outright I/O error, e.g. a read cut short by a truncated
+    /// write -- this stops and rewrites `path` to contain only the prefix
+    /// up to and including the last fully-valid record; the rest is
+    /// assumed to be a torn write from a crash mid-flush, not recoverable
+    /// data. A no-op (`bytes_truncated: 0`) if every record validates.
+    /// `SstReader::repair` is the `StdStorage` shortcut for this.
+    pub fn repair_with_storage(storage: &S, path: impl AsRef<Path>) -> io::Result<RepairReport> {
+        let path = path.as_ref();
+        let file_len = storage.stat(path)?.size;
+
+        let mut reader = BufReader::new(storage.open(path)?);
+        let header = format::read_header(&mut reader)?;
+        format::decoder_for_version(header.version)?;
+        let has_crc = header.version >= format::CHECKSUM_VERSION;
+
+        let scan_end = if header.version >= format::INDEX_VERSION && file_len >= FOOTER_LEN {
+            let mut index_file = storage.open(path)?;
+            Self::try_load_index(&mut index_file, file_len)
+                .map(|(_, index_start)| index_start)
+                .unwrap_or(file_len)
+        } else {
+            file_len
+        };
+
+        let mut report = RepairReport::default();
+        let mut last_good_offset = reader.stream_position()?;
+
+        loop {
+            let record_start = reader.stream_position()?;
+            if record_start >= scan_end {
+                break; // Reached the index (or EOF, if there's no footer).
+            }
+            match Self::read_and_validate_record(&mut reader, scan_end, has_crc) {
+                Ok(Some(())) => {
+                    report.records_recovered += 1;
+                    last_good_offset = reader.stream_position()?;
+                }
+                Ok(None) => break, // Clean EOF right at a record boundary.
+                Err(_) => {
+                    report.first_bad_offset = Some(record_start);
+                    break;
+                }
+            }
+        }
+
+        if report.first_bad_offset.is_some() {
+            report.bytes_truncated = file_len - last_good_offset;
+            Self::truncate_to(storage, path, last_good_offset)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Read one record starting at `reader`'s current position for
+    /// `repair_with_storage`, returning `Ok(None)` on a clean EOF before any
+    /// record bytes. Doesn't decrypt the value -- `repair_with_storage` only
+    /// cares that the record's shape (and, when `has_crc`, its checksum) is
+    /// intact, not what it decodes to.
+    fn read_and_validate_record(
+        reader: &mut BufReader<S::File>,
+        scan_end: u64,
+        has_crc: bool,
+    ) -> io::Result<Option<()>> {
+        let mut flag_buf = [0u8; 1];
+        if reader.read(&mut flag_buf)? == 0 {
+            return Ok(None);
+        }
+        let flag = flag_buf[0];
+        if flag != FLAG_PUT && flag != FLAG_DELETE {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "bad record flag"));
+        }
+
+        let mut len_buf = [0u8; 4];
+        reader.read_exact(&mut len_buf)?;
+        let k_len = u32::from_le_bytes(len_buf) as u64;
+        let after_k_len = reader.stream_position()?;
+        if after_k_len.checked_add(k_len).is_none_or(|end| end > scan_end) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "key length runs past end of record section",
+            ));
+        }
+        let mut key_buf = vec![0u8; k_len as usize];
+        reader.read_exact(&mut key_buf)?;
+        let key = String::from_utf8_lossy(&key_buf).to_string();
+
+        reader.read_exact(&mut len_buf)?;
+        let v_len = u32::from_le_bytes(len_buf) as u64;
+        let after_v_len = reader.stream_position()?;
+        let trailer_len = if has_crc { 4u64 } else { 0 };
+        if after_v_len
+            .checked_add(v_len)
+            .and_then(|end| end.checked_add(trailer_len))
+            .is_none_or(|end| end > scan_end)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "value length runs past end of record section",
+            ));
+        }
+        let mut val_buf = vec![0u8; v_len as usize];
+        reader.read_exact(&mut val_buf)?;
+
+        if has_crc {
+            let mut crc_buf = [0u8; 4];
+            reader.read_exact(&mut crc_buf)?;
+            let stored_crc = u32::from_le_bytes(crc_buf);
+            if format::record_crc(flag, &key, &val_buf) != stored_crc {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "record CRC mismatch",
+                ));
+            }
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Rewrite `path` to contain only its first `good_len` bytes: copy that
+    /// prefix into a sibling temp file, then atomically rename it over
+    /// `path` -- same pattern as `format::upgrade_record_file`.
+    fn truncate_to(storage: &S, path: &Path, good_len: u64) -> io::Result<()> {
+        let tmp_path = path.with_extension("repairing");
+        {
+            let mut src = storage.open(path)?;
+            let mut dst = storage.create(&tmp_path)?;
+            io::copy(&mut src.by_ref().take(good_len), &mut dst)?;
+            dst.flush()?;
+        }
+        storage.rename(&tmp_path, path)
+    }
+
+    /// Point lookup. With a loaded block index (`format::INDEX_VERSION` and
+    /// up), this binary-searches the index for the one block whose key
+    /// range could contain `search_key`, then linear-scans only that block
+    /// -- O(log blocks + block size) instead of the original M1
+    /// implementation's O(total records). Falls back to a full linear scan
+    /// of `[data_start, data_end)` for a file with no index.
     pub fn get(&mut self, search_key: &str) -> io::Result<SstSearchResult> {
-        self.file.seek(SeekFrom::Start(0))?; // Reset to start
+        if self.index.is_empty() {
+            return self.scan_range(self.data_start, self.data_end, search_key);
+        }
+
+        // `partition_point` finds the first block whose first key is
+        // *greater* than `search_key`; the block before it (if any) is the
+        // only one that could hold `search_key`, since every key in a
+        // later block sorts after this block's first key.
+        let block = self.index.partition_point(|(key, _)| key.as_str() <= search_key);
+        if block == 0 {
+            return Ok(SstSearchResult::NotFound); // Before the first block's first key.
+        }
+        let block_start = self.index[block - 1].1;
+        let block_end = self
+            .index
+            .get(block)
+            .map(|(_, offset)| *offset)
+            .unwrap_or(self.data_end);
+        self.scan_range(block_start, block_end, search_key)
+    }
+
+    /// Linear-scan `[start, end)` for `search_key`, stopping at `end`
+    /// rather than relying on EOF -- `end` may be a block boundary with
+    /// more records (or index/footer bytes) beyond it.
+    fn scan_range(&mut self, start: u64, end: u64, search_key: &str) -> io::Result<SstSearchResult> {
+        self.file.seek(SeekFrom::Start(start))?;
 
         let mut flag_buf = [0u8; 1];
         let mut len_buf = [0u8; 4];
+        let has_crc = self.version >= format::CHECKSUM_VERSION;
+
+        while self.file.stream_position()? < end {
+            let record_start = self.file.stream_position()?;
 
-        loop {
             // Read flag
             if self.file.read(&mut flag_buf)? == 0 {
                 return Ok(SstSearchResult::NotFound); // EOF
@@ -89,26 +579,189 @@ impl SstReader {
             // Read key
             let mut key_buf = vec![0u8; k_len];
             self.file.read_exact(&mut key_buf)?;
-            let key = String::from_utf8_lossy(&key_buf);
+            let key = String::from_utf8_lossy(&key_buf).to_string();
 
             // Read v_len
             self.file.read_exact(&mut len_buf)?;
             let v_len = u32::from_le_bytes(len_buf) as usize;
 
             if key == search_key {
+                let mut val_buf = vec![0u8; v_len];
+                self.file.read_exact(&mut val_buf)?;
+
+                if has_crc {
+                    let mut crc_buf = [0u8; 4];
+                    self.file.read_exact(&mut crc_buf)?;
+                    let stored_crc = u32::from_le_bytes(crc_buf);
+                    if format::record_crc(flag, &key, &val_buf) != stored_crc {
+                        return Ok(SstSearchResult::Corrupt {
+                            offset: record_start,
+                        });
+                    }
+                }
+
                 if flag == FLAG_DELETE {
-                    self.file.seek(SeekFrom::Current(v_len as i64))?;
                     return Ok(SstSearchResult::Deleted);
-                } else {
-                    let mut val_buf = vec![0u8; v_len];
-                    self.file.read_exact(&mut val_buf)?;
-                    return Ok(SstSearchResult::Found(val_buf));
                 }
+                return Ok(SstSearchResult::Found(self.decrypt(val_buf)?));
             } else {
-                // Skip value
-                self.file.seek(SeekFrom::Current(v_len as i64))?;
+                // Skip value, plus this record's trailing CRC if it has one.
+                let skip = v_len as i64 + if has_crc { 4 } else { 0 };
+                self.file.seek(SeekFrom::Current(skip))?;
             }
         }
+
+        Ok(SstSearchResult::NotFound)
+    }
+
+    /// Like `all_entries`, but skips any record whose key sorts before
+    /// `start`. `SstBuilder::build` writes records in the memtable's
+    /// ascending key order, so the file is already sorted and this needs no
+    /// seeking smarter than filtering a linear scan — same M1 caveat as
+    /// `get`. Used by `FireLocal::scan` to position this SST's side of the
+    /// merge at the requested start key.
+    pub fn entries_from(&mut self, start: &str) -> io::Result<Vec<(String, SstSearchResult)>> {
+        Ok(self
+            .all_entries()?
+            .into_iter()
+            .filter(|(key, _)| key.as_str() >= start)
+            .collect())
+    }
+
+    /// Consume this reader, yielding every record in on-disk (already
+    /// sorted, `SstBuilder::build` writes a memtable's keys in ascending
+    /// order) key order as `(key, value)`, one at a time -- `None` means a
+    /// tombstone. Used by `Compactor`'s streaming k-way merge in place of
+    /// `all_entries`, so a compaction never needs to hold one whole file's
+    /// contents in memory at once.
+    pub fn into_ordered_iter(
+        mut self,
+    ) -> io::Result<impl Iterator<Item = io::Result<(String, Option<Vec<u8>>)>>> {
+        self.file.seek(SeekFrom::Start(self.data_start))?;
+        Ok(std::iter::from_fn(move || self.next_ordered_record()))
+    }
+
+    /// Read the record at the file's current position and advance past it,
+    /// for `into_ordered_iter`. `Ok(None)` means a clean EOF.
+    fn next_ordered_record(&mut self) -> Option<io::Result<(String, Option<Vec<u8>>)>> {
+        let has_crc = self.version >= format::CHECKSUM_VERSION;
+
+        match self.file.stream_position() {
+            Ok(pos) if pos >= self.data_end => return None, // Reached the index (or EOF).
+            Ok(_) => {}
+            Err(e) => return Some(Err(e)),
+        }
+
+        let mut flag_buf = [0u8; 1];
+        match self.file.read(&mut flag_buf) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(e)),
+        }
+        let flag = flag_buf[0];
+
+        let mut len_buf = [0u8; 4];
+        if let Err(e) = self.file.read_exact(&mut len_buf) {
+            return Some(Err(e));
+        }
+        let k_len = u32::from_le_bytes(len_buf) as usize;
+        let mut key_buf = vec![0u8; k_len];
+        if let Err(e) = self.file.read_exact(&mut key_buf) {
+            return Some(Err(e));
+        }
+        let key = String::from_utf8_lossy(&key_buf).to_string();
+
+        if let Err(e) = self.file.read_exact(&mut len_buf) {
+            return Some(Err(e));
+        }
+        let v_len = u32::from_le_bytes(len_buf) as usize;
+        let mut val_buf = vec![0u8; v_len];
+        if let Err(e) = self.file.read_exact(&mut val_buf) {
+            return Some(Err(e));
+        }
+
+        if has_crc {
+            let mut crc_buf = [0u8; 4];
+            if let Err(e) = self.file.read_exact(&mut crc_buf) {
+                return Some(Err(e));
+            }
+            let stored_crc = u32::from_le_bytes(crc_buf);
+            if format::record_crc(flag, &key, &val_buf) != stored_crc {
+                // A corrupt record's own length fields can't be trusted
+                // either, so there's no reliable way to resync with
+                // whatever comes after -- treat the rest of the file the
+                // same as a clean EOF, same as `Compactor`'s merge would
+                // see a file truncated here. `repair` is the tool for
+                // recovering what's left on disk.
+                return None;
+            }
+        }
+
+        if flag == FLAG_DELETE {
+            Some(Ok((key, None)))
+        } else {
+            match self.decrypt(val_buf) {
+                Ok(val) => Some(Ok((key, Some(val)))),
+                Err(e) => Some(Err(e)),
+            }
+        }
+    }
+
+    /// Read every record in the file in on-disk order, without filtering by
+    /// key. Unlike `get`, this is a single linear pass; used by callers (like
+    /// dump/restore) that need the whole file rather than one lookup.
+    pub fn all_entries(&mut self) -> io::Result<Vec<(String, SstSearchResult)>> {
+        self.file.seek(SeekFrom::Start(self.data_start))?;
+        let has_crc = self.version >= format::CHECKSUM_VERSION;
+
+        let mut flag_buf = [0u8; 1];
+        let mut len_buf = [0u8; 4];
+        let mut out = Vec::new();
+
+        loop {
+            let record_start = self.file.stream_position()?;
+            if record_start >= self.data_end {
+                break; // Reached the index (or EOF, if there's no footer).
+            }
+            if self.file.read(&mut flag_buf)? == 0 {
+                break; // EOF
+            }
+            let flag = flag_buf[0];
+
+            self.file.read_exact(&mut len_buf)?;
+            let k_len = u32::from_le_bytes(len_buf) as usize;
+            let mut key_buf = vec![0u8; k_len];
+            self.file.read_exact(&mut key_buf)?;
+            let key = String::from_utf8_lossy(&key_buf).to_string();
+
+            self.file.read_exact(&mut len_buf)?;
+            let v_len = u32::from_le_bytes(len_buf) as usize;
+            let mut val_buf = vec![0u8; v_len];
+            self.file.read_exact(&mut val_buf)?;
+
+            if has_crc {
+                let mut crc_buf = [0u8; 4];
+                self.file.read_exact(&mut crc_buf)?;
+                let stored_crc = u32::from_le_bytes(crc_buf);
+                if format::record_crc(flag, &key, &val_buf) != stored_crc {
+                    out.push((
+                        key,
+                        SstSearchResult::Corrupt {
+                            offset: record_start,
+                        },
+                    ));
+                    break; // Can't trust anything past a corrupt record's own lengths.
+                }
+            }
+
+            if flag == FLAG_DELETE {
+                out.push((key, SstSearchResult::Deleted));
+            } else {
+                out.push((key, SstSearchResult::Found(self.decrypt(val_buf)?)));
+            }
+        }
+
+        Ok(out)
     }
 }
 
@@ -116,4 +769,23 @@ pub enum SstSearchResult {
     Found(Vec<u8>),
     Deleted,
     NotFound,
+    /// The matched record's stored CRC32 doesn't match what `format::record_crc`
+    /// recomputes (`format::CHECKSUM_VERSION` and up only) -- `offset` is
+    /// where the record starts, for `repair` to target.
+    Corrupt { offset: u64 },
+}
+
+/// Result of `SstReader::repair`: how much of a segment's tail was
+/// unreadable and had to be cut away.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize)]
+pub struct RepairReport {
+    /// Number of fully-valid records found before the first corrupt one (or
+    /// before a clean EOF, if the file wasn't actually corrupt).
+    pub records_recovered: usize,
+    /// How many trailing bytes were cut off the file. `0` if the file was
+    /// already intact.
+    pub bytes_truncated: u64,
+    /// Byte offset of the first record that failed validation, or `None` if
+    /// the file read cleanly to its end with no corruption.
+    pub first_bad_offset: Option<u64>,
 }
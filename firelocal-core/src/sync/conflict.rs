@@ -0,0 +1,218 @@
+//! Version-vector conflict detection for `SyncManager::reconcile`.
+//!
+//! Every `Document` carries a per-node write counter (`version_vector`) and a
+//! wall-clock `updated_at_ms`, bumped on each local `put` (see
+//! `FireLocal::put`). Comparing two vectors tells a puller whether the remote
+//! causally dominates the local copy, the local copy dominates the remote,
+//! or the two were edited independently (`Concurrent`) and need a
+//! `ConflictResolver` to pick (or merge) a winner.
+
+use crate::model::Document;
+use std::collections::{HashMap, HashSet};
+
+/// How two version vectors relate causally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorOrdering {
+    /// `a` happened-before `b`: every entry in `a` is <= the matching entry
+    /// in `b`, and at least one is strictly less.
+    Before,
+    /// `a` happened-after `b`.
+    After,
+    /// Identical vectors.
+    Equal,
+    /// Neither dominates the other: a genuine conflict.
+    Concurrent,
+}
+
+/// Compare two version vectors, treating an absent node as count `0`.
+pub fn compare(a: &HashMap<String, u64>, b: &HashMap<String, u64>) -> VectorOrdering {
+    let mut a_ahead = false;
+    let mut b_ahead = false;
+
+    let nodes: HashSet<&String> = a.keys().chain(b.keys()).collect();
+    for node in nodes {
+        let av = a.get(node).copied().unwrap_or(0);
+        let bv = b.get(node).copied().unwrap_or(0);
+        if av > bv {
+            a_ahead = true;
+        } else if av < bv {
+            b_ahead = true;
+        }
+    }
+
+    match (a_ahead, b_ahead) {
+        (false, false) => VectorOrdering::Equal,
+        (true, false) => VectorOrdering::After,
+        (false, true) => VectorOrdering::Before,
+        (true, true) => VectorOrdering::Concurrent,
+    }
+}
+
+/// Element-wise max of two version vectors: the vector a reconciled document
+/// should carry forward, since it causally succeeds both inputs.
+pub fn merge_vectors(a: &HashMap<String, u64>, b: &HashMap<String, u64>) -> HashMap<String, u64> {
+    let mut out = a.clone();
+    for (node, &count) in b {
+        let entry = out.entry(node.clone()).or_insert(0);
+        if count > *entry {
+            *entry = count;
+        }
+    }
+    out
+}
+
+/// Picks (or merges) a winner when `compare` reports `Concurrent`.
+pub trait ConflictResolver: Send + Sync {
+    fn resolve(&self, local: &Document, remote: &Document) -> Document;
+}
+
+/// Default resolver: whichever side wrote more recently wins outright.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LastWriteWinsResolver;
+
+impl ConflictResolver for LastWriteWinsResolver {
+    fn resolve(&self, local: &Document, remote: &Document) -> Document {
+        if local.updated_at_ms >= remote.updated_at_ms {
+            local.clone()
+        } else {
+            remote.clone()
+        }
+    }
+}
+
+/// Field-level resolver: unions the two documents' top-level fields instead
+/// of picking one side wholesale. A version vector only counts writes per
+/// node, not per field, so as a proxy for "which side edited this field more
+/// recently" each field is taken from whichever side has the higher total
+/// write count (summed across its vector) when both sides have it, and from
+/// whichever side has it at all otherwise.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FieldLevelMergeResolver;
+
+impl ConflictResolver for FieldLevelMergeResolver {
+    fn resolve(&self, local: &Document, remote: &Document) -> Document {
+        let local_weight: u64 = local.version_vector.values().sum();
+        let remote_weight: u64 = remote.version_vector.values().sum();
+
+        let mut fields = remote.fields.clone();
+        for (key, value) in &local.fields {
+            let local_wins = match remote.fields.get(key) {
+                Some(_) => local_weight >= remote_weight,
+                None => true,
+            };
+            if local_wins {
+                fields.insert(key.clone(), value.clone());
+            }
+        }
+
+        let newer = if local.updated_at_ms >= remote.updated_at_ms {
+            local
+        } else {
+            remote
+        };
+
+        Document {
+            path: newer.path.clone(),
+            fields,
+            version: newer.version,
+            version_vector: merge_vectors(&local.version_vector, &remote.version_vector),
+            updated_at_ms: newer.updated_at_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vector(pairs: &[(&str, u64)]) -> HashMap<String, u64> {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_compare_before_and_after() {
+        let a = vector(&[("node-a", 1)]);
+        let b = vector(&[("node-a", 2)]);
+        assert_eq!(compare(&a, &b), VectorOrdering::Before);
+        assert_eq!(compare(&b, &a), VectorOrdering::After);
+    }
+
+    #[test]
+    fn test_compare_equal() {
+        let a = vector(&[("node-a", 3)]);
+        assert_eq!(compare(&a, &a.clone()), VectorOrdering::Equal);
+    }
+
+    #[test]
+    fn test_compare_concurrent_when_each_node_leads_on_its_own_entry() {
+        let a = vector(&[("node-a", 2), ("node-b", 1)]);
+        let b = vector(&[("node-a", 1), ("node-b", 2)]);
+        assert_eq!(compare(&a, &b), VectorOrdering::Concurrent);
+    }
+
+    #[test]
+    fn test_merge_vectors_takes_elementwise_max() {
+        let a = vector(&[("node-a", 2), ("node-b", 1)]);
+        let b = vector(&[("node-a", 1), ("node-b", 3), ("node-c", 1)]);
+        let merged = merge_vectors(&a, &b);
+        assert_eq!(merged.get("node-a"), Some(&2));
+        assert_eq!(merged.get("node-b"), Some(&3));
+        assert_eq!(merged.get("node-c"), Some(&1));
+    }
+
+    #[test]
+    fn test_last_write_wins_resolver_picks_newer_timestamp() {
+        let local = Document {
+            path: "doc".to_string(),
+            fields: serde_json::from_value(json!({ "name": "local" })).unwrap(),
+            version_vector: vector(&[("node-a", 1)]),
+            updated_at_ms: 100,
+            ..Default::default()
+        };
+        let remote = Document {
+            path: "doc".to_string(),
+            fields: serde_json::from_value(json!({ "name": "remote" })).unwrap(),
+            version_vector: vector(&[("node-b", 1)]),
+            updated_at_ms: 200,
+            ..Default::default()
+        };
+
+        // Two nodes both edited the same document since their last sync, so
+        // neither vector dominates: a genuine concurrent edit.
+        assert_eq!(
+            compare(&local.version_vector, &remote.version_vector),
+            VectorOrdering::Concurrent
+        );
+
+        let resolved = LastWriteWinsResolver.resolve(&local, &remote);
+        assert_eq!(resolved.fields.get("name").unwrap(), "remote");
+    }
+
+    #[test]
+    fn test_field_level_merge_resolver_unions_fields() {
+        let local = Document {
+            path: "doc".to_string(),
+            fields: serde_json::from_value(json!({ "name": "local", "age": 30 })).unwrap(),
+            version_vector: vector(&[("node-a", 5)]),
+            updated_at_ms: 100,
+            ..Default::default()
+        };
+        let remote = Document {
+            path: "doc".to_string(),
+            fields: serde_json::from_value(json!({ "name": "remote", "city": "NYC" })).unwrap(),
+            version_vector: vector(&[("node-b", 1)]),
+            updated_at_ms: 200,
+            ..Default::default()
+        };
+
+        let resolved = FieldLevelMergeResolver.resolve(&local, &remote);
+        // local's heavier vector wins the contested "name" field...
+        assert_eq!(resolved.fields.get("name").unwrap(), "local");
+        // ...but fields unique to either side both survive the merge.
+        assert_eq!(resolved.fields.get("age").unwrap(), 30);
+        assert_eq!(resolved.fields.get("city").unwrap(), "NYC");
+        assert_eq!(resolved.version_vector.get("node-a"), Some(&5));
+        assert_eq!(resolved.version_vector.get("node-b"), Some(&1));
+    }
+}
@@ -1,14 +1,50 @@
+use crate::auth::now_ms;
 use crate::model::Document;
-use crate::sync::RemoteStore;
+use crate::sync::conflict::merge_vectors;
+use crate::sync::envelope::{self, EncryptedEnvelope};
+use crate::sync::{RemoteStore, SyncSummary};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 
+/// `Document.fields` keys an encrypted envelope occupies in place of the
+/// real fields -- see `EncryptionConfig` and `seal_document`. Prefixed and
+/// namespaced so they can't collide with a real field a caller chose.
+const ENC_NONCE_FIELD: &str = "__firelocal_enc_nonce";
+const ENC_CIPHERTEXT_FIELD: &str = "__firelocal_enc_ciphertext";
+const ENC_TAG_FIELD: &str = "__firelocal_enc_tag";
+
+/// AES-256-GCM configuration for encrypting `Document.fields` before
+/// `EnhancedSyncManager` hands a document to `RemoteStore::push` (see
+/// `crate::sync::envelope`). Absent by default, so existing callers keep
+/// syncing plaintext unless they opt in.
+#[derive(Clone)]
+pub struct EncryptionConfig {
+    master_key: [u8; 32],
+}
+
+impl EncryptionConfig {
+    pub fn new(master_key: [u8; 32]) -> Self {
+        Self { master_key }
+    }
+}
+
+impl std::fmt::Debug for EncryptionConfig {
+    /// Never prints key material, matching `EncryptionKeySource`'s Debug
+    /// impl in `crate::store::encryption`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "EncryptionConfig {{ .. }}")
+    }
+}
+
 /// Sync modes
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SyncMode {
     /// No syncing
+    #[default]
     Off,
     /// Manual sync only
     Manual,
@@ -29,6 +65,10 @@ pub enum ConflictResolution {
     ClientWins,
     /// Server always wins
     ServerWins,
+    /// Reconcile `Document.fields` per-key against a common ancestor (see
+    /// `EnhancedSyncManager::resolve_conflict`), so concurrent edits to
+    /// disjoint fields are both kept instead of one side winning outright.
+    ThreeWayMerge,
 }
 
 /// Retry configuration
@@ -51,6 +91,91 @@ impl Default for RetryConfig {
     }
 }
 
+/// One operation in a `bulk_write` call -- modeled on MongoDB's
+/// `bulkWrite`, so a batched sync can mix puts and deletes with a
+/// per-operation outcome instead of `batch_sync`'s puts-only, single
+/// all-or-nothing `Result<()>`.
+#[derive(Debug, Clone)]
+pub enum BulkWriteModel {
+    /// Upsert `path`'s fields. Counted as `inserted` in the returned
+    /// `BulkWriteResult` if the remote had nothing at `path` beforehand,
+    /// `modified` otherwise.
+    Put {
+        path: String,
+        fields: serde_json::Map<String, serde_json::Value>,
+    },
+    /// Remove `path` from the remote.
+    Delete { path: String },
+}
+
+/// `bulk_write`'s execution strategy.
+#[derive(Debug, Clone, Copy)]
+pub struct BulkWriteOptions {
+    /// `true` (MongoDB's default): stop at the first model that fails,
+    /// leaving every later model in the slice unattempted. `false`:
+    /// attempt every model regardless of earlier failures and collect
+    /// every error.
+    pub ordered: bool,
+}
+
+impl Default for BulkWriteOptions {
+    fn default() -> Self {
+        Self { ordered: true }
+    }
+}
+
+/// One `bulk_write` model that failed, keyed by its position in the
+/// `models` slice so a caller can match a failure back to the request that
+/// produced it.
+#[derive(Debug, Clone)]
+pub struct BulkWriteError {
+    pub index: usize,
+    pub error: String,
+}
+
+/// Result of `bulk_write`: per-kind counts for every model that succeeded,
+/// plus one `BulkWriteError` per model that didn't.
+#[derive(Debug, Clone, Default)]
+pub struct BulkWriteResult {
+    pub inserted: usize,
+    pub modified: usize,
+    pub deleted: usize,
+    pub errors: Vec<BulkWriteError>,
+}
+
+/// What a single `BulkWriteModel` did, once it succeeded -- folded into
+/// `BulkWriteResult`'s counts by `bulk_write`.
+enum BulkWriteOutcome {
+    Inserted,
+    Modified,
+    Deleted,
+}
+
+/// What `sync_collection` remembers about a collection (a `RemoteStore`
+/// path prefix) between rounds, so the next round only moves what changed
+/// instead of re-pushing and re-pulling everything -- the "collection
+/// state" `start_background_sync`/`batch_sync` lacked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionSyncState {
+    /// Highest local `Document::version` already pushed. On the next
+    /// round, only local docs with a higher version are pushed.
+    pub last_synced_version: u64,
+    /// `auth::now_ms()` timestamp of the last fully successful round.
+    pub last_synced_at: u64,
+    /// Opaque cursor returned by the last `RemoteStore::pull_since` call;
+    /// passed back in on the next round so it resumes from there.
+    pub remote_cursor: Option<String>,
+}
+
+/// Result of `sync_collection`: per-kind counts (same shape as
+/// `FireLocal::sync_push_all`/`sync_pull_all`'s `SyncSummary`), plus the
+/// resolved documents a caller should persist locally.
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalSyncResult {
+    pub summary: SyncSummary,
+    pub resolved: Vec<Document>,
+}
+
 /// Enhanced sync manager with multiple modes
 pub struct EnhancedSyncManager {
     mode: SyncMode,
@@ -59,6 +184,8 @@ pub struct EnhancedSyncManager {
     remote: Arc<Mutex<Box<dyn RemoteStore>>>,
     retry_config: RetryConfig,
     conflict_resolution: ConflictResolution,
+    encryption: Option<EncryptionConfig>,
+    collection_state: Mutex<HashMap<String, CollectionSyncState>>,
 }
 
 impl EnhancedSyncManager {
@@ -69,6 +196,8 @@ impl EnhancedSyncManager {
             remote: Arc::new(Mutex::new(remote)),
             retry_config: RetryConfig::default(),
             conflict_resolution: ConflictResolution::LastWriteWins,
+            encryption: None,
+            collection_state: Mutex::new(HashMap::new()),
         }
     }
 
@@ -82,6 +211,12 @@ impl EnhancedSyncManager {
         self.retry_config = config;
     }
 
+    /// Enable (or, with `None`, disable) end-to-end encryption of synced
+    /// document payloads -- see `EncryptionConfig`.
+    pub fn set_encryption_config(&mut self, config: Option<EncryptionConfig>) {
+        self.encryption = config;
+    }
+
     /// Start live sync (real-time)
     pub async fn start_live_sync(&self) -> Result<()> {
         if self.mode != SyncMode::Live {
@@ -97,7 +232,9 @@ impl EnhancedSyncManager {
         Ok(())
     }
 
-    /// Run batch sync
+    /// Run batch sync. Pushes every document in `docs` unconditionally --
+    /// for `Background`/`Batch` modes that should only move what changed
+    /// since the last round, see `sync_collection` instead.
     pub async fn batch_sync(&self, docs: &[Document]) -> Result<()> {
         let mut delay = self.retry_config.initial_delay;
 
@@ -123,15 +260,297 @@ impl EnhancedSyncManager {
         let remote = self.remote.lock().await;
 
         for doc in docs {
+            let outgoing = match &self.encryption {
+                Some(config) => self.seal_document(config, doc)?,
+                None => doc.clone(),
+            };
             remote
-                .push(doc)
+                .push(&outgoing)
                 .map_err(|e| anyhow::anyhow!("Sync error: {}", e))?;
         }
 
         Ok(())
     }
 
-    /// Start background sync
+    /// Current `CollectionSyncState` for `prefix`, or the zero-valued
+    /// default if `sync_collection` has never completed a round for it.
+    pub async fn collection_state(&self, prefix: &str) -> CollectionSyncState {
+        self.collection_state
+            .lock()
+            .await
+            .get(prefix)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Delta sync one collection (a `RemoteStore` path prefix) against the
+    /// `CollectionSyncState` left over from the last round, instead of
+    /// `batch_sync`'s full push of everything it's handed. Only
+    /// `local_docs` whose `version` exceeds `last_synced_version` are
+    /// pushed; only remote changes since `remote_cursor` are pulled (via
+    /// `RemoteStore::pull_since`) and fed through `resolve_conflict`
+    /// against their local counterpart. The state only advances once the
+    /// whole round -- push and pull -- succeeds, so a round that fails
+    /// partway leaves `last_synced_version`/`remote_cursor` untouched and
+    /// the next attempt re-covers the same ground rather than skipping it.
+    /// Retried with `self.retry_config`'s bounded exponential backoff, same
+    /// as `batch_sync`.
+    ///
+    /// The resolved remote documents are returned rather than written
+    /// anywhere, since `EnhancedSyncManager` has no local store of its own
+    /// -- the caller is expected to persist them.
+    pub async fn sync_collection(
+        &self,
+        prefix: &str,
+        local_docs: &[Document],
+    ) -> Result<IncrementalSyncResult> {
+        let mut delay = self.retry_config.initial_delay;
+
+        for attempt in 0..self.retry_config.max_attempts {
+            match self.try_sync_collection(prefix, local_docs).await {
+                Ok(result) => return Ok(result),
+                Err(_e) if attempt + 1 < self.retry_config.max_attempts => {
+                    tokio::time::sleep(delay).await;
+                    delay = Duration::from_secs_f64(
+                        (delay.as_secs_f64() * self.retry_config.multiplier)
+                            .min(self.retry_config.max_delay.as_secs_f64()),
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        unreachable!("loop above always returns on its last iteration")
+    }
+
+    async fn try_sync_collection(
+        &self,
+        prefix: &str,
+        local_docs: &[Document],
+    ) -> Result<IncrementalSyncResult> {
+        let state = self.collection_state(prefix).await;
+        let mut summary = SyncSummary::default();
+
+        let to_push: Vec<&Document> = local_docs
+            .iter()
+            .filter(|doc| doc.version > state.last_synced_version)
+            .collect();
+        {
+            let remote = self.remote.lock().await;
+            for doc in &to_push {
+                let outgoing = match &self.encryption {
+                    Some(config) => self.seal_document(config, doc)?,
+                    None => (*doc).clone(),
+                };
+                remote
+                    .push(&outgoing)
+                    .map_err(|e| anyhow::anyhow!("Sync error: {}", e))?;
+                summary.pushed += 1;
+            }
+        }
+
+        let (remote_docs, next_cursor) = {
+            let remote = self.remote.lock().await;
+            remote
+                .pull_since(prefix, state.remote_cursor.as_deref())
+                .map_err(|e| anyhow::anyhow!("Sync error: {}", e))?
+        };
+
+        let local_by_path: HashMap<&str, &Document> = local_docs
+            .iter()
+            .map(|doc| (doc.path.as_str(), doc))
+            .collect();
+        let resolved: Vec<Document> = remote_docs
+            .into_iter()
+            .map(|remote_doc| match local_by_path.get(remote_doc.path.as_str()) {
+                Some(local_doc) => self.resolve_conflict(local_doc, &remote_doc, None),
+                None => remote_doc,
+            })
+            .collect();
+        summary.pulled = resolved.len();
+
+        let new_version = to_push
+            .iter()
+            .map(|doc| doc.version)
+            .chain(resolved.iter().map(|doc| doc.version))
+            .fold(state.last_synced_version, u64::max);
+
+        self.collection_state.lock().await.insert(
+            prefix.to_string(),
+            CollectionSyncState {
+                last_synced_version: new_version,
+                last_synced_at: now_ms(),
+                remote_cursor: next_cursor,
+            },
+        );
+
+        Ok(IncrementalSyncResult { summary, resolved })
+    }
+
+    /// Batched put/delete against the remote store, modeled on MongoDB's
+    /// `bulkWrite`: unlike `batch_sync`, each model gets its own retried
+    /// push/delete and its own entry in the returned counts/errors,
+    /// indexed by its position in `models`. `options.ordered` stops at the
+    /// first failing model; unordered attempts every model and collects
+    /// every failure. Never returns an `Err` itself -- per-model failures
+    /// live in `BulkWriteResult::errors` instead.
+    pub async fn bulk_write(
+        &self,
+        models: &[BulkWriteModel],
+        options: BulkWriteOptions,
+    ) -> BulkWriteResult {
+        let mut result = BulkWriteResult::default();
+
+        for (index, model) in models.iter().enumerate() {
+            match self.execute_bulk_model(model).await {
+                Ok(BulkWriteOutcome::Inserted) => result.inserted += 1,
+                Ok(BulkWriteOutcome::Modified) => result.modified += 1,
+                Ok(BulkWriteOutcome::Deleted) => result.deleted += 1,
+                Err(error) => {
+                    result.errors.push(BulkWriteError { index, error });
+                    if options.ordered {
+                        break;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Run one `BulkWriteModel` against the remote, retried with
+    /// `self.retry_config`'s bounded exponential backoff -- same shape as
+    /// `batch_sync`'s retry loop, just around a single model instead of
+    /// the whole batch.
+    async fn execute_bulk_model(&self, model: &BulkWriteModel) -> Result<BulkWriteOutcome, String> {
+        let mut delay = self.retry_config.initial_delay;
+
+        for attempt in 0..self.retry_config.max_attempts {
+            match self.try_bulk_model(model).await {
+                Ok(outcome) => return Ok(outcome),
+                Err(_e) if attempt + 1 < self.retry_config.max_attempts => {
+                    tokio::time::sleep(delay).await;
+                    delay = Duration::from_secs_f64(
+                        (delay.as_secs_f64() * self.retry_config.multiplier)
+                            .min(self.retry_config.max_delay.as_secs_f64()),
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        unreachable!("loop above always returns on its last iteration")
+    }
+
+    async fn try_bulk_model(&self, model: &BulkWriteModel) -> Result<BulkWriteOutcome, String> {
+        let remote = self.remote.lock().await;
+
+        match model {
+            BulkWriteModel::Put { path, fields } => {
+                // A document round-trip to the remote is the only way to
+                // tell "inserted" from "modified" through the `RemoteStore`
+                // trait, which has no existence check of its own.
+                let existed = remote.pull(path)?.is_some();
+
+                let doc = Document {
+                    path: path.clone(),
+                    fields: fields.clone(),
+                    ..Default::default()
+                };
+                let outgoing = match &self.encryption {
+                    Some(config) => self
+                        .seal_document(config, &doc)
+                        .map_err(|e| e.to_string())?,
+                    None => doc,
+                };
+                remote.push(&outgoing)?;
+
+                Ok(if existed {
+                    BulkWriteOutcome::Modified
+                } else {
+                    BulkWriteOutcome::Inserted
+                })
+            }
+            BulkWriteModel::Delete { path } => {
+                remote.delete(path)?;
+                Ok(BulkWriteOutcome::Deleted)
+            }
+        }
+    }
+
+    /// Pull `path` from the remote store, transparently decrypting it if
+    /// `EncryptionConfig` is set and the stored record is an encrypted
+    /// envelope (see `try_batch_sync`). A record that isn't an envelope --
+    /// pre-existing plaintext data, or encryption not yet configured -- is
+    /// returned as-is, so a store holding a mix of both still pulls.
+    pub async fn pull(&self, path: &str) -> Result<Option<Document>> {
+        let remote = self.remote.lock().await;
+        let Some(doc) = remote
+            .pull(path)
+            .map_err(|e| anyhow::anyhow!("Sync error: {}", e))?
+        else {
+            return Ok(None);
+        };
+
+        let Some(config) = &self.encryption else {
+            return Ok(Some(doc));
+        };
+        let Some(sealed) = Self::extract_envelope(&doc) else {
+            return Ok(Some(doc));
+        };
+
+        let fields = envelope::decrypt_fields(&config.master_key, path, &sealed)?;
+        Ok(Some(Document {
+            path: doc.path,
+            fields,
+            version: doc.version,
+            version_vector: doc.version_vector,
+            updated_at_ms: doc.updated_at_ms,
+        }))
+    }
+
+    /// Replace `doc.fields` with an `EncryptedEnvelope` under the
+    /// `__firelocal_enc_*` keys, leaving `path`/`version`/`version_vector`/
+    /// `updated_at_ms` in cleartext for the remote to route and order.
+    fn seal_document(&self, config: &EncryptionConfig, doc: &Document) -> Result<Document> {
+        let sealed = envelope::encrypt_fields(&config.master_key, &doc.path, &doc.fields)?;
+
+        let mut fields = serde_json::Map::new();
+        fields.insert(
+            ENC_NONCE_FIELD.to_string(),
+            serde_json::Value::String(sealed.nonce),
+        );
+        fields.insert(
+            ENC_CIPHERTEXT_FIELD.to_string(),
+            serde_json::Value::String(sealed.ciphertext),
+        );
+        fields.insert(
+            ENC_TAG_FIELD.to_string(),
+            serde_json::Value::String(sealed.tag),
+        );
+
+        Ok(Document {
+            path: doc.path.clone(),
+            fields,
+            version: doc.version,
+            version_vector: doc.version_vector.clone(),
+            updated_at_ms: doc.updated_at_ms,
+        })
+    }
+
+    /// Recover the `EncryptedEnvelope` `seal_document` stashed in `doc.fields`,
+    /// or `None` if `doc` isn't one (e.g. pre-existing plaintext data).
+    fn extract_envelope(doc: &Document) -> Option<EncryptedEnvelope> {
+        Some(EncryptedEnvelope {
+            nonce: doc.fields.get(ENC_NONCE_FIELD)?.as_str()?.to_string(),
+            ciphertext: doc.fields.get(ENC_CIPHERTEXT_FIELD)?.as_str()?.to_string(),
+            tag: doc.fields.get(ENC_TAG_FIELD)?.as_str()?.to_string(),
+        })
+    }
+
+    /// Start background sync. A real interval-driven loop would call
+    /// `sync_collection` per tracked prefix so each round only moves what
+    /// changed since the last one.
     pub async fn start_background_sync(&self) -> Result<()> {
         if self.mode != SyncMode::Background && self.mode != SyncMode::Batch {
             return Ok(());
@@ -145,19 +564,87 @@ impl EnhancedSyncManager {
         Ok(())
     }
 
-    /// Resolve conflict between local and remote documents
-    pub fn resolve_conflict(&self, local: &Document, remote: &Document) -> Document {
+    /// Resolve a conflict between local and remote documents. `base` is the
+    /// last-synced snapshot of this document the caller tracked before
+    /// local and remote diverged -- only `ConflictResolution::ThreeWayMerge`
+    /// uses it (and, lacking one on a first sync, degrades to
+    /// `LastWriteWins`); the other strategies ignore it.
+    pub fn resolve_conflict(
+        &self,
+        local: &Document,
+        remote: &Document,
+        base: Option<&Document>,
+    ) -> Document {
         match self.conflict_resolution {
-            ConflictResolution::LastWriteWins => {
-                // Compare versions or timestamps
-                if local.version >= remote.version {
-                    local.clone()
-                } else {
-                    remote.clone()
-                }
-            }
+            ConflictResolution::LastWriteWins => self.last_write_wins(local, remote),
             ConflictResolution::ClientWins => local.clone(),
             ConflictResolution::ServerWins => remote.clone(),
+            ConflictResolution::ThreeWayMerge => match base {
+                Some(base) => self.three_way_merge(local, remote, base),
+                None => self.last_write_wins(local, remote),
+            },
+        }
+    }
+
+    /// Compare versions to pick a whole-document winner -- the
+    /// `ConflictResolution::LastWriteWins` strategy, also reused as the
+    /// per-field tie-breaker inside `three_way_merge`.
+    fn last_write_wins(&self, local: &Document, remote: &Document) -> Document {
+        if local.version >= remote.version {
+            local.clone()
+        } else {
+            remote.clone()
+        }
+    }
+
+    /// Reconcile `local`/`remote`'s fields against `base`, key by key: a
+    /// key changed on only one side relative to `base` takes that side's
+    /// value; changed identically on both sides takes the shared value;
+    /// changed to different values on each side falls back to
+    /// `last_write_wins` and takes that side's value (or absence) for just
+    /// this key. A key present in `base` but absent from a side that didn't
+    /// otherwise touch it is a deletion, modeled the same way as any other
+    /// unilateral change. The merged document's version is always
+    /// `max(local.version, remote.version) + 1`, since it's a new edit that
+    /// causally succeeds both inputs.
+    fn three_way_merge(&self, local: &Document, remote: &Document, base: &Document) -> Document {
+        let keys: HashSet<&String> = base
+            .fields
+            .keys()
+            .chain(local.fields.keys())
+            .chain(remote.fields.keys())
+            .collect();
+
+        let tie_breaker = self.last_write_wins(local, remote);
+
+        let mut fields = serde_json::Map::new();
+        for key in keys {
+            let base_value = base.fields.get(key);
+            let local_value = local.fields.get(key);
+            let remote_value = remote.fields.get(key);
+
+            let local_changed = local_value != base_value;
+            let remote_changed = remote_value != base_value;
+
+            let resolved = match (local_changed, remote_changed) {
+                (false, false) => base_value.cloned(),
+                (true, false) => local_value.cloned(),
+                (false, true) => remote_value.cloned(),
+                (true, true) if local_value == remote_value => local_value.cloned(),
+                (true, true) => tie_breaker.fields.get(key).cloned(),
+            };
+
+            if let Some(value) = resolved {
+                fields.insert(key.clone(), value);
+            }
+        }
+
+        Document {
+            path: local.path.clone(),
+            fields,
+            version: local.version.max(remote.version) + 1,
+            version_vector: merge_vectors(&local.version_vector, &remote.version_vector),
+            updated_at_ms: local.updated_at_ms.max(remote.updated_at_ms),
         }
     }
 }
@@ -187,15 +674,145 @@ mod tests {
             path: "test".to_string(),
             fields: serde_json::Map::new(),
             version: 2,
+            ..Default::default()
         };
 
         let remote = Document {
             path: "test".to_string(),
             fields: serde_json::Map::new(),
             version: 1,
+            ..Default::default()
         };
 
-        let result = manager.resolve_conflict(&local, &remote);
+        let result = manager.resolve_conflict(&local, &remote, None);
         assert_eq!(result.version, 2); // Local wins (higher version)
     }
+
+    fn doc(version: u64, fields: &[(&str, &str)]) -> Document {
+        let mut map = serde_json::Map::new();
+        for (key, value) in fields {
+            map.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+        }
+        Document {
+            path: "test".to_string(),
+            fields: map,
+            version,
+            ..Default::default()
+        }
+    }
+
+    fn three_way_manager() -> EnhancedSyncManager {
+        use crate::sync::MockRemoteStore;
+
+        let mut manager = EnhancedSyncManager::new(
+            Box::new(MockRemoteStore),
+            SyncMode::Manual,
+            Duration::from_secs(300),
+        );
+        manager.set_conflict_resolution(ConflictResolution::ThreeWayMerge);
+        manager
+    }
+
+    #[test]
+    fn test_three_way_merge_without_base_degrades_to_last_write_wins() {
+        let manager = three_way_manager();
+        let local = doc(2, &[("name", "Alice")]);
+        let remote = doc(1, &[("name", "Alicia")]);
+
+        let result = manager.resolve_conflict(&local, &remote, None);
+        assert_eq!(result.fields.get("name").unwrap(), "Alice");
+    }
+
+    #[test]
+    fn test_three_way_merge_keeps_disjoint_edits_from_both_sides() {
+        let manager = three_way_manager();
+        let base = doc(1, &[("name", "Alice"), ("city", "NYC")]);
+        let local = doc(1, &[("name", "Alice"), ("city", "Boston")]);
+        let remote = doc(1, &[("name", "Alicia"), ("city", "NYC")]);
+
+        let result = manager.resolve_conflict(&local, &remote, Some(&base));
+        assert_eq!(result.fields.get("name").unwrap(), "Alicia");
+        assert_eq!(result.fields.get("city").unwrap(), "Boston");
+        assert_eq!(result.version, 2); // max(1, 1) + 1
+    }
+
+    #[test]
+    fn test_three_way_merge_same_change_on_both_sides_is_not_a_conflict() {
+        let manager = three_way_manager();
+        let base = doc(1, &[("name", "Alice")]);
+        let local = doc(1, &[("name", "Alicia")]);
+        let remote = doc(1, &[("name", "Alicia")]);
+
+        let result = manager.resolve_conflict(&local, &remote, Some(&base));
+        assert_eq!(result.fields.get("name").unwrap(), "Alicia");
+    }
+
+    #[test]
+    fn test_three_way_merge_conflicting_edit_falls_back_to_version_tie_breaker() {
+        let manager = three_way_manager();
+        let base = doc(1, &[("name", "Alice")]);
+        let local = doc(2, &[("name", "Al")]);
+        let remote = doc(1, &[("name", "Alicia")]);
+
+        // Both sides changed "name" to different values -- local has the
+        // higher version, so its value wins for that field.
+        let result = manager.resolve_conflict(&local, &remote, Some(&base));
+        assert_eq!(result.fields.get("name").unwrap(), "Al");
+    }
+
+    #[test]
+    fn test_three_way_merge_deletion_on_one_side_is_kept() {
+        let manager = three_way_manager();
+        let base = doc(1, &[("name", "Alice"), ("nickname", "Al")]);
+        let local = doc(1, &[("name", "Alice")]); // deleted "nickname"
+        let remote = doc(1, &[("name", "Alice"), ("nickname", "Al")]);
+
+        let result = manager.resolve_conflict(&local, &remote, Some(&base));
+        assert!(result.fields.get("nickname").is_none());
+    }
+
+    fn encrypting_manager() -> EnhancedSyncManager {
+        use crate::sync::MockRemoteStore;
+
+        let mut manager = EnhancedSyncManager::new(
+            Box::new(MockRemoteStore),
+            SyncMode::Manual,
+            Duration::from_secs(300),
+        );
+        manager.set_encryption_config(Some(EncryptionConfig::new([5u8; 32])));
+        manager
+    }
+
+    #[test]
+    fn test_seal_document_round_trips_through_extract_envelope() {
+        let manager = encrypting_manager();
+        let config = EncryptionConfig::new([5u8; 32]);
+        let original = doc(1, &[("name", "Alice")]);
+
+        let sealed = manager.seal_document(&config, &original).unwrap();
+        assert_eq!(sealed.path, original.path);
+        assert_eq!(sealed.version, original.version);
+
+        let envelope = EnhancedSyncManager::extract_envelope(&sealed).unwrap();
+        let recovered = envelope::decrypt_fields(&config.master_key, &sealed.path, &envelope).unwrap();
+        assert_eq!(recovered, original.fields);
+    }
+
+    #[test]
+    fn test_seal_document_hides_plaintext_fields() {
+        let manager = encrypting_manager();
+        let config = EncryptionConfig::new([5u8; 32]);
+        let original = doc(1, &[("ssn", "123-45-6789")]);
+
+        let sealed = manager.seal_document(&config, &original).unwrap();
+        let serialized = serde_json::to_string(&sealed.fields).unwrap();
+        assert!(!serialized.contains("123-45-6789"));
+    }
+
+    #[test]
+    fn test_extract_envelope_returns_none_for_plaintext_document() {
+        let plain = doc(1, &[("name", "Alice")]);
+        assert!(EnhancedSyncManager::extract_envelope(&plain).is_none());
+    }
+
 }
@@ -0,0 +1,197 @@
+//! AES-256-GCM envelope encryption for sync payloads, so
+//! `EnhancedSyncManager` never hands a `RemoteStore` a document's plaintext
+//! `fields` once `EncryptionConfig` is set. Mirrors `crate::store::encryption`'s
+//! at-rest scheme (AES-256-GCM, one independently random nonce per record)
+//! but derives a distinct key per document path via HKDF, so a key leaked
+//! or brute-forced for one path doesn't expose any other path's data under
+//! the same master key. `path` and `version` stay outside the envelope in
+//! cleartext -- an untrusted remote still needs to route and order
+//! records it can't read. This follows the BSO crypto model in Firefox's
+//! sync15: an encrypted blob plus an integrity tag.
+
+use crate::error::{FireLocalError, Result};
+use crate::logging::log_security_event;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// An encrypted `Document.fields` payload, safe to hand to an untrusted
+/// `RemoteStore`: `nonce`/`ciphertext`/`tag` reveal nothing about the
+/// document's contents without the per-path key `derive_document_key`
+/// produces from the sync manager's master key. Hex-encoded so the triple
+/// fits into a `Document.fields` map, which only holds JSON values.
+#[derive(Debug, Clone)]
+pub struct EncryptedEnvelope {
+    pub nonce: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+/// Derive the 32-byte AES-256-GCM key for `path` from `master_key` via
+/// HKDF-SHA256, using `path` as the info parameter -- every document path
+/// gets an independent key, so compromising one doesn't expose any other
+/// path under the same master key.
+pub fn derive_document_key(master_key: &[u8; 32], path: &str) -> [u8; 32] {
+    let hk = Hkdf::<Sha256>::new(None, master_key);
+    let mut key = [0u8; 32];
+    hk.expand(path.as_bytes(), &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+/// Encrypt `fields` (serialized to JSON) for `path` under `master_key`,
+/// with a fresh random nonce.
+pub fn encrypt_fields(
+    master_key: &[u8; 32],
+    path: &str,
+    fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<EncryptedEnvelope> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&derive_document_key(
+        master_key, path,
+    )));
+
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let plaintext = serde_json::to_vec(fields)?;
+    let mut sealed = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|_| FireLocalError::Security("AES-256-GCM encryption failed".to_string()))?;
+
+    // `aes_gcm` appends the tag to the ciphertext; split it back off so the
+    // envelope carries it as its own field, matching the wire shape the
+    // request asks for.
+    let tag = sealed.split_off(sealed.len() - TAG_LEN);
+
+    Ok(EncryptedEnvelope {
+        nonce: hex_encode(&nonce),
+        ciphertext: hex_encode(&sealed),
+        tag: hex_encode(&tag),
+    })
+}
+
+/// Decrypt an `EncryptedEnvelope` produced by `encrypt_fields` for `path`,
+/// verifying its GCM tag before returning the recovered fields. Any tag
+/// mismatch -- tampering, a corrupt remote record, or the wrong key --
+/// is logged as `SYNC_DECRYPTION_FAILED` and reported as a
+/// `FireLocalError::Security` rather than parsed, so a caller never
+/// mistakes an unauthenticated payload for real data.
+pub fn decrypt_fields(
+    master_key: &[u8; 32],
+    path: &str,
+    envelope: &EncryptedEnvelope,
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&derive_document_key(
+        master_key, path,
+    )));
+
+    let nonce = hex_decode(&envelope.nonce)
+        .map_err(|_| FireLocalError::Security("malformed envelope nonce".to_string()))?;
+    if nonce.len() != NONCE_LEN {
+        return Err(FireLocalError::Security("malformed envelope nonce".to_string()));
+    }
+    let mut sealed = hex_decode(&envelope.ciphertext)
+        .map_err(|_| FireLocalError::Security("malformed envelope ciphertext".to_string()))?;
+    let tag = hex_decode(&envelope.tag)
+        .map_err(|_| FireLocalError::Security("malformed envelope tag".to_string()))?;
+    if tag.len() != TAG_LEN {
+        return Err(FireLocalError::Security("malformed envelope tag".to_string()));
+    }
+    sealed.extend(tag);
+
+    let plaintext = cipher.decrypt(Nonce::from_slice(&nonce), sealed.as_ref()).map_err(|_| {
+        log_security_event(
+            "SYNC_DECRYPTION_FAILED",
+            &format!("AES-256-GCM tag mismatch for '{path}'"),
+        );
+        FireLocalError::Security(format!("failed to decrypt sync payload for '{path}' (tag mismatch)"))
+    })?;
+
+    serde_json::from_slice(&plaintext).map_err(|e| {
+        FireLocalError::Serialization(format!("invalid JSON in decrypted sync payload: {e}"))
+    })
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Decode a hex string into bytes. Unlike naive byte-slicing, this never
+/// panics on attacker-controlled input: an odd length or a non-hex/non-ASCII
+/// character is reported as `Err` instead of indexing out of bounds or
+/// splitting a multi-byte `char` on a non-boundary.
+fn hex_decode(s: &str) -> std::result::Result<Vec<u8>, String> {
+    if s.len() % 2 != 0 {
+        return Err("odd-length hex string".to_string());
+    }
+    if !s.is_ascii() {
+        return Err("non-ASCII hex string".to_string());
+    }
+    let bytes = s.as_bytes();
+    (0..bytes.len())
+        .step_by(2)
+        .map(|i| {
+            let hi = (bytes[i] as char).to_digit(16);
+            let lo = (bytes[i + 1] as char).to_digit(16);
+            match (hi, lo) {
+                (Some(hi), Some(lo)) => Ok((hi * 16 + lo) as u8),
+                _ => Err("invalid hex digit".to_string()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> serde_json::Map<String, serde_json::Value> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_round_trips() {
+        let key = [7u8; 32];
+        let original = fields(&[("name", "Alice")]);
+        let envelope = encrypt_fields(&key, "users/alice", &original).unwrap();
+        let recovered = decrypt_fields(&key, "users/alice", &envelope).unwrap();
+        assert_eq!(recovered, original);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_ciphertext() {
+        let key = [7u8; 32];
+        let mut envelope =
+            encrypt_fields(&key, "users/alice", &fields(&[("name", "Alice")])).unwrap();
+        let mut bytes = hex_decode(&envelope.ciphertext).unwrap();
+        bytes[0] ^= 0xFF;
+        envelope.ciphertext = hex_encode(&bytes);
+        assert!(decrypt_fields(&key, "users/alice", &envelope).is_err());
+    }
+
+    #[test]
+    fn test_decrypt_rejects_mismatched_path() {
+        let key = [7u8; 32];
+        let envelope =
+            encrypt_fields(&key, "users/alice", &fields(&[("name", "Alice")])).unwrap();
+        assert!(decrypt_fields(&key, "users/bob", &envelope).is_err());
+    }
+
+    #[test]
+    fn test_derive_document_key_differs_per_path() {
+        let key = [3u8; 32];
+        assert_ne!(
+            derive_document_key(&key, "users/alice"),
+            derive_document_key(&key, "users/bob")
+        );
+    }
+}
@@ -3,6 +3,37 @@ use crate::sync::RemoteStore;
 use reqwest::blocking::Client;
 use serde_json::Value;
 use std::env;
+use std::time::Duration;
+
+/// Bounded exponential backoff for transient (429/5xx) batch-write failures.
+const BATCH_MAX_ATTEMPTS: u32 = 3;
+const BATCH_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const BATCH_MAX_DELAY: Duration = Duration::from_secs(30);
+const BATCH_BACKOFF_MULTIPLIER: f64 = 2.0;
+
+/// Result of a `push_batch` call: which documents made it to Firestore and
+/// which didn't, with the per-write status message Firestore reported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchOutcome {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+/// Per-write result parsed out of a `batchWrite` response's `status` array.
+enum WriteStatus {
+    Ok,
+    Failed(String),
+}
+
+/// Whether a batch-write attempt can be retried as-is.
+enum BatchWriteError {
+    /// The whole request failed transiently (network error, 429, 5xx) —
+    /// worth retrying the entire batch.
+    Transient(String),
+    /// The request failed in a way retrying won't fix (4xx other than 429,
+    /// or a malformed response).
+    Fatal(String),
+}
 
 pub struct FirebaseClient {
     client: Client,
@@ -78,11 +109,185 @@ impl RemoteStore for FirebaseClient {
                 path: path.to_string(),
                 fields: simple_fields,
                 version: 0,
+                ..Default::default()
             }));
         }
 
         Ok(None)
     }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut doc_names = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}/{}", self.base_url(), prefix);
+            if let Some(token) = &page_token {
+                url.push_str(&format!("?pageToken={token}"));
+            }
+
+            let mut req = self.client.get(&url);
+            if let Some(token) = &self.auth_token {
+                req = req.header("Authorization", format!("Bearer {}", token));
+            }
+
+            let resp = req.send().map_err(|e| e.to_string())?;
+            if !resp.status().is_success() {
+                return Err(format!("Remote error: {}", resp.status()));
+            }
+
+            let json: Value = resp.json().map_err(|e| e.to_string())?;
+            let documents_root = format!("{}/", self.base_url());
+            for doc in json.get("documents").and_then(Value::as_array).into_iter().flatten() {
+                if let Some(name) = doc.get("name").and_then(Value::as_str) {
+                    doc_names.push(name.strip_prefix(&documents_root).unwrap_or(name).to_string());
+                }
+            }
+
+            page_token = json
+                .get("nextPageToken")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if page_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(doc_names)
+    }
+
+    fn delete(&self, path: &str) -> Result<(), String> {
+        let url = format!("{}/{}", self.base_url(), path);
+        let mut req = self.client.delete(&url);
+        if let Some(token) = &self.auth_token {
+            req = req.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let resp = req.send().map_err(|e| e.to_string())?;
+        if resp.status().is_success() || resp.status() == 404 {
+            Ok(())
+        } else {
+            Err(format!("Remote error: {}", resp.status()))
+        }
+    }
+}
+
+impl FirebaseClient {
+    /// Push many documents in one request against Firestore's `batchWrite`
+    /// endpoint instead of one PATCH per document. Unlike `push`, a single
+    /// document failing doesn't fail the whole call: the per-write status
+    /// array is parsed into a `BatchOutcome` naming exactly which documents
+    /// succeeded and which didn't (with Firestore's status message). The
+    /// request as a whole is retried with bounded exponential backoff on
+    /// transient `429`/`5xx` responses.
+    pub fn push_batch(&self, docs: &[Document]) -> Result<BatchOutcome, String> {
+        if docs.is_empty() {
+            return Ok(BatchOutcome::default());
+        }
+
+        let url = format!("{}:batchWrite", self.base_url());
+        let writes: Vec<Value> = docs
+            .iter()
+            .map(|doc| {
+                serde_json::json!({
+                    "update": {
+                        "name": format!("{}/{}", self.base_url(), doc.path),
+                        "fields": map_to_firestore_json(&doc.fields),
+                    }
+                })
+            })
+            .collect();
+        let body = serde_json::json!({ "writes": writes });
+
+        let mut delay = BATCH_INITIAL_DELAY;
+
+        for attempt in 0..BATCH_MAX_ATTEMPTS {
+            match self.try_batch_write(&url, &body) {
+                Ok(statuses) => return Ok(Self::outcome_from_statuses(docs, statuses)),
+                Err(BatchWriteError::Fatal(msg)) => return Err(msg),
+                Err(BatchWriteError::Transient(msg)) => {
+                    if attempt + 1 == BATCH_MAX_ATTEMPTS {
+                        return Err(format!(
+                            "batchWrite failed after {BATCH_MAX_ATTEMPTS} attempts: {msg}"
+                        ));
+                    }
+                    std::thread::sleep(delay);
+                    delay = Duration::from_secs_f64(
+                        (delay.as_secs_f64() * BATCH_BACKOFF_MULTIPLIER)
+                            .min(BATCH_MAX_DELAY.as_secs_f64()),
+                    );
+                }
+            }
+        }
+
+        unreachable!("loop above always returns by its last iteration")
+    }
+
+    fn try_batch_write(&self, url: &str, body: &Value) -> Result<Vec<WriteStatus>, BatchWriteError> {
+        let mut req = self.client.post(url).json(body);
+        if let Some(token) = &self.auth_token {
+            req = req.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let resp = req
+            .send()
+            .map_err(|e| BatchWriteError::Transient(e.to_string()))?;
+        let status = resp.status();
+
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(BatchWriteError::Transient(format!("HTTP {status}")));
+        }
+        if !status.is_success() {
+            return Err(BatchWriteError::Fatal(format!("Remote error: {status}")));
+        }
+
+        let json: Value = resp
+            .json()
+            .map_err(|e| BatchWriteError::Fatal(format!("invalid batchWrite response: {e}")))?;
+
+        let statuses = json
+            .get("status")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(statuses
+            .into_iter()
+            .map(|s| {
+                let code = s.get("code").and_then(Value::as_i64).unwrap_or(0);
+                if code == 0 {
+                    WriteStatus::Ok
+                } else {
+                    let message = s
+                        .get("message")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown error")
+                        .to_string();
+                    WriteStatus::Failed(message)
+                }
+            })
+            .collect())
+    }
+
+    fn outcome_from_statuses(docs: &[Document], statuses: Vec<WriteStatus>) -> BatchOutcome {
+        let mut outcome = BatchOutcome::default();
+
+        for (i, doc) in docs.iter().enumerate() {
+            match statuses.get(i) {
+                Some(WriteStatus::Ok) => outcome.succeeded.push(doc.path.clone()),
+                Some(WriteStatus::Failed(message)) => {
+                    outcome.failed.push((doc.path.clone(), message.clone()))
+                }
+                // Firestore should return one status per write; if it
+                // returned fewer, don't silently assume success.
+                None => outcome
+                    .failed
+                    .push((doc.path.clone(), "no status returned".to_string())),
+            }
+        }
+
+        outcome
+    }
 }
 
 fn map_to_firestore_json(
@@ -1,20 +1,77 @@
+pub mod conflict;
 pub mod enhanced;
+pub mod envelope;
 pub mod firebase;
+pub mod object_store_remote;
 
 use crate::model::Document;
+use crate::sync::conflict::{ConflictResolver, LastWriteWinsResolver, VectorOrdering};
 
 pub trait RemoteStore: Send + Sync {
     fn push(&self, doc: &Document) -> Result<(), String>;
     fn pull(&self, path: &str) -> Result<Option<Document>, String>;
+    /// List the paths of every object whose key starts with `prefix`, so a
+    /// whole collection can be synced instead of pulling documents one at a
+    /// time by hand-enumerated path.
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String>;
+    /// Remove the remote object at `path`, if any.
+    fn delete(&self, path: &str) -> Result<(), String>;
+
+    /// Fetch every document under `prefix` that changed since `cursor`
+    /// (`None` means "from the beginning"), plus an opaque cursor that
+    /// captures this call, for `EnhancedSyncManager`'s incremental sync to
+    /// pass back on the next round.
+    ///
+    /// The default implementation has no real changefeed to draw on, so it
+    /// falls back to a full `list` + `pull` of `prefix` and filters by the
+    /// document version encoded in the cursor -- correct, but no cheaper
+    /// than a full rescan. A backend with an actual changes-since primitive
+    /// (a remote revision log, an object store's generation tokens, etc.)
+    /// should override this for a real incremental fetch.
+    fn pull_since(
+        &self,
+        prefix: &str,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<Document>, Option<String>), String> {
+        let since_version: u64 = cursor.and_then(|c| c.parse().ok()).unwrap_or(0);
+
+        let mut docs = Vec::new();
+        let mut max_version = since_version;
+        for path in self.list(prefix)? {
+            let Some(doc) = self.pull(&path)? else {
+                continue;
+            };
+            if doc.version > since_version {
+                max_version = max_version.max(doc.version);
+                docs.push(doc);
+            }
+        }
+
+        Ok((docs, Some(max_version.to_string())))
+    }
 }
 
 pub struct SyncManager {
     remote: Box<dyn RemoteStore>,
+    resolver: Box<dyn ConflictResolver>,
 }
 
 impl SyncManager {
     pub fn new(remote: Box<dyn RemoteStore>) -> Self {
-        Self { remote }
+        Self {
+            remote,
+            resolver: Box::new(LastWriteWinsResolver),
+        }
+    }
+
+    /// Build a `SyncManager` that resolves concurrent edits with `resolver`
+    /// instead of the default last-writer-wins behavior.
+    pub fn with_resolver(remote: Box<dyn RemoteStore>, resolver: Box<dyn ConflictResolver>) -> Self {
+        Self { remote, resolver }
+    }
+
+    pub fn set_resolver(&mut self, resolver: Box<dyn ConflictResolver>) {
+        self.resolver = resolver;
     }
 
     pub fn push(&self, doc: &Document) -> Result<(), String> {
@@ -24,6 +81,47 @@ impl SyncManager {
     pub fn pull(&self, path: &str) -> Result<Option<Document>, String> {
         self.remote.pull(path)
     }
+
+    pub fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        self.remote.list(prefix)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<(), String> {
+        self.remote.delete(path)
+    }
+
+    /// Reconcile a freshly pulled `remote` document against the local copy
+    /// (if any) by comparing version vectors: if one side causally dominates
+    /// the other, it wins outright; if the edits are concurrent, the
+    /// configured `ConflictResolver` decides, and the result's vector is the
+    /// element-wise merge of both so the next pull sees it as dominating
+    /// both its parents.
+    pub fn reconcile(&self, local: Option<&Document>, remote: Document) -> Document {
+        let Some(local) = local else {
+            return remote;
+        };
+
+        match conflict::compare(&local.version_vector, &remote.version_vector) {
+            VectorOrdering::Before | VectorOrdering::Equal => remote,
+            VectorOrdering::After => local.clone(),
+            VectorOrdering::Concurrent => {
+                let mut resolved = self.resolver.resolve(local, &remote);
+                resolved.version_vector =
+                    conflict::merge_vectors(&local.version_vector, &remote.version_vector);
+                resolved
+            }
+        }
+    }
+}
+
+/// Outcome of `FireLocal::sync_push_all`/`sync_pull_all`: how many documents
+/// were pushed, pulled, or deleted to reconcile the local and remote
+/// keyspaces under a prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub pushed: usize,
+    pub pulled: usize,
+    pub deleted: usize,
 }
 
 pub struct MockRemoteStore;
@@ -36,4 +134,12 @@ impl RemoteStore for MockRemoteStore {
     fn pull(&self, _path: &str) -> Result<Option<Document>, String> {
         Ok(None)
     }
+
+    fn list(&self, _prefix: &str) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
+    fn delete(&self, _path: &str) -> Result<(), String> {
+        Ok(())
+    }
 }
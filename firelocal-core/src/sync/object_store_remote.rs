@@ -0,0 +1,93 @@
+use crate::model::Document;
+use crate::sync::RemoteStore;
+use object_store::path::Path as ObjectPath;
+use object_store::{ObjectStore, PutPayload};
+use std::sync::Arc;
+
+/// A `RemoteStore` layered over the `object_store` crate's `ObjectStore`
+/// trait, so S3, GCS, Azure Blob, and local-filesystem backends are all
+/// supported by this one implementation instead of a bespoke HTTP client per
+/// provider. Each `Document` maps to an object keyed by its `path`,
+/// serialized as JSON.
+///
+/// `object_store`'s API is async; `RemoteStore` is not, so each method
+/// blocks on a private `tokio::runtime::Runtime` the way the rest of the
+/// crate bridges sync call sites into async work.
+pub struct ObjectStoreRemote {
+    store: Arc<dyn ObjectStore>,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl ObjectStoreRemote {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            runtime: tokio::runtime::Runtime::new().expect("failed to start object_store runtime"),
+        }
+    }
+}
+
+impl RemoteStore for ObjectStoreRemote {
+    fn push(&self, doc: &Document) -> Result<(), String> {
+        let path = ObjectPath::from(doc.path.as_str());
+        let body = doc.to_json().map_err(|e| e.to_string())?;
+
+        self.runtime.block_on(async {
+            self.store
+                .put(&path, PutPayload::from(body.into_bytes()))
+                .await
+                .map_err(|e| e.to_string())
+        })?;
+        Ok(())
+    }
+
+    fn pull(&self, path: &str) -> Result<Option<Document>, String> {
+        let object_path = ObjectPath::from(path);
+
+        self.runtime.block_on(async {
+            let result = match self.store.get(&object_path).await {
+                Ok(result) => result,
+                Err(object_store::Error::NotFound { .. }) => return Ok(None),
+                Err(e) => return Err(e.to_string()),
+            };
+
+            let bytes = result.bytes().await.map_err(|e| e.to_string())?;
+            let body = std::str::from_utf8(&bytes).map_err(|e| e.to_string())?;
+            let doc = Document::from_json(body).map_err(|e| e.to_string())?;
+            Ok(Some(doc))
+        })
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let object_prefix = if prefix.is_empty() {
+            None
+        } else {
+            Some(ObjectPath::from(prefix))
+        };
+
+        self.runtime.block_on(async {
+            use futures::TryStreamExt;
+
+            let paths: Vec<String> = self
+                .store
+                .list(object_prefix.as_ref())
+                .map_ok(|meta| meta.location.to_string())
+                .try_collect()
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(paths)
+        })
+    }
+
+    fn delete(&self, path: &str) -> Result<(), String> {
+        let object_path = ObjectPath::from(path);
+
+        self.runtime.block_on(async {
+            match self.store.delete(&object_path).await {
+                Ok(()) => Ok(()),
+                Err(object_store::Error::NotFound { .. }) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            }
+        })
+    }
+}
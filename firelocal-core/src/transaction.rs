@@ -2,21 +2,85 @@ use crate::store::io::Storage;
 use crate::store::memtable::Memtable;
 use crate::store::wal::{WalEntry, WalOp, WriteAheadLog};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// Default time a pessimistic transaction waits for a conflicting lock
+/// before aborting with `TransactionError::TransactionConflict`.
+pub const DEFAULT_LOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The column family a `BatchOperation` lands in when its `column` field is
+/// `None` -- the same keyspace `FireLocal::put`/`get`/`delete` use directly,
+/// as opposed to one opened via `put_cf`/`get_cf`/`delete_cf`.
+pub const DEFAULT_COLUMN_FAMILY: &str = "default";
+
 /// WriteBatch allows batching multiple write operations into a single atomic commit
 pub struct WriteBatch {
     operations: Vec<BatchOperation>,
     batch_id: String,
+    savepoints: Vec<usize>,
+    reads: Vec<String>,
+    conditions: Vec<BatchCondition>,
+}
+
+/// A precondition staged on a `WriteBatch` (via `check_version`/
+/// `set_if_absent`) that `FireLocal::commit_batch` checks against the
+/// store's current state before applying any of the batch's writes -- if
+/// any condition fails, none of the batch's operations are applied.
+#[derive(Debug, Clone)]
+pub enum BatchCondition {
+    /// Fails unless the document at `path` is currently at exactly
+    /// `expected_version` (see `Document::version`). A path with no
+    /// document, or whose bytes aren't a valid `Document`, counts as
+    /// version 0.
+    CheckVersion { path: String, expected_version: u64 },
+    /// Fails unless no document currently exists at `path` -- the
+    /// precondition behind `WriteBatch::set_if_absent`.
+    NotExists { path: String },
+}
+
+/// Returned by `FireLocal::commit_batch`: the values this batch's staged
+/// `get` reads held at the commit snapshot, i.e. immediately before any of
+/// the batch's own writes were applied. Lets a caller combine a read with a
+/// conditional write in one atomic round trip instead of racing a separate
+/// get/put.
+#[derive(Debug, Clone, Default)]
+pub struct BatchCommitResult {
+    pub reads: HashMap<String, Option<Vec<u8>>>,
 }
 
+/// A single operation staged in a `WriteBatch` or `Transaction`. `column`
+/// selects which column family (see `crate::store::column_family`) the
+/// operation lands in -- `None` means `DEFAULT_COLUMN_FAMILY`, the keyspace
+/// `FireLocal::put`/`get`/`delete` use directly.
 #[derive(Debug, Clone)]
 pub enum BatchOperation {
-    Set { path: String, data: Vec<u8> },
-    Update { path: String, data: Vec<u8> },
-    Delete { path: String },
+    Set {
+        path: String,
+        data: Vec<u8>,
+        column: Option<String>,
+    },
+    Update {
+        path: String,
+        data: Vec<u8>,
+        column: Option<String>,
+    },
+    Delete {
+        path: String,
+        column: Option<String>,
+    },
+    /// Appends `operand` to `path`'s pending merge operands instead of
+    /// overwriting it -- see `FireLocal::set_merge_operator` and
+    /// `FireLocal::merge`.
+    Merge {
+        path: String,
+        operand: Vec<u8>,
+        column: Option<String>,
+    },
 }
 
 impl WriteBatch {
@@ -25,24 +89,149 @@ impl WriteBatch {
         Self {
             operations: Vec::new(),
             batch_id: Uuid::new_v4().to_string(),
+            savepoints: Vec::new(),
+            reads: Vec::new(),
+            conditions: Vec::new(),
         }
     }
 
-    /// Add a set operation to the batch
+    /// Mark the current end of `operations` as a savepoint. A later
+    /// `rollback_to_savepoint` discards every operation staged since this
+    /// call; a later `pop_savepoint` just forgets the marker. Savepoints
+    /// nest: rolling back or popping always affects the most recently set
+    /// one first.
+    pub fn set_savepoint(&mut self) {
+        self.savepoints.push(self.operations.len());
+    }
+
+    /// Discard every operation staged since the most recent `set_savepoint`
+    /// and forget that marker. A no-op if no savepoint is set.
+    pub fn rollback_to_savepoint(&mut self) {
+        if let Some(mark) = self.savepoints.pop() {
+            self.operations.truncate(mark);
+        }
+    }
+
+    /// Forget the most recent savepoint without rolling back the operations
+    /// staged since it. A no-op if no savepoint is set.
+    pub fn pop_savepoint(&mut self) {
+        self.savepoints.pop();
+    }
+
+    /// Add a set operation to the batch, in the default column family.
     pub fn set(&mut self, path: String, data: Vec<u8>) -> &mut Self {
-        self.operations.push(BatchOperation::Set { path, data });
+        self.operations.push(BatchOperation::Set {
+            path,
+            data,
+            column: None,
+        });
         self
     }
 
-    /// Add an update operation to the batch
+    /// Like `set`, but in the named column family instead of the default.
+    pub fn set_cf(&mut self, column: impl Into<String>, path: String, data: Vec<u8>) -> &mut Self {
+        self.operations.push(BatchOperation::Set {
+            path,
+            data,
+            column: Some(column.into()),
+        });
+        self
+    }
+
+    /// Add an update operation to the batch, in the default column family.
     pub fn update(&mut self, path: String, data: Vec<u8>) -> &mut Self {
-        self.operations.push(BatchOperation::Update { path, data });
+        self.operations.push(BatchOperation::Update {
+            path,
+            data,
+            column: None,
+        });
         self
     }
 
-    /// Add a delete operation to the batch
+    /// Like `update`, but in the named column family instead of the default.
+    pub fn update_cf(&mut self, column: impl Into<String>, path: String, data: Vec<u8>) -> &mut Self {
+        self.operations.push(BatchOperation::Update {
+            path,
+            data,
+            column: Some(column.into()),
+        });
+        self
+    }
+
+    /// Add a delete operation to the batch, in the default column family.
     pub fn delete(&mut self, path: String) -> &mut Self {
-        self.operations.push(BatchOperation::Delete { path });
+        self.operations.push(BatchOperation::Delete {
+            path,
+            column: None,
+        });
+        self
+    }
+
+    /// Like `delete`, but in the named column family instead of the default.
+    pub fn delete_cf(&mut self, column: impl Into<String>, path: String) -> &mut Self {
+        self.operations.push(BatchOperation::Delete {
+            path,
+            column: Some(column.into()),
+        });
+        self
+    }
+
+    /// Add a merge operation to the batch, in the default column family --
+    /// see `BatchOperation::Merge`.
+    pub fn merge(&mut self, path: String, operand: Vec<u8>) -> &mut Self {
+        self.operations.push(BatchOperation::Merge {
+            path,
+            operand,
+            column: None,
+        });
+        self
+    }
+
+    /// Like `merge`, but in the named column family instead of the default.
+    pub fn merge_cf(&mut self, column: impl Into<String>, path: String, operand: Vec<u8>) -> &mut Self {
+        self.operations.push(BatchOperation::Merge {
+            path,
+            operand,
+            column: Some(column.into()),
+        });
+        self
+    }
+
+    /// Stage a read of `path`, resolved against the store's state at this
+    /// batch's commit snapshot (i.e. before any of the batch's own writes
+    /// are applied) and returned via `BatchCommitResult::reads` once
+    /// `FireLocal::commit_batch` succeeds.
+    pub fn get(&mut self, path: String) -> &mut Self {
+        self.reads.push(path);
+        self
+    }
+
+    /// Stage a `BatchCondition::CheckVersion` precondition: `commit_batch`
+    /// fails atomically (no batch writes applied) unless `path`'s document
+    /// is currently at `expected_version`. Lets a caller build optimistic
+    /// concurrency on top of `Document::version` instead of racing a
+    /// separate get/put.
+    pub fn check_version(&mut self, path: String, expected_version: u64) -> &mut Self {
+        self.conditions.push(BatchCondition::CheckVersion {
+            path,
+            expected_version,
+        });
+        self
+    }
+
+    /// Stage a set that only takes effect if `path` has no document yet:
+    /// `commit_batch` fails atomically if one already exists. Combines a
+    /// `BatchCondition::NotExists` precondition with an ordinary
+    /// `BatchOperation::Set`, in the default column family.
+    pub fn set_if_absent(&mut self, path: String, data: Vec<u8>) -> &mut Self {
+        self.conditions.push(BatchCondition::NotExists {
+            path: path.clone(),
+        });
+        self.operations.push(BatchOperation::Set {
+            path,
+            data,
+            column: None,
+        });
         self
     }
 
@@ -56,6 +245,16 @@ impl WriteBatch {
         &self.operations
     }
 
+    /// Paths staged via `get`.
+    pub fn reads(&self) -> &[String] {
+        &self.reads
+    }
+
+    /// Preconditions staged via `check_version`/`set_if_absent`.
+    pub fn conditions(&self) -> &[BatchCondition] {
+        &self.conditions
+    }
+
     /// Get the number of operations
     pub fn len(&self) -> usize {
         self.operations.len()
@@ -73,51 +272,392 @@ impl Default for WriteBatch {
     }
 }
 
-/// Transaction provides read-write transaction support with optimistic concurrency
+/// Error produced by a pessimistic (locking) transaction. Kept distinct from
+/// the `anyhow::Error` the optimistic path raises on `validate` failure
+/// because callers may want to tell "someone else is holding this lock right
+/// now" apart from "the wait timed out" -- e.g. to retry the former
+/// immediately but back off on the latter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionError {
+    /// Another live transaction holds an incompatible lock on `path` and the
+    /// caller asked not to wait for it (a zero lock-wait timeout).
+    WouldBlock { path: String },
+    /// A lock on `path` could not be acquired within the transaction's
+    /// lock-wait timeout. Returned instead of blocking forever so a
+    /// deadlocked pair of transactions each abort rather than hang.
+    TransactionConflict { path: String },
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::WouldBlock { path } => {
+                write!(f, "would block acquiring a lock on '{path}'")
+            }
+            TransactionError::TransactionConflict { path } => {
+                write!(f, "transaction conflict: timed out waiting for a lock on '{path}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+/// Whether a lock is held for reading (compatible with other shared holders)
+/// or writing (exclusive of every other holder).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    Shared,
+    Exclusive,
+}
+
+/// The lock state for a single document path: the set of transactions
+/// currently holding it, and whether that holder set is a single exclusive
+/// writer or any number of shared readers.
+#[derive(Debug, Default)]
+struct PathLock {
+    holders: HashSet<String>,
+    exclusive: bool,
+}
+
+/// Process-wide table of per-document-path locks for pessimistic
+/// transactions, modeled on RocksDB's `TransactionDB` lock manager. Unlike
+/// the optimistic path (which records read versions and checks them in
+/// `Transaction::validate` at commit time), a pessimistic transaction calls
+/// into this manager as it reads/writes, so conflicts are caught -- and
+/// either waited out or reported -- at the point of access instead of at
+/// commit.
+#[derive(Default)]
+pub struct LockManager {
+    locks: Mutex<HashMap<String, PathLock>>,
+    released: Condvar,
+}
+
+impl LockManager {
+    pub fn new() -> Self {
+        Self {
+            locks: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Acquire `mode` on `path` for `transaction_id`, waiting up to `timeout`
+    /// for a conflicting holder to release it first. A transaction that
+    /// already holds `path` (in either mode) is always compatible with
+    /// itself, so re-reading or upgrading to a write within the same
+    /// transaction never blocks on its own earlier lock.
+    ///
+    /// `timeout == Duration::ZERO` never waits: it returns
+    /// `TransactionError::WouldBlock` immediately on the first conflict.
+    /// Any longer timeout blocks until the lock is free or the deadline
+    /// passes, at which point it returns `TransactionError::TransactionConflict`
+    /// instead of waiting forever -- the guard against deadlocking on a
+    /// mutual wait.
+    fn acquire(&self, path: &str, transaction_id: &str, mode: LockMode, timeout: Duration) -> Result<(), TransactionError> {
+        let deadline = Instant::now() + timeout;
+        let mut locks = self.locks.lock().unwrap();
+        loop {
+            let compatible = match locks.get(path) {
+                None => true,
+                Some(lock) if lock.holders.is_empty() => true,
+                Some(lock) if lock.holders.len() == 1 && lock.holders.contains(transaction_id) => true,
+                Some(lock) => !lock.exclusive && mode == LockMode::Shared,
+            };
+
+            if compatible {
+                let entry = locks.entry(path.to_string()).or_default();
+                entry.holders.insert(transaction_id.to_string());
+                entry.exclusive = mode == LockMode::Exclusive;
+                return Ok(());
+            }
+
+            if timeout.is_zero() {
+                return Err(TransactionError::WouldBlock { path: path.to_string() });
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(TransactionError::TransactionConflict { path: path.to_string() });
+            }
+
+            let (guard, wait_result) = self
+                .released
+                .wait_timeout(locks, deadline - now)
+                .unwrap();
+            locks = guard;
+            if wait_result.timed_out() {
+                return Err(TransactionError::TransactionConflict { path: path.to_string() });
+            }
+        }
+    }
+
+    /// Release `transaction_id`'s hold on `path`, if any. A no-op if the
+    /// transaction doesn't hold it -- callers release every path they ever
+    /// locked without tracking which acquisitions actually succeeded.
+    fn release(&self, path: &str, transaction_id: &str) {
+        let mut locks = self.locks.lock().unwrap();
+        if let Some(lock) = locks.get_mut(path) {
+            lock.holders.remove(transaction_id);
+            if lock.holders.is_empty() {
+                locks.remove(path);
+            }
+        }
+        drop(locks);
+        self.released.notify_all();
+    }
+}
+
+/// RAII guard that releases every lock a pessimistic transaction acquired
+/// when the transaction commits, rolls back, or is simply dropped --
+/// whichever happens first releases them, and the other two become no-ops
+/// since `held` is already drained.
+struct TransactionLockGuard {
+    manager: Arc<LockManager>,
+    transaction_id: String,
+    held: Vec<String>,
+}
+
+impl TransactionLockGuard {
+    fn new(manager: Arc<LockManager>, transaction_id: String) -> Self {
+        Self {
+            manager,
+            transaction_id,
+            held: Vec::new(),
+        }
+    }
+
+    fn acquire(&mut self, path: &str, mode: LockMode, timeout: Duration) -> Result<(), TransactionError> {
+        self.manager.acquire(path, &self.transaction_id, mode, timeout)?;
+        self.held.push(path.to_string());
+        Ok(())
+    }
+
+    fn release_all(&mut self) {
+        for path in self.held.drain(..) {
+            self.manager.release(&path, &self.transaction_id);
+        }
+    }
+}
+
+impl Drop for TransactionLockGuard {
+    fn drop(&mut self) {
+        self.release_all();
+    }
+}
+
+/// How a `Transaction` detects conflicting concurrent writes: either by
+/// recording read versions and checking them at commit (`Optimistic`), or by
+/// taking locks eagerly as documents are read/written (`Pessimistic`).
+enum ConcurrencyMode {
+    Optimistic,
+    Pessimistic {
+        guard: TransactionLockGuard,
+        lock_timeout: Duration,
+    },
+}
+
+/// Transaction provides read-write transaction support, in either of two
+/// concurrency modes (see `ConcurrencyMode`): optimistic (the default,
+/// `Transaction::new`) or pessimistic locking (`Transaction::new_pessimistic`).
 pub struct Transaction {
     reads: HashMap<String, Option<(Vec<u8>, u64)>>, // path -> (data, version)
     writes: Vec<BatchOperation>,
     transaction_id: String,
+    mode: ConcurrencyMode,
+    savepoints: Vec<usize>,
 }
 
 impl Transaction {
-    /// Create a new transaction
+    /// Create a new transaction using optimistic concurrency control.
     pub fn new() -> Self {
         Self {
             reads: HashMap::new(),
             writes: Vec::new(),
             transaction_id: Uuid::new_v4().to_string(),
+            mode: ConcurrencyMode::Optimistic,
+            savepoints: Vec::new(),
         }
     }
 
-    /// Read a document in the transaction
+    /// Create a transaction that acquires locks eagerly from `lock_manager`
+    /// instead of validating read versions at commit. `get` takes a shared
+    /// lock on the path it reads; `set`/`update`/`delete` take an exclusive
+    /// lock. Each acquisition waits up to `lock_timeout` for a conflicting
+    /// transaction to release its lock before giving up with
+    /// `TransactionError::TransactionConflict`.
+    pub fn new_pessimistic(lock_manager: Arc<LockManager>, lock_timeout: Duration) -> Self {
+        let transaction_id = Uuid::new_v4().to_string();
+        Self {
+            reads: HashMap::new(),
+            writes: Vec::new(),
+            mode: ConcurrencyMode::Pessimistic {
+                guard: TransactionLockGuard::new(lock_manager, transaction_id.clone()),
+                lock_timeout,
+            },
+            transaction_id,
+            savepoints: Vec::new(),
+        }
+    }
+
+    /// Mark the current end of `writes` as a savepoint -- see
+    /// `WriteBatch::set_savepoint`. In pessimistic mode, locks taken by
+    /// operations staged after this point are NOT released by a later
+    /// `rollback_to_savepoint`; they're released as usual when the
+    /// transaction commits, rolls back entirely, or is dropped.
+    pub fn set_savepoint(&mut self) {
+        self.savepoints.push(self.writes.len());
+    }
+
+    /// Discard every write staged since the most recent `set_savepoint` and
+    /// forget that marker. A no-op if no savepoint is set.
+    pub fn rollback_to_savepoint(&mut self) {
+        if let Some(mark) = self.savepoints.pop() {
+            self.writes.truncate(mark);
+        }
+    }
+
+    /// Forget the most recent savepoint without rolling back the writes
+    /// staged since it. A no-op if no savepoint is set.
+    pub fn pop_savepoint(&mut self) {
+        self.savepoints.pop();
+    }
+
+    /// Read a document in the transaction. In pessimistic mode this blocks
+    /// until a shared lock on `path` is acquired (see `new_pessimistic`).
     pub fn get(
         &mut self,
         path: &str,
         current_data: Option<Vec<u8>>,
         version: u64,
-    ) -> Option<Vec<u8>> {
-        // Record the read
+    ) -> Result<Option<Vec<u8>>, TransactionError> {
+        if let ConcurrencyMode::Pessimistic { guard, lock_timeout } = &mut self.mode {
+            guard.acquire(path, LockMode::Shared, *lock_timeout)?;
+        }
         self.reads.insert(
             path.to_string(),
-            current_data.clone().map(|d| (d.clone(), version)),
+            current_data.clone().map(|d| (d, version)),
         );
-        current_data
+        Ok(current_data)
+    }
+
+    /// Set a document in the transaction's default column family. In
+    /// pessimistic mode this blocks until an exclusive lock on `path` is
+    /// acquired.
+    pub fn set(&mut self, path: String, data: Vec<u8>) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Set {
+            path,
+            data,
+            column: None,
+        });
+        Ok(())
     }
 
-    /// Set a document in the transaction
-    pub fn set(&mut self, path: String, data: Vec<u8>) {
-        self.writes.push(BatchOperation::Set { path, data });
+    /// Like `set`, but in the named column family instead of the default.
+    pub fn set_cf(
+        &mut self,
+        column: impl Into<String>,
+        path: String,
+        data: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Set {
+            path,
+            data,
+            column: Some(column.into()),
+        });
+        Ok(())
     }
 
-    /// Update a document in the transaction
-    pub fn update(&mut self, path: String, data: Vec<u8>) {
-        self.writes.push(BatchOperation::Update { path, data });
+    /// Update a document in the transaction's default column family. In
+    /// pessimistic mode this blocks until an exclusive lock on `path` is
+    /// acquired.
+    pub fn update(&mut self, path: String, data: Vec<u8>) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Update {
+            path,
+            data,
+            column: None,
+        });
+        Ok(())
     }
 
-    /// Delete a document in the transaction
-    pub fn delete(&mut self, path: String) {
-        self.writes.push(BatchOperation::Delete { path });
+    /// Like `update`, but in the named column family instead of the default.
+    pub fn update_cf(
+        &mut self,
+        column: impl Into<String>,
+        path: String,
+        data: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Update {
+            path,
+            data,
+            column: Some(column.into()),
+        });
+        Ok(())
+    }
+
+    /// Delete a document in the transaction's default column family. In
+    /// pessimistic mode this blocks until an exclusive lock on `path` is
+    /// acquired.
+    pub fn delete(&mut self, path: String) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Delete {
+            path,
+            column: None,
+        });
+        Ok(())
+    }
+
+    /// Like `delete`, but in the named column family instead of the default.
+    pub fn delete_cf(
+        &mut self,
+        column: impl Into<String>,
+        path: String,
+    ) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Delete {
+            path,
+            column: Some(column.into()),
+        });
+        Ok(())
+    }
+
+    /// Merge an operand into a document in the transaction's default column
+    /// family. In pessimistic mode this blocks until an exclusive lock on
+    /// `path` is acquired.
+    pub fn merge(&mut self, path: String, operand: Vec<u8>) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Merge {
+            path,
+            operand,
+            column: None,
+        });
+        Ok(())
+    }
+
+    /// Like `merge`, but in the named column family instead of the default.
+    pub fn merge_cf(
+        &mut self,
+        column: impl Into<String>,
+        path: String,
+        operand: Vec<u8>,
+    ) -> Result<(), TransactionError> {
+        self.lock_for_write(&path)?;
+        self.writes.push(BatchOperation::Merge {
+            path,
+            operand,
+            column: Some(column.into()),
+        });
+        Ok(())
+    }
+
+    fn lock_for_write(&mut self, path: &str) -> Result<(), TransactionError> {
+        if let ConcurrencyMode::Pessimistic { guard, lock_timeout } = &mut self.mode {
+            guard.acquire(path, LockMode::Exclusive, *lock_timeout)?;
+        }
+        Ok(())
     }
 
     /// Get the transaction ID
@@ -130,11 +670,17 @@ impl Transaction {
         &self.writes
     }
 
-    /// Validate that read versions haven't changed (optimistic concurrency check)
+    /// Validate that read versions haven't changed (optimistic concurrency
+    /// check). A no-op in pessimistic mode: locks already rule out
+    /// concurrent writers, so there's nothing left to check at commit.
     pub fn validate<F>(&self, get_current_version: F) -> Result<()>
     where
         F: Fn(&str) -> Option<u64>,
     {
+        if matches!(self.mode, ConcurrencyMode::Pessimistic { .. }) {
+            return Ok(());
+        }
+
         for (path, read_data) in &self.reads {
             let read_version = read_data.as_ref().map(|(_, v)| *v);
             let current_version = get_current_version(path);
@@ -149,6 +695,24 @@ impl Transaction {
         }
         Ok(())
     }
+
+    /// Discard this transaction's writes and release any locks it holds
+    /// (pessimistic mode only -- a no-op beyond clearing `writes` in
+    /// optimistic mode, since it never took any locks to release).
+    pub fn rollback(&mut self) {
+        self.writes.clear();
+        self.savepoints.clear();
+        self.release_locks();
+    }
+
+    /// Release this transaction's locks, if it holds any. Called by
+    /// `rollback` and by the commit path once writes are durably applied;
+    /// also runs implicitly via `Drop` if neither is called explicitly.
+    pub fn release_locks(&mut self) {
+        if let ConcurrencyMode::Pessimistic { guard, .. } = &mut self.mode {
+            guard.release_all();
+        }
+    }
 }
 
 impl Default for Transaction {
@@ -157,7 +721,34 @@ impl Default for Transaction {
     }
 }
 
-/// Helper to execute a batch operation
+/// The document path a `BatchOperation` affects, regardless of variant —
+/// used by callers (like a read cache layered on top of writes) that need
+/// to know which key was touched without matching on the operation twice.
+pub fn operation_path(op: &BatchOperation) -> &str {
+    match op {
+        BatchOperation::Set { path, .. }
+        | BatchOperation::Update { path, .. }
+        | BatchOperation::Delete { path, .. }
+        | BatchOperation::Merge { path, .. } => path,
+    }
+}
+
+/// The column family a `BatchOperation` targets -- `DEFAULT_COLUMN_FAMILY`
+/// if its `column` field is `None`.
+pub fn operation_column(op: &BatchOperation) -> &str {
+    let column = match op {
+        BatchOperation::Set { column, .. }
+        | BatchOperation::Update { column, .. }
+        | BatchOperation::Delete { column, .. }
+        | BatchOperation::Merge { column, .. } => column,
+    };
+    column.as_deref().unwrap_or(DEFAULT_COLUMN_FAMILY)
+}
+
+/// Apply `op` to the default column family's `wal`/`memtable`. Callers are
+/// responsible for routing an op whose `operation_column` isn't
+/// `DEFAULT_COLUMN_FAMILY` elsewhere (see `FireLocal::commit_batch`) --
+/// this never looks at `op`'s `column` field itself.
 pub fn execute_batch_operation<S: Storage>(
     op: &BatchOperation,
     wal: &mut WriteAheadLog<S>,
@@ -165,18 +756,32 @@ pub fn execute_batch_operation<S: Storage>(
     batch_id: Option<String>,
 ) -> Result<()> {
     match op {
-        BatchOperation::Set { path, data } | BatchOperation::Update { path, data } => {
-            let entry = WalEntry::put(path.clone(), data.clone(), batch_id.as_deref());
-            let entry_bytes = serde_json::to_vec(&entry)?;
-            wal.append(&entry_bytes)?;
+        BatchOperation::Set { path, data, .. } | BatchOperation::Update { path, data, .. } => {
+            // A value past `CHUNKING_THRESHOLD` is split into
+            // content-defined chunks instead of written inline (see
+            // `crate::store::chunking`), so re-saving it after a small
+            // edit only re-writes the chunk(s) that actually changed.
+            if data.len() > crate::store::chunking::CHUNKING_THRESHOLD {
+                wal.append_chunked(path.clone(), data, batch_id.as_deref())?;
+            } else {
+                let entry = WalEntry::put(path.clone(), data.clone(), batch_id.as_deref());
+                let entry_bytes = serde_json::to_vec(&entry)?;
+                wal.append(&entry_bytes)?;
+            }
             memtable.put(path.clone(), data.clone());
         }
-        BatchOperation::Delete { path } => {
+        BatchOperation::Delete { path, .. } => {
             let entry = WalEntry::delete(path.clone(), batch_id.as_deref());
             let entry_bytes = serde_json::to_vec(&entry)?;
             wal.append(&entry_bytes)?;
             memtable.delete(path.clone());
         }
+        BatchOperation::Merge { path, operand, .. } => {
+            let entry = WalEntry::merge(path.clone(), operand.clone(), batch_id.as_deref());
+            let entry_bytes = serde_json::to_vec(&entry)?;
+            wal.append(&entry_bytes)?;
+            memtable.merge(path.clone(), operand.clone());
+        }
     }
     Ok(())
 }
@@ -198,17 +803,134 @@ mod tests {
         assert!(!batch.batch_id().is_empty());
     }
 
+    #[test]
+    fn test_write_batch_merge() {
+        let mut batch = WriteBatch::new();
+        batch.merge("counters/views".to_string(), b"1".to_vec());
+
+        assert_eq!(batch.len(), 1);
+        assert!(matches!(
+            batch.operations()[0],
+            BatchOperation::Merge { .. }
+        ));
+    }
+
+    #[test]
+    fn test_write_batch_cf_routes_to_named_column() {
+        let mut batch = WriteBatch::new();
+        batch.set_cf("events", "log/1".to_string(), b"a".to_vec());
+        batch.merge_cf("events", "log/1".to_string(), b"b".to_vec());
+
+        assert_eq!(operation_column(&batch.operations()[0]), "events");
+        assert_eq!(operation_column(&batch.operations()[1]), "events");
+        assert_eq!(operation_path(&batch.operations()[0]), "log/1");
+
+        let mut default_batch = WriteBatch::new();
+        default_batch.set("users/alice".to_string(), b"a".to_vec());
+        assert_eq!(operation_column(&default_batch.operations()[0]), DEFAULT_COLUMN_FAMILY);
+    }
+
+    #[test]
+    fn test_write_batch_savepoint_rollback_discards_only_later_operations() {
+        let mut batch = WriteBatch::new();
+        batch.set("users/alice".to_string(), b"a".to_vec());
+        batch.set_savepoint();
+        batch.set("users/bob".to_string(), b"b".to_vec());
+        batch.delete("users/charlie".to_string());
+        assert_eq!(batch.len(), 3);
+
+        batch.rollback_to_savepoint();
+        assert_eq!(batch.len(), 1);
+        assert!(matches!(batch.operations()[0], BatchOperation::Set { .. }));
+
+        // Rolling back again with no savepoint set is a no-op.
+        batch.rollback_to_savepoint();
+        assert_eq!(batch.len(), 1);
+    }
+
+    #[test]
+    fn test_write_batch_get_stages_a_read_without_an_operation() {
+        let mut batch = WriteBatch::new();
+        batch.get("users/alice".to_string());
+        batch.get("users/bob".to_string());
+
+        assert_eq!(
+            batch.reads().to_vec(),
+            vec!["users/alice".to_string(), "users/bob".to_string()]
+        );
+        assert!(batch.is_empty(), "a staged read is not a write operation");
+    }
+
+    #[test]
+    fn test_write_batch_check_version_stages_a_condition() {
+        let mut batch = WriteBatch::new();
+        batch.check_version("users/alice".to_string(), 3);
+
+        assert_eq!(batch.conditions().len(), 1);
+        assert!(matches!(
+            batch.conditions()[0],
+            BatchCondition::CheckVersion {
+                expected_version: 3,
+                ..
+            }
+        ));
+        assert!(batch.is_empty(), "a precondition alone is not a write operation");
+    }
+
+    #[test]
+    fn test_write_batch_set_if_absent_stages_both_a_condition_and_a_set() {
+        let mut batch = WriteBatch::new();
+        batch.set_if_absent("users/alice".to_string(), b"a".to_vec());
+
+        assert_eq!(batch.len(), 1);
+        assert!(matches!(batch.operations()[0], BatchOperation::Set { .. }));
+        assert_eq!(batch.conditions().len(), 1);
+        assert!(matches!(
+            batch.conditions()[0],
+            BatchCondition::NotExists { .. }
+        ));
+    }
+
+    #[test]
+    fn test_write_batch_pop_savepoint_keeps_staged_operations() {
+        let mut batch = WriteBatch::new();
+        batch.set("users/alice".to_string(), b"a".to_vec());
+        batch.set_savepoint();
+        batch.set("users/bob".to_string(), b"b".to_vec());
+
+        batch.pop_savepoint();
+        assert_eq!(batch.len(), 2);
+
+        // With the marker forgotten, a rollback now has nothing to undo.
+        batch.rollback_to_savepoint();
+        assert_eq!(batch.len(), 2);
+    }
+
+    #[test]
+    fn test_transaction_savepoint_rollback_discards_only_later_writes() {
+        let mut txn = Transaction::new();
+        txn.set("users/alice".to_string(), b"a".to_vec()).unwrap();
+        txn.set_savepoint();
+        txn.set("users/bob".to_string(), b"b".to_vec()).unwrap();
+        txn.delete("users/charlie".to_string()).unwrap();
+        assert_eq!(txn.writes().len(), 3);
+
+        txn.rollback_to_savepoint();
+        assert_eq!(txn.writes().len(), 1);
+    }
+
     #[test]
     fn test_transaction() {
         let mut txn = Transaction::new();
 
         // Simulate reading a document
         let data = b"test_data".to_vec();
-        let result = txn.get("users/alice", Some(data.clone()), 1);
+        let result = txn.get("users/alice", Some(data.clone()), 1).unwrap();
         assert_eq!(result, Some(data));
 
         // Write in transaction
-        txn.set("users/alice".to_string(), b"new_data".to_vec());
+        txn.set("users/alice".to_string(), b"new_data".to_vec())
+            .unwrap();
 
         assert_eq!(txn.writes().len(), 1);
         assert!(!txn.transaction_id().is_empty());
@@ -219,7 +941,7 @@ mod tests {
         let mut txn = Transaction::new();
 
         // Read with version 1
-        txn.get("users/alice", Some(b"data".to_vec()), 1);
+        txn.get("users/alice", Some(b"data".to_vec()), 1).unwrap();
 
         // Validation should pass if version is still 1
         let result = txn.validate(|path| if path == "users/alice" { Some(1) } else { None });
@@ -229,4 +951,61 @@ mod tests {
         let result = txn.validate(|path| if path == "users/alice" { Some(2) } else { None });
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_pessimistic_exclusive_lock_blocks_conflicting_writer() {
+        let manager = Arc::new(LockManager::new());
+
+        let mut writer = Transaction::new_pessimistic(manager.clone(), Duration::from_millis(50));
+        writer.set("users/alice".to_string(), b"v1".to_vec()).unwrap();
+
+        // A second transaction trying to write the same path with no wait
+        // should be refused immediately rather than blocking.
+        let mut other = Transaction::new_pessimistic(manager.clone(), Duration::ZERO);
+        let result = other.set("users/alice".to_string(), b"v2".to_vec());
+        assert_eq!(
+            result,
+            Err(TransactionError::WouldBlock {
+                path: "users/alice".to_string()
+            })
+        );
+
+        // With a non-zero timeout and nobody ever releasing the lock, it
+        // should time out with a conflict rather than hang.
+        let mut blocked = Transaction::new_pessimistic(manager.clone(), Duration::from_millis(20));
+        let result = blocked.set("users/alice".to_string(), b"v3".to_vec());
+        assert_eq!(
+            result,
+            Err(TransactionError::TransactionConflict {
+                path: "users/alice".to_string()
+            })
+        );
+
+        // Once the original writer releases its locks, a new attempt succeeds.
+        writer.release_locks();
+        let mut after = Transaction::new_pessimistic(manager, Duration::from_millis(50));
+        assert!(after.set("users/alice".to_string(), b"v4".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn test_pessimistic_shared_reads_do_not_block_each_other() {
+        let manager = Arc::new(LockManager::new());
+
+        let mut reader_a = Transaction::new_pessimistic(manager.clone(), Duration::ZERO);
+        let mut reader_b = Transaction::new_pessimistic(manager.clone(), Duration::ZERO);
+
+        assert!(reader_a.get("users/alice", Some(b"v1".to_vec()), 1).is_ok());
+        assert!(reader_b.get("users/alice", Some(b"v1".to_vec()), 1).is_ok());
+
+        // But a concurrent writer must wait for both shared readers to drop
+        // their locks.
+        let mut writer = Transaction::new_pessimistic(manager, Duration::ZERO);
+        let result = writer.set("users/alice".to_string(), b"v2".to_vec());
+        assert_eq!(
+            result,
+            Err(TransactionError::WouldBlock {
+                path: "users/alice".to_string()
+            })
+        );
+    }
 }
@@ -118,58 +118,157 @@ pub fn validate_rules(rules: &str) -> Result<()> {
     Ok(())
 }
 
-/// Rate limiter for operations
+/// Rate limiting strategy `RateLimiter` enforces per key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitAlgorithm {
+    /// A sliding log of request timestamps per key. Simple, but a burst
+    /// right at a window boundary can momentarily double the effective rate
+    /// (the old window's requests haven't expired yet when the new window's
+    /// requests start landing).
+    FixedWindow,
+    /// A per-key token bucket that continuously refills at
+    /// `max_requests / window_secs` tokens/sec, capped at `max_requests`.
+    /// Smooths bursts that `FixedWindow` lets through at the edges.
+    TokenBucket,
+}
+
+enum Bucket {
+    FixedWindow(std::collections::VecDeque<std::time::Instant>),
+    TokenBucket {
+        tokens: f64,
+        last_refill: std::time::Instant,
+    },
+}
+
+const GLOBAL_BUCKET_KEY: &str = "__global__";
+
+/// Rate limiter for operations. Buckets are keyed by an arbitrary string —
+/// a document path, a `request.auth.uid` pulled from the rules context,
+/// whatever identifies the caller — so one noisy key can't exhaust the
+/// limit for everyone else. `check()` is a convenience wrapper around
+/// `check_keyed` for callers that only need a single global bucket.
 pub struct RateLimiter {
     max_requests: usize,
     window_secs: u64,
-    requests: std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<std::time::Instant>>>,
+    algorithm: RateLimitAlgorithm,
+    idle_eviction: std::time::Duration,
+    buckets: std::sync::Arc<
+        std::sync::Mutex<std::collections::HashMap<String, (Bucket, std::time::Instant)>>,
+    >,
 }
 
 impl RateLimiter {
-    /// Create a new rate limiter
+    /// Create a new fixed-window rate limiter.
     ///
     /// # Arguments
-    /// * `max_requests` - Maximum requests per window
+    /// * `max_requests` - Maximum requests per window, per key
     /// * `window_secs` - Time window in seconds
     pub fn new(max_requests: usize, window_secs: u64) -> Self {
+        Self::with_algorithm(max_requests, window_secs, RateLimitAlgorithm::FixedWindow)
+    }
+
+    /// Create a new rate limiter using the given algorithm. Keys idle for
+    /// more than `4 * window_secs` (minimum 5 minutes) are evicted on the
+    /// next `check`/`check_keyed` call, bounding memory when many distinct
+    /// keys are seen over the limiter's lifetime.
+    pub fn with_algorithm(
+        max_requests: usize,
+        window_secs: u64,
+        algorithm: RateLimitAlgorithm,
+    ) -> Self {
         Self {
             max_requests,
             window_secs,
-            requests: std::sync::Arc::new(std::sync::Mutex::new(std::collections::VecDeque::new())),
+            algorithm,
+            idle_eviction: std::time::Duration::from_secs((window_secs * 4).max(300)),
+            buckets: std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
         }
     }
 
-    /// Check if operation is allowed
+    /// Check if an operation against the single global bucket is allowed.
     pub fn check(&self) -> Result<()> {
-        let mut requests = self
-            .requests
+        self.check_keyed(GLOBAL_BUCKET_KEY)
+    }
+
+    /// Check if an operation keyed by `key` (e.g. a document path or an
+    /// authenticated uid) is allowed, consuming one unit of that key's quota
+    /// if so.
+    pub fn check_keyed(&self, key: &str) -> Result<()> {
+        let mut buckets = self
+            .buckets
             .lock()
             .map_err(|_| anyhow!("Rate limiter lock poisoned"))?;
 
         let now = std::time::Instant::now();
-        let window = std::time::Duration::from_secs(self.window_secs);
-
-        // Remove old requests outside the window
-        while let Some(&req_time) = requests.front() {
-            if now.duration_since(req_time) > window {
-                requests.pop_front();
-            } else {
-                break;
-            }
-        }
-
-        // Check if limit exceeded
-        if requests.len() >= self.max_requests {
-            return Err(anyhow!(
-                "Rate limit exceeded: {} requests per {} seconds",
+        buckets.retain(|_, (_, last_seen)| now.duration_since(*last_seen) <= self.idle_eviction);
+
+        let (bucket, last_seen) = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| (self.new_bucket(now), now));
+        *last_seen = now;
+
+        if self.check_bucket(bucket, now) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Rate limit exceeded for '{}': {} requests per {} seconds",
+                key,
                 self.max_requests,
                 self.window_secs
-            ));
+            ))
         }
+    }
 
-        // Record this request
-        requests.push_back(now);
-        Ok(())
+    fn new_bucket(&self, now: std::time::Instant) -> Bucket {
+        match self.algorithm {
+            RateLimitAlgorithm::FixedWindow => {
+                Bucket::FixedWindow(std::collections::VecDeque::new())
+            }
+            RateLimitAlgorithm::TokenBucket => Bucket::TokenBucket {
+                tokens: self.max_requests as f64,
+                last_refill: now,
+            },
+        }
+    }
+
+    /// Apply `self.algorithm`'s admission check to `bucket`, returning
+    /// whether the request is allowed.
+    fn check_bucket(&self, bucket: &mut Bucket, now: std::time::Instant) -> bool {
+        match bucket {
+            Bucket::FixedWindow(requests) => {
+                let window = std::time::Duration::from_secs(self.window_secs);
+                while let Some(&front) = requests.front() {
+                    if now.duration_since(front) > window {
+                        requests.pop_front();
+                    } else {
+                        break;
+                    }
+                }
+
+                if requests.len() >= self.max_requests {
+                    false
+                } else {
+                    requests.push_back(now);
+                    true
+                }
+            }
+            Bucket::TokenBucket {
+                tokens,
+                last_refill,
+            } => {
+                let elapsed_secs = now.duration_since(*last_refill).as_secs_f64();
+                let refill_rate = self.max_requests as f64 / self.window_secs as f64;
+                *tokens = (*tokens + elapsed_secs * refill_rate).min(self.max_requests as f64);
+                *last_refill = now;
+
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
     }
 }
 
@@ -247,4 +346,28 @@ mod tests {
         // 4th request should fail
         assert!(limiter.check().is_err());
     }
+
+    #[test]
+    fn test_rate_limiter_keyed_buckets_are_independent() {
+        let limiter = RateLimiter::new(2, 1);
+
+        assert!(limiter.check_keyed("alice").is_ok());
+        assert!(limiter.check_keyed("alice").is_ok());
+        assert!(limiter.check_keyed("alice").is_err());
+
+        // "bob" has his own bucket, unaffected by alice exhausting hers.
+        assert!(limiter.check_keyed("bob").is_ok());
+        assert!(limiter.check_keyed("bob").is_ok());
+    }
+
+    #[test]
+    fn test_token_bucket_allows_burst_up_to_capacity_then_denies() {
+        let limiter =
+            RateLimiter::with_algorithm(3, 10, RateLimitAlgorithm::TokenBucket);
+
+        assert!(limiter.check_keyed("k").is_ok());
+        assert!(limiter.check_keyed("k").is_ok());
+        assert!(limiter.check_keyed("k").is_ok());
+        assert!(limiter.check_keyed("k").is_err());
+    }
 }
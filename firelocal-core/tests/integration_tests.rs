@@ -1,5 +1,8 @@
+use firelocal_core::store::io::{MemoryStorage, StdStorage};
+use firelocal_core::store::wal::{WalEntry, WriteAheadLog};
 use firelocal_core::FireLocal;
 use std::fs;
+use std::sync::Arc;
 
 #[test]
 fn test_put_get_delete_cycle() {
@@ -99,6 +102,115 @@ fn test_batch_operations() {
     let _ = fs::remove_dir_all(test_dir);
 }
 
+#[test]
+fn test_batch_get_returns_commit_snapshot_value() {
+    let test_dir = "test_db_batch_get_snapshot";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    db.put(
+        "users/alice".to_string(),
+        br#"{"name":"Alice"}"#.to_vec(),
+    )
+    .expect("Failed to put document");
+
+    // A `get` staged on the same batch that overwrites the path should
+    // still see the value from before the batch's own write, not after.
+    let mut batch = db.batch();
+    batch.get("users/alice".to_string());
+    batch.get("users/missing".to_string());
+    batch.set(
+        "users/alice".to_string(),
+        br#"{"name":"Alice","age":30}"#.to_vec(),
+    );
+
+    let result = db.commit_batch(&batch).expect("Failed to commit batch");
+    assert_eq!(
+        result.reads.get("users/alice").cloned().flatten(),
+        Some(br#"{"name":"Alice"}"#.to_vec())
+    );
+    assert_eq!(result.reads.get("users/missing").cloned().flatten(), None);
+
+    // The write itself did land.
+    let after = db.get("users/alice").expect("Failed to get document");
+    assert_eq!(after, br#"{"name":"Alice","age":30}"#.to_vec());
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_batch_set_if_absent_rejects_existing_document_atomically() {
+    let test_dir = "test_db_batch_set_if_absent";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    db.put(
+        "users/alice".to_string(),
+        br#"{"name":"Alice"}"#.to_vec(),
+    )
+    .expect("Failed to put document");
+
+    let mut batch = db.batch();
+    batch.set_if_absent(
+        "users/alice".to_string(),
+        br#"{"name":"Someone Else"}"#.to_vec(),
+    );
+    batch.set("users/bob".to_string(), br#"{"name":"Bob"}"#.to_vec());
+
+    let result = db.commit_batch(&batch);
+    assert!(
+        result.is_err(),
+        "set_if_absent should fail when the document already exists"
+    );
+
+    // The whole batch should have been rejected, including the unrelated
+    // write alongside the failed precondition.
+    assert!(
+        db.get("users/bob").is_none(),
+        "an unrelated write in the same batch must not land when a precondition fails"
+    );
+    let alice = db.get("users/alice").expect("Failed to get document");
+    assert_eq!(alice, br#"{"name":"Alice"}"#.to_vec());
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_batch_check_version_precondition() {
+    let test_dir = "test_db_batch_check_version";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    let v1 = serde_json::json!({"path": "users/alice", "fields": {"name": "Alice"}, "version": 1})
+        .to_string();
+    db.put("users/alice".to_string(), v1.into_bytes())
+        .expect("Failed to put document");
+
+    let v2 = serde_json::json!({"path": "users/alice", "fields": {"name": "Alice"}, "version": 2})
+        .to_string();
+
+    // Wrong expected version: the batch must fail atomically.
+    let mut stale_batch = db.batch();
+    stale_batch.check_version("users/alice".to_string(), 2);
+    stale_batch.set("users/alice".to_string(), v2.clone().into_bytes());
+    assert!(db.commit_batch(&stale_batch).is_err());
+
+    // Correct expected version: the batch commits normally.
+    let mut fresh_batch = db.batch();
+    fresh_batch.check_version("users/alice".to_string(), 1);
+    fresh_batch.set("users/alice".to_string(), v2.clone().into_bytes());
+    db.commit_batch(&fresh_batch)
+        .expect("commit should succeed when the version matches");
+
+    let alice = db.get("users/alice").expect("Failed to get document");
+    assert_eq!(alice, v2.into_bytes());
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
 #[test]
 fn test_overwrite_document() {
     let test_dir = "test_db_overwrite";
@@ -168,15 +280,64 @@ fn test_compaction() {
         db.delete(key).expect("Failed to delete document");
     }
 
+    // Tombstones only show up to the compactor once they've left the
+    // memtable and landed in an SST file.
+    db.flush().expect("Failed to flush");
+
     // Run compaction
-    let _stats = db.compact().expect("Failed to compact");
+    let stats = db.compact().expect("Failed to compact");
 
     // Verify compaction happened
-    // TODO: Re-enable assertion once compaction is fully implemented (currently stubbed)
-    // assert!(
-    //     stats.tombstones_removed > 0,
-    //     "Compaction should remove tombstones"
-    // );
+    assert!(
+        stats.tombstones_removed > 0,
+        "Compaction should remove tombstones"
+    );
+
+    // Cleanup
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_compact_if_needed_does_not_resurrect_a_deleted_key_from_an_unmerged_level() {
+    use firelocal_core::store::compaction::CompactionPolicy;
+
+    let test_dir = "test_db_compact_if_needed_tombstone";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    // fanout: 0 means a single file at a level is already eligible, so each
+    // call below promotes exactly one level without needing a pile of files.
+    db.set_compaction_policy(CompactionPolicy::SizeTiered {
+        fanout: 0,
+        max_bytes: u64::MAX,
+    });
+
+    let key = "docs/tombstone-regression";
+
+    // 1. Write the key and flush it into a level-0 SST, then promote it to
+    // level 1 -- this is the "older, unmerged level" the second call below
+    // must not forget about.
+    db.put(key.to_string(), br#"{"data":"v1"}"#.to_vec())
+        .expect("put v1");
+    db.flush().expect("flush v1");
+    let promoted = db.compact_if_needed().expect("promote v1 to level 1");
+    assert_eq!(promoted.level_compacted, Some(0));
+
+    // 2. Delete the key; its tombstone lands alone in a fresh level-0 file.
+    db.delete(key.to_string()).expect("delete key");
+    db.flush().expect("flush tombstone");
+
+    // 3. Compact level 0 again. The level-1 file still holds the old `v1`
+    // put, so this merge must not drop the tombstone -- it has to promote it
+    // forward instead, or `v1` would resurface on the next read.
+    let second = db.compact_if_needed().expect("compact level 0 again");
+    assert_eq!(second.level_compacted, Some(0));
+
+    assert!(
+        db.get(key).is_none(),
+        "a deleted key must stay deleted after compacting only the level its tombstone landed in"
+    );
 
     // Cleanup
     let _ = fs::remove_dir_all(test_dir);
@@ -211,6 +372,159 @@ fn test_persistence_across_instances() {
     let _ = fs::remove_dir_all(test_dir);
 }
 
+#[test]
+fn test_batch_commit_survives_restart() {
+    let test_dir = "test_db_batch_commit_restart";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    {
+        let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+        let mut batch = db.batch();
+        batch.set("users/alice".to_string(), br#"{"name":"Alice"}"#.to_vec());
+        batch.set("users/bob".to_string(), br#"{"name":"Bob"}"#.to_vec());
+        db.commit_batch(&batch).expect("Failed to commit batch");
+    }
+
+    // Reopening replays the WAL from scratch; both entries were tagged
+    // with a Commit marker, so both should survive.
+    let db = FireLocal::new(test_dir).expect("Failed to reopen database");
+    assert!(db.get("users/alice").is_some(), "Alice should survive restart");
+    assert!(db.get("users/bob").is_some(), "Bob should survive restart");
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_incomplete_transaction_is_discarded_on_recovery() {
+    let test_dir = "test_db_incomplete_txn_recovery";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    {
+        let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+        db.put("users/alice".to_string(), br#"{"name":"Alice"}"#.to_vec())
+            .expect("Failed to put document");
+    }
+
+    // Simulate a crash partway through a batch commit: append BeginTxn and
+    // a Put, but never Prepare/Commit.
+    {
+        let storage = Arc::new(StdStorage);
+        let mut wal = WriteAheadLog::open(storage, std::path::Path::new(test_dir).join("wal.log"))
+            .expect("Failed to open WAL");
+        wal.append(
+            &serde_json::to_vec(&WalEntry::begin_txn("crashed-txn")).unwrap(),
+        )
+        .unwrap();
+        wal.append(
+            &serde_json::to_vec(&WalEntry::put(
+                "users/carol".to_string(),
+                br#"{"name":"Carol"}"#.to_vec(),
+                Some("crashed-txn"),
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+    }
+
+    // Reopening should keep the earlier, fully-applied write, but discard
+    // the never-committed group entirely.
+    let db = FireLocal::new(test_dir).expect("Failed to reopen database");
+    assert!(db.get("users/alice").is_some(), "Pre-crash write should survive");
+    assert!(
+        db.get("users/carol").is_none(),
+        "Uncommitted batch entries should not be replayed"
+    );
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_merge_without_operator_keeps_last_operand() {
+    let test_dir = "test_db_merge_default";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+
+    db.merge("counters/views".to_string(), b"1".to_vec())
+        .expect("Failed to merge");
+    db.merge("counters/views".to_string(), b"2".to_vec())
+        .expect("Failed to merge");
+
+    assert_eq!(
+        db.get("counters/views"),
+        Some(b"2".to_vec()),
+        "With no merge operator registered, the last operand wins"
+    );
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_merge_with_operator_accumulates_over_base_and_survives_flush() {
+    let test_dir = "test_db_merge_operator";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    db.set_merge_operator(|base, operands| {
+        let mut total: i64 = base
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        for operand in operands {
+            if let Ok(delta) = std::str::from_utf8(operand).unwrap_or("0").parse::<i64>() {
+                total += delta;
+            }
+        }
+        total.to_string().into_bytes()
+    });
+
+    db.put("counters/views".to_string(), b"10".to_vec())
+        .expect("Failed to put base value");
+    db.merge("counters/views".to_string(), b"1".to_vec())
+        .expect("Failed to merge");
+    db.merge("counters/views".to_string(), b"1".to_vec())
+        .expect("Failed to merge");
+
+    assert_eq!(db.get("counters/views"), Some(b"12".to_vec()));
+
+    // Flushing resolves the pending merge into a concrete value before it's
+    // written to the SST, rather than carrying the raw operands on disk.
+    db.flush().expect("Failed to flush");
+    assert_eq!(db.get("counters/views"), Some(b"12".to_vec()));
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
+#[test]
+fn test_batch_routes_named_column_ops_to_their_own_column_family() {
+    let test_dir = "test_db_batch_cf";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+    db.load_rules("service cloud.firestore { match /databases/{database}/documents { match /{document=**} { allow read, write: if true; } } }").unwrap();
+
+    let mut batch = db.batch();
+    batch.set("users/alice".to_string(), b"default_value".to_vec());
+    batch.set_cf("events", "log/1".to_string(), b"event_value".to_vec());
+    db.commit_batch(&batch).expect("Failed to commit batch");
+
+    assert_eq!(db.get("users/alice"), Some(b"default_value".to_vec()));
+    assert_eq!(db.get("log/1"), None, "A named column family's writes must not leak into the default keyspace");
+    assert_eq!(
+        db.get_cf("events", "log/1").unwrap(),
+        Some(b"event_value".to_vec())
+    );
+
+    assert!(db.list_column_families().unwrap().contains(&"events".to_string()));
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
 #[test]
 fn test_empty_database() {
     let test_dir = "test_db_empty";
@@ -255,6 +569,75 @@ fn test_large_document() {
     let _ = fs::remove_dir_all(test_dir);
 }
 
+#[test]
+fn test_in_memory_backend_never_touches_disk() {
+    let test_dir = "test_db_in_memory_should_not_exist";
+    let _ = fs::remove_dir_all(test_dir);
+
+    let mut db = FireLocal::<MemoryStorage>::new_in_memory()
+        .expect("Failed to create in-memory database");
+
+    db.put("users/alice".to_string(), br#"{"name":"Alice"}"#.to_vec())
+        .expect("Failed to put document");
+    assert_eq!(
+        db.get("users/alice"),
+        Some(br#"{"name":"Alice"}"#.to_vec())
+    );
+
+    db.delete("users/alice".to_string())
+        .expect("Failed to delete document");
+    assert!(db.get("users/alice").is_none());
+
+    assert!(
+        !std::path::Path::new(test_dir).exists(),
+        "an in-memory store must never create a directory on disk"
+    );
+}
+
+#[test]
+fn test_dump_restore_round_trips_between_backends() {
+    let test_dir = "test_db_dump_restore";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let alice = serde_json::json!({"path": "users/alice", "fields": {"name": "Alice"}})
+        .to_string();
+    let bob = serde_json::json!({"path": "users/bob", "fields": {"name": "Bob"}}).to_string();
+
+    let mut memory_db =
+        FireLocal::<MemoryStorage>::new_in_memory().expect("Failed to create in-memory database");
+    memory_db
+        .put("users/alice".to_string(), alice.into_bytes())
+        .expect("Failed to put document");
+    memory_db
+        .put("users/bob".to_string(), bob.into_bytes())
+        .expect("Failed to put document");
+
+    let archive = memory_db
+        .dump(firelocal_core::dump::Codec::Zstd)
+        .expect("Failed to dump in-memory database");
+
+    let mut file_db = FireLocal::new(test_dir).expect("Failed to create database");
+    file_db
+        .restore(&archive)
+        .expect("Failed to restore into file-backed database");
+
+    let alice_doc = file_db.get("users/alice").expect("alice should be restored");
+    let alice_doc = firelocal_core::model::Document::from_json(
+        std::str::from_utf8(&alice_doc).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(alice_doc.fields.get("name").unwrap(), "Alice");
+
+    let bob_doc = file_db.get("users/bob").expect("bob should be restored");
+    let bob_doc =
+        firelocal_core::model::Document::from_json(std::str::from_utf8(&bob_doc).unwrap())
+            .unwrap();
+    assert_eq!(bob_doc.fields.get("name").unwrap(), "Bob");
+
+    let _ = fs::remove_dir_all(test_dir);
+}
+
 #[test]
 fn test_special_characters_in_path() {
     let test_dir = "test_db_special";
@@ -284,3 +667,37 @@ fn test_special_characters_in_path() {
     // Cleanup
     let _ = fs::remove_dir_all(test_dir);
 }
+
+#[test]
+fn test_scan_prefix_lists_matching_keys_across_memtable_and_sst() {
+    let test_dir = "test_db_scan_prefix";
+    let _ = fs::remove_dir_all(test_dir);
+    fs::create_dir_all(test_dir).unwrap();
+
+    let mut db = FireLocal::new(test_dir).expect("Failed to create database");
+
+    db.put("users/alice".to_string(), br#"{"name":"Alice"}"#.to_vec())
+        .unwrap();
+    db.put("users/bob".to_string(), br#"{"name":"Bob"}"#.to_vec())
+        .unwrap();
+    db.flush().expect("Failed to flush Memtable");
+    db.put("users/carol".to_string(), br#"{"name":"Carol"}"#.to_vec())
+        .unwrap();
+    db.put("posts/1".to_string(), br#"{"title":"Hello"}"#.to_vec())
+        .unwrap();
+    db.delete("users/bob".to_string()).unwrap();
+
+    let users = db.scan_prefix("users/").expect("Failed to scan");
+    let keys: Vec<&str> = users.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(
+        keys,
+        vec!["users/alice", "users/carol"],
+        "scan_prefix should merge memtable and SST, skip the deleted key, and sort ascending"
+    );
+
+    let everything = db.scan_prefix("").expect("Failed to scan with empty prefix");
+    assert_eq!(everything.len(), 3, "an empty prefix should match every live key");
+
+    // Cleanup
+    let _ = fs::remove_dir_all(test_dir);
+}
@@ -41,3 +41,44 @@ fn test_rules_parser_and_enforcement() {
 
     let _ = fs::remove_dir_all(path);
 }
+
+#[test]
+fn test_rules_enforce_owner_only_access_via_auth_context() {
+    let path = "tmp_test_db_rules_owner";
+    let _ = fs::remove_dir_all(path);
+
+    let mut db = FireLocal::new(path).unwrap();
+
+    // A condition beyond a bare `true`/`false` literal needs `request.auth.uid`
+    // and the `{userId}` wildcard actually wired through to evaluation.
+    let rules = r#"
+        service cloud.firestore {
+            match /databases/{database}/documents {
+                match /users/{userId} {
+                    allow read, write: if request.auth.uid == userId;
+                }
+            }
+        }
+    "#;
+    db.load_rules(rules).expect("Failed to parse rules");
+
+    // No identity set: request.auth.uid is missing, so even the owner's own
+    // path is denied.
+    assert!(db.put("users/alice".to_string(), b"{}".to_vec()).is_err());
+
+    // Authenticated, but as someone other than the document owner.
+    db.set_auth_context(Some("bob".to_string()));
+    assert!(db.put("users/alice".to_string(), b"{}".to_vec()).is_err());
+    assert!(db.get("users/alice").is_none());
+
+    // Authenticated as the owner: writes and reads go through.
+    db.set_auth_context(Some("alice".to_string()));
+    assert!(db.put("users/alice".to_string(), b"{}".to_vec()).is_ok());
+    assert!(db.get("users/alice").is_some());
+
+    // Switching back to a non-owner still can't read what the owner wrote.
+    db.set_auth_context(Some("bob".to_string()));
+    assert!(db.get("users/alice").is_none());
+
+    let _ = fs::remove_dir_all(path);
+}
@@ -19,6 +19,20 @@ impl RemoteStore for MemoryRemoteStore {
         let storage = self.storage.lock().unwrap();
         Ok(storage.iter().find(|d| d.path == path).cloned())
     }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let storage = self.storage.lock().unwrap();
+        Ok(storage
+            .iter()
+            .filter(|d| d.path.starts_with(prefix))
+            .map(|d| d.path.clone())
+            .collect())
+    }
+
+    fn delete(&self, path: &str) -> Result<(), String> {
+        self.storage.lock().unwrap().retain(|d| d.path != path);
+        Ok(())
+    }
 }
 
 #[test]
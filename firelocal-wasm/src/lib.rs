@@ -1,8 +1,18 @@
 use firelocal_core::store::io::MemoryStorage;
 use firelocal_core::FireLocal as FireLocalCore;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
+use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode};
+
+// The IndexedDB snapshot persistence below needs `web-sys`'s "Window",
+// "IdbFactory", "IdbOpenDbRequest", "IdbDatabase", "IdbTransaction",
+// "IdbTransactionMode", "IdbObjectStore", "IdbRequest", and "Event"
+// features enabled.
 
 #[wasm_bindgen]
 extern "C" {
@@ -20,9 +30,23 @@ extern "C" {
 // We can store it in a Mutex assuming single-threaded WASM context usually,
 // but Rust checks bounds.
 
+/// Name of the browser-global IndexedDB database `persist`/`hydrate` read
+/// and write a snapshot blob through.
+const IDB_DB_NAME: &str = "firelocal";
+/// Object store within `IDB_DB_NAME` that holds one snapshot blob per
+/// `path`, keyed by `path` itself.
+const IDB_STORE_NAME: &str = "snapshots";
+
 #[wasm_bindgen]
 pub struct FireLocal {
     inner: Arc<Mutex<FireLocalCore<MemoryStorage>>>,
+    // `MemoryStorage` is a cheap `Clone` (an `Arc`-backed in-memory
+    // filesystem underneath), so this is the same backing store `inner`
+    // was opened against -- kept here so `export_snapshot`/`import_snapshot`
+    // can read and replace its files directly, without `FireLocalCore`
+    // needing to expose its storage handle.
+    storage: MemoryStorage,
+    path: String,
 }
 
 #[wasm_bindgen]
@@ -34,15 +58,19 @@ impl FireLocal {
             path
         ));
 
-        // Initialize Core with MemoryStorage
-        // In the future, we can back MemoryStorage with IndexedDB by loading/saving snapshots.
+        // Initialize Core with MemoryStorage, keeping `storage` in sync so
+        // a snapshot can be exported/imported later. A caller that wants
+        // this restored from the last session should `await hydrate()`
+        // right after construction -- `wasm_bindgen` constructors can't be
+        // async themselves, since opening IndexedDB is.
         let storage = MemoryStorage::new();
-
-        let db = FireLocalCore::new_with_storage(path, storage)
+        let db = FireLocalCore::new_with_storage(path.clone(), storage.clone())
             .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
         Ok(FireLocal {
             inner: Arc::new(Mutex::new(db)),
+            storage,
+            path,
         })
     }
 
@@ -88,13 +116,246 @@ impl FireLocal {
         Ok(())
     }
 
+    /// Serialize the full store -- every WAL and SST file `storage` is
+    /// currently holding, which between them reconstruct the current
+    /// keyspace on replay -- into one compact MessagePack blob. The
+    /// counterpart is `import_snapshot`; `persist` wraps both of these
+    /// around an IndexedDB write.
+    #[wasm_bindgen]
+    pub async fn export_snapshot(&self) -> Result<JsValue, JsValue> {
+        let files = self.storage.snapshot_files();
+        let bytes = firelocal_core::codec::encode(&files)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(js_sys::Uint8Array::from(bytes.as_slice()).into())
+    }
+
+    /// Replace this instance's store with the contents of a blob produced
+    /// by `export_snapshot`, then reopen it (replaying its WAL and
+    /// re-reading its SSTs the same way `FireLocal::new_with_storage`
+    /// always does) so every subsequent `get`/`put`/`delete` sees the
+    /// imported state.
+    #[wasm_bindgen]
+    pub async fn import_snapshot(&self, bytes: JsValue) -> Result<(), JsValue> {
+        let array: js_sys::Uint8Array = bytes
+            .dyn_into()
+            .map_err(|_| JsValue::from_str("import_snapshot expects a Uint8Array"))?;
+        let files: Vec<(PathBuf, Vec<u8>)> = firelocal_core::codec::decode(&array.to_vec())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        self.storage.restore_files(files);
+
+        let db = FireLocalCore::new_with_storage(self.path.clone(), self.storage.clone())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        *self.inner.lock().unwrap() = db;
+
+        Ok(())
+    }
+
+    /// Write the current `export_snapshot` blob into the browser's
+    /// IndexedDB, under `IDB_DB_NAME`/`IDB_STORE_NAME` keyed by `path` --
+    /// call this after any batch of writes a page reload shouldn't lose.
+    #[wasm_bindgen]
+    pub async fn persist(&self) -> Result<(), JsValue> {
+        let snapshot = self.export_snapshot().await?;
+        let array: js_sys::Uint8Array = snapshot
+            .dyn_into()
+            .map_err(|_| JsValue::from_str("export_snapshot did not return a Uint8Array"))?;
+
+        let db = idb_open().await?;
+        let tx = db.transaction_with_str_and_mode(IDB_STORE_NAME, IdbTransactionMode::Readwrite)?;
+        let store = tx.object_store(IDB_STORE_NAME)?;
+        store.put_with_key(&array, &JsValue::from_str(&self.path))?;
+        idb_await_transaction(&tx).await?;
+        db.close();
+        Ok(())
+    }
+
+    /// Load whatever `persist` last wrote for `path` and import it, so a
+    /// fresh page load picks up where the last session left off. Returns
+    /// `false` (leaving this instance's freshly-constructed empty store
+    /// untouched) if nothing has been persisted for `path` yet.
+    #[wasm_bindgen]
+    pub async fn hydrate(&self) -> Result<bool, JsValue> {
+        let db = idb_open().await?;
+        let tx = db.transaction_with_str(IDB_STORE_NAME)?;
+        let store = tx.object_store(IDB_STORE_NAME)?;
+        let get_request = store.get(&JsValue::from_str(&self.path))?;
+        let value = idb_await_request(&get_request).await?;
+        db.close();
+
+        if value.is_undefined() || value.is_null() {
+            return Ok(false);
+        }
+
+        self.import_snapshot(value).await?;
+        Ok(true)
+    }
+
     /// Run compaction
     #[wasm_bindgen]
     pub async fn compact(&self) -> Result<JsValue, JsValue> {
-        log("Running compaction (Stub)");
-        // let db = self.inner.lock().unwrap();
-        // let stats = db.compact().map_err(|e| JsValue::from_str(&e.to_string()))?;
-        // For now just return empty object
-        Ok(js_sys::Object::new().into())
+        log("Running compaction");
+
+        let stats = {
+            let db = self.inner.lock().unwrap();
+            db.compact()
+                .map_err(|e| JsValue::from_str(&e.to_string()))?
+        };
+
+        // `compact()` merges SST files through `std::fs`
+        // (`crate::store::compaction::Compactor`), which has nothing to
+        // find under a `MemoryStorage` path, so this is presently a no-op
+        // in the WASM build -- real (if zeroed) stats are still returned
+        // rather than a stub object, and the persisted snapshot is
+        // refreshed in case a future `Compactor` generic over `Storage`
+        // changes that.
+        self.persist().await?;
+
+        let result = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("filesBefore"),
+            &JsValue::from_f64(stats.files_before as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("filesAfter"),
+            &JsValue::from_f64(stats.files_after as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("entriesBefore"),
+            &JsValue::from_f64(stats.entries_before as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("entriesAfter"),
+            &JsValue::from_f64(stats.entries_after as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("tombstonesRemoved"),
+            &JsValue::from_f64(stats.tombstones_removed as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("sizeBefore"),
+            &JsValue::from_f64(stats.size_before as f64),
+        )?;
+        js_sys::Reflect::set(
+            &result,
+            &JsValue::from_str("sizeAfter"),
+            &JsValue::from_f64(stats.size_after as f64),
+        )?;
+        Ok(result.into())
     }
 }
+
+/// Open (creating on first use) the shared IndexedDB database `persist`/
+/// `hydrate` read and write snapshots through, with `IDB_STORE_NAME`
+/// created as part of the version-1 upgrade.
+async fn idb_open() -> Result<IdbDatabase, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no global `window`"))?;
+    let factory = window
+        .indexed_db()?
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available in this context"))?;
+    let open_request = factory.open_with_u32(IDB_DB_NAME, 1)?;
+
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let upgrade_request = open_request.clone();
+        let on_upgrade = Closure::once(move |_evt: web_sys::Event| {
+            if let Ok(result) = upgrade_request.result() {
+                if let Ok(db) = result.dyn_into::<IdbDatabase>() {
+                    if !db.object_store_names().contains(IDB_STORE_NAME) {
+                        let _ = db.create_object_store(IDB_STORE_NAME);
+                    }
+                }
+            }
+        });
+        open_request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+        on_upgrade.forget();
+
+        let success_request = open_request.clone();
+        let on_success = Closure::once(move |_evt: web_sys::Event| {
+            match success_request.result() {
+                Ok(db) => {
+                    let _ = resolve.call1(&JsValue::NULL, &db);
+                }
+                Err(e) => {
+                    let _ = reject.call1(&JsValue::NULL, &e);
+                }
+            }
+        });
+        open_request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        on_success.forget();
+
+        let error_request = open_request.clone();
+        let on_error = Closure::once(move |_evt: web_sys::Event| {
+            let err = error_request
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or(JsValue::NULL);
+            let _ = reject.call1(&JsValue::NULL, &err);
+        });
+        open_request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        on_error.forget();
+    });
+
+    JsFuture::from(promise).await?.dyn_into::<IdbDatabase>()
+}
+
+/// Wait for a transaction opened against an `idb_open`ed database to
+/// either commit (`oncomplete`) or fail (`onerror`).
+async fn idb_await_transaction(tx: &web_sys::IdbTransaction) -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let on_complete = Closure::once(move |_evt: web_sys::Event| {
+            let _ = resolve.call0(&JsValue::NULL);
+        });
+        tx.set_oncomplete(Some(on_complete.as_ref().unchecked_ref()));
+        on_complete.forget();
+
+        let on_error = Closure::once(move |_evt: web_sys::Event| {
+            let _ = reject.call0(&JsValue::NULL);
+        });
+        tx.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        on_error.forget();
+    });
+    JsFuture::from(promise).await?;
+    Ok(())
+}
+
+/// Wait for a single `IdbRequest` (e.g. an object store `get`) to resolve,
+/// returning its result value (`undefined` if the key wasn't found).
+async fn idb_await_request(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once(move |_evt: web_sys::Event| {
+            match success_request.result() {
+                Ok(value) => {
+                    let _ = resolve.call1(&JsValue::NULL, &value);
+                }
+                Err(e) => {
+                    let _ = reject.call1(&JsValue::NULL, &e);
+                }
+            }
+        });
+        request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        on_success.forget();
+
+        let error_request = request.clone();
+        let on_error = Closure::once(move |_evt: web_sys::Event| {
+            let err = error_request
+                .error()
+                .ok()
+                .flatten()
+                .map(JsValue::from)
+                .unwrap_or(JsValue::NULL);
+            let _ = reject.call1(&JsValue::NULL, &err);
+        });
+        request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        on_error.forget();
+    });
+    JsFuture::from(promise).await
+}
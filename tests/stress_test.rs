@@ -1,290 +1,437 @@
+use anyhow::{Context, Result};
+use clap::Parser;
 use firelocal_core::FireLocal;
-use serde_json::{json, Value};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
-use std::collections::HashMap;
-use anyhow::Result;
-
-#[derive(Clone)]
-struct TestData {
-    id: String,
-    name: String,
-    data: HashMap<String, Value>,
-    timestamp: u64,
+
+/// Minimal HDR-style latency histogram: samples are bucketed by power-of-two
+/// nanosecond ranges (64 buckets covers nanoseconds through ~292 years), so
+/// memory is O(1) regardless of run length. Percentiles are reported as the
+/// upper bound of the bucket they fall in, clamped to the true observed max —
+/// accurate to within the bucket's width (~2x at the low end, far tighter near
+/// typical latencies) rather than exact, which is the usual HDR trade-off.
+struct LatencyHistogram {
+    buckets: [u64; 64],
+    count: u64,
+    max_nanos: u64,
 }
 
-impl TestData {
-    fn new(id: &str, name: &str) -> Self {
-        let mut data = HashMap::new();
-        data.insert("field1".to_string(), json!("value1"));
-        data.insert("field2".to_string(), json!("value2"));
-        data.insert("field3".to_string(), json!(42));
-        
+impl LatencyHistogram {
+    fn new() -> Self {
         Self {
-            id: id.to_string(),
-            name: name.to_string(),
-            data,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            buckets: [0; 64],
+            count: 0,
+            max_nanos: 0,
         }
     }
 
-    fn to_bytes(&self) -> Vec<u8> {
-        serde_json::to_vec(&self).unwrap()
+    fn record(&mut self, d: Duration) {
+        let nanos = d.as_nanos().min(u64::MAX as u128) as u64;
+        let idx = if nanos == 0 {
+            0
+        } else {
+            (63 - nanos.leading_zeros()) as usize
+        };
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.max_nanos = self.max_nanos.max(nanos);
     }
-}
 
-fn stress_test_basic_operations(db: &FireLocal, num_ops: usize) -> Result<()> {
-    println!("🔄 Starting basic operations stress test: {} operations", num_ops);
-    
-    let start = Instant::now();
-    
-    // Write operations
-    for i in 0..num_ops {
-        let test_data = TestData::new(&format!("doc_{}", i), &format!("Document {}", i));
-        let key = format!("stress_test/{}", i);
-        db.put(key, test_data.to_bytes())?;
-        
-        if i % 1000 == 0 {
-            println!("  ✅ Completed {} write operations", i);
+    fn merge(&mut self, other: &LatencyHistogram) {
+        for i in 0..64 {
+            self.buckets[i] += other.buckets[i];
         }
+        self.count += other.count;
+        self.max_nanos = self.max_nanos.max(other.max_nanos);
     }
-    
-    let write_duration = start.elapsed();
-    println!("  📝 Write operations completed in {:?}", write_duration);
-    
-    // Read operations
-    let read_start = Instant::now();
-    for i in 0..num_ops {
-        let key = format!("stress_test/{}", i);
-        let result = db.get(&key);
-        
-        if result.is_none() {
-            return Err(anyhow::anyhow!("Failed to retrieve document: {}", key));
+
+    fn percentile(&self, p: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
         }
-        
-        if i % 1000 == 0 {
-            println!("  ✅ Completed {} read operations", i);
+        let target = (((p / 100.0) * self.count as f64).ceil() as u64).max(1);
+        let mut cumulative = 0u64;
+        for (i, &c) in self.buckets.iter().enumerate() {
+            cumulative += c;
+            if cumulative >= target {
+                let upper = if i == 63 { u64::MAX } else { 1u64 << (i + 1) };
+                return Duration::from_nanos(upper.min(self.max_nanos));
+            }
         }
+        Duration::from_nanos(self.max_nanos)
+    }
+
+    fn max(&self) -> Duration {
+        Duration::from_nanos(self.max_nanos)
     }
-    
-    let total_duration = start.elapsed();
-    let read_duration = read_start.elapsed();
-    
-    println!("  📖 Read operations completed in {:?}", read_duration);
-    println!("  ⏱️ Total test time: {:?}", total_duration);
-    
-    // Calculate performance metrics
-    let write_ops_per_sec = num_ops as f64 / write_duration.as_secs_f64();
-    let read_ops_per_sec = num_ops as f64 / read_duration.as_secs_f64();
-    
-    println!("  📊 Performance Metrics:");
-    println!("    - Write: {:.2} ops/sec", write_ops_per_sec);
-    println!("    - Read: {:.2} ops/sec", read_ops_per_sec);
-    
-    Ok(())
 }
 
-fn stress_test_batch_operations(db: &FireLocal, batch_size: usize, num_batches: usize) -> Result<()> {
-    println!("🔄 Starting batch operations stress test: {} batches of {} operations", num_batches, batch_size);
-    
-    let start = Instant::now();
-    let total_ops = batch_size * num_batches;
-    
-    for batch_num in 0..num_batches {
-        let batch = db.batch();
-        
-        for i in 0..batch_size {
-            let doc_id = batch_num * batch_size + i;
-            let test_data = TestData::new(&format!("batch_doc_{}", doc_id), &format!("Batch Document {}", doc_id));
-            let key = format!("batch_test/{}", doc_id);
-            batch.set(key, test_data.to_bytes());
-        }
-        
-        batch.commit()?;
-        
-        if batch_num % 10 == 0 {
-            println!("  ✅ Completed {} batches", batch_num + 1);
-        }
+/// A named, parameterized operation mix that the bench engine drives against
+/// the database. New workloads are added by implementing this trait rather
+/// than bolting another `stress_test_*` function onto `main`.
+trait Workload: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn params(&self) -> Vec<(&'static str, String)>;
+    /// Perform one unit of work and return how long it took. `worker_id` and
+    /// `op_index` give each call a unique, deterministic key so concurrent
+    /// workers never collide.
+    fn run_once(&self, db: &Mutex<FireLocal>, worker_id: usize, op_index: u64) -> Result<Duration>;
+}
+
+/// Fixed-size puts against unique keys: a baseline write-latency workload.
+struct UniformWorkload {
+    value_size_bytes: usize,
+}
+
+impl Workload for UniformWorkload {
+    fn name(&self) -> &'static str {
+        "uniform"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("value_size_bytes", self.value_size_bytes.to_string())]
+    }
+
+    fn run_once(&self, db: &Mutex<FireLocal>, worker_id: usize, op_index: u64) -> Result<Duration> {
+        let key = format!("bench/uniform/{worker_id}/{op_index}");
+        let value = vec![b'x'; self.value_size_bytes];
+        let start = Instant::now();
+        db.lock().unwrap().put(key, value)?;
+        Ok(start.elapsed())
     }
-    
-    let duration = start.elapsed();
-    let ops_per_sec = total_ops as f64 / duration.as_secs_f64();
-    
-    println!("  ⏱️ Batch test completed in {:?}", duration);
-    println!("  📊 Batch performance: {:.2} ops/sec", ops_per_sec);
-    
-    Ok(())
 }
 
-fn stress_test_concurrent_access(db_path: &str, num_threads: usize, ops_per_thread: usize) -> Result<()> {
-    println!("🔄 Starting concurrent access stress test: {} threads, {} ops/thread", num_threads, ops_per_thread);
-    
-    let start = Instant::now();
-    let db = Arc::new(Mutex::new(FireLocal::new(db_path)?));
-    let mut handles = vec![];
-    
-    for thread_id in 0..num_threads {
-        let db_clone = Arc::clone(&db);
-        let path = format!("concurrent_test_{}", thread_id);
-        
-        let handle = thread::spawn(move || -> Result<()> {
-            for i in 0..ops_per_thread {
-                let test_data = TestData::new(&format!("thread_{}_doc_{}", thread_id, i), &format!("Thread {} Document {}", thread_id, i));
-                let key = format!("{}/doc_{}", path, i);
-                
-                let db_guard = db_clone.lock().unwrap();
-                db_guard.put(key, test_data.to_bytes())?;
-                
-                // Read back to verify
-                let result = db_guard.get(&key);
-                if result.is_none() {
-                    return Err(anyhow::anyhow!("Failed to read back document: {}", key));
-                }
-            }
-            Ok(())
-        });
-        
-        handles.push(handle);
+/// Weighted read/write/delete mix over a bounded keyspace per worker, so
+/// reads and deletes mostly land on keys a prior write already created.
+struct MixedWorkload {
+    write_pct: u8,
+    read_pct: u8,
+    keyspace: u64,
+}
+
+impl Workload for MixedWorkload {
+    fn name(&self) -> &'static str {
+        "mixed"
     }
-    
-    // Wait for all threads to complete
-    for handle in handles {
-        handle.join().unwrap()?;
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("write_pct", self.write_pct.to_string()),
+            ("read_pct", self.read_pct.to_string()),
+            ("delete_pct", (100 - self.write_pct - self.read_pct).to_string()),
+            ("keyspace", self.keyspace.to_string()),
+        ]
+    }
+
+    fn run_once(&self, db: &Mutex<FireLocal>, worker_id: usize, op_index: u64) -> Result<Duration> {
+        // A cheap deterministic hash, not real randomness, but ample for
+        // spreading operations across the write/read/delete split.
+        let roll = ((op_index.wrapping_mul(2_654_435_761).wrapping_add(worker_id as u64)) % 100) as u8;
+        let key = format!("bench/mixed/{worker_id}/{}", op_index % self.keyspace.max(1));
+
+        let start = Instant::now();
+        if roll < self.write_pct {
+            db.lock().unwrap().put(key, vec![b'x'; 256])?;
+        } else if roll < self.write_pct + self.read_pct {
+            db.lock().unwrap().get(&key);
+        } else {
+            db.lock().unwrap().delete(key)?;
+        }
+        Ok(start.elapsed())
     }
-    
-    let duration = start.elapsed();
-    let total_ops = (num_threads * ops_per_thread) as f64;
-    let ops_per_sec = total_ops / duration.as_secs_f64();
-    
-    println!("  ⏱️ Concurrent test completed in {:?}", duration);
-    println!("  📊 Concurrent performance: {:.2} ops/sec", ops_per_sec);
-    
-    Ok(())
 }
 
-fn stress_test_large_documents(db: &FireLocal, doc_size_kb: usize, num_docs: usize) -> Result<()> {
-    println!("🔄 Starting large document stress test: {} documents of {}KB each", num_docs, doc_size_kb);
-    
-    let start = Instant::now();
-    
-    // Create large document
-    let mut large_data = HashMap::new();
-    for i in 0..(doc_size_kb * 100) {
-        large_data.insert(format!("field_{}", i), json!("This is some test data with a moderately long string to simulate real-world document content with multiple fields and various data types including numbers, strings, and nested objects to test the performance characteristics of the database when handling larger documents."));
+fn build_workload(name: &str) -> Result<Box<dyn Workload>> {
+    match name {
+        "uniform" => Ok(Box::new(UniformWorkload {
+            value_size_bytes: 256,
+        })),
+        "mixed" => Ok(Box::new(MixedWorkload {
+            write_pct: 70,
+            read_pct: 25,
+            keyspace: 10_000,
+        })),
+        other => Err(anyhow::anyhow!(
+            "unknown workload '{other}' (expected 'uniform' or 'mixed')"
+        )),
     }
-    
-    for i in 0..num_docs {
-        let doc = TestData {
-            id: format!("large_doc_{}", i),
-            name: format!("Large Document {}", i),
-            data: large_data.clone(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        };
-        
-        let key = format!("large_test/{}", i);
-        db.put(key, serde_json::to_vec(&doc).unwrap())?;
-        
-        if i % 10 == 0 {
-            println!("  ✅ Completed {} large documents", i + 1);
+}
+
+#[derive(Parser, Debug)]
+#[command(name = "firelocal-bench")]
+#[command(about = "Configurable workload benchmark for FireLocal", long_about = None)]
+struct BenchArgs {
+    /// Named workload to run: `uniform` (fixed-size puts) or `mixed`
+    /// (weighted read/write/delete ratios)
+    #[arg(long, default_value = "uniform")]
+    workload: String,
+
+    /// How long to run the benchmark, in seconds
+    #[arg(long, default_value_t = 30)]
+    bench_length_seconds: u64,
+
+    /// Cap on total operations per second across all workers combined
+    /// (unbounded if omitted)
+    #[arg(long)]
+    operations_per_second: Option<u64>,
+
+    /// Number of concurrent worker threads driving the workload
+    #[arg(long, default_value_t = 1)]
+    concurrency: usize,
+
+    /// Seconds over which workers are started gradually, rather than all at
+    /// once, to avoid a cold-start latency spike skewing the summary
+    #[arg(long, default_value_t = 0)]
+    ramp_up: u64,
+
+    /// Directory to write a machine-readable JSON/CSV summary to
+    #[arg(short = 'o', long)]
+    output_dir: Option<PathBuf>,
+
+    /// Database directory to benchmark against
+    #[arg(long, default_value = "./bench_db")]
+    db_path: String,
+}
+
+/// A single worker's contribution to the run: its latency samples and how
+/// many operations failed.
+struct WorkerResult {
+    histogram: LatencyHistogram,
+    completed: u64,
+    errors: u64,
+}
+
+/// The machine-readable summary written to `-o <dir>` and printed at the end
+/// of every run, so results can be diffed across commits.
+struct BenchSummary {
+    workload: String,
+    params: Vec<(&'static str, String)>,
+    elapsed: Duration,
+    completed: u64,
+    errors: u64,
+    p50: Duration,
+    p90: Duration,
+    p99: Duration,
+    p999: Duration,
+    max: Duration,
+}
+
+impl BenchSummary {
+    fn throughput(&self) -> f64 {
+        if self.elapsed.as_secs_f64() > 0.0 {
+            self.completed as f64 / self.elapsed.as_secs_f64()
+        } else {
+            0.0
         }
     }
-    
-    let duration = start.elapsed();
-    let ops_per_sec = num_docs as f64 / duration.as_secs_f64();
-    
-    println!("  ⏱️ Large document test completed in {:?}", duration);
-    println!("  📊 Large doc performance: {:.2} ops/sec", ops_per_sec);
-    
-    Ok(())
+
+    fn print(&self) {
+        println!("\n📊 Benchmark Summary: {}", self.workload);
+        for (k, v) in &self.params {
+            println!("    - {k}: {v}");
+        }
+        println!("    - elapsed: {:.2}s", self.elapsed.as_secs_f64());
+        println!("    - completed: {}", self.completed);
+        println!("    - errors: {}", self.errors);
+        println!("    - throughput: {:.2} ops/sec", self.throughput());
+        println!("    - p50: {:?}", self.p50);
+        println!("    - p90: {:?}", self.p90);
+        println!("    - p99: {:?}", self.p99);
+        println!("    - p999: {:?}", self.p999);
+        println!("    - max: {:?}", self.max);
+    }
+
+    fn to_json(&self) -> String {
+        let params_json = self
+            .params
+            .iter()
+            .map(|(k, v)| format!(r#""{k}": "{v}""#))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            r#"{{
+  "workload": "{}",
+  "params": {{ {} }},
+  "elapsed_secs": {:.3},
+  "completed": {},
+  "errors": {},
+  "throughput_ops_per_sec": {:.2},
+  "latency_ns": {{
+    "p50": {},
+    "p90": {},
+    "p99": {},
+    "p999": {},
+    "max": {}
+  }}
+}}"#,
+            self.workload,
+            params_json,
+            self.elapsed.as_secs_f64(),
+            self.completed,
+            self.errors,
+            self.throughput(),
+            self.p50.as_nanos(),
+            self.p90.as_nanos(),
+            self.p99.as_nanos(),
+            self.p999.as_nanos(),
+            self.max.as_nanos(),
+        )
+    }
+
+    fn to_csv_row(&self) -> String {
+        format!(
+            "{},{:.3},{},{},{:.2},{},{},{},{},{}\n",
+            self.workload,
+            self.elapsed.as_secs_f64(),
+            self.completed,
+            self.errors,
+            self.throughput(),
+            self.p50.as_nanos(),
+            self.p90.as_nanos(),
+            self.p99.as_nanos(),
+            self.p999.as_nanos(),
+            self.max.as_nanos(),
+        )
+    }
+
+    fn write_to(&self, dir: &PathBuf) -> Result<()> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("creating output directory {}", dir.display()))?;
+
+        let json_path = dir.join(format!("{}.json", self.workload));
+        fs::write(&json_path, self.to_json())
+            .with_context(|| format!("writing {}", json_path.display()))?;
+
+        let csv_path = dir.join(format!("{}.csv", self.workload));
+        let mut file = fs::File::create(&csv_path)
+            .with_context(|| format!("writing {}", csv_path.display()))?;
+        writeln!(
+            file,
+            "workload,elapsed_secs,completed,errors,throughput_ops_per_sec,p50_ns,p90_ns,p99_ns,p999_ns,max_ns"
+        )?;
+        file.write_all(self.to_csv_row().as_bytes())?;
+
+        println!("📁 Summary written to {}", dir.display());
+        Ok(())
+    }
 }
 
-fn stress_test_mixed_workload(db: &FireLocal, duration_secs: u64) -> Result<()> {
-    println!("🔄 Starting mixed workload stress test: {} seconds", duration_secs);
-    
-    let start = Instant::now();
-    let mut write_count = 0;
-    let mut read_count = 0;
-    let mut delete_count = 0;
-    
-    while start.elapsed().as_secs() < duration_secs {
-        // Mix of operations: 70% writes, 25% reads, 5% deletes
-        let rand_num = (start.elapsed().as_nanos() % 100) as u32;
-        
-        if rand_num < 70 {
-            // Write operation
-            let test_data = TestData::new(&format!("mixed_{}", write_count), "Mixed Write");
-            let key = format!("mixed/write/{}", write_count);
-            db.put(key, test_data.to_bytes())?;
-            write_count += 1;
-        } else if rand_num < 95 {
-            // Read operation
-            if write_count > 0 {
-                let key = format!("mixed/write/{}", write_count - 1);
-                db.get(&key);
-                read_count += 1;
+fn run_bench(args: &BenchArgs, running: Arc<AtomicBool>) -> Result<BenchSummary> {
+    let workload = build_workload(&args.workload)?;
+
+    if std::path::Path::new(&args.db_path).exists() {
+        fs::remove_dir_all(&args.db_path)?;
+    }
+    let db = Arc::new(Mutex::new(FireLocal::new(&args.db_path)?));
+
+    let per_worker_rate = args
+        .operations_per_second
+        .map(|total| (total as f64 / args.concurrency as f64).max(1.0));
+    let per_worker_interval = per_worker_rate.map(|rate| Duration::from_secs_f64(1.0 / rate));
+    let ramp_step = if args.concurrency > 0 {
+        Duration::from_secs_f64(args.ramp_up as f64 / args.concurrency as f64)
+    } else {
+        Duration::ZERO
+    };
+
+    let bench_end = Instant::now() + Duration::from_secs(args.bench_length_seconds);
+
+    let mut handles = Vec::with_capacity(args.concurrency);
+    for worker_id in 0..args.concurrency {
+        let db = Arc::clone(&db);
+        let running = Arc::clone(&running);
+        let workload_name = args.workload.clone();
+        let start_delay = ramp_step * worker_id as u32;
+
+        handles.push(thread::spawn(move || -> WorkerResult {
+            thread::sleep(start_delay);
+            let workload = build_workload(&workload_name).expect("workload built once already");
+
+            let mut histogram = LatencyHistogram::new();
+            let mut completed = 0u64;
+            let mut errors = 0u64;
+            let mut op_index = 0u64;
+            let mut next_op_at = Instant::now();
+
+            while running.load(Ordering::Relaxed) && Instant::now() < bench_end {
+                if let Some(interval) = per_worker_interval {
+                    let now = Instant::now();
+                    if now < next_op_at {
+                        thread::sleep(next_op_at - now);
+                    }
+                    next_op_at += interval;
+                }
+
+                match workload.run_once(&db, worker_id, op_index) {
+                    Ok(latency) => {
+                        histogram.record(latency);
+                        completed += 1;
+                    }
+                    Err(_) => errors += 1,
+                }
+                op_index += 1;
             }
-        } else {
-            // Delete operation
-            if write_count > 10 {
-                let key = format!("mixed/write/{}", write_count - 10);
-                db.delete(&key)?;
-                delete_count += 1;
+
+            WorkerResult {
+                histogram,
+                completed,
+                errors,
             }
-        }
+        }));
     }
-    
-    let total_ops = write_count + read_count + delete_count;
-    let ops_per_sec = total_ops as f64 / duration_secs as f64;
-    
-    println!("  ⏱️ Mixed workload test completed");
-    println!("  📊 Mixed workload stats:");
-    println!("    - Writes: {}", write_count);
-    println!("    - Reads: {}", read_count);
-    println!("    - Deletes: {}", delete_count);
-    println!("    - Total ops: {}", total_ops);
-    println!("    - Performance: {:.2} ops/sec", ops_per_sec);
-    
-    Ok(())
+
+    let run_start = Instant::now();
+    let results: Vec<WorkerResult> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    let elapsed = run_start.elapsed();
+
+    let mut histogram = LatencyHistogram::new();
+    let mut completed_total = 0u64;
+    let mut errors_total = 0u64;
+    for r in &results {
+        histogram.merge(&r.histogram);
+        completed_total += r.completed;
+        errors_total += r.errors;
+    }
+
+    Ok(BenchSummary {
+        workload: workload.name().to_string(),
+        params: workload.params(),
+        elapsed,
+        completed: completed_total,
+        errors: errors_total,
+        p50: histogram.percentile(50.0),
+        p90: histogram.percentile(90.0),
+        p99: histogram.percentile(99.0),
+        p999: histogram.percentile(99.9),
+        max: histogram.max(),
+    })
 }
 
 fn main() -> Result<()> {
-    println!("🔥 FireLocal Stress Test Suite");
+    println!("🔥 FireLocal Benchmark Engine");
     println!("================================");
-    
-    // Test database path
-    let test_db_path = "./stress_test_db";
-    
-    // Clean up any existing test database
-    if std::path::Path::new(test_db_path).exists() {
-        std::fs::remove_dir_all(test_db_path)?;
+
+    let args = BenchArgs::parse();
+
+    let running = Arc::new(AtomicBool::new(true));
+    let interrupt_flag = Arc::clone(&running);
+    ctrlc::set_handler(move || {
+        println!("\n🛑 SIGINT received, draining in-flight operations...");
+        interrupt_flag.store(false, Ordering::Relaxed);
+    })
+    .context("installing SIGINT handler")?;
+
+    let summary = run_bench(&args, running)?;
+    summary.print();
+
+    if let Some(dir) = &args.output_dir {
+        summary.write_to(dir)?;
     }
-    
-    let db = FireLocal::new(test_db_path)?;
-    
-    println!("\n📊 Test 1: Basic Operations");
-    stress_test_basic_operations(&db, 10000)?;
-    
-    println!("\n📊 Test 2: Batch Operations");
-    stress_test_batch_operations(&db, 100, 100)?;
-    
-    println!("\n📊 Test 3: Concurrent Access");
-    stress_test_concurrent_access("./concurrent_test_db", 8, 1000)?;
-    
-    println!("\n📊 Test 4: Large Documents");
-    stress_test_large_documents(&db, 10, 100)?;
-    
-    println!("\n📊 Test 5: Mixed Workload");
-    stress_test_mixed_workload(&db, 30)?;
-    
-    println!("\n🎉 All stress tests completed successfully!");
-    println!("📈 FireLocal is ready for production deployment!");
-    
+
     Ok(())
 }